@@ -0,0 +1,25 @@
+#ifndef CARGO_SCOUT_H
+#define CARGO_SCOUT_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Lints the project at `path`, and returns the findings introduced since
+ * `target_branch`, as the JSON document `--output json` writes.
+ * Failures and panics are returned as {"error": "..."}.
+ *
+ * The current directory of the process changes while the run lasts.
+ * The result must be released with scout_string_free.
+ */
+char *scout_run(const char *path, const char *target_branch);
+
+/* Releases a string returned by scout_run. */
+void scout_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
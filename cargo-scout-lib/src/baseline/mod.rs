@@ -0,0 +1,290 @@
+use crate::linter::Lint;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The default baseline file name
+pub const DEFAULT_BASELINE_PATH: &str = ".scout-baseline.json";
+
+/// How many lines a baselined lint may have moved
+/// and still be considered the same lint
+pub const DEFAULT_LINE_TOLERANCE: u32 = 3;
+
+/// A set of pre-existing findings that must not fail a run.
+///
+/// Lints are matched against the baseline by code and path,
+/// with a line drift tolerance so unrelated edits above a lint
+/// don't bring it back.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Baseline {
+    entries: Vec<BaselineEntry>,
+    #[serde(skip, default = "default_tolerance")]
+    tolerance: u32,
+}
+
+fn default_tolerance() -> u32 {
+    DEFAULT_LINE_TOLERANCE
+}
+
+impl Default for Baseline {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            tolerance: DEFAULT_LINE_TOLERANCE,
+        }
+    }
+}
+
+/// A single baselined finding
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct BaselineEntry {
+    /// The lint code
+    /// Example:
+    /// `Some("clippy::doc_markdown".to_string())`
+    pub code: Option<String>,
+    /// The file the lint was reported on
+    pub file_name: String,
+    /// The first line the lint was reported on
+    pub line: u32,
+}
+
+impl BaselineEntry {
+    fn from_lint(lint: &Lint) -> Option<Self> {
+        let span = lint.primary_span()?;
+        Some(Self {
+            code: lint.code().map(String::from),
+            file_name: span.file_name.replace('\\', "/"),
+            line: span.line_start,
+        })
+    }
+
+    fn matches(&self, other: &Self, tolerance: u32) -> bool {
+        self.code == other.code
+            && self.file_name == other.file_name
+            && (i64::from(self.line) - i64::from(other.line)).abs() <= i64::from(tolerance)
+    }
+}
+
+impl Baseline {
+    /// Creates a baseline out of the current findings.
+    #[must_use]
+    pub fn from_lints(lints: &[Lint]) -> Self {
+        Self {
+            entries: lints.iter().filter_map(BaselineEntry::from_lint).collect(),
+            tolerance: DEFAULT_LINE_TOLERANCE,
+        }
+    }
+
+    /// Reads a baseline file.
+    ///
+    /// If the file doesn't exist, an empty baseline is returned.
+    ///
+    /// # Errors
+    /// Fails if the file can't be read or isn't a valid baseline.
+    pub fn from_path(p: impl AsRef<Path>) -> Result<Self, crate::error::Error> {
+        let p = p.as_ref();
+        if p.exists() {
+            Ok(serde_json::from_str(&std::fs::read_to_string(p)?)?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Writes the baseline to a file.
+    ///
+    /// # Errors
+    /// Fails if the file can't be written.
+    pub fn save(&self, p: impl AsRef<Path>) -> Result<(), crate::error::Error> {
+        std::fs::write(p, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Adds a finding to the baseline.
+    ///
+    /// Returns false if the finding has no location, and can't be baselined.
+    pub fn add(&mut self, lint: &Lint) -> bool {
+        match BaselineEntry::from_lint(lint) {
+            Some(entry) => {
+                self.entries.push(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set how many lines a baselined lint may have moved
+    pub fn set_tolerance(&mut self, tolerance: u32) -> &mut Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    #[must_use]
+    pub fn entries(&self) -> &[BaselineEntry] {
+        &self.entries
+    }
+
+    /// Removes the baselined lints.
+    ///
+    /// Each baseline entry can only absorb one lint,
+    /// so new occurrences of an existing lint are still reported.
+    /// The closest matches are paired first.
+    #[must_use]
+    pub fn subtract(&self, lints: Vec<Lint>) -> Vec<Lint> {
+        let baselined = self.baselined(&lints);
+        lints
+            .into_iter()
+            .zip(baselined)
+            .filter(|(_, baselined)| !baselined)
+            .map(|(lint, _)| lint)
+            .collect()
+    }
+
+    /// Tells, for each lint, whether the baseline absorbs it.
+    #[must_use]
+    pub fn baselined(&self, lints: &[Lint]) -> Vec<bool> {
+        let lint_entries: Vec<Option<BaselineEntry>> =
+            lints.iter().map(BaselineEntry::from_lint).collect();
+        let mut candidates = Vec::new();
+        for (lint_index, lint_entry) in lint_entries.iter().enumerate() {
+            if let Some(lint_entry) = lint_entry {
+                for (entry_index, entry) in self.entries.iter().enumerate() {
+                    if entry.matches(lint_entry, self.tolerance) {
+                        let distance = (i64::from(entry.line) - i64::from(lint_entry.line)).abs();
+                        candidates.push((distance, lint_index, entry_index));
+                    }
+                }
+            }
+        }
+        candidates.sort_unstable();
+        let mut baselined = vec![false; lints.len()];
+        let mut used = vec![false; self.entries.len()];
+        for (_, lint_index, entry_index) in candidates {
+            if !baselined[lint_index] && !used[entry_index] {
+                baselined[lint_index] = true;
+                used[entry_index] = true;
+            }
+        }
+        baselined
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::span;
+    use crate::linter::{Code, Message, Span};
+
+    fn lint(code: &str, file_name: &str, line: u32) -> Lint {
+        Lint {
+            package_id: "cargo-scout".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "this is a test lint".to_string(),
+                code: Some(Code {
+                    code: code.to_string(),
+                    explanation: None,
+                }),
+                level: None,
+                children: Vec::new(),
+                spans: vec![span(file_name, line, line)],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_empty_baseline_keeps_everything() {
+        let lints = vec![lint("clippy::doc_markdown", "src/lib.rs", 10)];
+        assert_eq!(lints.clone(), Baseline::default().subtract(lints));
+    }
+
+    #[test]
+    fn test_subtract_with_line_drift() {
+        let baseline = Baseline::from_lints(&[
+            lint("clippy::doc_markdown", "src/lib.rs", 10),
+            lint("clippy::doc_markdown", "src/main.rs", 10),
+        ]);
+        let lints = vec![
+            // Moved two lines down
+            lint("clippy::doc_markdown", "src/lib.rs", 12),
+            // Moved too far away
+            lint("clippy::doc_markdown", "src/main.rs", 20),
+            // Another lint code
+            lint("clippy::needless_pass_by_value", "src/lib.rs", 10),
+        ];
+        assert_eq!(
+            vec![
+                lint("clippy::doc_markdown", "src/main.rs", 20),
+                lint("clippy::needless_pass_by_value", "src/lib.rs", 10),
+            ],
+            baseline.subtract(lints)
+        );
+    }
+
+    #[test]
+    fn test_entries_use_the_primary_span() {
+        // Clippy can list a secondary span first, such as the definition a lint refers to
+        let mut reported = lint("clippy::doc_markdown", "src/lib.rs", 10);
+        let secondary = Span {
+            is_primary: false,
+            ..span("src/main.rs", 3, 3)
+        };
+        if let Some(m) = &mut reported.message {
+            m.spans.insert(0, secondary);
+        }
+        let baseline = Baseline::from_lints(&[reported]);
+        assert!(baseline
+            .subtract(vec![lint("clippy::doc_markdown", "src/lib.rs", 10)])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_entries_are_only_used_once() {
+        let baseline = Baseline::from_lints(&[lint("clippy::doc_markdown", "src/lib.rs", 10)]);
+        let lints = vec![
+            lint("clippy::doc_markdown", "src/lib.rs", 11),
+            lint("clippy::doc_markdown", "src/lib.rs", 10),
+        ];
+        assert_eq!(
+            vec![lint("clippy::doc_markdown", "src/lib.rs", 11)],
+            baseline.subtract(lints)
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join(DEFAULT_BASELINE_PATH);
+        let mut baseline = Baseline::from_lints(&[lint("clippy::doc_markdown", "src\\lib.rs", 10)]);
+        baseline.save(&path)?;
+        let loaded = Baseline::from_path(&path)?;
+        assert_eq!(baseline, loaded);
+        assert_eq!("src/lib.rs", loaded.entries()[0].file_name);
+        baseline.set_tolerance(0);
+        assert_eq!(0, baseline.tolerance);
+        Ok(())
+    }
+
+    #[test]
+    fn test_add() {
+        let mut baseline = Baseline::default();
+        assert!(baseline.add(&lint("clippy::doc_markdown", "src/lib.rs", 10)));
+        assert!(!baseline.add(&Lint {
+            package_id: "cargo-scout".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: None,
+        }));
+        assert_eq!(
+            Vec::<Lint>::new(),
+            baseline.subtract(vec![lint("clippy::doc_markdown", "src/lib.rs", 11)])
+        );
+    }
+
+    #[test]
+    fn test_missing_file() -> Result<(), crate::error::Error> {
+        let baseline = Baseline::from_path("does-not-exist.json")?;
+        assert!(baseline.entries().is_empty());
+        Ok(())
+    }
+}
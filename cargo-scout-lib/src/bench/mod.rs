@@ -0,0 +1,293 @@
+use crate::project::{Package, Project};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The default regression threshold, in percent of the mean time
+pub const DEFAULT_THRESHOLD: f64 = 5.0;
+
+/// Runs the criterion benchmarks of some packages, and reads their mean times.
+///
+/// Each run builds in its own directory under the target directory:
+/// cargo tells the sources apart by their modification time,
+/// which a checkout of the target branch doesn't keep.
+#[derive(Debug, Clone)]
+pub struct Bench {
+    target_dir: PathBuf,
+    packages: Vec<String>,
+}
+
+impl Bench {
+    #[must_use]
+    pub fn new(target_dir: PathBuf) -> Self {
+        Self {
+            target_dir,
+            packages: Vec::new(),
+        }
+    }
+
+    /// The packages whose benchmarks are run, all of them if empty.
+    pub fn set_packages(&mut self, packages: Vec<String>) -> &mut Self {
+        self.packages = packages;
+        self
+    }
+
+    /// Runs `cargo bench` in `dir`, keeping the results under `name`.
+    ///
+    /// # Errors
+    /// Fails if cargo can't be run, or if the benchmarks don't build or fail.
+    pub fn run(&self, dir: &Path, name: &str) -> Result<BenchResults, crate::error::Error> {
+        let target_dir = self.target_dir.join(name);
+        let criterion_home = target_dir.join("criterion");
+        // Benchmarks that were removed since the last run must not show up
+        if criterion_home.exists() {
+            std::fs::remove_dir_all(&criterion_home)?;
+        }
+        log::info!(
+            "[Bench] - running the benchmarks of {} in directory {}",
+            name,
+            dir.display()
+        );
+        let mut command = Command::new("cargo");
+        command
+            .current_dir(dir)
+            .env("CARGO_TARGET_DIR", &target_dir)
+            .env("CRITERION_HOME", &criterion_home)
+            .arg("bench");
+        for package in &self.packages {
+            command.args(["-p", package]);
+        }
+        let output = command
+            .output()
+            .map_err(|e| crate::error::Error::spawn("cargo", e))?;
+        if !output.status.success() {
+            return Err(crate::error::Error::Command(format!(
+                "cargo bench failed on {}:\n{}",
+                name,
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        BenchResults::from_dir(&criterion_home)
+    }
+}
+
+/// The mean time of each benchmark, in nanoseconds, by criterion id (e.g. `fib/20`)
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct BenchResults {
+    pub means: BTreeMap<String, f64>,
+}
+
+#[derive(Deserialize)]
+struct Estimates {
+    mean: Estimate,
+}
+
+#[derive(Deserialize)]
+struct Estimate {
+    point_estimate: f64,
+}
+
+impl BenchResults {
+    /// Reads the `<id>/new/estimates.json` files criterion wrote in `dir`.
+    ///
+    /// # Errors
+    /// Fails if an estimates file can't be read or parsed.
+    pub fn from_dir(dir: &Path) -> Result<Self, crate::error::Error> {
+        let pattern = dir.join("**").join("new").join("estimates.json");
+        let mut means = BTreeMap::new();
+        for file in glob::glob(&pattern.to_string_lossy())?.filter_map(Result::ok) {
+            let id = match file
+                .parent()
+                .and_then(Path::parent)
+                .and_then(|d| d.strip_prefix(dir).ok())
+            {
+                Some(id) => id.to_string_lossy().replace('\\', "/"),
+                None => continue,
+            };
+            let estimates: Estimates = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+            means.insert(id, estimates.mean.point_estimate);
+        }
+        Ok(Self { means })
+    }
+}
+
+/// How the mean time of a benchmark changed
+#[derive(Debug, PartialEq, Clone)]
+pub struct BenchChange {
+    pub id: String,
+    /// In nanoseconds
+    pub before: f64,
+    /// In nanoseconds
+    pub after: f64,
+}
+
+impl BenchChange {
+    /// The change of the mean time in percent, positive when it got slower
+    #[must_use]
+    pub fn percent(&self) -> f64 {
+        (self.after - self.before) / self.before * 100.0
+    }
+
+    /// Returns true if it got slower by more than `threshold` percent.
+    #[must_use]
+    pub fn is_regression(&self, threshold: f64) -> bool {
+        self.percent() > threshold
+    }
+}
+
+/// The changes of the benchmarks both runs have.
+#[must_use]
+pub fn compare(before: &BenchResults, after: &BenchResults) -> Vec<BenchChange> {
+    before
+        .means
+        .iter()
+        .filter(|(_, before)| **before > 0.0)
+        .filter_map(|(id, before)| {
+            Some(BenchChange {
+                id: id.clone(),
+                before: *before,
+                after: *after.means.get(id)?,
+            })
+        })
+        .collect()
+}
+
+/// The packages with benchmarks that the changed files can slow down:
+/// the ones containing them and the ones depending on those, transitively.
+#[must_use]
+pub fn impacted_packages(project: &Project, changed_files: &[PathBuf]) -> Vec<String> {
+    let mut impacted: BTreeSet<&str> = BTreeSet::new();
+    let mut pending: Vec<&Package> = changed_files
+        .iter()
+        .filter_map(|f| project.package_of(f))
+        .collect();
+    while let Some(package) = pending.pop() {
+        if impacted.insert(&package.name) {
+            pending.extend(project.dependents(package));
+        }
+    }
+    project
+        .packages()
+        .iter()
+        .filter(|p| impacted.contains(p.name.as_str()))
+        .filter(|p| {
+            p.targets
+                .iter()
+                .any(|t| t.kind.iter().any(|k| k == "bench"))
+        })
+        .map(|p| p.name.clone())
+        .collect()
+}
+
+/// Formats a time in nanoseconds with the unit criterion would use.
+///
+/// # Example
+/// ```
+/// # use cargo_scout_lib::bench::format_time;
+/// assert_eq!("812.00 ns", format_time(812.0));
+/// assert_eq!("1.25 ms", format_time(1_250_000.0));
+/// ```
+#[must_use]
+pub fn format_time(nanoseconds: f64) -> String {
+    let (value, unit) = if nanoseconds < 1e3 {
+        (nanoseconds, "ns")
+    } else if nanoseconds < 1e6 {
+        (nanoseconds / 1e3, "µs")
+    } else if nanoseconds < 1e9 {
+        (nanoseconds / 1e6, "ms")
+    } else {
+        (nanoseconds / 1e9, "s")
+    };
+    format!("{value:.2} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_estimates(dir: &Path, id: &str, mean: f64) -> Result<(), crate::error::Error> {
+        let new = dir.join(id).join("new");
+        std::fs::create_dir_all(&new)?;
+        std::fs::write(
+            new.join("estimates.json"),
+            format!(
+                r#"{{"mean":{{"confidence_interval":{{"confidence_level":0.95,"lower_bound":1.0,"upper_bound":2.0}},"point_estimate":{},"standard_error":0.1}},"median":{{"point_estimate":1.0}}}}"#,
+                mean
+            ),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare() -> Result<(), crate::error::Error> {
+        let (before_dir, after_dir) = (tempfile::TempDir::new()?, tempfile::TempDir::new()?);
+        write_estimates(before_dir.path(), "fib/20", 100.0)?;
+        write_estimates(before_dir.path(), "sort", 1000.0)?;
+        write_estimates(before_dir.path(), "removed", 10.0)?;
+        write_estimates(after_dir.path(), "fib/20", 110.0)?;
+        write_estimates(after_dir.path(), "sort", 980.0)?;
+        write_estimates(after_dir.path(), "added", 10.0)?;
+        std::fs::create_dir_all(after_dir.path().join("report"))?;
+
+        let before = BenchResults::from_dir(before_dir.path())?;
+        let after = BenchResults::from_dir(after_dir.path())?;
+        assert_eq!(Some(&100.0), before.means.get("fib/20"));
+        let changes = compare(&before, &after);
+        assert_eq!(
+            vec!["fib/20", "sort"],
+            changes.iter().map(|c| c.id.as_str()).collect::<Vec<_>>()
+        );
+        assert!((changes[0].percent() - 10.0).abs() < 1e-9);
+        assert!(changes[0].is_regression(DEFAULT_THRESHOLD));
+        assert!(!changes[0].is_regression(15.0));
+        assert!(!changes[1].is_regression(DEFAULT_THRESHOLD));
+        Ok(())
+    }
+
+    #[test]
+    fn test_impacted_packages() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let write = |file: &str, contents: &str| -> std::io::Result<()> {
+            let file = dir.path().join(file);
+            std::fs::create_dir_all(file.parent().unwrap())?;
+            std::fs::write(file, contents)
+        };
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\n",
+        )?;
+        write(
+            "a/Cargo.toml",
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\n\n[dependencies]\nb = { path = \"../b\" }\n",
+        )?;
+        write("a/src/lib.rs", "")?;
+        write("a/benches/a.rs", "fn main() {}\n")?;
+        for member in &["b", "c"] {
+            write(
+                &format!("{}/Cargo.toml", member),
+                &format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", member),
+            )?;
+            write(&format!("{}/src/lib.rs", member), "")?;
+            write(
+                &format!("{}/benches/{}.rs", member, member),
+                "fn main() {}\n",
+            )?;
+        }
+        let project = Project::from_manifest_path(dir.path().join("Cargo.toml"))?;
+        let root = project.workspace_root().to_path_buf();
+        // a uses b, c is unrelated
+        assert_eq!(
+            vec!["a", "b"],
+            impacted_packages(&project, &[root.join("b/src/lib.rs")])
+        );
+        assert_eq!(
+            vec!["a"],
+            impacted_packages(
+                &project,
+                &[root.join("a/src/lib.rs"), root.join("README.md")]
+            )
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,490 @@
+use crate::linter::clippy::same_package;
+use crate::linter::{Lint, Linter};
+use crate::lock::target_dir;
+use crate::project::{Package, Project};
+use crate::vcs::Section;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// FNV-1a, which unlike the std hashers is stable across releases and platforms
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl StableHasher {
+    /// Hashes `bytes`, followed by a separator so consecutive writes can't collide
+    pub(crate) fn write(&mut self, bytes: &[u8]) -> &mut Self {
+        for byte in bytes.iter().chain(std::iter::once(&0)) {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+        self
+    }
+
+    pub(crate) fn finish(self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    lints: Vec<Lint>,
+}
+
+/// Reuses the lints of a previous run when nothing they depend on has changed.
+///
+/// An entry is keyed by the sources of the crate and of its path dependencies,
+/// the toolchain, the linter configuration, the cargo and clippy environment variables,
+/// and the manifests, `Cargo.lock`, clippy and cargo configurations above the crate.
+/// Each crate keeps a single entry, in `target/scout-cache` by default.
+pub struct CachedLinter<L: Linter> {
+    linter: L,
+    enabled: bool,
+    dir: Option<PathBuf>,
+}
+
+impl<L: Linter> CachedLinter<L> {
+    pub fn new(linter: L) -> Self {
+        Self {
+            linter,
+            enabled: true,
+            dir: None,
+        }
+    }
+
+    /// Always run the linter when false, without reading or writing the cache
+    pub fn set_enabled(&mut self, enabled: bool) -> &mut Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Store the entries in `dir` instead of `target/scout-cache`
+    pub fn set_dir(&mut self, dir: PathBuf) -> &mut Self {
+        self.dir = Some(dir);
+        self
+    }
+
+    // Where the entry of the crate in `working_dir` is, and the key it must have
+    fn entry(&self, working_dir: &Path) -> Result<(PathBuf, String), crate::error::Error> {
+        let dir = self
+            .dir
+            .clone()
+            .unwrap_or_else(|| target_dir(working_dir).join("scout-cache"));
+        let canonical = std::fs::canonicalize(working_dir)?;
+        let path = dir.join(format!(
+            "{}.json",
+            StableHasher::default()
+                .write(canonical.to_string_lossy().as_bytes())
+                .finish()
+        ));
+        Ok((path, self.key(&canonical)?))
+    }
+
+    fn key(&self, working_dir: &Path) -> Result<String, crate::error::Error> {
+        let mut hasher = StableHasher::default();
+        hasher.write(self.linter.configuration().as_bytes());
+        // The toolchain rustup picks for this directory
+        if let Ok(output) = Command::new("rustc")
+            .current_dir(working_dir)
+            .arg("-vV")
+            .output()
+        {
+            hasher.write(&output.stdout);
+        }
+        // The flags cargo and clippy read from the environment
+        let mut vars: Vec<(String, String)> = std::env::vars()
+            .filter(|(name, _)| {
+                ["CARGO_", "RUSTC", "RUSTFLAGS", "RUSTDOCFLAGS", "CLIPPY_"]
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+            })
+            .collect();
+        vars.sort();
+        for (name, value) in vars {
+            hasher.write(name.as_bytes()).write(value.as_bytes());
+        }
+        // The workspace manifest, lockfile, clippy and cargo configurations
+        // can live in any directory above the crate
+        for dir in working_dir.ancestors() {
+            for file in &[
+                "Cargo.toml",
+                "Cargo.lock",
+                "clippy.toml",
+                ".clippy.toml",
+                ".cargo/config",
+                ".cargo/config.toml",
+            ] {
+                let path = dir.join(file);
+                if path.is_file() {
+                    hasher
+                        .write(path.to_string_lossy().as_bytes())
+                        .write(&std::fs::read(path)?);
+                }
+            }
+        }
+        hash_sources(&mut hasher, working_dir, working_dir)?;
+        for dir in path_dependencies(working_dir) {
+            hasher.write(dir.to_string_lossy().as_bytes());
+            hash_sources(&mut hasher, &dir, &dir)?;
+        }
+        Ok(hasher.finish())
+    }
+}
+
+// The lints of the entry at `path`, if it has the given key
+fn read_entry(path: &Path, key: &str) -> Option<Vec<Lint>> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Entry>(&contents).ok())
+        .filter(|entry| entry.key == key)
+        .map(|entry| entry.lints)
+}
+
+fn write_entry(path: &Path, key: String, lints: Vec<Lint>) -> Result<(), crate::error::Error> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string(&Entry { key, lints })?)?;
+    Ok(())
+}
+
+// The package id of the crate in `working_dir`, as cargo reports it in its messages
+fn package_id(working_dir: &Path) -> Option<String> {
+    Project::from_manifest_path(working_dir.join("Cargo.toml"))
+        .ok()?
+        .current_package()
+        .map(|package| package.id.clone())
+}
+
+// The directories of the local crates the crate in `working_dir` uses, directly or not.
+// The path dependencies of a crate outside the workspace aren't followed
+fn path_dependencies(working_dir: &Path) -> Vec<PathBuf> {
+    let project = match Project::from_manifest_path(working_dir.join("Cargo.toml")) {
+        Ok(project) => project,
+        Err(e) => {
+            log::debug!(
+                "[Cache] - could not read the path dependencies of {}: {}",
+                working_dir.display(),
+                e
+            );
+            return Vec::new();
+        }
+    };
+    let mut dependencies: Vec<PathBuf> = Vec::new();
+    let mut pending: Vec<&Package> = project.current_package().into_iter().collect();
+    while let Some(package) = pending.pop() {
+        for dir in &package.path_dependencies {
+            if dependencies.contains(dir) || *dir == package.dir {
+                continue;
+            }
+            dependencies.push(dir.clone());
+            pending.extend(project.packages().iter().find(|p| p.dir == *dir));
+        }
+    }
+    dependencies.sort();
+    dependencies
+}
+
+// Hashes the paths and contents of every file in `dir`,
+// leaving out build outputs and hidden directories such as `.git`
+fn hash_sources(
+    hasher: &mut StableHasher,
+    root: &Path,
+    dir: &Path,
+) -> Result<(), crate::error::Error> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for path in entries {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() {
+            if name != "target" && !name.starts_with('.') {
+                hash_sources(hasher, root, &path)?;
+            }
+        } else {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            hasher
+                .write(relative.to_string_lossy().replace('\\', "/").as_bytes())
+                .write(&std::fs::read(&path)?);
+        }
+    }
+    Ok(())
+}
+
+impl<L: Linter> Linter for CachedLinter<L> {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        if !self.enabled {
+            return self.linter.lints(working_dir);
+        }
+        let (path, key) = self.entry(&working_dir)?;
+        if let Some(lints) = read_entry(&path, &key) {
+            log::debug!("[Cache] - reusing the lints for {}", working_dir.display());
+            return Ok(lints);
+        }
+        let lints = self.linter.lints(working_dir)?;
+        write_entry(&path, key, lints.clone())?;
+        Ok(lints)
+    }
+
+    // The members without an up to date entry are linted together
+    fn lints_for_members(
+        &self,
+        members: &[PathBuf],
+        sections: Option<&[Section]>,
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        if !self.enabled {
+            return self.linter.lints_for_members(members, sections);
+        }
+        let mut lints = Vec::new();
+        let mut missing = Vec::new();
+        for member in members {
+            let (path, key) = self.entry(member)?;
+            match read_entry(&path, &key) {
+                Some(cached) => {
+                    log::debug!("[Cache] - reusing the lints for {}", member.display());
+                    lints.extend(cached);
+                }
+                None => missing.push((member.clone(), path, key)),
+            }
+        }
+        if missing.is_empty() {
+            return Ok(lints);
+        }
+        let dirs: Vec<PathBuf> = missing
+            .iter()
+            .map(|(member, _, _)| member.clone())
+            .collect();
+        let found = self.linter.lints_for_members(&dirs, sections)?;
+        // Each crate keeps its own entry, the lints are told apart by their package
+        let owners: Option<Vec<usize>> = if missing.len() == 1 {
+            Some(vec![0; found.len()])
+        } else {
+            dirs.iter()
+                .map(|dir| package_id(dir))
+                .collect::<Option<Vec<String>>>()
+                .and_then(|ids| {
+                    found
+                        .iter()
+                        .map(|lint| ids.iter().position(|id| same_package(&lint.package_id, id)))
+                        .collect()
+                })
+        };
+        match owners {
+            Some(owners) => {
+                for (i, (_, path, key)) in missing.into_iter().enumerate() {
+                    let owned = found
+                        .iter()
+                        .zip(&owners)
+                        .filter(|(_, owner)| **owner == i)
+                        .map(|(lint, _)| lint.clone());
+                    write_entry(&path, key, owned.collect())?;
+                }
+            }
+            None => log::debug!(
+                "[Cache] - could not tell which crates the lints belong to, they aren't cached"
+            ),
+        }
+        lints.extend(found);
+        Ok(lints)
+    }
+
+    fn configuration(&self) -> String {
+        self.linter.configuration()
+    }
+
+    fn builds(&self) -> bool {
+        self.linter.builds()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Default)]
+    struct CountingLinter {
+        calls: Cell<usize>,
+    }
+
+    impl Linter for CountingLinter {
+        fn lints(&self, _working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(vec![Lint {
+                package_id: "foo".to_string(),
+                src_path: None,
+                reproducer: None,
+                message: None,
+            }])
+        }
+    }
+
+    #[test]
+    fn test_stable_hasher() {
+        assert_eq!("cbf29ce484222325", StableHasher::default().finish());
+        assert_ne!(
+            StableHasher::default().write(b"ab").write(b"c").finish(),
+            StableHasher::default().write(b"a").write(b"bc").finish()
+        );
+    }
+
+    #[test]
+    fn test_cached_linter() {
+        let workspace = tempfile::tempdir().unwrap();
+        let cache = tempfile::tempdir().unwrap();
+        std::fs::create_dir(workspace.path().join("src")).unwrap();
+        std::fs::write(workspace.path().join("src/lib.rs"), "fn foo() {}").unwrap();
+        let mut linter = CachedLinter::new(CountingLinter::default());
+        linter.set_dir(cache.path().to_path_buf());
+        let lints = linter.lints(workspace.path().to_path_buf()).unwrap();
+        assert_eq!(lints, linter.lints(workspace.path().to_path_buf()).unwrap());
+        assert_eq!(1, linter.linter.calls.get());
+
+        // Build outputs don't matter, sources and Cargo.lock do
+        std::fs::create_dir(workspace.path().join("target")).unwrap();
+        std::fs::write(workspace.path().join("target/foo.rmeta"), "").unwrap();
+        let _ = linter.lints(workspace.path().to_path_buf()).unwrap();
+        assert_eq!(1, linter.linter.calls.get());
+        std::fs::write(workspace.path().join("src/lib.rs"), "fn bar() {}").unwrap();
+        let _ = linter.lints(workspace.path().to_path_buf()).unwrap();
+        assert_eq!(2, linter.linter.calls.get());
+        std::fs::write(workspace.path().join("Cargo.lock"), "version = 3").unwrap();
+        let _ = linter.lints(workspace.path().to_path_buf()).unwrap();
+        assert_eq!(3, linter.linter.calls.get());
+
+        linter.set_enabled(false);
+        let _ = linter.lints(workspace.path().to_path_buf()).unwrap();
+        assert_eq!(4, linter.linter.calls.get());
+    }
+
+    #[test]
+    fn test_cached_linter_workspace() -> Result<(), crate::error::Error> {
+        let workspace = tempfile::tempdir()?;
+        let cache = tempfile::tempdir()?;
+        let write = |file: &str, contents: &str| -> std::io::Result<()> {
+            let file = workspace.path().join(file);
+            std::fs::create_dir_all(file.parent().unwrap())?;
+            std::fs::write(file, contents)
+        };
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\n",
+        )?;
+        write(
+            "a/Cargo.toml",
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\n\n[dependencies]\nb = { path = \"../b\" }\n",
+        )?;
+        for member in &["b", "c"] {
+            write(
+                &format!("{}/Cargo.toml", member),
+                &format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", member),
+            )?;
+        }
+        for member in &["a", "b", "c"] {
+            write(&format!("{}/src/lib.rs", member), "")?;
+        }
+        let mut linter = CachedLinter::new(CountingLinter::default());
+        linter.set_dir(cache.path().to_path_buf());
+        let member = workspace.path().join("a");
+        let _ = linter.lints(member.clone())?;
+        let _ = linter.lints(member.clone())?;
+        assert_eq!(1, linter.linter.calls.get());
+
+        // a uses b, c is unrelated
+        write("c/src/lib.rs", "pub fn c() {}")?;
+        let _ = linter.lints(member.clone())?;
+        assert_eq!(1, linter.linter.calls.get());
+        write("b/src/lib.rs", "pub fn b() {}")?;
+        let _ = linter.lints(member.clone())?;
+        assert_eq!(2, linter.linter.calls.get());
+
+        // The configurations of the workspace apply to its members
+        write("clippy.toml", "msrv = \"1.40\"\n")?;
+        let _ = linter.lints(member.clone())?;
+        assert_eq!(3, linter.linter.calls.get());
+        write(
+            ".cargo/config.toml",
+            "[build]\nrustflags = [\"-Dwarnings\"]\n",
+        )?;
+        let _ = linter.lints(member.clone())?;
+        assert_eq!(4, linter.linter.calls.get());
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\n\n[workspace.lints.rust]\nunsafe_code = \"forbid\"\n",
+        )?;
+        let _ = linter.lints(member)?;
+        assert_eq!(5, linter.linter.calls.get());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cached_linter_members() -> Result<(), crate::error::Error> {
+        // Lints each member at once, a lint for each of them
+        #[derive(Default)]
+        struct MembersLinter {
+            calls: RefCell<Vec<Vec<PathBuf>>>,
+        }
+        impl Linter for MembersLinter {
+            fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+                self.lints_for_members(&[working_dir], None)
+            }
+            fn lints_for_members(
+                &self,
+                members: &[PathBuf],
+                _sections: Option<&[Section]>,
+            ) -> Result<Vec<Lint>, crate::error::Error> {
+                self.calls.borrow_mut().push(members.to_vec());
+                Ok(members
+                    .iter()
+                    .map(|member| Lint {
+                        package_id: package_id(member).unwrap(),
+                        src_path: None,
+                        reproducer: None,
+                        message: None,
+                    })
+                    .collect())
+            }
+        }
+        let workspace = tempfile::tempdir()?;
+        let cache = tempfile::tempdir()?;
+        std::fs::write(
+            workspace.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"a\", \"b\"]\n",
+        )?;
+        for member in &["a", "b"] {
+            std::fs::create_dir_all(workspace.path().join(member).join("src"))?;
+            std::fs::write(
+                workspace.path().join(member).join("Cargo.toml"),
+                format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", member),
+            )?;
+            std::fs::write(workspace.path().join(member).join("src/lib.rs"), "")?;
+        }
+        let mut linter = CachedLinter::new(MembersLinter::default());
+        linter.set_dir(cache.path().to_path_buf());
+        let members = vec![workspace.path().join("a"), workspace.path().join("b")];
+        let lints = linter.lints_for_members(&members, None)?;
+        assert_eq!(2, lints.len());
+        assert_eq!(lints, linter.lints_for_members(&members, None)?);
+        assert_eq!(vec![members.clone()], *linter.linter.calls.borrow());
+
+        // Only the changed member is linted again, the other one is reused
+        std::fs::write(workspace.path().join("b/src/lib.rs"), "pub fn b() {}")?;
+        let lints = linter.lints_for_members(&members, None)?;
+        assert_eq!(2, lints.len());
+        assert_eq!(
+            vec![members.clone(), vec![members[1].clone()]],
+            *linter.linter.calls.borrow()
+        );
+        // Each crate got its own lint
+        let _ = linter.lints(members[0].clone())?;
+        assert_eq!(2, linter.linter.calls.borrow().len());
+        Ok(())
+    }
+}
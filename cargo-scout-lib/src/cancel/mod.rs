@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Stops a run, either from another thread or once it took too long.
+///
+/// Clones share their state: give the same token to the `Scout`,
+/// which stops between members, and to the linter, which kills the processes it started.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    deadline: Option<(Instant, Duration)>,
+}
+
+impl CancellationToken {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a token that cancels the run `timeout` from now.
+    #[must_use]
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                cancelled: AtomicBool::new(false),
+                deadline: Some((Instant::now() + timeout, timeout)),
+            }),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.check().is_err()
+    }
+
+    /// Returns the error the run stops with, if it must stop.
+    ///
+    /// # Errors
+    /// Returns `Error::Cancelled` once the run is cancelled,
+    /// and `Error::Timeout` once its deadline has passed.
+    pub fn check(&self) -> Result<(), crate::error::Error> {
+        if self.inner.cancelled.load(Ordering::SeqCst) {
+            return Err(crate::error::Error::Cancelled);
+        }
+        match self.inner.deadline {
+            Some((deadline, timeout)) if Instant::now() >= deadline => {
+                Err(crate::error::Error::Timeout { after: timeout })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(token.check().is_ok());
+        clone.cancel();
+        assert!(matches!(token.check(), Err(crate::error::Error::Cancelled)));
+    }
+
+    #[test]
+    fn test_timeout() {
+        assert!(!CancellationToken::with_timeout(Duration::from_secs(60)).is_cancelled());
+        let token = CancellationToken::with_timeout(Duration::from_secs(0));
+        assert!(matches!(
+            token.check(),
+            Err(crate::error::Error::Timeout { after }) if after == Duration::from_secs(0)
+        ));
+    }
+}
@@ -1,4 +1,8 @@
+use std::path::Path;
+
+pub mod profile;
 pub mod rust;
+pub mod scout;
 
 /// This trait is responsible for providing a list of members,
 /// which are directories to be linted against.
@@ -75,4 +79,20 @@ pub trait Config {
     /// }
     /// ```
     fn members(&self) -> Vec<String>;
+
+    /// Returns the members that depend on `member` directly, as returned by `members`.
+    ///
+    /// Scout can lint them too, changing a member can break the ones that use it.
+    /// By default, members don't depend on each other.
+    fn dependents(&self, _member: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns the members that use `file` although it belongs to none of them,
+    /// through a path dependency or a patched crate.
+    ///
+    /// By default, files outside the members don't concern them.
+    fn users_of(&self, _file: &Path) -> Vec<String> {
+        Vec::new()
+    }
 }
@@ -0,0 +1,84 @@
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// A preset of lints, selected with `--profile` or `profile` in `scout.toml`
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Profile {
+    /// Code that can panic or overflow, which clippy allows by default
+    Security,
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "security" => Ok(Profile::Security),
+            _ => Err(format!("unknown profile {s}, expected security")),
+        }
+    }
+}
+
+impl Profile {
+    /// The lints clippy warns about with this profile, besides `clippy::pedantic`.
+    ///
+    /// `clippy::integer_arithmetic` was renamed `clippy::arithmetic_side_effects`.
+    #[must_use]
+    pub fn lints(self) -> Vec<String> {
+        match self {
+            Profile::Security => [
+                "clippy::unwrap_used",
+                "clippy::expect_used",
+                "clippy::panic",
+                "clippy::indexing_slicing",
+                "clippy::arithmetic_side_effects",
+            ]
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        }
+    }
+}
+
+/// A tool run after clippy, whose findings are reported like its lints
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    /// `cargo audit`, for the dependencies with security advisories
+    Audit,
+    /// `cargo geiger`, for the dependencies using unsafe code
+    Geiger,
+    /// The `unsafe` blocks, functions, impls and traits of the changed files
+    Unsafe,
+    /// The added lines matching the `text-patterns` of `scout.toml`
+    TextPatterns,
+    /// `typos`, for the spelling mistakes of the added lines
+    Typos,
+    /// The new files without the `license-headers` of `scout.toml`
+    LicenseHeaders,
+    /// The added lines the tests don't cover, from the `coverage` table of `scout.toml`
+    Coverage,
+    /// `cargo doc`, for the broken intra-doc links and missing docs of the changed lines
+    Doc,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "audit" => Ok(Backend::Audit),
+            "geiger" => Ok(Backend::Geiger),
+            "unsafe" => Ok(Backend::Unsafe),
+            "text-patterns" => Ok(Backend::TextPatterns),
+            "typos" => Ok(Backend::Typos),
+            "license-headers" => Ok(Backend::LicenseHeaders),
+            "coverage" => Ok(Backend::Coverage),
+            "doc" => Ok(Backend::Doc),
+            _ => Err(format!(
+                "unknown backend {s}, expected one of audit, geiger, unsafe, text-patterns, typos, license-headers, coverage, doc"
+            )),
+        }
+    }
+}
@@ -1,23 +1,68 @@
 use crate::config::Config;
-use std::path::Path;
+use crate::linter::clippy::workspace_root;
+use crate::project::{Package, Project};
+use std::path::{Path, PathBuf};
 
 /// This struct represents a Cargo project configuration.
 pub struct CargoConfig {
     members: Vec<String>,
+    // The directory of the manifest, members are relative to it
+    root: PathBuf,
+    // None when `cargo metadata` can't run, the manifests are read instead
+    project: Option<Project>,
 }
 
 impl Config for CargoConfig {
-    #[must_use]
     fn members(&self) -> Vec<String> {
         self.members.clone()
     }
+
+    fn dependents(&self, member: &str) -> Vec<String> {
+        let Ok(dir) = std::fs::canonicalize(self.root.join(member)) else {
+            return Vec::new();
+        };
+        self.members
+            .iter()
+            .filter(|other| *other != member)
+            .filter(|other| {
+                std::fs::canonicalize(self.root.join(other))
+                    .ok()
+                    .is_some_and(|other| self.path_dependencies(&other).contains(&dir))
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn users_of(&self, file: &Path) -> Vec<String> {
+        let Some(project) = &self.project else {
+            return Vec::new();
+        };
+        // Deleted files can't be canonicalized, their directory usually can
+        let file = std::fs::canonicalize(file).unwrap_or_else(|_| {
+            match (file.parent(), file.file_name()) {
+                (Some(dir), Some(name)) => std::fs::canonicalize(dir)
+                    .map_or_else(|_| file.to_path_buf(), |dir| dir.join(name)),
+                _ => file.to_path_buf(),
+            }
+        });
+        let users = project.users_of(&file);
+        self.members
+            .iter()
+            .filter(|member| {
+                std::fs::canonicalize(self.root.join(member))
+                    .ok()
+                    .is_some_and(|dir| users.iter().any(|p| p.dir == dir))
+            })
+            .cloned()
+            .collect()
+    }
 }
 
 impl CargoConfig {
     /// This function will instantiate a Config from a Cargo.toml path.
     ///
-    /// If in a workspace, `get_members` will return the members
-    /// of the [[workspace]] members section in Cargo.toml.
+    /// If it's the manifest of a workspace, `members` will return
+    /// the directories of its members, as `cargo metadata` lists them.
     ///
     /// Else, it will return `vec![".".to_string()]`
     ///
@@ -40,20 +85,175 @@ impl CargoConfig {
     /// assert_eq!(vec!["cargo-scout".to_string(), "cargo-scout-lib".to_string()], config.members());
     /// # Ok::<(), cargo_scout_lib::Error>(())
     /// ```
+    ///
+    /// # Errors
+    /// Fails if neither `cargo metadata` nor the manifest can be read.
     pub fn from_manifest_path(p: impl AsRef<Path>) -> Result<Self, crate::error::Error> {
-        Ok(Self::from_manifest(cargo_toml::Manifest::from_path(p)?))
+        let p = p.as_ref();
+        let root = p.parent().map_or_else(PathBuf::new, Path::to_path_buf);
+        let mut config = match Project::from_manifest_path(p) {
+            Ok(project) => Self::from_project(project),
+            Err(e) => {
+                log::debug!(
+                    "[CargoConfig] - cargo metadata failed, reading the manifest instead: {e}"
+                );
+                Self::from_manifest(cargo_toml::Manifest::from_path(p)?)
+            }
+        };
+        config.root = root;
+        Ok(config)
+    }
+
+    fn from_project(project: Project) -> Self {
+        // Run from a member, only that member is linted, like cargo does
+        let members = if project.is_workspace_root() {
+            let mut members: Vec<String> = project
+                .packages()
+                .iter()
+                .map(|p| match p.dir.strip_prefix(project.workspace_root()) {
+                    Ok(dir) if dir.as_os_str().is_empty() => ".".to_string(),
+                    Ok(dir) => dir.to_string_lossy().replace('\\', "/"),
+                    Err(_) => p.dir.to_string_lossy().into_owned(),
+                })
+                .collect();
+            members.sort();
+            members
+        } else {
+            vec![".".to_string()]
+        };
+        Self {
+            members,
+            root: PathBuf::new(),
+            project: Some(project),
+        }
     }
 
     fn from_manifest(m: cargo_toml::Manifest) -> Self {
         if let Some(w) = m.workspace {
-            Self { members: w.members }
+            let mut members = w.members;
+            // A workspace manifest that isn't virtual is a member too
+            if m.package.is_some() && !members.iter().any(|m| m == "." || m.is_empty()) {
+                members.insert(0, ".".to_string());
+            }
+            Self {
+                members,
+                root: PathBuf::new(),
+                project: None,
+            }
         } else {
             Self {
                 // Project root only
                 members: vec![".".to_string()],
+                root: PathBuf::new(),
+                project: None,
             }
         }
     }
+
+    /// What `cargo metadata` reports about the workspace, if it could run
+    #[must_use]
+    pub fn project(&self) -> Option<&Project> {
+        self.project.as_ref()
+    }
+
+    /// The root directory of the workspace,
+    /// from `cargo locate-project` if `cargo metadata` couldn't run.
+    ///
+    /// # Errors
+    /// Fails if `cargo locate-project` can't run or doesn't find a manifest.
+    pub fn workspace_root(&self) -> Result<PathBuf, crate::error::Error> {
+        match &self.project {
+            Some(project) => Ok(project.workspace_root().to_path_buf()),
+            None if self.root.as_os_str().is_empty() => workspace_root("."),
+            None => workspace_root(&self.root),
+        }
+    }
+
+    /// Only keeps the members whose package is named in `packages`, like `cargo -p`.
+    ///
+    /// Members that can't be parsed are matched by directory name.
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownPackage` for a name no member has.
+    pub fn select_packages(
+        &mut self,
+        packages: &[String],
+    ) -> Result<&mut Self, crate::error::Error> {
+        let named: Vec<(String, String)> = self
+            .members
+            .iter()
+            .map(|member| (self.package_name(member), member.clone()))
+            .collect();
+        if let Some(unknown) = packages
+            .iter()
+            .find(|p| !named.iter().any(|(name, _)| name == *p))
+        {
+            return Err(crate::error::Error::UnknownPackage(unknown.clone()));
+        }
+        self.members = named
+            .into_iter()
+            .filter(|(name, _)| packages.contains(name))
+            .map(|(_, member)| member)
+            .collect();
+        Ok(self)
+    }
+
+    // The package of the member in the canonical directory `dir`
+    fn package(&self, dir: &Path) -> Option<&Package> {
+        self.project
+            .as_ref()?
+            .packages()
+            .iter()
+            .find(|p| p.dir == dir)
+    }
+
+    // `cargo metadata` resolves the inherited and the target specific dependencies
+    fn path_dependencies(&self, dir: &Path) -> Vec<PathBuf> {
+        match self.package(dir) {
+            Some(package) => package.path_dependencies.clone(),
+            None => manifest_path_dependencies(dir),
+        }
+    }
+
+    fn package_name(&self, member: &str) -> String {
+        let dir = self.root.join(member);
+        if let Some(package) = std::fs::canonicalize(&dir)
+            .ok()
+            .and_then(|dir| self.package(&dir))
+        {
+            return package.name.clone();
+        }
+        cargo_toml::Manifest::from_path(dir.join("Cargo.toml"))
+            .ok()
+            .and_then(|m| m.package)
+            .map(|p| p.name)
+            .or_else(|| {
+                std::fs::canonicalize(&dir)
+                    .ok()
+                    .and_then(|d| d.file_name().map(|n| n.to_string_lossy().into_owned()))
+            })
+            .unwrap_or_else(|| member.to_string())
+    }
+}
+
+// The directories of the path dependencies of the package in `dir`,
+// including the dev and build ones, but not the inherited ones.
+// The manifest is read as plain TOML, cargo_toml doesn't know the recent editions
+fn manifest_path_dependencies(dir: &Path) -> Vec<PathBuf> {
+    let manifest: toml::Value = match std::fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|m| m.parse().ok())
+    {
+        Some(manifest) => manifest,
+        None => return Vec::new(),
+    };
+    ["dependencies", "dev-dependencies", "build-dependencies"]
+        .iter()
+        .filter_map(|kind| manifest.get(kind)?.as_table())
+        .flat_map(|dependencies| dependencies.values())
+        .filter_map(|dependency| dependency.get("path")?.as_str())
+        .filter_map(|path| std::fs::canonicalize(dir.join(path)).ok())
+        .collect()
 }
 
 #[cfg(test)]
@@ -75,6 +275,101 @@ mod tests {
         assert_eq!(vec!["."], config.members());
     }
     #[test]
+    fn test_select_packages() {
+        let mut config = CargoConfig::from_manifest_path("../Cargo.toml").unwrap();
+        config
+            .select_packages(&["cargo-scout-lib".to_string()])
+            .unwrap();
+        assert_eq!(vec!["cargo-scout-lib"], config.members());
+        let mut config = CargoConfig::from_manifest_path("../Cargo.toml").unwrap();
+        assert!(matches!(
+            config.select_packages(&["cargo-scout".to_string(), "foo".to_string()]),
+            Err(crate::error::Error::UnknownPackage(ref p)) if p == "foo"
+        ));
+    }
+    #[test]
+    fn test_dependents() {
+        let config = CargoConfig::from_manifest_path("../Cargo.toml").unwrap();
+        assert_eq!(vec!["cargo-scout"], config.dependents("cargo-scout-lib"));
+        assert!(config.dependents("cargo-scout").is_empty());
+    }
+    #[test]
+    fn test_workspace_metadata() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let package = |name: &str, dependencies: &str| -> std::io::Result<()> {
+            let package_dir = dir.path().join("crates").join(name);
+            std::fs::create_dir_all(package_dir.join("src"))?;
+            std::fs::write(package_dir.join("src/lib.rs"), "")?;
+            std::fs::write(
+                package_dir.join("Cargo.toml"),
+                format!(
+                    "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n{}",
+                    name, dependencies
+                ),
+            )
+        };
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n[workspace.dependencies]\na = { path = \"crates/a\" }\n",
+        )?;
+        package("a", "")?;
+        package("b", "[dependencies]\na.workspace = true\n")?;
+        let config = CargoConfig::from_manifest_path(dir.path().join("Cargo.toml"))?;
+        // cargo expands the globs, and resolves the inherited dependencies
+        assert_eq!(vec!["crates/a", "crates/b"], config.members());
+        assert_eq!(vec!["crates/b"], config.dependents("crates/a"));
+        assert_eq!("b", config.package_name("crates/b"));
+        Ok(())
+    }
+    #[test]
+    fn test_nested_members() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let package = |path: &str, name: &str, dependencies: &str| -> std::io::Result<()> {
+            let package_dir = dir.path().join(path);
+            std::fs::create_dir_all(package_dir.join("src"))?;
+            std::fs::write(package_dir.join("src/lib.rs"), "")?;
+            std::fs::write(
+                package_dir.join("Cargo.toml"),
+                format!(
+                    "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n{}",
+                    name, dependencies
+                ),
+            )
+        };
+        // The root package is a member, and crates/foo is nested in it.
+        // They use crates next to the workspace
+        package(
+            "ws",
+            "root",
+            "[dependencies]\nbaz = \"0.1\"\n[workspace]\nmembers = [\"crates/foo\"]\n\
+             [patch.crates-io]\nbaz = { path = \"../patched/baz\" }\n",
+        )?;
+        package(
+            "ws/crates/foo",
+            "foo",
+            "[dependencies]\nbar = { path = \"../../../vendor/bar\" }\n",
+        )?;
+        package("vendor/bar", "bar", "")?;
+        package("patched/baz", "baz", "")?;
+        let config = CargoConfig::from_manifest_path(dir.path().join("ws/Cargo.toml"))?;
+        assert_eq!(vec![".", "crates/foo"], config.members());
+        let users = |file: &str| config.users_of(&dir.path().join(file));
+        assert!(users("ws/crates/foo/src/lib.rs").is_empty());
+        assert_eq!(vec!["crates/foo"], users("vendor/bar/src/lib.rs"));
+        // Deleted files are used too
+        assert_eq!(vec!["."], users("patched/baz/src/deleted.rs"));
+        Ok(())
+    }
+    #[test]
+    fn test_root_package_manifest() {
+        let manifest = cargo_toml::Manifest::from_slice(
+            b"[package]\nname = \"root\"\nversion = \"0.1.0\"\n[workspace]\nmembers = [\"foo\"]\n",
+        )
+        .unwrap();
+        let config = CargoConfig::from_manifest(manifest);
+        assert_eq!(vec![".", "foo"], config.members());
+    }
+    #[test]
     fn test_neqo_members_manifest() {
         let neqo_toml = r#"[workspace]
         members = [
@@ -0,0 +1,465 @@
+use crate::config::profile::{Backend, Profile};
+use crate::filter::{LintFilter, PathFilter, PathRule};
+use crate::linter::audit::CargoAudit;
+use crate::linter::coverage::{Coverage, CoverageConfig};
+use crate::linter::doc::CargoDoc;
+use crate::linter::geiger::CargoGeiger;
+use crate::linter::license_headers::{LicenseHeader, LicenseHeaders};
+use crate::linter::text_patterns::{default_patterns, TextPattern, TextPatterns};
+use crate::linter::typos::Typos;
+use crate::linter::unsafe_code::UnsafeCode;
+use crate::linter::{Level, Lint, Linter};
+use crate::policy::{DenyNew, FailOn};
+use crate::scout::limits::DiffLimits;
+use serde::Deserialize;
+use std::path::Path;
+use std::str::FromStr;
+
+/// This struct represents the optional `scout.toml` configuration file.
+///
+/// Every setting has a command line equivalent,
+/// which is merged with the values found in the file.
+///
+/// # Example
+/// ```toml
+/// ignore-lints = ["clippy::module_name_repetitions"]
+/// only-lints = []
+/// fail-on = "warning"
+/// deny-new = ["clippy::unwrap_used", "clippy::panic"]
+/// profile = "security"
+/// backends = ["audit", "text-patterns"]
+/// exclude = ["vendor/**"]
+/// max-changed-files = 200
+/// max-changed-lines = 20000
+///
+/// # Generated code is only checked for undocumented unsafe blocks
+/// [[path-rules]]
+/// paths = ["src/generated/**"]
+/// only-lints = ["clippy::undocumented_unsafe_blocks"]
+///
+/// [[text-patterns]]
+/// name = "dbg"
+/// pattern = '\bdbg!'
+/// message = "`dbg!` is left in the code"
+///
+/// [[license-headers]]
+/// paths = ["**/*.rs"]
+/// header = "// SPDX-License-Identifier: MIT"
+///
+/// [coverage]
+/// tool = "llvm-cov"
+/// ```
+#[derive(Deserialize, Debug, Default, PartialEq, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ScoutConfig {
+    /// Lint codes that will never be reported
+    pub ignore_lints: Vec<String>,
+    /// If not empty, only these lint codes will be reported
+    pub only_lints: Vec<String>,
+    /// The lint severity that makes the run fail
+    pub fail_on: Option<FailOn>,
+    /// If not empty, only these lint codes fail the run, the other findings are advisory
+    pub deny_new: Vec<String>,
+    /// A preset of lints clippy warns about besides `clippy::pedantic`
+    pub profile: Option<Profile>,
+    /// Tools run after clippy, whose findings are reported like its lints
+    pub backends: Vec<Backend>,
+    /// The patterns of the `text-patterns` backend, the default ones if empty
+    pub text_patterns: Vec<TextPattern>,
+    /// The headers the new files must start with, for the `license-headers` backend
+    pub license_headers: Vec<LicenseHeader>,
+    /// How the `coverage` backend measures the coverage
+    pub coverage: CoverageConfig,
+    /// Reduced rule sets for some paths
+    pub path_rules: Vec<PathRule>,
+    /// If not empty, only changes and lints in files matching these globs are considered
+    pub include: Vec<String>,
+    /// Changes and lints in files matching these globs are never considered
+    pub exclude: Vec<String>,
+    /// Beyond this many changed files, the linter isn't run
+    pub max_changed_files: Option<usize>,
+    /// Beyond this many changed lines, the linter isn't run
+    pub max_changed_lines: Option<usize>,
+}
+
+impl ScoutConfig {
+    /// This function will read a `ScoutConfig` from a `scout.toml` path.
+    ///
+    /// If the file doesn't exist, the default configuration is returned.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::config::scout::ScoutConfig;
+    /// let config = ScoutConfig::from_path("does-not-exist.toml")?;
+    /// assert_eq!(ScoutConfig::default(), config);
+    /// # Ok::<(), cargo_scout_lib::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Fails if the file can't be read or isn't valid TOML.
+    pub fn from_path(p: impl AsRef<Path>) -> Result<Self, crate::error::Error> {
+        let p = p.as_ref();
+        if p.exists() {
+            std::fs::read_to_string(p)?.parse()
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Returns the filter matching the `ignore-lints` and `only-lints` settings.
+    #[must_use]
+    pub fn lint_filter(&self) -> LintFilter {
+        let mut filter = LintFilter::default();
+        filter
+            .set_ignore_lints(self.ignore_lints.clone())
+            .set_only_lints(self.only_lints.clone())
+            .set_path_rules(self.path_rules.clone());
+        filter
+    }
+
+    /// Returns the filter matching the `include` and `exclude` settings.
+    #[must_use]
+    pub fn path_filter(&self) -> PathFilter {
+        let mut filter = PathFilter::default();
+        filter
+            .set_include(self.include.clone())
+            .set_exclude(self.exclude.clone());
+        filter
+    }
+
+    /// Returns the limits matching the `max-changed-files` and `max-changed-lines` settings.
+    #[must_use]
+    pub fn diff_limits(&self) -> DiffLimits {
+        DiffLimits {
+            max_files: self.max_changed_files,
+            max_lines: self.max_changed_lines,
+        }
+    }
+
+    /// Returns the lint severity that makes the run fail, once `deny_new` is applied.
+    #[must_use]
+    pub fn fail_on(&self) -> FailOn {
+        self.deny_new().fail_on(self.fail_on.unwrap_or_default())
+    }
+
+    /// Returns the lints clippy warns about besides `clippy::pedantic`, from the `profile` setting.
+    #[must_use]
+    pub fn clippy_lints(&self) -> Vec<String> {
+        self.profile.map(Profile::lints).unwrap_or_default()
+    }
+
+    /// Returns the linters of the `backends` setting, run after clippy.
+    ///
+    /// # Errors
+    /// Fails if the patterns or license headers of a backend are invalid.
+    pub fn backend_linters(&self) -> Result<Vec<Box<dyn Linter>>, crate::error::Error> {
+        self.backends
+            .iter()
+            .map(|backend| -> Result<Box<dyn Linter>, crate::error::Error> {
+                Ok(match backend {
+                    Backend::Audit => Box::new(CargoAudit),
+                    Backend::Geiger => Box::new(CargoGeiger),
+                    Backend::Unsafe => Box::new(UnsafeCode),
+                    Backend::TextPatterns => Box::new(TextPatterns::new(self.text_patterns())?),
+                    Backend::Typos => Box::new(Typos),
+                    Backend::LicenseHeaders => {
+                        if self.license_headers.is_empty() {
+                            log::warn!(
+                                "The license-headers backend has no [[license-headers]] to check"
+                            );
+                        }
+                        Box::new(LicenseHeaders::new(self.license_headers.clone())?)
+                    }
+                    Backend::Coverage => {
+                        let mut coverage = Coverage::default();
+                        coverage
+                            .set_tool(self.coverage.tool)
+                            .set_report(self.coverage.report.clone())
+                            .set_level(self.coverage.level.unwrap_or(Level::Warning));
+                        Box::new(coverage)
+                    }
+                    Backend::Doc => Box::new(CargoDoc),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the `text-patterns` setting, or the default patterns if it's empty.
+    #[must_use]
+    pub fn text_patterns(&self) -> Vec<TextPattern> {
+        if self.text_patterns.is_empty() {
+            default_patterns()
+        } else {
+            self.text_patterns.clone()
+        }
+    }
+
+    /// Returns the severity overrides matching the `deny-new` setting.
+    #[must_use]
+    pub fn deny_new(&self) -> DenyNew {
+        DenyNew::new(self.deny_new.clone())
+    }
+
+    /// Compares the outcome of a run with this configuration
+    /// against the outcome with an `old` configuration.
+    ///
+    /// `lints` must be the unfiltered findings of the run.
+    #[must_use]
+    pub fn preview(&self, old: &Self, lints: &[Lint]) -> ConfigPreview {
+        let old_lints = old.lint_filter().filter(lints.to_vec());
+        let new_lints = self.lint_filter().filter(lints.to_vec());
+        ConfigPreview {
+            added: new_lints
+                .iter()
+                .filter(|l| !old_lints.contains(l))
+                .cloned()
+                .collect(),
+            removed: old_lints
+                .iter()
+                .filter(|l| !new_lints.contains(l))
+                .cloned()
+                .collect(),
+            old_fails: old
+                .fail_on()
+                .should_fail(&old.deny_new().apply(old_lints.clone())),
+            new_fails: self
+                .fail_on()
+                .should_fail(&self.deny_new().apply(new_lints.clone())),
+        }
+    }
+}
+
+/// How the findings and the exit status of a run
+/// differ between two configurations.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConfigPreview {
+    /// Lints that are only reported with the new configuration
+    pub added: Vec<Lint>,
+    /// Lints that are only reported with the old configuration
+    pub removed: Vec<Lint>,
+    /// Whether the run fails with the old configuration
+    pub old_fails: bool,
+    /// Whether the run fails with the new configuration
+    pub new_fails: bool,
+}
+
+impl FromStr for ScoutConfig {
+    type Err = crate::error::Error;
+
+    /// This function will parse a `ScoutConfig` from the contents of a `scout.toml` file.
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let config: Self = toml::from_str(contents)?;
+        for rule in &config.path_rules {
+            rule.validate()?;
+        }
+        config.path_filter().validate()?;
+        TextPatterns::new(config.text_patterns.clone())?;
+        LicenseHeaders::new(config.license_headers.clone())?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backend, DiffLimits, ScoutConfig};
+    use crate::linter::coverage::CoverageTool;
+    use crate::linter::{Code, Level, Lint, Message};
+    use crate::policy::FailOn;
+
+    fn lint(code: &str, level: Level) -> Lint {
+        Lint {
+            package_id: "cargo-scout".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "this is a test lint".to_string(),
+                code: Some(Code {
+                    code: code.to_string(),
+                    explanation: None,
+                }),
+                level: Some(level),
+                children: Vec::new(),
+                spans: Vec::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_empty_config() {
+        let config = "".parse::<ScoutConfig>().unwrap();
+        assert_eq!(ScoutConfig::default(), config);
+    }
+
+    #[test]
+    fn test_lints_config() {
+        let config: ScoutConfig = r#"
+            ignore-lints = ["clippy::module_name_repetitions"]
+            only-lints = ["clippy::needless_pass_by_value", "clippy::doc_markdown"]
+            fail-on = "error"
+            "#
+        .parse()
+        .unwrap();
+        assert_eq!(vec!["clippy::module_name_repetitions"], config.ignore_lints);
+        assert_eq!(
+            vec!["clippy::needless_pass_by_value", "clippy::doc_markdown"],
+            config.only_lints
+        );
+        assert_eq!(Some(FailOn::Error), config.fail_on);
+    }
+
+    #[test]
+    fn test_profile_config() {
+        let config: ScoutConfig = r#"
+            profile = "security"
+            backends = ["audit", "geiger", "unsafe", "text-patterns", "typos", "license-headers", "coverage", "doc"]
+
+            [[text-patterns]]
+            name = "unwrap"
+            pattern = '\.unwrap\(\)'
+            message = "unwraps must be justified"
+
+            [[license-headers]]
+            paths = ["src/**"]
+            header = "// MIT"
+
+            [coverage]
+            tool = "tarpaulin"
+            level = "error"
+            "#
+        .parse()
+        .unwrap();
+        assert!(config
+            .clippy_lints()
+            .contains(&"clippy::unwrap_used".to_string()));
+        assert_eq!(
+            vec![
+                Backend::Audit,
+                Backend::Geiger,
+                Backend::Unsafe,
+                Backend::TextPatterns,
+                Backend::Typos,
+                Backend::LicenseHeaders,
+                Backend::Coverage,
+                Backend::Doc
+            ],
+            config.backends
+        );
+        assert!(ScoutConfig::default().clippy_lints().is_empty());
+        assert_eq!("pattern::unwrap", config.text_patterns()[0].code());
+        assert_eq!(CoverageTool::Tarpaulin, config.coverage.tool);
+        assert_eq!(8, config.backend_linters().unwrap().len());
+        assert!(r#"profile = "paranoid""#.parse::<ScoutConfig>().is_err());
+        assert!(r#"
+            [[text-patterns]]
+            name = "broken"
+            pattern = "("
+            message = ""
+            "#
+        .parse::<ScoutConfig>()
+        .is_err());
+        assert!(r#"
+            [[license-headers]]
+            paths = ["[src"]
+            header = "// MIT"
+            "#
+        .parse::<ScoutConfig>()
+        .is_err());
+    }
+
+    #[test]
+    fn test_path_rules_config() {
+        let config: ScoutConfig = r#"
+            [[path-rules]]
+            paths = ["src/generated/**", "build/*.rs"]
+            ignore-lints = ["clippy::doc_markdown"]
+            "#
+        .parse()
+        .unwrap();
+        assert_eq!(1, config.path_rules.len());
+        assert_eq!(
+            vec!["src/generated/**", "build/*.rs"],
+            config.path_rules[0].paths
+        );
+        assert_eq!(
+            vec!["clippy::doc_markdown"],
+            config.path_rules[0].ignore_lints
+        );
+        assert!(r#"
+            [[path-rules]]
+            paths = ["src/["]
+            "#
+        .parse::<ScoutConfig>()
+        .is_err());
+    }
+
+    #[test]
+    fn test_path_filter_config() {
+        let config: ScoutConfig = r#"
+            include = ["src/**"]
+            exclude = ["src/generated/**"]
+            "#
+        .parse()
+        .unwrap();
+        let filter = config.path_filter();
+        assert!(filter.is_included("src/lib.rs"));
+        assert!(!filter.is_included("src/generated/foo.rs"));
+        assert!(r#"exclude = ["src/["]"#.parse::<ScoutConfig>().is_err());
+    }
+
+    #[test]
+    fn test_diff_limits_config() {
+        let config: ScoutConfig = "max-changed-lines = 1000".parse().unwrap();
+        assert_eq!(
+            DiffLimits {
+                max_files: None,
+                max_lines: Some(1000),
+            },
+            config.diff_limits()
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!("ignore-lints = 3".parse::<ScoutConfig>().is_err());
+    }
+
+    #[test]
+    fn test_preview() {
+        let lints = vec![
+            lint("clippy::doc_markdown", Level::Warning),
+            lint("clippy::module_name_repetitions", Level::Warning),
+        ];
+        let old: ScoutConfig = r#"
+            ignore-lints = ["clippy::doc_markdown", "clippy::module_name_repetitions"]
+            "#
+        .parse()
+        .unwrap();
+        let new: ScoutConfig = r#"
+            ignore-lints = ["clippy::module_name_repetitions"]
+            fail-on = "error"
+            "#
+        .parse()
+        .unwrap();
+
+        let preview = new.preview(&old, &lints);
+        assert_eq!(
+            vec![lint("clippy::doc_markdown", Level::Warning)],
+            preview.added
+        );
+        assert!(preview.removed.is_empty());
+        assert!(!preview.old_fails);
+        assert!(!preview.new_fails);
+
+        let preview = old.preview(&new, &lints);
+        assert!(preview.added.is_empty());
+        assert_eq!(
+            vec![lint("clippy::doc_markdown", Level::Warning)],
+            preview.removed
+        );
+
+        let preview = ScoutConfig::default().preview(&new, &lints);
+        assert!(!preview.old_fails);
+        assert!(preview.new_fails);
+    }
+}
@@ -1,5 +1,13 @@
+use std::fmt::Write as _;
 use thiserror::Error;
 
+/// The exit code of a run that completed, but found lints that fail it
+pub const EXIT_FINDINGS: i32 = 1;
+/// The exit code of a run that couldn't complete, because git, cargo or clippy failed
+pub const EXIT_TOOL_FAILURE: i32 = 2;
+/// The exit code of a run that was given invalid arguments or configuration
+pub const EXIT_USAGE: i32 = 3;
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("ScoutBuilder error")]
@@ -8,14 +16,247 @@ pub enum Error {
     CargoToml(#[from] cargo_toml::Error),
     #[error("Command error: {0}")]
     Command(String),
+    /// The arguments can't be used as given, such as a subcommand run in the wrong state
+    #[error("{0}")]
+    Usage(String),
+    #[error("Clippy failed:\n{stderr}")]
+    ClippyFailed { stderr: String },
+    #[error("Could not parse line {line} of the linter output: {source}")]
+    Parse {
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[error("The code doesn't compile:{}", compile_errors(.errors))]
+    Compilation { errors: Vec<crate::linter::Lint> },
+    #[error("Your diff introduces compile errors:{}", compile_errors(.errors))]
+    CompilationInDiff { errors: Vec<crate::linter::Lint> },
+    #[error("Could not run {name}, is it installed and in your PATH?")]
+    ToolMissing { name: String },
     #[error("Utf8 error: {0}")]
     Utf8(#[from] std::string::FromUtf8Error),
+    #[error("Toml error: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("Toml error: {0}")]
+    TomlSerialize(#[from] toml::ser::Error),
+    #[error("Pattern error: {0}")]
+    Pattern(#[from] glob::PatternError),
+    #[error("Regex error: {0}")]
+    Regex(#[from] regex::Error),
     #[error("Json error: {0}")]
     Json(#[from] serde_json::Error),
-    #[error("NotClean error")]
-    NotClean,
+    #[error("The changes introduce {count} findings")]
+    NotClean { count: usize },
+    #[error("The findings exceed the ratchet budget of {}", .0.join(", "))]
+    RatchetExceeded(Vec<String>),
+    #[error("The benchmarks {} regressed", .0.join(", "))]
+    BenchRegressions(Vec<String>),
     #[error("Io error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("No workspace member is named {0}")]
+    UnknownPackage(String),
+    #[error("There is no lint or error code named {0}")]
+    UnknownLintCode(String),
+    #[error("AllowFailures error: {0}")]
+    AllowFailures(String),
+    #[error("Publish error: {0}")]
+    Publish(String),
+    #[error("Hook error: {0}")]
+    Hook(String),
+    #[error("Another scout run holds the lock {0}")]
+    Locked(std::path::PathBuf),
+    #[error("The run timed out after {}s", .after.as_secs())]
+    Timeout { after: std::time::Duration },
+    #[error("The run was cancelled")]
+    Cancelled,
+    #[error("Could not fetch {refspec} from {remote}: {reason}")]
+    Fetch {
+        remote: String,
+        refspec: String,
+        reason: String,
+    },
     #[error("Git error: {0}")]
     Git(#[from] git2::Error),
+    #[error("Could not read the workspace: {0}")]
+    Metadata(#[from] cargo_metadata::Error),
+    #[error(
+        "Some prerequisites are missing:{}",
+        problems(.0)
+    )]
+    Preflight(Vec<crate::preflight::Problem>),
+}
+
+impl Error {
+    /// Wraps an error raised while spawning `name`,
+    /// telling a missing executable apart from other failures.
+    #[must_use]
+    pub fn spawn(name: &str, error: std::io::Error) -> Self {
+        if error.kind() == std::io::ErrorKind::NotFound {
+            Self::ToolMissing {
+                name: name.to_string(),
+            }
+        } else {
+            Self::Io(error)
+        }
+    }
+
+    /// The code the process exits with when a run ends with this error,
+    /// so scripts can tell findings from tool failures and usage errors.
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::NotClean { .. }
+            | Self::CompilationInDiff { .. }
+            | Self::RatchetExceeded(_)
+            | Self::BenchRegressions(_) => EXIT_FINDINGS,
+            Self::ScoutBuilder
+            | Self::CargoToml(_)
+            | Self::Toml(_)
+            | Self::Pattern(_)
+            | Self::Regex(_)
+            | Self::AllowFailures(_)
+            | Self::Hook(_)
+            | Self::UnknownPackage(_)
+            | Self::UnknownLintCode(_)
+            | Self::Usage(_) => EXIT_USAGE,
+            Self::Command(_)
+            | Self::ClippyFailed { .. }
+            | Self::Compilation { .. }
+            | Self::Parse { .. }
+            | Self::ToolMissing { .. }
+            | Self::Utf8(_)
+            | Self::Json(_)
+            | Self::TomlSerialize(_)
+            | Self::Io(_)
+            | Self::Publish(_)
+            | Self::Locked(_)
+            | Self::Git(_)
+            | Self::Metadata(_)
+            | Self::Fetch { .. }
+            | Self::Timeout { .. }
+            | Self::Cancelled
+            | Self::Preflight(_) => EXIT_TOOL_FAILURE,
+        }
+    }
+}
+
+// Lists the errors one per line, with their location
+fn compile_errors(errors: &[crate::linter::Lint]) -> String {
+    errors.iter().fold(String::new(), |mut list, error| {
+        let location = error
+            .message
+            .as_ref()
+            .and_then(primary_span)
+            .map_or_else(String::new, |s| {
+                format!("{}:{}: ", s.file_name, s.line_start)
+            });
+        let _ = write!(list, "\n  {}{}", location, crate::output::headline(error));
+        list
+    })
+}
+
+// Lists the missing prerequisites one per line
+fn problems(problems: &[crate::preflight::Problem]) -> String {
+    problems.iter().fold(String::new(), |mut list, problem| {
+        let _ = write!(list, "\n  - {problem}");
+        list
+    })
+}
+
+// The span rustc points at with `--> file:line:column`,
+// secondary spans such as similarly named items can come before it
+fn primary_span(message: &crate::linter::Message) -> Option<&crate::linter::Span> {
+    let position = message.rendered.lines().find_map(|line| {
+        let location = line.trim_start().strip_prefix("--> ")?;
+        let mut parts = location.rsplitn(3, ':');
+        let column = parts.next()?.parse::<u32>().ok()?;
+        let line = parts.next()?.parse::<u32>().ok()?;
+        Some((line, column))
+    });
+    position
+        .and_then(|(line, column)| {
+            message
+                .spans
+                .iter()
+                .find(|s| s.line_start == line && s.column_start == column)
+        })
+        .or_else(|| message.primary_spans().into_iter().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(EXIT_FINDINGS, Error::NotClean { count: 2 }.exit_code());
+        assert_eq!(
+            EXIT_TOOL_FAILURE,
+            Error::ClippyFailed {
+                stderr: String::new()
+            }
+            .exit_code()
+        );
+        assert_eq!(EXIT_USAGE, Error::ScoutBuilder.exit_code());
+        assert_eq!(
+            EXIT_USAGE,
+            Error::Usage("ratchet.toml already exists".to_string()).exit_code()
+        );
+    }
+
+    #[test]
+    fn test_compile_errors_display() {
+        let error = crate::linter::Lint {
+            package_id: "foo".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(crate::linter::Message {
+                rendered:
+                    "error[E0425]: cannot find value `x` in this scope\n --> src/lib.rs:3:5\n"
+                        .to_string(),
+                spans: vec![
+                    // A similarly named item
+                    crate::linter::Span {
+                        file_name: "src/lib.rs".to_string(),
+                        line_start: 1,
+                        line_end: 1,
+                        column_start: 1,
+                        column_end: 18,
+                        is_primary: true,
+                        ..crate::linter::Span::default()
+                    },
+                    crate::linter::Span {
+                        file_name: "src/lib.rs".to_string(),
+                        line_start: 3,
+                        line_end: 3,
+                        column_start: 5,
+                        column_end: 6,
+                        is_primary: true,
+                        ..crate::linter::Span::default()
+                    },
+                ],
+                ..crate::linter::Message::default()
+            }),
+        };
+        let error = Error::CompilationInDiff {
+            errors: vec![error],
+        };
+        assert_eq!(
+            "Your diff introduces compile errors:\n  src/lib.rs:3: cannot find value `x` in this scope",
+            error.to_string()
+        );
+        assert_eq!(EXIT_FINDINGS, error.exit_code());
+    }
+
+    #[test]
+    fn test_spawn() {
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let error = Error::spawn("cargo", missing);
+        assert!(matches!(error, Error::ToolMissing { ref name } if name == "cargo"));
+        assert_eq!(
+            "Could not run cargo, is it installed and in your PATH?",
+            error.to_string()
+        );
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(matches!(Error::spawn("cargo", denied), Error::Io(_)));
+    }
 }
@@ -0,0 +1,148 @@
+//! A C interface to the scout pipeline, for tools that can't depend on a Rust crate.
+//!
+//! Build it as a shared library with
+//! `cargo rustc -p cargo-scout-lib --features ffi --crate-type cdylib --release`,
+//! the matching declarations are in `include/cargo_scout.h`.
+use crate::config::rust::CargoConfig;
+use crate::config::Config;
+use crate::linter::clippy::Clippy;
+use crate::linter::Lint;
+use crate::output::json::JsonFormatter;
+use crate::output::{Formatter, RunMetadata};
+use crate::paths::{PathNormalizer, PathResolver};
+use crate::scout::Scout;
+use crate::vcs::git::{repo_root, Git};
+use serde_json::json;
+use std::any::Any;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::UnwindSafe;
+use std::path::{Path, PathBuf};
+
+/// Lints the project at `path`, and returns the findings introduced since `target_branch`.
+///
+/// The result is the document `--output json` writes,
+/// or `{"error": "..."}` if the run failed or panicked.
+/// It must be released with `scout_string_free`.
+///
+/// The run changes the current directory of the process while it lasts,
+/// so it must not run concurrently with code that relies on it.
+///
+/// # Safety
+/// `path` and `target_branch` must be null or valid nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn scout_run(
+    path: *const c_char,
+    target_branch: *const c_char,
+) -> *mut c_char {
+    let output = guarded(|| {
+        // SAFETY: the caller guarantees both pointers are null or valid C strings
+        let (path, target_branch) = unsafe { (to_str(path), to_str(target_branch)) };
+        match (path, target_branch) {
+            (Some(path), Some(target_branch)) => {
+                run_in(Path::new(path), target_branch).map_err(|e| e.to_string())
+            }
+            _ => Err("path and target_branch must be valid UTF-8 strings".to_string()),
+        }
+    });
+    // JSON strings escape nul characters, so this can't fail
+    CString::new(output).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// Releases a string returned by `scout_run`.
+///
+/// # Safety
+/// `s` must be null, or a string returned by `scout_run` that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn scout_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: the caller guarantees the string comes from `CString::into_raw`
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+// Runs `f`, returning its errors and panics as `{"error": "..."}`:
+// unwinding into the caller, which isn't Rust code, is undefined behavior
+fn guarded(f: impl FnOnce() -> Result<String, String> + UnwindSafe) -> String {
+    std::panic::catch_unwind(f)
+        .unwrap_or_else(|panic| Err(format!("scout panicked: {}", panic_message(&*panic))))
+        .unwrap_or_else(|e| json!({ "error": e }).to_string())
+}
+
+// The message given to `panic!`, which is a `&str` or a `String`
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause")
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    // SAFETY: the caller guarantees the pointer is a valid C string
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+fn run_in(path: &Path, target_branch: &str) -> Result<String, crate::error::Error> {
+    let previous_dir = std::env::current_dir()?;
+    std::env::set_current_dir(path)?;
+    // The current directory is restored before a panic goes on unwinding
+    let result = std::panic::catch_unwind(|| run(target_branch));
+    std::env::set_current_dir(previous_dir)?;
+    result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+fn run(target_branch: &str) -> Result<String, crate::error::Error> {
+    let current_dir = std::env::current_dir()?;
+    let config = CargoConfig::from_manifest_path(PathBuf::from("Cargo.toml"))?;
+    let resolver = PathResolver::new(config.members());
+    let normalizer = PathNormalizer::new(repo_root(&current_dir)?, config.workspace_root()?);
+    let mut scout = Scout::new(
+        Git::with_target(target_branch.to_string()),
+        config,
+        Clippy::default(),
+    );
+    scout.set_path_normalizer(normalizer);
+    let lints: Vec<Lint> = scout.run()?.into_iter().map(Lint::from).collect();
+    let mut metadata = RunMetadata::new(env!("CARGO_PKG_VERSION"));
+    metadata.set_target_branch(Some(target_branch.to_string()));
+    Ok(JsonFormatter::new(resolver).format(&lints, &metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn call(path: Option<&str>, target_branch: Option<&str>) -> Value {
+        let path = path.map(|p| CString::new(p).unwrap());
+        let target_branch = target_branch.map(|b| CString::new(b).unwrap());
+        let as_ptr = |s: &Option<CString>| s.as_ref().map_or(std::ptr::null(), |s| s.as_ptr());
+        unsafe {
+            let result = scout_run(as_ptr(&path), as_ptr(&target_branch));
+            let value = serde_json::from_str(CStr::from_ptr(result).to_str().unwrap()).unwrap();
+            scout_string_free(result);
+            value
+        }
+    }
+
+    #[test]
+    fn test_errors_are_reported_as_json() {
+        assert!(call(None, Some("master"))["error"].is_string());
+        // Other tests rely on the current directory, so it must not change
+        assert!(call(Some("does-not-exist"), Some("master"))["error"].is_string());
+        unsafe { scout_string_free(std::ptr::null_mut()) };
+    }
+
+    #[test]
+    fn test_panics_are_reported_as_json() {
+        let output: Value = serde_json::from_str(&guarded(|| panic!("boom"))).unwrap();
+        assert_eq!("scout panicked: boom", output["error"]);
+        let output: Value =
+            serde_json::from_str(&guarded(|| panic!("{} went wrong", "it"))).unwrap();
+        assert_eq!("scout panicked: it went wrong", output["error"]);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::linter::Lint;
+use crate::vcs::Section;
+use glob::Pattern;
+use serde::Deserialize;
+
+/// Filters lints by their code, after they have been intersected with the diff.
+///
+/// This allows teams to phase-in pedantic lints gradually.
+#[derive(Default, Debug, Clone)]
+pub struct LintFilter {
+    ignore_lints: Vec<String>,
+    only_lints: Vec<String>,
+    path_rules: Vec<PathRule>,
+}
+
+/// A reduced rule set, for files matching one of the `paths` globs.
+///
+/// This is useful for generated code, which can't be fully excluded
+/// (e.g. `unsafe` additions must still be reported) but shouldn't be held
+/// to the style lints.
+/// The rule set applies on top of the global `ignore-lints` and `only-lints`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PathRule {
+    /// Globs matched against the file the lint has been reported on
+    /// Example:
+    /// "src/generated/**"
+    pub paths: Vec<String>,
+    /// Lint codes that will never be reported for these paths
+    pub ignore_lints: Vec<String>,
+    /// If not empty, only these lint codes will be reported for these paths
+    pub only_lints: Vec<String>,
+}
+
+impl PathRule {
+    /// Checks that the globs can be parsed.
+    ///
+    /// # Errors
+    /// Returns an error if one of the globs is invalid.
+    pub fn validate(&self) -> Result<(), crate::error::Error> {
+        for p in &self.paths {
+            Pattern::new(p)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if the file matches one of the globs.
+    #[must_use]
+    pub fn matches(&self, file_name: &str) -> bool {
+        let file_name = file_name.replace('\\', "/");
+        self.paths
+            .iter()
+            .filter_map(|p| Pattern::new(p).ok())
+            .any(|p| p.matches(&file_name))
+    }
+}
+
+fn code_allowed(code: Option<&str>, ignore_lints: &[String], only_lints: &[String]) -> bool {
+    match code {
+        Some(code) => {
+            !ignore_lints.iter().any(|l| l == code)
+                && (only_lints.is_empty() || only_lints.iter().any(|l| l == code))
+        }
+        None => only_lints.is_empty(),
+    }
+}
+
+impl LintFilter {
+    /// Lints with one of these codes will be dropped.
+    pub fn set_ignore_lints(&mut self, ignore_lints: Vec<String>) -> &mut Self {
+        self.ignore_lints = ignore_lints;
+        self
+    }
+
+    /// If not empty, only lints with one of these codes will be kept.
+    pub fn set_only_lints(&mut self, only_lints: Vec<String>) -> &mut Self {
+        self.only_lints = only_lints;
+        self
+    }
+
+    /// Reduced rule sets for some paths.
+    pub fn set_path_rules(&mut self, path_rules: Vec<PathRule>) -> &mut Self {
+        self.path_rules = path_rules;
+        self
+    }
+
+    /// Returns true if the lint passes the filter.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::filter::LintFilter;
+    /// # use cargo_scout_lib::linter::Lint;
+    /// let lint = Lint {
+    ///     package_id: "cargo-scout".to_string(),
+    ///     src_path: None,
+    ///     reproducer: None,
+    ///     message: None,
+    /// };
+    /// let mut filter = LintFilter::default();
+    /// // A lint without a code is only dropped by an allowlist
+    /// assert!(filter.is_allowed(&lint));
+    /// filter.set_only_lints(vec!["clippy::doc_markdown".to_string()]);
+    /// assert!(!filter.is_allowed(&lint));
+    /// ```
+    #[must_use]
+    pub fn is_allowed(&self, lint: &Lint) -> bool {
+        let code = lint.code();
+        if !code_allowed(code, &self.ignore_lints, &self.only_lints) {
+            return false;
+        }
+        match lint.primary_span() {
+            Some(span) => self
+                .path_rules
+                .iter()
+                .filter(|r| r.matches(&span.file_name))
+                .all(|r| code_allowed(code, &r.ignore_lints, &r.only_lints)),
+            None => true,
+        }
+    }
+
+    #[must_use]
+    pub fn filter(&self, lints: Vec<Lint>) -> Vec<Lint> {
+        lints.into_iter().filter(|l| self.is_allowed(l)).collect()
+    }
+}
+
+/// Restricts the changes and the lints scout looks at to some paths.
+///
+/// Both the diff sections and the lint locations are matched,
+/// so excluded files never trigger failures, whatever the linter.
+#[derive(Default, Debug, Clone)]
+pub struct PathFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl PathFilter {
+    /// If not empty, only the files matching one of these globs will be kept.
+    pub fn set_include(&mut self, include: Vec<String>) -> &mut Self {
+        self.include = include;
+        self
+    }
+
+    /// Files matching one of these globs will be dropped.
+    pub fn set_exclude(&mut self, exclude: Vec<String>) -> &mut Self {
+        self.exclude = exclude;
+        self
+    }
+
+    /// Checks that the globs can be parsed.
+    ///
+    /// # Errors
+    /// Returns an error if one of the globs is invalid.
+    pub fn validate(&self) -> Result<(), crate::error::Error> {
+        for p in self.include.iter().chain(&self.exclude) {
+            Pattern::new(p)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if the file, relative to the repository root, passes the filter.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::filter::PathFilter;
+    /// let mut filter = PathFilter::default();
+    /// filter
+    ///     .set_include(vec!["src/**".to_string()])
+    ///     .set_exclude(vec!["src/generated/**".to_string()]);
+    /// assert!(filter.is_included("src/lib.rs"));
+    /// assert!(!filter.is_included("src/generated/bindings.rs"));
+    /// assert!(!filter.is_included("tests/fixtures.rs"));
+    /// ```
+    #[must_use]
+    pub fn is_included(&self, file_name: &str) -> bool {
+        let file_name = file_name.replace('\\', "/");
+        let matches = |globs: &[String]| {
+            globs
+                .iter()
+                .filter_map(|p| Pattern::new(p).ok())
+                .any(|p| p.matches(&file_name))
+        };
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
+    }
+
+    /// Drops the sections in files that don't pass the filter.
+    #[must_use]
+    pub fn filter_sections(&self, sections: Vec<Section>) -> Vec<Section> {
+        sections
+            .into_iter()
+            .filter(|s| self.is_included(&s.file_name))
+            .collect()
+    }
+
+    /// Drops the lints reported on files that don't pass the filter.
+    ///
+    /// Lints without a location are kept.
+    #[must_use]
+    pub fn filter(&self, lints: Vec<Lint>) -> Vec<Lint> {
+        lints
+            .into_iter()
+            .filter(|l| {
+                l.primary_span()
+                    .is_none_or(|s| self.is_included(&s.file_name))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::span;
+    use crate::linter::{Code, Message};
+
+    fn lint(code: Option<&str>) -> Lint {
+        lint_in(code, "src/lib.rs")
+    }
+
+    fn lint_in(code: Option<&str>, file_name: &str) -> Lint {
+        Lint {
+            package_id: "cargo-scout".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "this is a test lint".to_string(),
+                code: code.map(|c| Code {
+                    code: c.to_string(),
+                    explanation: None,
+                }),
+                level: None,
+                children: Vec::new(),
+                spans: vec![span(file_name, 1, 1)],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_default_filter_keeps_everything() {
+        let lints = vec![lint(Some("clippy::doc_markdown")), lint(None)];
+        assert_eq!(lints.clone(), LintFilter::default().filter(lints));
+    }
+
+    #[test]
+    fn test_ignore_lints() {
+        let mut filter = LintFilter::default();
+        filter.set_ignore_lints(vec!["clippy::module_name_repetitions".to_string()]);
+        let lints = vec![
+            lint(Some("clippy::module_name_repetitions")),
+            lint(Some("clippy::doc_markdown")),
+            lint(None),
+        ];
+        assert_eq!(
+            vec![lint(Some("clippy::doc_markdown")), lint(None)],
+            filter.filter(lints)
+        );
+    }
+
+    #[test]
+    fn test_only_lints() {
+        let mut filter = LintFilter::default();
+        filter.set_only_lints(vec!["clippy::doc_markdown".to_string()]);
+        let lints = vec![
+            lint(Some("clippy::module_name_repetitions")),
+            lint(Some("clippy::doc_markdown")),
+            lint(None),
+        ];
+        assert_eq!(
+            vec![lint(Some("clippy::doc_markdown"))],
+            filter.filter(lints)
+        );
+    }
+
+    #[test]
+    fn test_ignore_wins_over_only() {
+        let mut filter = LintFilter::default();
+        filter
+            .set_only_lints(vec!["clippy::doc_markdown".to_string()])
+            .set_ignore_lints(vec!["clippy::doc_markdown".to_string()]);
+        assert!(filter
+            .filter(vec![lint(Some("clippy::doc_markdown"))])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_path_rules() {
+        let mut filter = LintFilter::default();
+        filter
+            .set_ignore_lints(vec!["clippy::cast_lossless".to_string()])
+            .set_path_rules(vec![PathRule {
+                paths: vec!["src/generated/**".to_string()],
+                ignore_lints: Vec::new(),
+                only_lints: vec!["clippy::undocumented_unsafe_blocks".to_string()],
+            }]);
+        let lints = vec![
+            lint_in(Some("clippy::doc_markdown"), "src/generated/foo.rs"),
+            lint_in(
+                Some("clippy::undocumented_unsafe_blocks"),
+                "src\\generated\\foo.rs",
+            ),
+            lint_in(Some("clippy::cast_lossless"), "src/generated/foo.rs"),
+            lint_in(Some("clippy::doc_markdown"), "src/lib.rs"),
+        ];
+        assert_eq!(
+            vec![
+                lint_in(
+                    Some("clippy::undocumented_unsafe_blocks"),
+                    "src\\generated\\foo.rs",
+                ),
+                lint_in(Some("clippy::doc_markdown"), "src/lib.rs"),
+            ],
+            filter.filter(lints)
+        );
+    }
+
+    #[test]
+    fn test_path_rule_validation() {
+        let rule = PathRule {
+            paths: vec!["src/[".to_string()],
+            ..PathRule::default()
+        };
+        assert!(rule.validate().is_err());
+        assert!(!rule.matches("src/["));
+    }
+
+    #[test]
+    fn test_path_filter() {
+        let mut filter = PathFilter::default();
+        filter.set_exclude(vec!["generated/**".to_string()]);
+        let lints = vec![
+            lint_in(None, "generated/foo.rs"),
+            lint_in(None, "src/lib.rs"),
+            Lint {
+                package_id: "cargo-scout".to_string(),
+                src_path: None,
+                reproducer: None,
+                message: None,
+            },
+        ];
+        assert_eq!(lints[1..].to_vec(), filter.filter(lints.clone()));
+        filter.set_include(vec!["src/**".to_string()]);
+        assert!(filter.validate().is_ok());
+        let sections = vec![
+            Section {
+                file_name: "src\\main.rs".to_string(),
+                line_start: 1,
+                line_end: 1,
+                added_lines: Vec::new(),
+            },
+            Section {
+                file_name: "README.md".to_string(),
+                line_start: 1,
+                line_end: 1,
+                added_lines: Vec::new(),
+            },
+        ];
+        assert_eq!(sections[..1].to_vec(), filter.filter_sections(sections));
+        filter.set_exclude(vec!["src/[".to_string()]);
+        assert!(filter.validate().is_err());
+    }
+}
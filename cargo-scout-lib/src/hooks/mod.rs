@@ -0,0 +1,217 @@
+use crate::error::Error;
+use git2::Repository;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+// The line that tells the hooks scout wrote from the ones it must not touch
+const HOOK_MARKER: &str = "# Installed by cargo-scout";
+
+/// The git hooks scout can be installed as
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum HookKind {
+    PreCommit,
+    PrePush,
+}
+
+impl FromStr for HookKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pre-commit" => Ok(HookKind::PreCommit),
+            "pre-push" => Ok(HookKind::PrePush),
+            _ => Err(format!(
+                "unknown hook {s}, expected one of pre-commit, pre-push"
+            )),
+        }
+    }
+}
+
+impl HookKind {
+    /// The name of the hook, as git expects it in the hooks directory
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            HookKind::PreCommit => "pre-commit",
+            HookKind::PrePush => "pre-push",
+        }
+    }
+}
+
+/// What the hook checks
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum HookMode {
+    /// Runs scout against the upstream branch
+    Full,
+    /// Only checks the formatting, which takes a fraction of a second
+    Fast,
+}
+
+impl FromStr for HookMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(HookMode::Full),
+            "fast" => Ok(HookMode::Fast),
+            _ => Err(format!("unknown hook mode {s}, expected one of full, fast")),
+        }
+    }
+}
+
+/// Returns the directory git runs the hooks of the repository at `repo_path` from,
+/// which is `core.hooksPath` if it is set.
+///
+/// # Errors
+/// Fails if `repo_path` isn't in a git repository.
+pub fn hooks_dir(repo_path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+    let repo = Repository::discover(repo_path)?;
+    let default = repo.path().join("hooks");
+    let Ok(hooks_path) = repo.config()?.get_path("core.hooksPath") else {
+        return Ok(default);
+    };
+    // A relative hooks path is relative to the root of the working tree
+    Ok(match repo.workdir() {
+        Some(workdir) if hooks_path.is_relative() => workdir.join(hooks_path),
+        _ => hooks_path,
+    })
+}
+
+/// Returns the script of the hook.
+#[must_use]
+pub fn script(kind: HookKind, mode: HookMode) -> String {
+    let mut script = format!(
+        "#!/bin/sh\n{}, remove it with `cargo scout uninstall-hook --hook {}`\n",
+        HOOK_MARKER,
+        kind.name()
+    );
+    match mode {
+        HookMode::Full => script.push_str(
+            "# Lints the changes that aren't on the upstream branch yet
+upstream=$(git rev-parse --abbrev-ref --symbolic-full-name '@{upstream}' 2>/dev/null)
+if [ -n \"$upstream\" ]; then
+    exec cargo scout --branch \"$upstream\"
+fi
+exec cargo scout
+",
+        ),
+        HookMode::Fast => script.push_str(
+            "# Only checks the formatting, run `cargo scout` for the lints
+exec cargo fmt --all -- --check
+",
+        ),
+    }
+    script
+}
+
+/// Writes the hook to `hooks_dir`, and returns its path.
+///
+/// A hook scout didn't install is only overwritten if `force` is true.
+///
+/// # Errors
+/// Fails if a hook scout didn't install is in the way, or if it can't be written.
+pub fn install(
+    hooks_dir: impl AsRef<Path>,
+    kind: HookKind,
+    mode: HookMode,
+    force: bool,
+) -> Result<PathBuf, Error> {
+    let path = hooks_dir.as_ref().join(kind.name());
+    if !force && path.exists() && !is_scout_hook(&path)? {
+        return Err(Error::Hook(format!(
+            "{} already exists, run with --force to overwrite it",
+            path.display()
+        )));
+    }
+    std::fs::create_dir_all(hooks_dir.as_ref())?;
+    std::fs::write(&path, script(kind, mode))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(path)
+}
+
+/// Removes the hook from `hooks_dir`.
+///
+/// Returns false if there was no such hook,
+/// and fails if the hook wasn't installed by scout.
+///
+/// # Errors
+/// Returns `Error::Hook` if scout didn't install the hook.
+pub fn uninstall(hooks_dir: impl AsRef<Path>, kind: HookKind) -> Result<bool, Error> {
+    let path = hooks_dir.as_ref().join(kind.name());
+    if !path.exists() {
+        return Ok(false);
+    }
+    if !is_scout_hook(&path)? {
+        return Err(Error::Hook(format!(
+            "{} wasn't installed by cargo-scout, remove it yourself",
+            path.display()
+        )));
+    }
+    std::fs::remove_file(&path)?;
+    Ok(true)
+}
+
+fn is_scout_hook(path: &Path) -> Result<bool, Error> {
+    let content = std::fs::read(path)?;
+    Ok(String::from_utf8_lossy(&content)
+        .lines()
+        .any(|line| line.starts_with(HOOK_MARKER)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_install_and_uninstall() -> Result<(), Error> {
+        let dir = TempDir::new()?;
+        let path = install(dir.path(), HookKind::PrePush, HookMode::Full, false)?;
+        assert_eq!(dir.path().join("pre-push"), path);
+        let content = std::fs::read_to_string(&path)?;
+        assert!(content.starts_with("#!/bin/sh\n# Installed by cargo-scout"));
+        assert!(content.contains("exec cargo scout --branch \"$upstream\""));
+
+        // Reinstalling our own hook doesn't need --force
+        install(dir.path(), HookKind::PrePush, HookMode::Fast, false)?;
+        assert!(std::fs::read_to_string(&path)?.contains("cargo fmt --all -- --check"));
+
+        assert!(uninstall(dir.path(), HookKind::PrePush)?);
+        assert!(!path.exists());
+        assert!(!uninstall(dir.path(), HookKind::PrePush)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_foreign_hooks_are_kept() -> Result<(), Error> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("pre-commit");
+        std::fs::write(&path, "#!/bin/sh\nmake lint\n")?;
+
+        assert!(install(dir.path(), HookKind::PreCommit, HookMode::Full, false).is_err());
+        assert!(uninstall(dir.path(), HookKind::PreCommit).is_err());
+        assert_eq!("#!/bin/sh\nmake lint\n", std::fs::read_to_string(&path)?);
+
+        install(dir.path(), HookKind::PreCommit, HookMode::Full, true)?;
+        assert!(is_scout_hook(&path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hooks_dir() -> Result<(), Error> {
+        let dir = TempDir::new()?;
+        let repo = Repository::init(dir.path())?;
+        assert_eq!(repo.path().join("hooks"), hooks_dir(dir.path())?);
+
+        repo.config()?.set_str("core.hooksPath", ".githooks")?;
+        assert_eq!(
+            repo.workdir().unwrap().join(".githooks"),
+            hooks_dir(dir.path())?
+        );
+        Ok(())
+    }
+}
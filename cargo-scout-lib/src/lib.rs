@@ -1,7 +1,26 @@
+pub mod baseline;
+pub mod bench;
+pub mod cache;
+pub mod cancel;
 pub mod config;
 pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filter;
+pub mod hooks;
 pub mod linter;
+pub mod lock;
+pub mod output;
+pub mod paths;
+pub mod policy;
+pub mod preflight;
+pub mod progress;
+pub mod project;
+pub mod publish;
+pub mod schema;
 pub mod scout;
+pub mod stats;
 pub mod vcs;
 
 pub use error::Error;
+pub use scout::{Scout, TrackedLint};
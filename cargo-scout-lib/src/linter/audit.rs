@@ -0,0 +1,282 @@
+use crate::linter::clippy::workspace_root;
+use crate::linter::{lockfile_span, package_id, Code, Level, Lint, Linter, Message};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// The code of the findings about yanked crates, which have no advisory
+pub const YANKED_CODE: &str = "yanked";
+
+/// Reports the dependencies of `Cargo.lock` that have security advisories,
+/// as `cargo audit` finds them in the `RustSec` advisory database.
+///
+/// The findings are on the lines of the package in `Cargo.lock`,
+/// so a diff adding or upgrading to a vulnerable version is responsible for them.
+#[derive(Default)]
+pub struct CargoAudit;
+
+impl Linter for CargoAudit {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        log::debug!(
+            "[CargoAudit] - auditing the dependencies of directory {}",
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        let lockfile = workspace_root(&working_dir)?.join("Cargo.lock");
+        if !lockfile.exists() {
+            log::debug!("[CargoAudit] - there is no Cargo.lock to audit");
+            return Ok(Vec::new());
+        }
+        let output = Command::new("cargo")
+            .current_dir(&working_dir)
+            .args(["audit", "--json", "--file"])
+            .arg(&lockfile)
+            .output()
+            .map_err(|e| crate::error::Error::spawn("cargo", e))?;
+        checked(&output, &lockfile, &package_id(&working_dir))
+    }
+}
+
+// The findings of cargo audit's report, or the error it failed with.
+// It exits with 1 when it finds vulnerabilities
+fn checked(
+    output: &Output,
+    lockfile: &Path,
+    package_id: &str,
+) -> Result<Vec<Lint>, crate::error::Error> {
+    match serde_json::from_slice::<Report>(&output.stdout) {
+        Ok(report) => Ok(lints(&report, lockfile, package_id)),
+        Err(_) => Err(subcommand_error("audit", output)),
+    }
+}
+
+/// The error of a cargo subcommand that didn't report anything,
+/// telling a missing `cargo-<name>` apart from other failures.
+pub(crate) fn subcommand_error(name: &str, output: &Output) -> crate::error::Error {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("no such command") || stderr.contains("no such subcommand") {
+        crate::error::Error::ToolMissing {
+            name: format!("cargo-{name}"),
+        }
+    } else {
+        crate::error::Error::Command(format!("cargo {name} failed:\n{stderr}"))
+    }
+}
+
+#[derive(Deserialize)]
+struct Report {
+    vulnerabilities: Vulnerabilities,
+    // Keyed by kind: unmaintained, unsound, yanked...
+    #[serde(default)]
+    warnings: BTreeMap<String, Vec<Warning>>,
+}
+
+#[derive(Deserialize)]
+struct Vulnerabilities {
+    list: Vec<Vulnerability>,
+}
+
+#[derive(Deserialize)]
+struct Vulnerability {
+    advisory: Advisory,
+    package: Package,
+    #[serde(default)]
+    versions: Option<Versions>,
+}
+
+#[derive(Deserialize)]
+struct Warning {
+    kind: String,
+    package: Package,
+    // Yanked crates have none
+    #[serde(default)]
+    advisory: Option<Advisory>,
+}
+
+#[derive(Deserialize)]
+struct Advisory {
+    id: String,
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct Versions {
+    #[serde(default)]
+    patched: Vec<String>,
+}
+
+fn lints(report: &Report, lockfile: &Path, package_id: &str) -> Vec<Lint> {
+    let vulnerabilities = report.vulnerabilities.list.iter().map(|v| {
+        let patched = v.versions.as_ref().map_or(&[][..], |v| &v.patched[..]);
+        let note = if patched.is_empty() {
+            format!(
+                "{} {} is affected, and no version is patched",
+                v.package.name, v.package.version
+            )
+        } else {
+            format!(
+                "{} {} is affected, upgrade to {}",
+                v.package.name,
+                v.package.version,
+                patched.join(" or ")
+            )
+        };
+        lint(
+            lockfile,
+            package_id,
+            &v.package,
+            &v.advisory.id,
+            Level::Error,
+            &v.advisory.title,
+            &note,
+        )
+    });
+    let warnings = report.warnings.values().flatten().map(|w| {
+        let (code, title) = match &w.advisory {
+            Some(advisory) => (advisory.id.as_str(), advisory.title.clone()),
+            None => (YANKED_CODE, "the crate version was yanked".to_string()),
+        };
+        lint(
+            lockfile,
+            package_id,
+            &w.package,
+            code,
+            Level::Warning,
+            &title,
+            &format!("{} {} is {}", w.package.name, w.package.version, w.kind),
+        )
+    });
+    vulnerabilities.chain(warnings).collect()
+}
+
+fn lint(
+    lockfile: &Path,
+    package_id: &str,
+    package: &Package,
+    code: &str,
+    level: Level,
+    title: &str,
+    note: &str,
+) -> Lint {
+    let spans: Vec<_> = lockfile_span(lockfile, &package.name, &package.version)
+        .into_iter()
+        .collect();
+    let location = spans
+        .first()
+        .map(|s| format!("  --> Cargo.lock:{}:{}\n", s.line_start, s.column_start))
+        .unwrap_or_default();
+    Lint {
+        package_id: package_id.to_string(),
+        src_path: None,
+        reproducer: Some("cargo audit".to_string()),
+        message: Some(Message {
+            message: title.to_string(),
+            rendered: format!(
+                "{}[{}]: {}\n{}  = note: {}\n",
+                crate::output::level_name(Some(level)),
+                code,
+                title,
+                location,
+                note
+            ),
+            code: Some(Code {
+                code: code.to_string(),
+                explanation: None,
+            }),
+            level: Some(level),
+            spans,
+            children: Vec::new(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{
+        "database": {"advisory-count": 600},
+        "lockfile": {"dependency-count": 3},
+        "vulnerabilities": {
+            "found": true,
+            "count": 1,
+            "list": [{
+                "advisory": {
+                    "id": "RUSTSEC-2020-0071",
+                    "package": "time",
+                    "title": "Potential segfault in the time crate",
+                    "url": "https://github.com/time-rs/time/issues/293"
+                },
+                "versions": {"patched": [">=0.2.23"], "unaffected": ["=0.2.0"]},
+                "package": {"name": "time", "version": "0.1.45", "source": "registry+https://github.com/rust-lang/crates.io-index"}
+            }]
+        },
+        "warnings": {
+            "yanked": [{
+                "kind": "yanked",
+                "package": {"name": "foo", "version": "1.0.0"},
+                "advisory": null
+            }]
+        }
+    }"#;
+
+    const LOCKFILE: &str = r#"version = 3
+
+[[package]]
+name = "foo"
+version = "1.0.0"
+
+[[package]]
+name = "time"
+version = "0.1.44"
+
+[[package]]
+name = "time"
+version = "0.1.45"
+"#;
+
+    #[test]
+    fn test_lints() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let lockfile = dir.path().join("Cargo.lock");
+        std::fs::write(&lockfile, LOCKFILE)?;
+        let report: Report = serde_json::from_str(REPORT)?;
+        let lints = lints(&report, &lockfile, "bar");
+        assert_eq!(2, lints.len());
+
+        let vulnerability = &lints[0];
+        assert_eq!(Some("RUSTSEC-2020-0071"), vulnerability.code());
+        assert_eq!(Some(Level::Error), vulnerability.level());
+        let span = vulnerability.primary_span().unwrap();
+        assert_eq!((12, 13), (span.line_start, span.line_end));
+        assert_eq!(
+            "error[RUSTSEC-2020-0071]: Potential segfault in the time crate\n  --> Cargo.lock:12:1\n  = note: time 0.1.45 is affected, upgrade to >=0.2.23\n",
+            vulnerability.message.as_ref().unwrap().rendered
+        );
+
+        let yanked = &lints[1];
+        assert_eq!(Some(YANKED_CODE), yanked.code());
+        assert_eq!(Some(Level::Warning), yanked.level());
+        assert_eq!(4, yanked.primary_span().unwrap().line_start);
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_subcommand() {
+        let output = Output {
+            status: std::process::ExitStatus::default(),
+            stdout: Vec::new(),
+            stderr: b"error: no such command: `audit`".to_vec(),
+        };
+        assert!(matches!(
+            subcommand_error("audit", &output),
+            crate::error::Error::ToolMissing { ref name } if name == "cargo-audit"
+        ));
+    }
+}
@@ -1,30 +1,202 @@
-use crate::linter::{Lint, Linter};
-use std::io::{self, Write};
+use crate::cancel::CancellationToken;
+use crate::linter::wrapper::{self, CAPTURE_DIR_ENV};
+#[cfg(feature = "async")]
+use crate::linter::AsyncLinter;
+use crate::linter::{Level, Lint, Linter};
+use crate::lock;
+use crate::project::Project;
+use crate::vcs::Section;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::path::Path;
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::Duration;
 
-#[derive(Default)]
+const MAX_LOCK_RETRIES: usize = 3;
+// How often a running cargo is checked for cancellation
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const LOCK_RETRY_DELAY: Duration = Duration::from_secs(5);
+// `clippy-driver` reads its lint flags from `CLIPPY_ARGS`, with this separator
+const CLIPPY_ARGS_SEPARATOR: &str = "__CLIPPY_HACKERY__";
+
+/// Targets cargo lints besides the libraries and binaries it picks by default
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TargetKind {
+    All,
+    Bins,
+    Tests,
+    Examples,
+    Benches,
+}
+
+impl TargetKind {
+    fn flag(self) -> &'static str {
+        match self {
+            TargetKind::All => "--all-targets",
+            TargetKind::Bins => "--bins",
+            TargetKind::Tests => "--tests",
+            TargetKind::Examples => "--examples",
+            TargetKind::Benches => "--benches",
+        }
+    }
+}
+
+// Each flag maps to a cargo option
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Default, Clone)]
 pub struct Clippy {
     verbose: bool,
     no_default_features: bool,
     all_features: bool,
     features: Option<String>,
+    target: Option<String>,
+    target_kinds: Vec<TargetKind>,
+    // Warned about besides clippy::pedantic
+    lints: Vec<String>,
     preview: bool,
+    wrapper: Option<PathBuf>,
+    target_dir: Option<PathBuf>,
+    // Kept as the `+toolchain` argument cargo takes
+    toolchain: Option<String>,
+    cancellation: Option<CancellationToken>,
+    clean_on_failure: bool,
+    // Given to cargo with -p, when the members of a workspace are linted at once
+    packages: Vec<String>,
 }
 
 impl Linter for Clippy {
     fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
-        println!(
+        log::debug!(
+            "[Clippy] - getting lints for directory {}",
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        let lints = if self.wrapper.is_none() {
+            self.clippy(&working_dir)?
+        } else {
+            self.clear_captured(&working_dir)?;
+            let cargo_output = self.clippy(&working_dir)?;
+            self.captured_lints(&working_dir, cargo_output)?
+        };
+        let toolchain = active_toolchain_command(&working_dir)
+            .output()
+            .ok()
+            .and_then(toolchain_name);
+        Ok(self.with_reproducers(&working_dir, toolchain.as_deref(), lints))
+    }
+
+    // Cargo runs once per workspace, from its root, with a -p flag for each member
+    fn lints_for_members(
+        &self,
+        members: &[PathBuf],
+        _sections: Option<&[Section]>,
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut lints = Vec::new();
+        for (dir, packages) in workspaces(members) {
+            let mut clippy = self.clone();
+            clippy.packages = packages;
+            lints.extend(clippy.lints(dir)?);
+        }
+        Ok(lints)
+    }
+
+    fn configuration(&self) -> String {
+        let mut configuration = self.command_parameters().join(" ");
+        if self.wrapper.is_some() {
+            configuration.push_str(" (wrapper)");
+            for lint in &self.lints {
+                let _ = write!(configuration, " -W {lint}");
+            }
+        }
+        configuration
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncLinter for Clippy {
+    async fn lints_async(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        log::debug!(
             "[Clippy] - getting lints for directory {}",
             &working_dir.to_str().unwrap_or("<no directory>")
         );
-        self.clippy(working_dir)
-            .map(|clippy_output| lints(clippy_output.as_ref()))
+        let lints = if self.wrapper.is_none() {
+            self.clippy_async(&working_dir).await?
+        } else {
+            self.clear_captured(&working_dir)?;
+            let cargo_output = self.clippy_async(&working_dir).await?;
+            self.captured_lints(&working_dir, cargo_output)?
+        };
+        let toolchain = tokio::process::Command::from(active_toolchain_command(&working_dir))
+            .output()
+            .await
+            .ok()
+            .and_then(toolchain_name);
+        Ok(self.with_reproducers(&working_dir, toolchain.as_deref(), lints))
+    }
+
+    async fn lints_for_members_async(
+        &self,
+        members: &[PathBuf],
+        _sections: Option<&[Section]>,
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut lints = Vec::new();
+        for (dir, packages) in workspaces(members) {
+            let mut clippy = self.clone();
+            clippy.packages = packages;
+            lints.extend(clippy.lints_async(dir).await?);
+        }
+        Ok(lints)
     }
 }
 
 impl Clippy {
+    // With the wrapper, scout runs as rustc and captures what clippy reports
+    fn clear_captured(&self, working_dir: &Path) -> Result<(), crate::error::Error> {
+        let capture_dir = self.target_dir(working_dir).join("scout-diagnostics");
+        match std::fs::remove_dir_all(&capture_dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        std::fs::create_dir_all(&capture_dir)?;
+        Ok(())
+    }
+
+    // Reads back what the wrapper captured, completed by what cargo printed
+    fn captured_lints(
+        &self,
+        working_dir: &Path,
+        cargo_output: Vec<Lint>,
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let capture_dir = self.target_dir(working_dir).join("scout-diagnostics");
+        let mut captured = wrapper::captured_lints(&capture_dir)?;
+        // Crates that are up to date aren't compiled again,
+        // cargo replays their diagnostics instead
+        let compiled: HashSet<String> = captured.iter().map(|l| l.package_id.clone()).collect();
+        captured.extend(
+            cargo_output
+                .into_iter()
+                .filter(|l| !compiled.iter().any(|id| same_package(id, &l.package_id))),
+        );
+        Ok(captured)
+    }
+
+    fn with_reproducers(
+        &self,
+        working_dir: &Path,
+        toolchain: Option<&str>,
+        lints: Vec<Lint>,
+    ) -> Vec<Lint> {
+        lints
+            .into_iter()
+            .map(|lint| Lint {
+                reproducer: Some(self.reproducer(working_dir, toolchain, &lint.package_id)),
+                ..lint
+            })
+            .collect()
+    }
+
     pub fn set_verbose(&mut self, verbose: bool) -> &mut Self {
         self.verbose = verbose;
         self
@@ -45,13 +217,117 @@ impl Clippy {
         self
     }
 
+    /// Lint for `target` (e.g. `thumbv7em-none-eabihf`) instead of the host
+    pub fn set_target(&mut self, target: Option<String>) -> &mut Self {
+        self.target = target;
+        self
+    }
+
+    /// Also lint these targets, so lints in tests, examples or benches aren't missed
+    pub fn set_target_kinds(&mut self, target_kinds: Vec<TargetKind>) -> &mut Self {
+        self.target_kinds = target_kinds;
+        self
+    }
+
+    /// Also warn about these lints, e.g. the `restriction` ones clippy allows by default
+    pub fn set_lints(&mut self, lints: Vec<String>) -> &mut Self {
+        self.lints = lints;
+        self
+    }
+
     pub fn set_preview(&mut self, preview: bool) -> &mut Self {
         self.preview = preview;
         self
     }
 
+    /// Capture the diagnostics of each crate with a `RUSTC_WORKSPACE_WRAPPER`,
+    /// instead of parsing the output of `cargo clippy`.
+    ///
+    /// `wrapper` is an executable that calls `wrapper::wrap` when
+    /// `wrapper::is_wrapper_invocation` returns true, such as cargo-scout itself.
+    pub fn set_wrapper(&mut self, wrapper: Option<PathBuf>) -> &mut Self {
+        self.wrapper = wrapper;
+        self
+    }
+
+    /// Build in `target_dir` instead of the project's target directory,
+    /// so scout doesn't invalidate the user's incremental build cache.
+    pub fn set_target_dir(&mut self, target_dir: Option<PathBuf>) -> &mut Self {
+        self.target_dir = target_dir;
+        self
+    }
+
+    /// Run clippy from `toolchain` instead of the toolchain rustup picks,
+    /// `--preview` always runs nightly.
+    pub fn set_toolchain(&mut self, toolchain: Option<String>) -> &mut Self {
+        self.toolchain = toolchain.map(|t| format!("+{t}"));
+        self
+    }
+
+    /// Clear the incremental cache and run again when it's corrupted
+    pub fn set_clean_on_failure(&mut self, clean_on_failure: bool) -> &mut Self {
+        self.clean_on_failure = clean_on_failure;
+        self
+    }
+
+    /// Kill cargo when the token cancels the run
+    pub fn set_cancellation(&mut self, cancellation: Option<CancellationToken>) -> &mut Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    // The target directory cargo uses for a run in `path`
+    fn target_dir(&self, path: &Path) -> PathBuf {
+        self.target_dir
+            .clone()
+            .unwrap_or_else(|| lock::target_dir(path))
+    }
+
+    // Returns the command that runs clippy on a single package, as a user would type it
+    fn reproducer(&self, working_dir: &Path, toolchain: Option<&str>, package_id: &str) -> String {
+        let spec = package_spec(package_id);
+        let mut params: Vec<&str> = self
+            .parameters(false)
+            .into_iter()
+            .filter(|p| !["--message-format", "json", "--verbose"].contains(p))
+            .collect();
+        let separator = params
+            .iter()
+            .position(|p| *p == "--")
+            .unwrap_or(params.len());
+        params.splice(separator..separator, ["-p", spec.as_str()]);
+        let mut command = format!("cd {} && ", shell_quote(&working_dir.to_string_lossy()));
+        if let Some(target_dir) = &self.target_dir {
+            let _ = write!(
+                command,
+                "CARGO_TARGET_DIR={} ",
+                shell_quote(&target_dir.to_string_lossy())
+            );
+        }
+        command.push_str("cargo");
+        if let Some(toolchain) = toolchain.filter(|_| !self.preview && self.toolchain.is_none()) {
+            let _ = write!(command, " +{toolchain}");
+        }
+        for param in params {
+            command.push(' ');
+            command.push_str(&shell_quote(param));
+        }
+        command
+    }
+
     fn command_parameters(&self) -> Vec<&str> {
-        let mut params = if self.preview {
+        self.parameters(self.wrapper.is_some())
+    }
+
+    // `check` runs cargo check, for the wrapper to lint with clippy-driver
+    fn parameters(&self, check: bool) -> Vec<&str> {
+        let mut params = match self.toolchain.as_deref().filter(|_| !self.preview) {
+            Some(toolchain) => vec![toolchain],
+            None => vec![],
+        };
+        params.extend(if check {
+            vec!["check", "--message-format", "json"]
+        } else if self.preview {
             vec![
                 "+nightly",
                 "clippy-preview",
@@ -62,7 +338,7 @@ impl Clippy {
             ]
         } else {
             vec!["clippy", "--message-format", "json"]
-        };
+        });
         if self.verbose {
             params.push("--verbose");
         }
@@ -75,10 +351,32 @@ impl Clippy {
         if let Some(features) = &self.features {
             params.append(&mut vec!["--features", features]);
         }
-        params.append(&mut vec!["--", "-W", "clippy::pedantic"]);
+        if let Some(target) = &self.target {
+            params.append(&mut vec!["--target", target]);
+        }
+        params.extend(self.target_kinds.iter().map(|kind| kind.flag()));
+        if !check {
+            params.append(&mut vec!["--", "-W", "clippy::pedantic"]);
+            for lint in &self.lints {
+                params.append(&mut vec!["-W", lint]);
+            }
+        }
         params
     }
 
+    // The lint flags of `parameters`, as `clippy-driver` reads them from `CLIPPY_ARGS`
+    fn clippy_args(&self) -> String {
+        std::iter::once("clippy::pedantic")
+            .chain(self.lints.iter().map(String::as_str))
+            .fold(String::new(), |mut args, lint| {
+                let _ = write!(
+                    args,
+                    "-W{CLIPPY_ARGS_SEPARATOR}{lint}{CLIPPY_ARGS_SEPARATOR}"
+                );
+                args
+            })
+    }
+
     fn envs(&self) -> Vec<(&str, &str)> {
         let mut envs = vec![];
         if self.verbose {
@@ -87,66 +385,544 @@ impl Clippy {
         envs
     }
 
-    fn clippy(&self, path: impl AsRef<Path>) -> Result<String, crate::error::Error> {
-        let clippy_pedantic_output = Command::new("cargo")
-            .current_dir(path)
-            .args(self.command_parameters())
-            .envs(self.envs())
-            .output()
-            .expect("failed to run clippy pedantic");
+    // `verbose` asks cargo for the commands it runs, to find out why a run failed
+    fn command(&self, path: &Path, verbose: bool) -> Command {
+        let mut params = self.command_parameters();
+        if verbose && !self.verbose {
+            let options_end = params.iter().position(|p| *p == "--");
+            params.insert(options_end.unwrap_or(params.len()), "--verbose");
+        }
+        let options_end = params
+            .iter()
+            .position(|p| *p == "--")
+            .unwrap_or(params.len());
+        params.splice(
+            options_end..options_end,
+            self.packages.iter().flat_map(|p| ["-p", p.as_str()]),
+        );
+        let mut command = Command::new("cargo");
+        command.current_dir(path).args(params).envs(self.envs());
+        if let Some(target_dir) = &self.target_dir {
+            command.env("CARGO_TARGET_DIR", target_dir);
+        }
+        if let Some(wrapper) = &self.wrapper {
+            command
+                .env("RUSTC_WORKSPACE_WRAPPER", wrapper)
+                .env(
+                    CAPTURE_DIR_ENV,
+                    self.target_dir(path).join("scout-diagnostics"),
+                )
+                .env("CLIPPY_ARGS", self.clippy_args());
+        }
+        command
+    }
 
+    // Without -v, cargo's output is kept aside in case the run fails
+    fn run_log(&self, path: &Path) -> Result<Option<RunLog>, crate::error::Error> {
         if self.verbose {
-            println!(
-                "{}",
-                String::from_utf8(clippy_pedantic_output.stdout.clone())?
-            );
+            Ok(None)
+        } else {
+            RunLog::create(self.target_dir(path).join("scout-logs").join("clippy.log")).map(Some)
+        }
+    }
+
+    // Runs cargo, and parses the lints as cargo prints them
+    fn clippy(&self, path: impl AsRef<Path>) -> Result<Vec<Lint>, crate::error::Error> {
+        let path = path.as_ref();
+        let mut attempts = 0;
+        let mut verbose_retry = false;
+        loop {
+            let mut child = self
+                .command(path, verbose_retry)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| crate::error::Error::spawn("cargo", e))?;
+            // Read stderr on the side, so cargo never blocks on a full pipe
+            let mut stderr = child.stderr.take().expect("stderr is piped");
+            let stderr_reader = std::thread::spawn(move || {
+                let mut output = Vec::new();
+                stderr.read_to_end(&mut output).map(|_| output)
+            });
+            let mut run_log = self.run_log(path)?;
+            let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+            let verbose = self.verbose;
+            let stdout_reader = std::thread::spawn(move || {
+                lints(stdout, verbose, run_log.as_mut()).map(|lints| (lints, run_log))
+            });
+            let status = self.wait(&mut child)?;
+            let (lints, run_log) = stdout_reader
+                .join()
+                .expect("reading stdout doesn't panic")?;
+            let stderr = stderr_reader
+                .join()
+                .expect("reading stderr doesn't panic")?;
+            let output = CargoOutput {
+                success: status.success(),
+                lints,
+                stderr,
+                run_log,
+            };
+            match self.outcome(path, attempts, verbose_retry, output)? {
+                Attempt::Done(lints) => return Ok(lints),
+                Attempt::Retry(delay) => std::thread::sleep(delay),
+                Attempt::RetryVerbose => verbose_retry = true,
+            }
+            attempts += 1;
+        }
+    }
+
+    // Waits for cargo to exit, and kills it if the run is cancelled
+    fn wait(&self, child: &mut Child) -> Result<ExitStatus, crate::error::Error> {
+        // Without a token there's nothing to poll
+        let Some(cancellation) = &self.cancellation else {
+            return Ok(child.wait()?);
+        };
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+            if let Err(e) = cancellation.check() {
+                log::warn!("{e}, stopping cargo");
+                child.kill()?;
+                child.wait()?;
+                return Err(e);
+            }
+            std::thread::sleep(CANCELLATION_POLL_INTERVAL);
+        }
+    }
+
+    // Same as clippy, without blocking the thread while cargo runs
+    #[cfg(feature = "async")]
+    async fn clippy_async(&self, path: &Path) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut attempts = 0;
+        let mut verbose_retry = false;
+        loop {
+            let child = tokio::process::Command::from(self.command(path, verbose_retry))
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                // Dropping the child when the run is cancelled kills cargo
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| crate::error::Error::spawn("cargo", e))?;
+            let mut output = Box::pin(child.wait_with_output());
+            let output = loop {
+                match tokio::time::timeout(CANCELLATION_POLL_INTERVAL, &mut output).await {
+                    Ok(output) => break output?,
+                    Err(_) => {
+                        if let Some(cancellation) = &self.cancellation {
+                            cancellation.check()?;
+                        }
+                    }
+                }
+            };
+            let mut run_log = self.run_log(path)?;
+            let lints = lints(output.stdout.as_slice(), self.verbose, run_log.as_mut())?;
+            let output = CargoOutput {
+                success: output.status.success(),
+                lints,
+                stderr: output.stderr,
+                run_log,
+            };
+            match self.outcome(path, attempts, verbose_retry, output)? {
+                Attempt::Done(lints) => return Ok(lints),
+                Attempt::Retry(delay) => tokio::time::sleep(delay).await,
+                Attempt::RetryVerbose => verbose_retry = true,
+            }
+            attempts += 1;
         }
-        if clippy_pedantic_output.status.success() {
-            Ok(String::from_utf8(clippy_pedantic_output.stdout)?)
-        } else if self.verbose {
-            println!("Clippy run failed");
-            println!("cleaning and building with full backtrace");
-            let _ = Command::new("cargo")
-                .args(&["clean"])
-                .envs(self.envs())
-                .output()
-                .expect("failed to start cargo clean");
-            let build = Command::new("cargo")
-                .args(&["build"])
-                .envs(self.envs())
-                .output()
-                .expect("failed to start cargo build");
-            if build.status.success() {
-                Err(crate::error::Error::Command(String::from_utf8(
-                    build.stdout,
-                )?))
-            } else {
-                io::stdout().write_all(&build.stdout)?;
-                Err(crate::error::Error::Command(String::from_utf8(
-                    build.stderr,
-                )?))
+    }
+
+    // Tells whether a cargo run is over, once cargo exited
+    fn outcome(
+        &self,
+        path: &Path,
+        attempts: usize,
+        verbose_retry: bool,
+        output: CargoOutput,
+    ) -> Result<Attempt, crate::error::Error> {
+        let CargoOutput {
+            success,
+            lints,
+            stderr,
+            run_log,
+        } = output;
+        if success {
+            if let Some(run_log) = run_log {
+                run_log.discard()?;
+            }
+            return Ok(Attempt::Done(lints));
+        }
+        let stderr = String::from_utf8(stderr)?;
+        let failure = Failure::classify(&stderr);
+        match failure {
+            Failure::Unknown | Failure::LockedTargetDir
+                if lints.iter().any(|l| l.level() == Some(Level::Error)) =>
+            {
+                // The code doesn't compile, the errors tell more than stderr
+                if let Some(run_log) = run_log {
+                    let log_path = run_log.keep(&stderr)?;
+                    log::info!("The full output of cargo is in {}", log_path.display());
+                }
+                Err(crate::error::Error::Compilation {
+                    errors: lints
+                        .into_iter()
+                        .filter(|l| l.level() == Some(Level::Error))
+                        .collect(),
+                })
+            }
+            Failure::LockedTargetDir if attempts < MAX_LOCK_RETRIES => {
+                log::warn!(
+                    "The target directory is locked, retrying in {}s",
+                    LOCK_RETRY_DELAY.as_secs()
+                );
+                Ok(Attempt::Retry(LOCK_RETRY_DELAY))
             }
+            Failure::CorruptedIncremental if self.clean_on_failure && attempts == 0 => {
+                // Cross-compiled builds go in a directory named after the target
+                let incremental = match &self.target {
+                    Some(target) => self.target_dir(path).join(target),
+                    None => self.target_dir(path),
+                }
+                .join("debug")
+                .join("incremental");
+                log::warn!(
+                    "The incremental cache is corrupted, clearing {}",
+                    incremental.display()
+                );
+                match std::fs::remove_dir_all(&incremental) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+                Ok(Attempt::Retry(Duration::default()))
+            }
+            Failure::Unknown if !self.verbose && !verbose_retry => {
+                log::warn!("Clippy run failed, running it again with --verbose to find out why");
+                Ok(Attempt::RetryVerbose)
+            }
+            _ => {
+                log::warn!("Clippy run failed");
+                let mut message = stderr.clone();
+                if let Some(run_log) = run_log {
+                    let log_path = run_log.keep(&stderr)?;
+                    log::warn!("The full output of cargo is in {}", log_path.display());
+                    let _ = write!(
+                        message,
+                        "\nThe full output of cargo is in {}",
+                        log_path.display()
+                    );
+                }
+                if let Some(hint) = failure.hint(self.preview) {
+                    let _ = write!(message, "\n{hint}");
+                }
+                Err(crate::error::Error::ClippyFailed { stderr: message })
+            }
+        }
+    }
+}
+
+// What a cargo run left behind
+struct CargoOutput {
+    success: bool,
+    lints: Vec<Lint>,
+    stderr: Vec<u8>,
+    run_log: Option<RunLog>,
+}
+
+// What to do after a cargo run
+enum Attempt {
+    Done(Vec<Lint>),
+    Retry(Duration),
+    // Run again, with cargo telling the commands it runs
+    RetryVerbose,
+}
+
+/// Returns the root directory of the workspace `dir` belongs to,
+/// as reported by `cargo locate-project --workspace`.
+///
+/// # Errors
+/// Fails if cargo can't be run or doesn't find a workspace.
+pub fn workspace_root(dir: impl AsRef<Path>) -> Result<PathBuf, crate::error::Error> {
+    let output = Command::new("cargo")
+        .current_dir(dir)
+        .args(["locate-project", "--workspace", "--message-format", "plain"])
+        .output()
+        .map_err(|e| crate::error::Error::spawn("cargo", e))?;
+    if !output.status.success() {
+        return Err(crate::error::Error::Command(String::from_utf8(
+            output.stderr,
+        )?));
+    }
+    let manifest = PathBuf::from(String::from_utf8(output.stdout)?.trim());
+    Ok(manifest
+        .parent()
+        .map_or_else(PathBuf::new, Path::to_path_buf))
+}
+
+/// The reasons a clippy run commonly fails for, which don't come from the code itself
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Failure {
+    /// Another cargo process holds the lock on the target directory
+    LockedTargetDir,
+    /// Clippy isn't installed for the toolchain
+    MissingClippy,
+    /// The disk is full
+    OutOfDisk,
+    /// The incremental compilation cache can't be loaded
+    CorruptedIncremental,
+    Unknown,
+}
+
+impl Failure {
+    /// Finds out why clippy failed, from what it wrote to stderr.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::linter::clippy::Failure;
+    /// let stderr = "error: no such command: `clippy`";
+    /// assert_eq!(Failure::MissingClippy, Failure::classify(stderr));
+    /// ```
+    #[must_use]
+    pub fn classify(stderr: &str) -> Self {
+        let stderr = stderr.to_lowercase();
+        if stderr.contains("no space left on device") {
+            Failure::OutOfDisk
+        } else if stderr.contains("no such command: `clippy`")
+            || stderr.contains("no such subcommand: `clippy`")
+            || stderr.contains("'cargo-clippy' is not installed")
+        {
+            Failure::MissingClippy
+        } else if stderr.contains("incremental compilation")
+            || stderr.contains("failed to load dep-graph")
+        {
+            Failure::CorruptedIncremental
+        } else if stderr.contains("failed to lock")
+            || stderr.contains("failed to acquire")
+            || stderr.contains("could not acquire")
+        {
+            // Cargo blocks until the lock is released, "Blocking waiting for file lock"
+            // only tells that it waited
+            Failure::LockedTargetDir
         } else {
-            Err(crate::error::Error::Command(String::from_utf8(
-                clippy_pedantic_output.stderr,
-            )?))
+            Failure::Unknown
+        }
+    }
+
+    /// What the user can do about the failure, when scout can't fix it itself.
+    #[must_use]
+    pub fn hint(self, preview: bool) -> Option<String> {
+        match self {
+            Failure::MissingClippy if preview => Some(
+                "Try installing clippy: rustup component add clippy --toolchain nightly".into(),
+            ),
+            Failure::MissingClippy => {
+                Some("Try installing clippy: rustup component add clippy".into())
+            }
+            Failure::OutOfDisk => Some(
+                "The disk is full, try freeing some space, for example with cargo clean".into(),
+            ),
+            Failure::LockedTargetDir => {
+                Some("The target directory is still locked by another cargo process".into())
+            }
+            Failure::CorruptedIncremental => Some(
+                "The incremental cache is corrupted, run with --clean-on-failure to clear it"
+                    .into(),
+            ),
+            Failure::Unknown => None,
+        }
+    }
+}
+
+// Asks rustup for the toolchain it selects in `dir`
+fn active_toolchain_command(dir: &Path) -> Command {
+    let mut command = Command::new("rustup");
+    command.current_dir(dir).args(["show", "active-toolchain"]);
+    command
+}
+
+// The toolchain rustup selected, such as `stable-x86_64-unknown-linux-gnu`
+fn toolchain_name(output: std::process::Output) -> Option<String> {
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout)
+        .ok()?
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+}
+
+// Turns a package id into something `cargo -p` accepts.
+// Package id specs are accepted as is, the legacy `name version (source)` format isn't.
+fn package_spec(package_id: &str) -> String {
+    let mut parts = package_id.split(' ');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(name), Some(version), Some(_source)) => format!("{name}@{version}"),
+        _ => package_id.to_string(),
+    }
+}
+
+fn shell_quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:@+=,#".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+// The wrapper reports package ids in the legacy `name version (source)` format,
+// while recent cargo versions use package id specs
+pub(crate) fn same_package(captured: &str, package_id: &str) -> bool {
+    let mut parts = captured.split(' ');
+    match (parts.next(), parts.next()) {
+        (Some(name), Some(version)) => {
+            captured == package_id
+                || package_id.ends_with(&format!("#{name}@{version}"))
+                || package_id.ends_with(&format!("/{name}#{version}"))
+        }
+        _ => captured == package_id,
+    }
+}
+
+// Keeps the output of a cargo run in a file, so it can be looked at if the run fails
+struct RunLog {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl RunLog {
+    fn create(path: PathBuf) -> Result<Self, crate::error::Error> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = BufWriter::new(File::create(&path)?);
+        Ok(Self { path, file })
+    }
+
+    fn line(&mut self, line: &str) -> Result<(), crate::error::Error> {
+        writeln!(self.file, "{line}")?;
+        Ok(())
+    }
+
+    // Appends stderr after stdout, and returns the path of the log
+    fn keep(mut self, stderr: &str) -> Result<PathBuf, crate::error::Error> {
+        write!(self.file, "\n--- stderr ---\n{stderr}")?;
+        self.file.flush()?;
+        Ok(self.path)
+    }
+
+    fn discard(self) -> Result<(), crate::error::Error> {
+        drop(self.file);
+        std::fs::remove_file(self.path)?;
+        Ok(())
+    }
+}
+
+/// Parses the lints of the JSON messages of any cargo command,
+/// such as `cargo doc --message-format json`.
+pub(crate) fn compiler_messages(output: impl BufRead) -> Result<Vec<Lint>, crate::error::Error> {
+    lints(output, false, None)
+}
+
+// The directories to run cargo in, with the packages to give it there.
+// The members of a workspace are linted at once from its root, the member
+// that is alone in its workspace, or whose package is unknown, from its directory
+fn workspaces(members: &[PathBuf]) -> Vec<(PathBuf, Vec<String>)> {
+    let mut runs: Vec<(PathBuf, Vec<String>)> = Vec::new();
+    let mut workspaces: Vec<(Project, Vec<(PathBuf, String)>)> = Vec::new();
+    for member in members {
+        let dir = std::fs::canonicalize(member).unwrap_or_else(|_| member.clone());
+        let known = workspaces
+            .iter()
+            .position(|(project, _)| project.packages().iter().any(|p| p.dir == dir));
+        let position = match known {
+            Some(position) => position,
+            None => match Project::from_manifest_path(member.join("Cargo.toml")) {
+                Ok(project) => {
+                    workspaces.push((project, Vec::new()));
+                    workspaces.len() - 1
+                }
+                Err(e) => {
+                    log::debug!(
+                        "[Clippy] - could not read the package of {}: {}",
+                        member.display(),
+                        e
+                    );
+                    runs.push((member.clone(), Vec::new()));
+                    continue;
+                }
+            },
+        };
+        let (project, packages) = &mut workspaces[position];
+        match project.packages().iter().find(|p| p.dir == dir) {
+            Some(package) => packages.push((member.clone(), package.name.clone())),
+            None => runs.push((member.clone(), Vec::new())),
+        }
+    }
+    for (project, packages) in workspaces {
+        match packages.as_slice() {
+            [] => {}
+            [(member, _)] => runs.push((member.clone(), Vec::new())),
+            _ => runs.push((
+                project.workspace_root().to_path_buf(),
+                packages.into_iter().map(|(_, name)| name).collect(),
+            )),
         }
     }
+    runs
+}
+
+// Parses the lints one line at a time, as cargo prints them,
+// so the whole output never has to be held in memory
+fn lints(
+    mut clippy_output: impl BufRead,
+    verbose: bool,
+    run_log: Option<&mut RunLog>,
+) -> Result<Vec<Lint>, crate::error::Error> {
+    let lints = parse_lints(&mut clippy_output, verbose, run_log);
+    if lints.is_err() {
+        // Keep draining the output, cargo would block on a full pipe and never exit
+        let _ = std::io::copy(&mut clippy_output, &mut std::io::sink());
+    }
+    lints
 }
-#[must_use]
-fn lints(clippy_output: &str) -> Vec<Lint> {
-    clippy_output
-        .lines()
-        .filter(|l| l.starts_with('{'))
-        .filter_map(|line| serde_json::from_str(line).ok())
-        .filter(|lint: &Lint| {
-            if let Some(m) = &lint.message {
-                !m.spans.is_empty()
-            } else {
-                false
+
+fn parse_lints(
+    clippy_output: impl BufRead,
+    verbose: bool,
+    mut run_log: Option<&mut RunLog>,
+) -> Result<Vec<Lint>, crate::error::Error> {
+    let mut lints = Vec::new();
+    for (index, line) in clippy_output.lines().enumerate() {
+        let line = line?;
+        if verbose {
+            log::debug!("{line}");
+        }
+        if let Some(run_log) = run_log.as_mut() {
+            run_log.line(&line)?;
+        }
+        if !line.starts_with('{') {
+            continue;
+        }
+        match serde_json::from_str::<Lint>(&line) {
+            Ok(parsed) => {
+                if parsed.message.as_ref().is_some_and(|m| !m.spans.is_empty()) {
+                    log::trace!("[Clippy] - found {}", parsed.code().unwrap_or("a lint"));
+                    lints.push(parsed);
+                }
+            }
+            // Cargo only prints valid JSON, other messages don't describe lints
+            Err(e) if e.is_syntax() || e.is_eof() => {
+                return Err(crate::error::Error::Parse {
+                    line: index + 1,
+                    source: e,
+                })
             }
-        })
-        .collect::<Vec<Lint>>()
+            Err(_) => {}
+        }
+    }
+    Ok(lints)
 }
 
 #[cfg(test)]
@@ -155,13 +931,13 @@ mod tests {
     #[test]
     fn test_set_verbose() {
         let mut linter = Clippy::default();
-        assert_eq!(false, linter.verbose);
+        assert!(!linter.verbose);
 
         let l2 = linter.set_verbose(true);
-        assert_eq!(true, l2.verbose);
+        assert!(l2.verbose);
 
         let l3 = l2.set_verbose(false);
-        assert_eq!(false, l3.verbose);
+        assert!(!l3.verbose);
     }
     #[test]
     fn test_get_envs() {
@@ -174,6 +950,145 @@ mod tests {
         assert_eq!(expected_envs, verbose_linter.envs());
     }
     #[test]
+    fn test_wrapper_command_parameters() {
+        let mut linter = Clippy::default();
+        linter
+            .set_wrapper(Some(PathBuf::from("cargo-scout")))
+            .set_all_features(true);
+        assert_eq!(
+            vec!["check", "--message-format", "json", "--all-features"],
+            linter.command_parameters()
+        );
+    }
+    #[test]
+    fn test_lints_command_parameters() {
+        let mut linter = Clippy::default();
+        linter.set_lints(vec!["clippy::unwrap_used".to_string()]);
+        assert_eq!(
+            vec![
+                "clippy",
+                "--message-format",
+                "json",
+                "--",
+                "-W",
+                "clippy::pedantic",
+                "-W",
+                "clippy::unwrap_used"
+            ],
+            linter.command_parameters()
+        );
+        assert_eq!(
+            "-W__CLIPPY_HACKERY__clippy::pedantic__CLIPPY_HACKERY__\
+             -W__CLIPPY_HACKERY__clippy::unwrap_used__CLIPPY_HACKERY__",
+            linter.clippy_args()
+        );
+        linter.set_wrapper(Some(PathBuf::from("cargo-scout")));
+        assert!(linter
+            .configuration()
+            .ends_with("(wrapper) -W clippy::unwrap_used"));
+    }
+    #[test]
+    fn test_toolchain_command_parameters() {
+        let mut linter = Clippy::default();
+        linter.set_toolchain(Some("1.80.0".to_string()));
+        assert_eq!(
+            vec![
+                "+1.80.0",
+                "clippy",
+                "--message-format",
+                "json",
+                "--",
+                "-W",
+                "clippy::pedantic"
+            ],
+            linter.command_parameters()
+        );
+        assert_eq!(
+            "cd /repo && cargo +1.80.0 clippy -p foo@0.1.0 -- -W clippy::pedantic",
+            linter.reproducer(
+                Path::new("/repo"),
+                Some("stable"),
+                "foo 0.1.0 (path+file:///repo)"
+            )
+        );
+        linter.set_preview(true);
+        assert_eq!("+nightly", linter.command_parameters()[0]);
+    }
+    #[test]
+    fn test_cross_compiled_command_parameters() {
+        let mut linter = Clippy::default();
+        linter.set_target(Some("thumbv7em-none-eabihf".to_string()));
+        assert_eq!(
+            vec![
+                "clippy",
+                "--message-format",
+                "json",
+                "--target",
+                "thumbv7em-none-eabihf",
+                "--",
+                "-W",
+                "clippy::pedantic"
+            ],
+            linter.command_parameters()
+        );
+    }
+    #[test]
+    fn test_target_kinds_command_parameters() {
+        let mut linter = Clippy::default();
+        linter.set_target_kinds(vec![TargetKind::Tests, TargetKind::Examples]);
+        assert_eq!(
+            vec![
+                "clippy",
+                "--message-format",
+                "json",
+                "--tests",
+                "--examples",
+                "--",
+                "-W",
+                "clippy::pedantic"
+            ],
+            linter.command_parameters()
+        );
+    }
+    #[test]
+    fn test_target_dir() {
+        let mut linter = Clippy::default();
+        let dir = PathBuf::from("/tmp/scout-target");
+        linter.set_target_dir(Some(dir.clone()));
+        assert_eq!(dir, linter.target_dir(Path::new(".")));
+    }
+    #[test]
+    fn test_reproducer() {
+        let mut linter = Clippy::default();
+        linter
+            .set_verbose(true)
+            .set_features(Some("a b".to_string()));
+        assert_eq!(
+            "cd '/repo/my crate' && cargo +stable clippy --features 'a b' -p foo@0.1.0 -- -W clippy::pedantic",
+            linter.reproducer(
+                Path::new("/repo/my crate"),
+                Some("stable"),
+                "foo 0.1.0 (path+file:///repo/my crate)"
+            )
+        );
+        linter
+            .set_features(None)
+            .set_target_dir(Some(PathBuf::from("/tmp/target")));
+        assert_eq!(
+            "cd /repo && CARGO_TARGET_DIR=/tmp/target cargo clippy -p path+file:///repo#0.1.0 -- -W clippy::pedantic",
+            linter.reproducer(Path::new("/repo"), None, "path+file:///repo#0.1.0")
+        );
+    }
+    #[test]
+    fn test_same_package() {
+        let captured = "foo 0.1.0 (path+file:///repo/foo)";
+        assert!(same_package(captured, captured));
+        assert!(same_package(captured, "path+file:///repo/foo#0.1.0"));
+        assert!(same_package(captured, "path+file:///repo/bar#foo@0.1.0"));
+        assert!(!same_package(captured, "path+file:///repo/bar#0.1.0"));
+        assert!(!same_package(captured, "foo 0.2.0 (path+file:///repo/foo)"));
+    }
+    #[test]
     fn test_get_command_parameters() {
         let mut linter = Clippy::default();
         let expected_command_parameters = vec![
@@ -350,23 +1265,330 @@ mod tests {
         );
     }
     #[test]
+    fn test_compile_errors_outcome() {
+        let error = |level| Lint {
+            package_id: "foo".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(crate::linter::Message {
+                rendered: "error[E0425]: cannot find value `x` in this scope".to_string(),
+                level: Some(level),
+                ..crate::linter::Message::default()
+            }),
+        };
+        let stderr = b"error: could not compile `foo` due to 1 previous error".to_vec();
+        let output = |lints, stderr| CargoOutput {
+            success: false,
+            lints,
+            stderr,
+            run_log: None,
+        };
+        let outcome = Clippy::default().outcome(
+            Path::new("."),
+            0,
+            false,
+            output(
+                vec![error(Level::Error), error(Level::Warning)],
+                stderr.clone(),
+            ),
+        );
+        assert!(matches!(
+            outcome,
+            Err(crate::error::Error::Compilation { ref errors }) if errors == &[error(Level::Error)]
+        ));
+        // Waiting on the lock of the build directory doesn't hide the errors
+        let blocked = [
+            b"Blocking waiting for file lock on build directory\n".to_vec(),
+            stderr.clone(),
+        ]
+        .concat();
+        let outcome = Clippy::default().outcome(
+            Path::new("."),
+            0,
+            false,
+            output(vec![error(Level::Error)], blocked),
+        );
+        assert!(matches!(
+            outcome,
+            Err(crate::error::Error::Compilation { .. })
+        ));
+        // Without errors to show, clippy runs again with --verbose, then stderr is all there is
+        let outcome =
+            Clippy::default().outcome(Path::new("."), 0, false, output(Vec::new(), stderr.clone()));
+        assert!(matches!(outcome, Ok(Attempt::RetryVerbose)));
+        let outcome =
+            Clippy::default().outcome(Path::new("."), 1, true, output(Vec::new(), stderr));
+        assert!(matches!(
+            outcome,
+            Err(crate::error::Error::ClippyFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_corrupted_incremental_outcome() {
+        let output = || CargoOutput {
+            success: false,
+            lints: Vec::new(),
+            stderr: b"error: failed to load dep-graph".to_vec(),
+            run_log: None,
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let mut linter = Clippy::default();
+        linter.set_target_dir(Some(dir.path().to_path_buf()));
+        // The cache is only cleared on request
+        assert!(matches!(
+            linter.outcome(dir.path(), 0, false, output()),
+            Err(crate::error::Error::ClippyFailed { ref stderr }) if stderr.contains("--clean-on-failure")
+        ));
+        linter.set_clean_on_failure(true);
+        assert!(matches!(
+            linter.outcome(dir.path(), 0, false, output()),
+            Ok(Attempt::Retry(_))
+        ));
+    }
+
+    #[test]
+    fn test_verbose_retry_command() {
+        let args = |verbose| -> Vec<String> {
+            Clippy::default()
+                .command(Path::new("."), verbose)
+                .get_args()
+                .map(|a| a.to_string_lossy().into_owned())
+                .collect()
+        };
+        assert_eq!(
+            vec![
+                "clippy",
+                "--message-format",
+                "json",
+                "--",
+                "-W",
+                "clippy::pedantic"
+            ],
+            args(false)
+        );
+        assert_eq!(
+            vec![
+                "clippy",
+                "--message-format",
+                "json",
+                "--verbose",
+                "--",
+                "-W",
+                "clippy::pedantic"
+            ],
+            args(true)
+        );
+    }
+
+    #[test]
+    fn test_packages_command() {
+        let linter = Clippy {
+            packages: vec!["foo".to_string(), "bar".to_string()],
+            ..Clippy::default()
+        };
+        let args: Vec<String> = linter
+            .command(Path::new("."), false)
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            vec![
+                "clippy",
+                "--message-format",
+                "json",
+                "-p",
+                "foo",
+                "-p",
+                "bar",
+                "--",
+                "-W",
+                "clippy::pedantic"
+            ],
+            args
+        );
+        // The reproducers point at a single package
+        assert_eq!(
+            "cd /repo && cargo clippy -p foo@0.1.0 -- -W clippy::pedantic",
+            linter.reproducer(
+                Path::new("/repo"),
+                None,
+                "foo 0.1.0 (path+file:///repo/foo)"
+            )
+        );
+    }
+
+    #[test]
+    fn test_workspaces() {
+        let lib = PathBuf::from(".");
+        let bin = PathBuf::from("../cargo-scout");
+        let root = workspace_root(".").unwrap().canonicalize().unwrap();
+        assert_eq!(
+            vec![(
+                root,
+                vec!["cargo-scout-lib".to_string(), "cargo-scout".to_string()]
+            )],
+            workspaces(&[lib.clone(), bin])
+        );
+        // A single member is linted from its directory, as without a workspace
+        assert_eq!(vec![(lib.clone(), Vec::new())], workspaces(&[lib]));
+        let unknown = PathBuf::from("does-not-exist");
+        assert_eq!(vec![(unknown.clone(), Vec::new())], workspaces(&[unknown]));
+    }
+
+    #[test]
+    fn test_classify_failures() {
+        assert_eq!(
+            Failure::OutOfDisk,
+            Failure::classify("error: failed to write: No space left on device (os error 28)")
+        );
+        assert_eq!(
+            Failure::MissingClippy,
+            Failure::classify("error: 'cargo-clippy' is not installed for the toolchain 'nightly'")
+        );
+        assert_eq!(
+            Failure::CorruptedIncremental,
+            Failure::classify("error: failed to load dep-graph")
+        );
+        assert_eq!(
+            Failure::LockedTargetDir,
+            Failure::classify("error: failed to lock file: /repo/target/debug/.cargo-lock")
+        );
+        // Cargo waited for another build, that's not why it failed
+        assert_eq!(
+            Failure::Unknown,
+            Failure::classify("Blocking waiting for file lock on build directory")
+        );
+        assert_eq!(
+            Failure::Unknown,
+            Failure::classify("error[E0425]: cannot find value")
+        );
+        assert!(Failure::MissingClippy
+            .hint(true)
+            .unwrap()
+            .contains("--toolchain nightly"));
+        assert_eq!(None, Failure::Unknown.hint(false));
+    }
+    #[test]
+    fn test_run_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scout-logs").join("clippy.log");
+        let output = "   Compiling foo v0.1.0\n{\"reason\":\"build-finished\"}\n";
+        let mut run_log = RunLog::create(path.clone()).unwrap();
+        assert!(lints(output.as_bytes(), false, Some(&mut run_log))
+            .unwrap()
+            .is_empty());
+        assert_eq!(path, run_log.keep("error: could not compile").unwrap());
+        assert_eq!(
+            format!("{}\n--- stderr ---\nerror: could not compile", output),
+            std::fs::read_to_string(&path).unwrap()
+        );
+
+        let run_log = RunLog::create(path.clone()).unwrap();
+        run_log.discard().unwrap();
+        assert!(!path.exists());
+    }
+    #[test]
+    fn test_workspace_root() {
+        let root = workspace_root(".").unwrap();
+        assert!(root.join("Cargo.toml").is_file());
+        assert!(Path::new(".").canonicalize().unwrap().starts_with(root));
+    }
+    #[test]
     fn test_lints() {
         use crate::linter::{Message, Span};
         let expected_lints = vec![Lint {
             package_id: "cargo-scout".to_string(),
             src_path: Some("test/foo/bar.rs".to_string()),
+            reproducer: None,
             message: Some(Message {
+                message: String::new(),
                 rendered: "this is a test lint".to_string(),
+                code: None,
+                level: None,
+                children: Vec::new(),
                 spans: vec![Span {
                     file_name: "test/foo/baz.rs".to_string(),
                     line_start: 10,
                     line_end: 12,
+                    column_start: 0,
+                    column_end: 0,
+                    is_primary: true,
+                    ..Span::default()
                 }],
             }),
         }];
 
-        let clippy_output = r#"{"package_id": "cargo-scout","src_path": "test/foo/bar.rs","message": { "rendered": "this is a test lint","spans": [{"file_name": "test/foo/baz.rs","line_start": 10,"line_end": 12}]}}"#;
+        let clippy_output = r#"{"package_id": "cargo-scout","src_path": "test/foo/bar.rs","message": { "rendered": "this is a test lint","spans": [{"file_name": "test/foo/baz.rs","line_start": 10,"line_end": 12,"is_primary": true}]}}"#;
+
+        assert_eq!(
+            expected_lints,
+            lints(clippy_output.as_bytes(), false, None).unwrap()
+        );
+    }
+    #[test]
+    fn test_lints_drains_output_on_parse_error() {
+        let clippy_output = format!(
+            "{{\"truncated\n{}",
+            "{\"reason\":\"compiler-message\"}\n".repeat(100)
+        );
+        let mut output = std::io::Cursor::new(clippy_output.as_bytes());
+        let result = lints(&mut output, false, None);
+        assert!(matches!(
+            result,
+            Err(crate::error::Error::Parse { line: 1, .. })
+        ));
+        // cargo is still writing, it must not block on a full pipe
+        assert_eq!(clippy_output.len() as u64, output.position());
+    }
+    #[test]
+    fn test_lints_with_code() {
+        use crate::linter::{Code, Level, Message, Span};
+        let expected_lints = vec![Lint {
+            package_id: "cargo-scout".to_string(),
+            src_path: Some("test/foo/bar.rs".to_string()),
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "this is a test lint".to_string(),
+                code: Some(Code {
+                    code: "clippy::module_name_repetitions".to_string(),
+                    explanation: None,
+                }),
+                level: Some(Level::Warning),
+                children: Vec::new(),
+                spans: vec![Span {
+                    file_name: "test/foo/baz.rs".to_string(),
+                    line_start: 10,
+                    line_end: 12,
+                    column_start: 0,
+                    column_end: 0,
+                    is_primary: true,
+                    ..Span::default()
+                }],
+            }),
+        }];
+
+        let clippy_output = r#"{"package_id": "cargo-scout","src_path": "test/foo/bar.rs","message": { "rendered": "this is a test lint","code": {"code": "clippy::module_name_repetitions","explanation": null},"level": "warning","spans": [{"file_name": "test/foo/baz.rs","line_start": 10,"line_end": 12,"is_primary": true}]}}"#;
+
+        let actual_lints = lints(clippy_output.as_bytes(), false, None).unwrap();
+        assert_eq!(expected_lints, actual_lints);
+        assert_eq!(
+            Some("clippy::module_name_repetitions"),
+            actual_lints[0].code()
+        );
+    }
+    #[test]
+    fn test_lints_with_suggestion() {
+        let clippy_output = r#"{"package_id": "cargo-scout","src_path": "test/foo/bar.rs","message": { "rendered": "this is a test lint","spans": [{"file_name": "test/foo/baz.rs","line_start": 10,"line_end": 10,"column_start": 5,"column_end": 13}],"children": [{"message": "try","rendered": null,"level": "help","spans": [{"file_name": "test/foo/baz.rs","line_start": 10,"line_end": 10,"column_start": 5,"column_end": 13,"suggested_replacement": "x","suggestion_applicability": "MachineApplicable"}]}]}}"#;
 
-        assert_eq!(expected_lints, lints(clippy_output));
+        let actual_lints = lints(clippy_output.as_bytes(), false, None).unwrap();
+        let message = actual_lints[0].message.as_ref().unwrap();
+        let suggestions = message.machine_applicable_suggestions();
+        assert_eq!(1, suggestions.len());
+        assert_eq!(Some("x"), suggestions[0].suggested_replacement.as_deref());
+        assert_eq!(5, suggestions[0].column_start);
+        assert_eq!("try", message.children[0].message);
+        assert_eq!("", message.children[0].rendered);
     }
 }
@@ -0,0 +1,444 @@
+use crate::linter::audit::subcommand_error;
+use crate::linter::clippy::workspace_root;
+use crate::linter::{
+    package_id, shown_path, Code, DiffAwareLinter, Level, Lint, Linter, Message, Span,
+};
+use crate::vcs::Section;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The code of the findings `Coverage` reports
+pub const COVERAGE_CODE: &str = "uncovered_lines";
+
+/// The tool `Coverage` measures the coverage with
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CoverageTool {
+    /// `cargo llvm-cov`
+    #[default]
+    LlvmCov,
+    /// `cargo tarpaulin`
+    Tarpaulin,
+}
+
+impl CoverageTool {
+    fn name(self) -> &'static str {
+        match self {
+            CoverageTool::LlvmCov => "llvm-cov",
+            CoverageTool::Tarpaulin => "tarpaulin",
+        }
+    }
+}
+
+/// The `[coverage]` table of `scout.toml`
+///
+/// # Example
+/// ```toml
+/// [coverage]
+/// tool = "tarpaulin"
+/// level = "error"
+/// ```
+#[derive(Deserialize, Debug, Default, PartialEq, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CoverageConfig {
+    /// The tool run to measure the coverage, `llvm-cov` or `tarpaulin`
+    pub tool: CoverageTool,
+    /// An lcov or cobertura report to read instead of running the tool
+    pub report: Option<PathBuf>,
+    /// The severity of the findings, warning by default
+    pub level: Option<Level>,
+}
+
+/// Reports the lines the tests don't run, from an lcov or cobertura report.
+///
+/// Only the lines the diff adds are reported when there's a diff.
+/// The report is produced by `cargo llvm-cov` or `cargo tarpaulin`,
+/// which run the tests, unless an existing report is given.
+pub struct Coverage {
+    tool: CoverageTool,
+    report: Option<PathBuf>,
+    level: Level,
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self {
+            tool: CoverageTool::default(),
+            report: None,
+            level: Level::Warning,
+        }
+    }
+}
+
+impl Coverage {
+    /// The tool run to measure the coverage.
+    pub fn set_tool(&mut self, tool: CoverageTool) -> &mut Self {
+        self.tool = tool;
+        self
+    }
+
+    /// An lcov or cobertura report to read instead of running the tool.
+    pub fn set_report(&mut self, report: Option<PathBuf>) -> &mut Self {
+        self.report = report;
+        self
+    }
+
+    /// The severity of the findings, `Level::Error` to fail on uncovered lines
+    /// when the run only fails on errors.
+    pub fn set_level(&mut self, level: Level) -> &mut Self {
+        self.level = level;
+        self
+    }
+
+    // The hits of each line of each file, with absolute file names
+    fn coverage(&self, working_dir: &Path) -> Result<Report, crate::error::Error> {
+        let root = workspace_root(working_dir)?;
+        let contents = match &self.report {
+            Some(report) => std::fs::read_to_string(report)?,
+            None => self.run(working_dir, &root)?,
+        };
+        Ok(if contents.trim_start().starts_with('<') {
+            parse_cobertura(&contents, &root)
+        } else {
+            parse_lcov(&contents, &root)
+        })
+    }
+
+    // Runs the tests with the coverage tool, and returns its lcov report
+    fn run(&self, working_dir: &Path, root: &Path) -> Result<String, crate::error::Error> {
+        log::info!(
+            "[Coverage] - running the tests with cargo {} in directory {}",
+            self.tool.name(),
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        let mut command = Command::new("cargo");
+        command.current_dir(working_dir);
+        let output_dir = root.join("target").join("scout-coverage");
+        match self.tool {
+            CoverageTool::LlvmCov => command.args(["llvm-cov", "--lcov"]),
+            CoverageTool::Tarpaulin => command
+                .args(["tarpaulin", "--out", "Lcov", "--output-dir"])
+                .arg(&output_dir),
+        };
+        let output = command
+            .output()
+            .map_err(|e| crate::error::Error::spawn("cargo", e))?;
+        if !output.status.success() {
+            return Err(subcommand_error(self.tool.name(), &output));
+        }
+        match self.tool {
+            CoverageTool::LlvmCov => Ok(String::from_utf8(output.stdout)?),
+            CoverageTool::Tarpaulin => Ok(std::fs::read_to_string(output_dir.join("lcov.info"))?),
+        }
+    }
+
+    fn uncovered_lints(&self, package_id: &str, uncovered: BTreeMap<&Path, Vec<u32>>) -> Vec<Lint> {
+        uncovered
+            .into_iter()
+            .flat_map(|(file, lines)| {
+                ranges(&lines)
+                    .into_iter()
+                    .map(move |range| self.lint(package_id, file, range))
+            })
+            .collect()
+    }
+
+    fn lint(&self, package_id: &str, file: &Path, (line_start, line_end): (u32, u32)) -> Lint {
+        let file_name = file.to_string_lossy().to_string();
+        let message = if line_start == line_end {
+            format!("line {line_start} is not covered by the tests")
+        } else {
+            format!("lines {line_start} to {line_end} are not covered by the tests")
+        };
+        let rendered = format!(
+            "{}[{}]: {}\n --> {}:{}:1\n",
+            crate::output::level_name(Some(self.level)),
+            COVERAGE_CODE,
+            message,
+            shown_path(file).display(),
+            line_start
+        );
+        Lint {
+            package_id: package_id.to_string(),
+            src_path: Some(file_name.clone()),
+            reproducer: Some(format!("cargo {}", self.tool.name())),
+            message: Some(Message {
+                message,
+                rendered,
+                code: Some(Code {
+                    code: COVERAGE_CODE.to_string(),
+                    explanation: None,
+                }),
+                level: Some(self.level),
+                spans: vec![Span {
+                    file_name,
+                    line_start,
+                    line_end,
+                    column_start: 1,
+                    column_end: 1,
+                    is_primary: true,
+                    ..Span::default()
+                }],
+                children: Vec::new(),
+            }),
+        }
+    }
+}
+
+impl Linter for Coverage {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        let report = self.coverage(&working_dir)?;
+        let uncovered = report
+            .iter()
+            .filter(|(file, _)| file.starts_with(&working_dir))
+            .map(|(file, hits)| (file.as_path(), uncovered(hits)))
+            .collect();
+        Ok(self.uncovered_lints(&package_id(&working_dir), uncovered))
+    }
+
+    fn configuration(&self) -> String {
+        match &self.report {
+            Some(report) => format!("report={}", report.display()),
+            None => format!("tool={}", self.tool.name()),
+        }
+    }
+
+    fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+        Some(self)
+    }
+}
+
+impl DiffAwareLinter for Coverage {
+    fn lints_for_files(
+        &self,
+        working_dir: PathBuf,
+        changed_files: &[PathBuf],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let report = self.coverage(&working_dir)?;
+        let uncovered = changed_files
+            .iter()
+            .filter_map(|file| {
+                let hits = report.get(file)?;
+                Some((file.as_path(), uncovered(hits)))
+            })
+            .collect();
+        Ok(self.uncovered_lints(&package_id(&working_dir), uncovered))
+    }
+
+    /// Only reports the lines the sections add.
+    fn lints_for_sections(
+        &self,
+        working_dir: PathBuf,
+        sections: &[Section],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let report = self.coverage(&working_dir)?;
+        let mut uncovered: BTreeMap<&Path, Vec<u32>> = BTreeMap::new();
+        for section in sections {
+            let file = Path::new(&section.file_name);
+            if let Some(hits) = report.get(file) {
+                let lines = section
+                    .added_lines
+                    .iter()
+                    .map(|l| l.line)
+                    .filter(|l| hits.get(l) == Some(&0));
+                uncovered.entry(file).or_default().extend(lines);
+            }
+        }
+        for lines in uncovered.values_mut() {
+            lines.sort_unstable();
+            lines.dedup();
+        }
+        Ok(self.uncovered_lints(&package_id(&working_dir), uncovered))
+    }
+}
+
+// The hits of each line of each file
+type Report = BTreeMap<PathBuf, BTreeMap<u32, u64>>;
+
+fn uncovered(hits: &BTreeMap<u32, u64>) -> Vec<u32> {
+    hits.iter()
+        .filter(|(_, hits)| **hits == 0)
+        .map(|(line, _)| *line)
+        .collect()
+}
+
+// The runs of consecutive lines, from sorted lines
+fn ranges(lines: &[u32]) -> Vec<(u32, u32)> {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &line in lines {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => ranges.push((line, line)),
+        }
+    }
+    ranges
+}
+
+// Relative file names are relative to the workspace root
+fn resolve(root: &Path, file: &str) -> PathBuf {
+    root.join(file)
+}
+
+// The `SF:<file>` and `DA:<line>,<hits>` records of an lcov report
+fn parse_lcov(contents: &str, root: &Path) -> Report {
+    let mut report = Report::new();
+    let mut file = None;
+    for line in contents.lines() {
+        if let Some(name) = line.strip_prefix("SF:") {
+            file = Some(resolve(root, name.trim()));
+        } else if let (Some(data), Some(file)) = (line.strip_prefix("DA:"), &file) {
+            let mut fields = data.split(',');
+            let line = fields.next().and_then(|l| l.trim().parse().ok());
+            let hits: Option<u64> = fields.next().and_then(|h| h.trim().parse().ok());
+            if let (Some(line), Some(hits)) = (line, hits) {
+                *report
+                    .entry(file.clone())
+                    .or_default()
+                    .entry(line)
+                    .or_default() += hits;
+            }
+        } else if line.trim() == "end_of_record" {
+            file = None;
+        }
+    }
+    report
+}
+
+// The `<class filename>` and `<line number hits>` elements of a cobertura report,
+// whose file names are relative to its first `<source>`
+fn parse_cobertura(contents: &str, root: &Path) -> Report {
+    let source = Regex::new(r"<source>([^<]*)</source>").unwrap();
+    let element = Regex::new(r"<(class|line)\b([^>]*)>").unwrap();
+    let attribute = Regex::new(r#"([\w-]+)="([^"]*)""#).unwrap();
+    let root = source
+        .captures(contents)
+        .map_or_else(|| root.to_path_buf(), |c| resolve(root, c[1].trim()));
+    let mut report = Report::new();
+    let mut file = None;
+    for element in element.captures_iter(contents) {
+        let attributes: BTreeMap<&str, &str> = attribute
+            .captures_iter(element.get(2).map_or("", |a| a.as_str()))
+            .filter_map(|a| Some((a.get(1)?.as_str(), a.get(2)?.as_str())))
+            .collect();
+        if &element[1] == "class" {
+            file = attributes.get("filename").map(|f| resolve(&root, f));
+        } else if let Some(file) = &file {
+            let line = attributes.get("number").and_then(|l| l.parse().ok());
+            let hits: Option<u64> = attributes.get("hits").and_then(|h| h.parse().ok());
+            if let (Some(line), Some(hits)) = (line, hits) {
+                *report
+                    .entry(file.clone())
+                    .or_default()
+                    .entry(line)
+                    .or_default() += hits;
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vcs::AddedLine;
+
+    const LCOV: &str = "TN:
+SF:/repo/src/lib.rs
+FN:1,foo
+DA:1,3
+DA:2,0
+DA:3,0
+DA:5,0
+DA:6,1
+LF:5
+LH:2
+end_of_record
+SF:src/main.rs
+DA:1,0
+end_of_record
+";
+
+    const COBERTURA: &str = r#"<?xml version="1.0"?>
+<coverage lines-valid="5" lines-covered="2" line-rate="0.4" version="1.9" timestamp="1">
+  <sources>
+    <source>/repo</source>
+  </sources>
+  <packages>
+    <package name="foo" line-rate="0.4">
+      <classes>
+        <class name="lib" filename="src/lib.rs" line-rate="0.4">
+          <methods/>
+          <lines>
+            <line number="1" hits="3"/>
+            <line number="2" hits="0"/>
+            <line number="3" hits="0"/>
+            <line number="5" hits="0"/>
+            <line number="6" hits="1"/>
+          </lines>
+        </class>
+      </classes>
+    </package>
+  </packages>
+</coverage>
+"#;
+
+    #[test]
+    fn test_parse_reports() {
+        let lcov = parse_lcov(LCOV, Path::new("/repo"));
+        let lib = &lcov[Path::new("/repo/src/lib.rs")];
+        assert_eq!(vec![2, 3, 5], uncovered(lib));
+        assert_eq!(vec![1], uncovered(&lcov[Path::new("/repo/src/main.rs")]));
+        assert_eq!(
+            vec![(Path::new("/repo/src/lib.rs").to_path_buf(), lib.clone())],
+            parse_cobertura(COBERTURA, Path::new("/elsewhere"))
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec![(2, 3), (5, 5)], ranges(&uncovered(lib)));
+    }
+
+    #[test]
+    fn test_lints_for_sections() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n",
+        )?;
+        std::fs::create_dir(dir.path().join("src"))?;
+        std::fs::write(dir.path().join("src/lib.rs"), "")?;
+        let report = dir.path().join("lcov.info");
+        std::fs::write(&report, LCOV.replace("/repo/", ""))?;
+        let mut coverage = Coverage::default();
+        coverage.set_report(Some(report)).set_level(Level::Error);
+        let root = workspace_root(dir.path())?;
+        let section = Section {
+            file_name: root.join("src/lib.rs").to_string_lossy().to_string(),
+            line_start: 1,
+            line_end: 6,
+            added_lines: [1, 2, 3, 4]
+                .iter()
+                .map(|&line| AddedLine {
+                    line,
+                    text: String::new(),
+                })
+                .collect(),
+        };
+        let lints = coverage.lints_for_sections(root.clone(), &[section])?;
+        assert_eq!(1, lints.len());
+        assert_eq!(Some(COVERAGE_CODE), lints[0].code());
+        assert_eq!(Some(Level::Error), lints[0].level());
+        let span = lints[0].primary_span().unwrap();
+        assert_eq!((2, 3), (span.line_start, span.line_end));
+        assert_eq!(
+            "lines 2 to 3 are not covered by the tests",
+            lints[0].message.as_ref().unwrap().message
+        );
+
+        // Without a diff, every uncovered line is reported
+        assert_eq!(3, coverage.lints(root)?.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use crate::linter::clippy::compiler_messages;
+use crate::linter::{Lint, Linter};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The lints rustdoc warns about besides its own, such as `rustdoc::broken_intra_doc_links`
+const RUSTDOC_LINTS: &[&str] = &["-W", "missing_docs"];
+
+/// Reports the warnings of `cargo doc`: broken intra-doc links,
+/// invalid code blocks and undocumented public items.
+///
+/// Like clippy's lints, only the ones on the changed lines are kept.
+#[derive(Default)]
+pub struct CargoDoc;
+
+impl CargoDoc {
+    // Appends the lints to the RUSTDOCFLAGS of the environment
+    fn rustdoc_flags() -> String {
+        let mut flags = std::env::var("RUSTDOCFLAGS").unwrap_or_default();
+        for flag in RUSTDOC_LINTS {
+            if !flags.is_empty() {
+                flags.push(' ');
+            }
+            flags.push_str(flag);
+        }
+        flags
+    }
+}
+
+impl Linter for CargoDoc {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        log::debug!(
+            "[CargoDoc] - documenting directory {}",
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        let output = Command::new("cargo")
+            .current_dir(&working_dir)
+            .env("RUSTDOCFLAGS", Self::rustdoc_flags())
+            .args(["doc", "--no-deps", "--message-format", "json"])
+            .output()
+            .map_err(|e| crate::error::Error::spawn("cargo", e))?;
+        if !output.status.success() {
+            return Err(crate::error::Error::Command(format!(
+                "cargo doc failed:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        compiler_messages(output.stdout.as_slice())
+    }
+
+    fn configuration(&self) -> String {
+        Self::rustdoc_flags()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lints() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        std::fs::create_dir(dir.path().join("src"))?;
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"docs\"\nversion = \"0.1.0\"\n\n[workspace]\n",
+        )?;
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "//! Docs\n\n/// Links to [`Missing`]\npub fn linked() {}\n\npub fn undocumented() {}\n",
+        )?;
+        let mut lints: Vec<(String, u32)> = CargoDoc
+            .lints(dir.path().to_path_buf())?
+            .iter()
+            .map(|l| {
+                let span = l.primary_span().unwrap();
+                (l.code().unwrap_or_default().to_string(), span.line_start)
+            })
+            .collect();
+        lints.sort();
+        assert_eq!(
+            vec![
+                ("missing_docs".to_string(), 6),
+                ("rustdoc::broken_intra_doc_links".to_string(), 3)
+            ],
+            lints
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use std::process::Command;
+
+/// Returns the documentation of a lint code, as the toolchain prints it.
+///
+/// Error codes such as `E0308` are explained by `rustc --explain`,
+/// lints such as `clippy::needless_pass_by_value` by `cargo clippy --explain`.
+///
+/// # Errors
+/// Fails if the toolchain can't be run or doesn't know the code.
+pub fn explain(code: &str) -> Result<String, crate::error::Error> {
+    let (name, mut command) = explain_command(code);
+    let output = command
+        .output()
+        .map_err(|e| crate::error::Error::spawn(name, e))?;
+    let explanation = String::from_utf8(output.stdout)?;
+    // Both exit with an error for the codes they don't know
+    if !output.status.success() || explanation.trim().is_empty() {
+        log::debug!(
+            "[Explain] - {} failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Err(crate::error::Error::UnknownLintCode(code.to_string()));
+    }
+    Ok(explanation)
+}
+
+// The tool that explains the code, and the command that runs it
+fn explain_command(code: &str) -> (&'static str, Command) {
+    if is_error_code(code) {
+        let mut command = Command::new("rustc");
+        command.args(["--explain", &code.to_uppercase()]);
+        ("rustc", command)
+    } else {
+        let mut command = Command::new("cargo");
+        command.args(["clippy", "--explain", code.trim_start_matches("clippy::")]);
+        ("cargo", command)
+    }
+}
+
+// E0308, and e0308 as it is sometimes typed
+fn is_error_code(code: &str) -> bool {
+    code.len() == 5 && code.starts_with(['E', 'e']) && code[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<String> {
+        command
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_explain_command() {
+        let (name, command) = explain_command("e0308");
+        assert_eq!("rustc", name);
+        assert_eq!(vec!["--explain", "E0308"], args(&command));
+        let (name, command) = explain_command("clippy::needless_pass_by_value");
+        assert_eq!("cargo", name);
+        assert_eq!(
+            vec!["clippy", "--explain", "needless_pass_by_value"],
+            args(&command)
+        );
+        assert!(!is_error_code("E03O8"));
+    }
+
+    #[test]
+    fn test_explain_error_code() -> Result<(), crate::error::Error> {
+        assert!(explain("E0308")?.contains("Expected type did not match the received type"));
+        assert!(matches!(
+            explain("E9999"),
+            Err(crate::error::Error::UnknownLintCode(ref c)) if c == "E9999"
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+//! Lints and spans for the tests, built from the few fields a test cares about
+//! so they don't need updating whenever the model gets a new field.
+use crate::linter::{Code, Level, Lint, Message, Span};
+
+/// A primary span from the first column of `line_start` to the first column of `line_end`
+pub fn span(file_name: &str, line_start: u32, line_end: u32) -> Span {
+    Span {
+        file_name: file_name.to_string(),
+        line_start,
+        line_end,
+        column_start: 1,
+        column_end: 1,
+        is_primary: true,
+        ..Span::default()
+    }
+}
+
+/// A lint of the `cargo-scout` package
+pub fn lint(code: &str, level: Level, rendered: &str, spans: Vec<Span>) -> Lint {
+    Lint {
+        package_id: "cargo-scout".to_string(),
+        message: Some(Message {
+            rendered: rendered.to_string(),
+            code: Some(Code {
+                code: code.to_string(),
+                explanation: None,
+            }),
+            level: Some(level),
+            spans,
+            ..Message::default()
+        }),
+        ..Lint::default()
+    }
+}
+
+/// A lint of the `cargo-scout` package on the `lines` of `file_name`,
+/// from the fifth to the ninth column
+pub fn lint_at(
+    code: &str,
+    level: Level,
+    rendered: &str,
+    file_name: &str,
+    lines: (u32, u32),
+) -> Lint {
+    let span = Span {
+        column_start: 5,
+        column_end: 9,
+        ..span(file_name, lines.0, lines.1)
+    };
+    lint(code, level, rendered, vec![span])
+}
@@ -0,0 +1,181 @@
+use crate::linter::audit::subcommand_error;
+use crate::linter::clippy::workspace_root;
+use crate::linter::{lockfile_span, package_id, Code, Level, Lint, Linter, Message};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The code of the findings `CargoGeiger` reports
+pub const GEIGER_CODE: &str = "geiger::unsafe_code";
+
+/// Reports the dependencies that use unsafe code, as `cargo geiger` counts it.
+///
+/// The findings are on the lines of the package in `Cargo.lock`,
+/// so a diff adding such a dependency is responsible for them.
+/// Local crates aren't reported, clippy lints their unsafe code.
+#[derive(Default)]
+pub struct CargoGeiger;
+
+impl Linter for CargoGeiger {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        log::debug!(
+            "[CargoGeiger] - counting the unsafe code of the dependencies of directory {}",
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        let output = Command::new("cargo")
+            .current_dir(&working_dir)
+            .args(["geiger", "--output-format", "Json"])
+            .output()
+            .map_err(|e| crate::error::Error::spawn("cargo", e))?;
+        // cargo geiger exits with an error when unsafe code is found
+        let report: Report = serde_json::from_slice(&output.stdout)
+            .map_err(|_| subcommand_error("geiger", &output))?;
+        let lockfile = workspace_root(&working_dir)?.join("Cargo.lock");
+        Ok(lints(&report, &lockfile, &package_id(&working_dir)))
+    }
+}
+
+#[derive(Deserialize)]
+struct Report {
+    packages: Vec<PackageReport>,
+}
+
+#[derive(Deserialize)]
+struct PackageReport {
+    package: Package,
+    unsafety: Unsafety,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: PackageId,
+}
+
+#[derive(Deserialize)]
+struct PackageId {
+    name: String,
+    version: String,
+    // Local crates have none
+    #[serde(default)]
+    source: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Unsafety {
+    used: Counters,
+}
+
+#[derive(Deserialize)]
+struct Counters {
+    functions: Count,
+    exprs: Count,
+    item_impls: Count,
+    item_traits: Count,
+    methods: Count,
+}
+
+#[derive(Deserialize)]
+struct Count {
+    unsafe_: u64,
+}
+
+fn lints(report: &Report, lockfile: &Path, package_id: &str) -> Vec<Lint> {
+    report
+        .packages
+        .iter()
+        .filter(|p| p.package.id.source.as_ref().is_some_and(|s| !s.is_null()))
+        .filter_map(|p| {
+            let used = &p.unsafety.used;
+            let count = used.functions.unsafe_
+                + used.exprs.unsafe_
+                + used.item_impls.unsafe_
+                + used.item_traits.unsafe_
+                + used.methods.unsafe_;
+            if count == 0 {
+                return None;
+            }
+            let id = &p.package.id;
+            let spans: Vec<_> = lockfile_span(lockfile, &id.name, &id.version)
+                .into_iter()
+                .collect();
+            let location = spans
+                .first()
+                .map(|s| format!("  --> Cargo.lock:{}:{}\n", s.line_start, s.column_start))
+                .unwrap_or_default();
+            let message = format!(
+                "{} {} uses unsafe code in {} places",
+                id.name, id.version, count
+            );
+            Some(Lint {
+                package_id: package_id.to_string(),
+                src_path: None,
+                reproducer: Some("cargo geiger".to_string()),
+                message: Some(Message {
+                    rendered: format!("warning[{GEIGER_CODE}]: {message}\n{location}"),
+                    message,
+                    code: Some(Code {
+                        code: GEIGER_CODE.to_string(),
+                        explanation: None,
+                    }),
+                    level: Some(Level::Warning),
+                    spans,
+                    children: Vec::new(),
+                }),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, source: &str, unsafe_exprs: u64) -> String {
+        let count = |n: u64| format!(r#"{{"safe": 3, "unsafe_": {}}}"#, n);
+        let counters = |n: u64| {
+            format!(
+                r#"{{"functions": {0}, "exprs": {1}, "item_impls": {0}, "item_traits": {0}, "methods": {0}}}"#,
+                count(0),
+                count(n)
+            )
+        };
+        format!(
+            r#"{{
+                "package": {{"id": {{"name": "{}", "version": "1.0.0", "source": {}}}, "dependencies": []}},
+                "unsafety": {{"used": {}, "unused": {}, "forbids_unsafe": false}}
+            }}"#,
+            name,
+            source,
+            counters(unsafe_exprs),
+            counters(1)
+        )
+    }
+
+    #[test]
+    fn test_lints() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let lockfile = dir.path().join("Cargo.lock");
+        std::fs::write(
+            &lockfile,
+            "[[package]]\nname = \"bar\"\nversion = \"1.0.0\"\n\n[[package]]\nname = \"libc\"\nversion = \"1.0.0\"\n",
+        )?;
+        let registry = r#"{"Registry": {"name": "crates.io", "url": "https://github.com/rust-lang/crates.io-index"}}"#;
+        let report = format!(
+            r#"{{"packages": [{}, {}, {}], "packages_without_metrics": [], "used_but_not_scanned_files": []}}"#,
+            // A local crate, and a dependency that only has unused unsafe code
+            package("bar", "null", 2),
+            package("libc", registry, 4),
+            package("safe", registry, 0)
+        );
+        let report: Report = serde_json::from_str(&report)?;
+        let lints = lints(&report, &lockfile, "bar");
+        assert_eq!(1, lints.len());
+        assert_eq!(Some(GEIGER_CODE), lints[0].code());
+        assert_eq!(
+            "libc 1.0.0 uses unsafe code in 4 places",
+            lints[0].message.as_ref().unwrap().message
+        );
+        assert_eq!(6, lints[0].primary_span().unwrap().line_start);
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+use crate::linter::{
+    package_id, shown_path, Applicability, Code, DiffAwareLinter, Level, Lint, Linter, Message,
+    Span,
+};
+use crate::vcs::Section;
+use glob::Pattern;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+
+/// The code of the findings `LicenseHeaders` reports
+pub const LICENSE_HEADER_CODE: &str = "license_header";
+
+/// The header the files matching `paths` start with, as `[[license-headers]]` in `scout.toml`
+///
+/// # Example
+/// ```toml
+/// [[license-headers]]
+/// paths = ["**/*.rs"]
+/// header = """
+/// // Copyright {year} The Foo Authors
+/// // SPDX-License-Identifier: MIT
+/// """
+/// ```
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+pub struct LicenseHeader {
+    /// Globs matched against the files, relative to the current directory
+    pub paths: Vec<String>,
+    /// The first lines of the files, where `{year}` stands for any year
+    pub header: String,
+}
+
+/// Reports the new files that don't start with their license header.
+///
+/// A file is new when the diff adds all of its lines,
+/// the first header whose `paths` match it applies.
+/// Without a diff, every file matching a header is checked.
+pub struct LicenseHeaders {
+    headers: Vec<(Vec<Pattern>, Vec<Regex>, LicenseHeader)>,
+}
+
+impl LicenseHeaders {
+    /// Compiles the globs and header lines of each header.
+    ///
+    /// # Errors
+    /// Fails if one of the globs is invalid.
+    pub fn new(headers: Vec<LicenseHeader>) -> Result<Self, crate::error::Error> {
+        let headers = headers
+            .into_iter()
+            .map(|h| -> Result<_, crate::error::Error> {
+                let paths = h
+                    .paths
+                    .iter()
+                    .map(|p| Pattern::new(p))
+                    .collect::<Result<_, _>>()?;
+                let lines = h
+                    .header
+                    .lines()
+                    .map(|l| {
+                        let line = regex::escape(l.trim_end()).replace(r"\{year\}", r"\d{4}");
+                        Regex::new(&format!("^{line}$"))
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok((paths, lines, h))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { headers })
+    }
+
+    // The header lines of the first header matching the file
+    fn header(&self, file: &Path) -> Option<(&[Regex], &LicenseHeader)> {
+        let shown = shown_path(file).to_string_lossy().replace('\\', "/");
+        self.headers
+            .iter()
+            .find(|(paths, _, _)| paths.iter().any(|p| p.matches(&shown)))
+            .map(|(_, lines, header)| (&lines[..], header))
+    }
+
+    fn check(&self, package_id: &str, files: &[PathBuf]) -> Vec<Lint> {
+        files
+            .iter()
+            .filter_map(|file| {
+                let (lines, header) = self.header(file)?;
+                let contents = std::fs::read_to_string(file).ok()?;
+                let mismatch = mismatch(lines, &contents)?;
+                Some(lint(package_id, file, header, mismatch))
+            })
+            .collect()
+    }
+}
+
+impl Linter for LicenseHeaders {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        log::debug!(
+            "[LicenseHeaders] - checking the license headers in directory {}",
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        let pattern = working_dir.join("**").join("*");
+        let files: Vec<PathBuf> = glob::glob(&pattern.to_string_lossy())?
+            .filter_map(Result::ok)
+            .filter(|f| f.is_file())
+            .filter(|f| {
+                !f.strip_prefix(&working_dir)
+                    .is_ok_and(|f| f.starts_with("target"))
+            })
+            .collect();
+        Ok(self.check(&package_id(&working_dir), &files))
+    }
+
+    fn configuration(&self) -> String {
+        self.headers
+            .iter()
+            .map(|(_, _, h)| format!("{}={:?}", h.paths.join(","), h.header))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+        Some(self)
+    }
+
+    fn builds(&self) -> bool {
+        false
+    }
+}
+
+impl DiffAwareLinter for LicenseHeaders {
+    /// Without the added lines, every changed file is checked.
+    fn lints_for_files(
+        &self,
+        working_dir: PathBuf,
+        changed_files: &[PathBuf],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let files: Vec<PathBuf> = changed_files
+            .iter()
+            .filter(|f| f.starts_with(&working_dir))
+            .cloned()
+            .collect();
+        Ok(self.check(&package_id(&working_dir), &files))
+    }
+
+    /// Only checks the files the sections add.
+    fn lints_for_sections(
+        &self,
+        working_dir: PathBuf,
+        sections: &[Section],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let files: Vec<PathBuf> = new_files(sections)
+            .into_iter()
+            .filter(|f| f.starts_with(&working_dir))
+            .collect();
+        log::debug!(
+            "[LicenseHeaders] - checking the license headers of {} new files in directory {}",
+            files.len(),
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        Ok(self.check(&package_id(&working_dir), &files))
+    }
+}
+
+// The files whose lines have all been added by the sections
+fn new_files(sections: &[Section]) -> Vec<PathBuf> {
+    let mut added: BTreeMap<&str, Vec<u32>> = BTreeMap::new();
+    for section in sections {
+        added
+            .entry(&section.file_name)
+            .or_default()
+            .extend(section.added_lines.iter().map(|l| l.line));
+    }
+    added
+        .into_iter()
+        .filter_map(|(file, mut lines)| {
+            lines.sort_unstable();
+            lines.dedup();
+            let contents = std::fs::read_to_string(file).ok()?;
+            let count = contents.lines().count();
+            let all_added = !lines.is_empty()
+                && lines.len() == count
+                && lines.iter().enumerate().all(|(i, l)| *l as usize == i + 1);
+            if all_added {
+                Some(PathBuf::from(file))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// What is wrong with the header of a file
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Mismatch {
+    // The file doesn't start with the header
+    Missing,
+    // The line of the file that differs from the header, starting at 1
+    Incorrect(usize),
+}
+
+fn mismatch(header: &[Regex], contents: &str) -> Option<Mismatch> {
+    let mut lines = contents.lines();
+    let line = header
+        .iter()
+        .position(|h| lines.next().is_none_or(|l| !h.is_match(l.trim_end())))?;
+    if line == 0 {
+        Some(Mismatch::Missing)
+    } else {
+        Some(Mismatch::Incorrect(line + 1))
+    }
+}
+
+fn lint(package_id: &str, file: &Path, header: &LicenseHeader, mismatch: Mismatch) -> Lint {
+    let file_name = file.to_string_lossy().to_string();
+    let (message, line) = match mismatch {
+        Mismatch::Missing => ("the license header is missing".to_string(), 1),
+        Mismatch::Incorrect(line) => (
+            format!("the license header differs from the template on line {line}"),
+            line,
+        ),
+    };
+    let line = u32::try_from(line).unwrap_or(u32::MAX);
+    let rendered = format!(
+        "warning[{}]: {}\n --> {}:{}:1\n  = note: the files matching {} start with:\n{}\n",
+        LICENSE_HEADER_CODE,
+        message,
+        shown_path(file).display(),
+        line,
+        header.paths.join(", "),
+        header.header.trim_end()
+    );
+    // A missing header can be inserted, unless it needs a year
+    let (suggested_replacement, suggestion_applicability) =
+        if mismatch == Mismatch::Missing && !header.header.contains("{year}") {
+            (
+                Some(format!("{}\n", header.header.trim_end())),
+                Some(Applicability::MachineApplicable),
+            )
+        } else {
+            (None, None)
+        };
+    Lint {
+        package_id: package_id.to_string(),
+        src_path: Some(file_name.clone()),
+        reproducer: None,
+        message: Some(Message {
+            message,
+            rendered,
+            code: Some(Code {
+                code: LICENSE_HEADER_CODE.to_string(),
+                explanation: None,
+            }),
+            level: Some(Level::Warning),
+            spans: vec![Span {
+                file_name,
+                line_start: line,
+                line_end: line,
+                column_start: 1,
+                column_end: 1,
+                suggested_replacement,
+                suggestion_applicability,
+                is_primary: true,
+                ..Span::default()
+            }],
+            children: Vec::new(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vcs::AddedLine;
+
+    fn header(paths: &[&str], header: &str) -> LicenseHeader {
+        LicenseHeader {
+            paths: paths.iter().map(ToString::to_string).collect(),
+            header: header.to_string(),
+        }
+    }
+
+    fn section(file: &Path, lines: &[u32]) -> Section {
+        Section {
+            file_name: file.to_string_lossy().to_string(),
+            line_start: 1,
+            line_end: 3,
+            added_lines: lines
+                .iter()
+                .map(|&line| AddedLine {
+                    line,
+                    text: String::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_mismatch() -> Result<(), crate::error::Error> {
+        let headers =
+            LicenseHeaders::new(vec![header(&["*.rs"], "// Copyright {year} Foo\n// MIT\n")])?;
+        let lines = &headers.headers[0].1;
+        assert_eq!(
+            None,
+            mismatch(lines, "// Copyright 2021 Foo\n// MIT \nfn foo() {}")
+        );
+        assert_eq!(Some(Mismatch::Missing), mismatch(lines, "fn foo() {}\n"));
+        assert_eq!(
+            Some(Mismatch::Incorrect(2)),
+            mismatch(lines, "// Copyright 2021 Foo\n// Apache-2.0\n")
+        );
+        assert_eq!(
+            Some(Mismatch::Incorrect(2)),
+            mismatch(lines, "// Copyright 2021 Foo\n")
+        );
+        assert_eq!(
+            Some(Mismatch::Missing),
+            mismatch(lines, "// Copyright 20 Foo\n")
+        );
+        assert!(LicenseHeaders::new(vec![header(&["[*.rs"], "")]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lints_for_sections() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let new = dir.path().join("new.rs");
+        let modified = dir.path().join("modified.rs");
+        let licensed = dir.path().join("licensed.rs");
+        std::fs::write(&new, "fn foo() {}\nfn bar() {}\n")?;
+        std::fs::write(&modified, "fn foo() {}\nfn bar() {}\n")?;
+        std::fs::write(&licensed, "// MIT\nfn foo() {}\n")?;
+        let headers = LicenseHeaders::new(vec![
+            header(&["**/licensed.rs", "**/new.rs"], "// MIT\n"),
+            header(&["**/*.rs"], "// {year}\n"),
+        ])?;
+        let sections = [
+            section(&new, &[1]),
+            section(&new, &[2]),
+            section(&modified, &[2]),
+            section(&licensed, &[1, 2]),
+        ];
+        let lints = headers.lints_for_sections(dir.path().to_path_buf(), &sections)?;
+        assert_eq!(1, lints.len());
+        assert_eq!(Some(LICENSE_HEADER_CODE), lints[0].code());
+        let span = lints[0].primary_span().unwrap();
+        assert_eq!(new.to_string_lossy(), span.file_name);
+        assert_eq!(Some("// MIT\n"), span.suggested_replacement.as_deref());
+
+        // The existing files are checked too without a diff, with their own header
+        let lints = headers.lints(dir.path().to_path_buf())?;
+        assert_eq!(2, lints.len());
+        let span = lints[0].primary_span().unwrap();
+        assert_eq!(modified.to_string_lossy(), span.file_name);
+        assert_eq!(None, span.suggested_replacement);
+        Ok(())
+    }
+}
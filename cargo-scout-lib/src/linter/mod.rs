@@ -1,13 +1,305 @@
-use serde::Deserialize;
-use std::path::PathBuf;
+use crate::cache::StableHasher;
+use crate::vcs::Section;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use std::convert::TryFrom;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
 
+pub mod audit;
 pub mod clippy;
+pub mod coverage;
+pub mod doc;
+pub mod explain;
+pub mod geiger;
+pub mod license_headers;
+pub mod rustfmt;
+pub mod text_patterns;
+pub mod typos;
+pub mod unsafe_code;
+pub mod wrapper;
+
+#[cfg(test)]
+pub(crate) mod fixtures;
 
 pub trait Linter {
+    /// Returns the lints found in `working_dir`.
+    ///
+    /// # Errors
+    /// Fails if the linter can't run, or if its output can't be read.
     fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error>;
+
+    /// Describes the settings that change what the linter reports,
+    /// so cached lints aren't reused across configurations.
+    fn configuration(&self) -> String {
+        String::new()
+    }
+
+    /// The linter as a `DiffAwareLinter`, if it makes use of the diff.
+    fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+        None
+    }
+
+    /// Whether the linter builds the code, as clippy does.
+    fn builds(&self) -> bool {
+        true
+    }
+
+    /// Returns the lints found in the workspace members in `members`,
+    /// giving the changed sections to the linter if it's diff aware.
+    ///
+    /// The members are linted one at a time, a linter that checks
+    /// several crates at once, as clippy does, can override it to run once.
+    ///
+    /// # Errors
+    /// Fails if the linter fails on one of the members.
+    fn lints_for_members(
+        &self,
+        members: &[PathBuf],
+        sections: Option<&[Section]>,
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut lints = Vec::new();
+        for member in members {
+            lints.extend(match sections {
+                Some(sections) => lints_for_sections(self, member.clone(), sections)?,
+                None => self.lints(member.clone())?,
+            });
+        }
+        Ok(lints)
+    }
+
+    /// The lints of the diff aware linters that don't build the code,
+    /// for the diffs that only change comments or are too large to build.
+    ///
+    /// # Errors
+    /// Fails if one of the linters fails.
+    fn lints_without_build(
+        &self,
+        working_dir: PathBuf,
+        sections: &[Section],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        match self.diff_aware() {
+            Some(linter) if !self.builds() => linter.lints_for_sections(working_dir, sections),
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+/// A `Linter` that is told which files the diff changes before it runs,
+/// e.g. to only check those files instead of the whole member.
+pub trait DiffAwareLinter: Linter {
+    /// Same as `lints`, knowing the changed files, which can be outside of `working_dir`.
+    ///
+    /// They are absolute when scout knows the repository root, from its `PathNormalizer`.
+    ///
+    /// # Errors
+    /// Fails if the linter can't run, or if its output can't be read.
+    fn lints_for_files(
+        &self,
+        working_dir: PathBuf,
+        changed_files: &[PathBuf],
+    ) -> Result<Vec<Lint>, crate::error::Error>;
+
+    /// Same as `lints_for_files`, knowing the changed sections and the lines they add,
+    /// e.g. to only check the added lines.
+    ///
+    /// Their file names are absolute when scout knows the repository root.
+    ///
+    /// # Errors
+    /// Fails if the linter can't run, or if its output can't be read.
+    fn lints_for_sections(
+        &self,
+        working_dir: PathBuf,
+        sections: &[Section],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let files: BTreeSet<&str> = sections.iter().map(|s| s.file_name.as_str()).collect();
+        let files: Vec<PathBuf> = files.into_iter().map(PathBuf::from).collect();
+        self.lints_for_files(working_dir, &files)
+    }
+}
+
+/// Gives the changed sections to the linter if it's diff aware.
+pub(crate) fn lints_for_sections<L: Linter + ?Sized>(
+    linter: &L,
+    working_dir: PathBuf,
+    sections: &[Section],
+) -> Result<Vec<Lint>, crate::error::Error> {
+    match linter.diff_aware() {
+        Some(linter) => linter.lints_for_sections(working_dir, sections),
+        None => linter.lints(working_dir),
+    }
+}
+
+impl<L: Linter + ?Sized> Linter for Box<L> {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        (**self).lints(working_dir)
+    }
+
+    fn configuration(&self) -> String {
+        (**self).configuration()
+    }
+
+    fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+        (**self).diff_aware()
+    }
+
+    fn builds(&self) -> bool {
+        (**self).builds()
+    }
+
+    fn lints_for_members(
+        &self,
+        members: &[PathBuf],
+        sections: Option<&[Section]>,
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        (**self).lints_for_members(members, sections)
+    }
+
+    fn lints_without_build(
+        &self,
+        working_dir: PathBuf,
+        sections: &[Section],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        (**self).lints_without_build(working_dir, sections)
+    }
 }
 
-#[derive(Deserialize, PartialEq, Debug, Clone)]
+/// A `Linter` that can run without blocking the thread, for `Scout::run_async`.
+#[cfg(feature = "async")]
+pub trait AsyncLinter: Linter {
+    fn lints_async(
+        &self,
+        working_dir: PathBuf,
+    ) -> impl std::future::Future<Output = Result<Vec<Lint>, crate::error::Error>>;
+
+    /// Same as `Linter::lints_for_members`, without blocking the thread.
+    ///
+    /// Diff aware linters are given the changed sections, like `Scout::run` does.
+    /// They don't build the code, so they run on the thread.
+    fn lints_for_members_async(
+        &self,
+        members: &[PathBuf],
+        sections: Option<&[Section]>,
+    ) -> impl std::future::Future<Output = Result<Vec<Lint>, crate::error::Error>> {
+        async move {
+            let mut lints = Vec::new();
+            for member in members {
+                lints.extend(match (sections, self.diff_aware()) {
+                    (Some(sections), Some(linter)) => {
+                        linter.lints_for_sections(member.clone(), sections)?
+                    }
+                    _ => self.lints_async(member.clone()).await?,
+                });
+            }
+            Ok(lints)
+        }
+    }
+}
+
+/// Runs every linter, and reports all of their lints.
+impl<L: Linter> Linter for Vec<L> {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut lints = Vec::new();
+        for linter in self {
+            lints.extend(linter.lints(working_dir.clone())?);
+        }
+        Ok(lints)
+    }
+
+    fn configuration(&self) -> String {
+        self.iter()
+            .map(Linter::configuration)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+        Some(self)
+    }
+
+    fn builds(&self) -> bool {
+        self.iter().any(Linter::builds)
+    }
+
+    fn lints_for_members(
+        &self,
+        members: &[PathBuf],
+        sections: Option<&[Section]>,
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut lints = Vec::new();
+        for linter in self {
+            lints.extend(linter.lints_for_members(members, sections)?);
+        }
+        Ok(lints)
+    }
+
+    fn lints_without_build(
+        &self,
+        working_dir: PathBuf,
+        sections: &[Section],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut lints = Vec::new();
+        for linter in self {
+            lints.extend(linter.lints_without_build(working_dir.clone(), sections)?);
+        }
+        Ok(lints)
+    }
+}
+
+/// Gives the changed files to the linters that are diff aware.
+impl<L: Linter> DiffAwareLinter for Vec<L> {
+    fn lints_for_files(
+        &self,
+        working_dir: PathBuf,
+        changed_files: &[PathBuf],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut lints = Vec::new();
+        for linter in self {
+            lints.extend(match linter.diff_aware() {
+                Some(linter) => linter.lints_for_files(working_dir.clone(), changed_files)?,
+                None => linter.lints(working_dir.clone())?,
+            });
+        }
+        Ok(lints)
+    }
+
+    fn lints_for_sections(
+        &self,
+        working_dir: PathBuf,
+        sections: &[Section],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut lints = Vec::new();
+        for linter in self {
+            lints.extend(lints_for_sections(linter, working_dir.clone(), sections)?);
+        }
+        Ok(lints)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<L: AsyncLinter> AsyncLinter for Vec<L> {
+    async fn lints_async(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut lints = Vec::new();
+        for linter in self {
+            lints.extend(linter.lints_async(working_dir.clone()).await?);
+        }
+        Ok(lints)
+    }
+
+    async fn lints_for_members_async(
+        &self,
+        members: &[PathBuf],
+        sections: Option<&[Section]>,
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut lints = Vec::new();
+        for linter in self {
+            lints.extend(linter.lints_for_members_async(members, sections).await?);
+        }
+        Ok(lints)
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
 /// A `Linter`s output is a `Vec<Lint>`
 pub struct Lint {
     /// The package id
@@ -16,29 +308,353 @@ pub struct Lint {
     pub package_id: String,
     /// The file the lint was reported on
     /// Example:
-    /// Some("src/lib.rs".to_string())
+    /// `Some("src/lib.rs".to_string())`
     pub src_path: Option<String>,
+    /// A command that reproduces the lint on its own, when the linter knows one
+    /// Example:
+    /// `Some("cd foo && cargo +stable clippy -p foo -- -W clippy::pedantic".to_string())`
+    #[serde(default)]
+    pub reproducer: Option<String>,
     /// The message structure
     pub message: Option<Message>,
 }
 
-#[derive(Deserialize, PartialEq, Debug, Clone)]
+impl Lint {
+    /// The lint identifier, if the linter reported one
+    /// Example:
+    /// `Some("clippy::module_name_repetitions")`
+    #[must_use]
+    pub fn code(&self) -> Option<&str> {
+        self.message
+            .as_ref()
+            .and_then(|m| m.code.as_ref())
+            .map(|c| c.code.as_str())
+    }
+
+    /// The severity of the lint, if the linter reported one
+    #[must_use]
+    pub fn level(&self) -> Option<Level> {
+        self.message.as_ref().and_then(|m| m.level)
+    }
+
+    /// A stable identifier of the finding, made of its code, file and message.
+    ///
+    /// The line number isn't part of it,
+    /// so the fingerprint survives unrelated edits to the file.
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        let file_name = self
+            .primary_span()
+            .map(|s| s.file_name.replace('\\', "/"))
+            .unwrap_or_default();
+        let first_line = self
+            .message
+            .as_ref()
+            .and_then(|m| m.rendered.lines().next())
+            .unwrap_or_default();
+        StableHasher::default()
+            .write(self.code().unwrap_or_default().as_bytes())
+            .write(file_name.as_bytes())
+            .write(first_line.as_bytes())
+            .finish()
+    }
+
+    /// Where the lint was reported, see `Message::primary_spans`
+    #[must_use]
+    pub fn primary_span(&self) -> Option<&Span> {
+        self.message
+            .as_ref()
+            .and_then(|m| m.primary_spans().into_iter().next())
+    }
+}
+
+/// Removes the lints reported more than once, keeping the first one.
+///
+/// Clippy reports the same lint once per target a file is compiled for,
+/// such as the library and its tests. Lints are the same when they have the same code,
+/// file, primary span and message, whatever crate or target reported them.
+///
+/// # Example
+/// ```
+/// # use cargo_scout_lib::linter::{dedup, Lint};
+/// let lint = Lint {
+///     package_id: "foo".to_string(),
+///     src_path: Some("src/lib.rs".to_string()),
+///     reproducer: None,
+///     message: None,
+/// };
+/// let mut test_target = lint.clone();
+/// test_target.src_path = Some("tests/it.rs".to_string());
+/// assert_eq!(vec![lint.clone()], dedup(vec![lint, test_target]));
+/// ```
+#[must_use]
+pub fn dedup(lints: Vec<Lint>) -> Vec<Lint> {
+    let mut seen = HashSet::new();
+    let total = lints.len();
+    let lints: Vec<Lint> = lints
+        .into_iter()
+        .filter(|lint| {
+            let span = lint.primary_span();
+            seen.insert((
+                lint.code().map(ToString::to_string),
+                span.map(|s| {
+                    (
+                        s.file_name.replace('\\', "/"),
+                        s.line_start,
+                        s.line_end,
+                        s.column_start,
+                        s.column_end,
+                    )
+                }),
+                lint.message.as_ref().map(|m| m.message.clone()),
+                lint.message.as_ref().map(|m| m.rendered.clone()),
+            ))
+        })
+        .collect();
+    if lints.len() < total {
+        log::debug!(
+            "[Linter] - removed {} duplicated lints",
+            total - lints.len()
+        );
+    }
+    lints
+}
+
+// The name of the package in `working_dir`, or the name of the directory
+pub(crate) fn package_id(working_dir: &Path) -> String {
+    cargo_toml::Manifest::from_path(working_dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|m| m.package)
+        .map(|p| p.name)
+        .or_else(|| {
+            working_dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+        })
+        .unwrap_or_default()
+}
+
+// The path shown in rendered messages, relative to where scout runs like the compiler's
+pub(crate) fn shown_path(file: &Path) -> PathBuf {
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| file.strip_prefix(dir).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| file.to_path_buf())
+}
+
+// The `name` and `version` lines of a package in `Cargo.lock`,
+// so findings about dependencies are on the lines the diff changes when adding them
+pub(crate) fn lockfile_span(lockfile: &Path, name: &str, version: &str) -> Option<Span> {
+    let contents = std::fs::read_to_string(lockfile).ok()?;
+    let lines: Vec<&str> = contents.lines().collect();
+    let name_line = format!("name = \"{name}\"");
+    let version_line = format!("version = \"{version}\"");
+    let index = lines
+        .windows(2)
+        .position(|w| w[0].trim() == name_line && w[1].trim() == version_line)?;
+    let line = u32::try_from(index).ok()? + 1;
+    Some(Span {
+        file_name: lockfile.to_string_lossy().to_string(),
+        line_start: line,
+        line_end: line + 1,
+        column_start: 1,
+        column_end: u32::try_from(version_line.len()).ok()? + 1,
+        is_primary: true,
+        ..Span::default()
+    })
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
 /// This struct contains the message output,
 /// and a `Vec<Span>` with the message location
 pub struct Message {
+    /// The text of the message, without its location
+    /// Example:
+    /// unused variable: `count`
+    #[serde(default)]
+    pub message: String,
     /// The message string
     /// Example:
     /// unused variable `count`
+    ///
+    /// Child messages are not rendered, theirs is empty
+    #[serde(default, deserialize_with = "null_as_empty")]
     pub rendered: String,
+    /// The lint code, if any
+    #[serde(default)]
+    pub code: Option<Code>,
+    /// The severity of the message
+    #[serde(default)]
+    pub level: Option<Level>,
     /// The file names and lines the lint
     /// was reported on
     pub spans: Vec<Span>,
+    /// The help and note messages attached to this one
+    #[serde(default)]
+    pub children: Vec<Message>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+/// The identifier of the lint that has been raised
+pub struct Code {
+    /// The lint identifier
+    /// Example:
+    /// `"clippy::needless_pass_by_value"`
+    pub code: String,
+    /// A longer explanation of the lint, if available
+    pub explanation: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+/// The severity of a message
+pub enum Level {
+    Error,
+    Warning,
+    Note,
+    Help,
+    /// Any level the linter reported that we don't know about
+    #[serde(other)]
+    Other,
 }
 
-#[derive(Deserialize, PartialEq, Debug, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
 /// A `Span` has a file name, a start and an end line
 pub struct Span {
     pub file_name: String,
     pub line_start: u32,
     pub line_end: u32,
+    /// The first column of the span, starting at 1
+    #[serde(default)]
+    pub column_start: u32,
+    /// The column right after the end of the span
+    #[serde(default)]
+    pub column_end: u32,
+    /// The code the linter suggests to replace the span with
+    #[serde(default)]
+    pub suggested_replacement: Option<String>,
+    /// How confident the linter is that the suggestion is correct
+    #[serde(default)]
+    pub suggestion_applicability: Option<Applicability>,
+    /// The macro invocation the span was expanded from, if it's in a macro
+    #[serde(default)]
+    pub expansion: Option<Box<Expansion>>,
+    /// Whether the lint is about this span, the other ones only add context
+    #[serde(default)]
+    pub is_primary: bool,
+    /// What the span shows
+    /// Example:
+    /// `Some("value moved here".to_string())`
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+/// A macro invocation, as rustc reports it
+pub struct Expansion {
+    /// Where the macro was invoked, itself in another expansion for nested macros
+    pub span: Span,
+    /// Example:
+    /// "vec!"
+    pub macro_decl_name: String,
+}
+
+impl Span {
+    /// The spans of the macro invocations the span was expanded from,
+    /// from the innermost to the outermost one
+    #[must_use]
+    pub fn call_sites(&self) -> Vec<&Span> {
+        let mut call_sites = Vec::new();
+        let mut span = self;
+        while let Some(expansion) = &span.expansion {
+            span = &expansion.span;
+            call_sites.push(span);
+        }
+        call_sites
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+/// How confident the linter is that a suggestion is correct
+pub enum Applicability {
+    /// The suggestion is definitely what the user intended
+    MachineApplicable,
+    MaybeIncorrect,
+    HasPlaceholders,
+    Unspecified,
+}
+
+fn null_as_empty<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Option::<String>::deserialize(deserializer).map(Option::unwrap_or_default)
+}
+
+impl Message {
+    /// Renders the children as indented `= level: message` lines,
+    /// followed by the locations of their spans.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::linter::{Level, Message};
+    /// let child = Message {
+    ///     message: "remove this".to_string(),
+    ///     rendered: String::new(),
+    ///     code: None,
+    ///     level: Some(Level::Help),
+    ///     spans: Vec::new(),
+    ///     children: Vec::new(),
+    /// };
+    /// let message = Message {
+    ///     children: vec![child],
+    ///     ..Message::default()
+    /// };
+    /// assert_eq!("  = help: remove this\n", message.render_children("  "));
+    /// ```
+    #[must_use]
+    pub fn render_children(&self, indent: &str) -> String {
+        let mut output = String::new();
+        for child in &self.children {
+            let level = match child.level {
+                Some(Level::Error) => "error",
+                Some(Level::Warning) => "warning",
+                Some(Level::Help) => "help",
+                _ => "note",
+            };
+            let _ = writeln!(output, "{}= {}: {}", indent, level, child.message);
+            for span in &child.spans {
+                let _ = writeln!(
+                    output,
+                    "{}  --> {}:{}",
+                    indent, span.file_name, span.line_start
+                );
+            }
+        }
+        output
+    }
+
+    /// The spans the message is about, or all of them if the linter doesn't tell
+    #[must_use]
+    pub fn primary_spans(&self) -> Vec<&Span> {
+        let primary: Vec<&Span> = self.spans.iter().filter(|s| s.is_primary).collect();
+        if primary.is_empty() {
+            self.spans.iter().collect()
+        } else {
+            primary
+        }
+    }
+
+    /// The suggested replacements of this message and its children,
+    /// that can be applied without human review
+    #[must_use]
+    pub fn machine_applicable_suggestions(&self) -> Vec<&Span> {
+        self.spans
+            .iter()
+            .filter(|s| s.suggested_replacement.is_some())
+            .filter(|s| s.suggestion_applicability == Some(Applicability::MachineApplicable))
+            .chain(
+                self.children
+                    .iter()
+                    .flat_map(Message::machine_applicable_suggestions),
+            )
+            .collect()
+    }
 }
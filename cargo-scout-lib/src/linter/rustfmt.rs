@@ -0,0 +1,345 @@
+use crate::linter::{package_id, Code, DiffAwareLinter, Level, Lint, Linter, Message, Span};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// The code of the lints `RustFmt` reports
+pub const RUSTFMT_CODE: &str = "rustfmt";
+
+/// Reports the code that `cargo fmt` would reformat, one lint per diff hunk
+#[derive(Default)]
+pub struct RustFmt {
+    // Kept as the `+toolchain` argument cargo takes
+    toolchain: Option<String>,
+    config_path: Option<PathBuf>,
+    edition: Option<String>,
+}
+
+impl Linter for RustFmt {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        log::debug!(
+            "[RustFmt] - checking the formatting of directory {}",
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        let output = self
+            .cargo_command(&working_dir)
+            .output()
+            .map_err(|e| crate::error::Error::spawn("cargo", e))?;
+        checked(&output, &working_dir)
+    }
+
+    fn configuration(&self) -> String {
+        self.cargo_parameters().join(" ")
+    }
+
+    fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+        Some(self)
+    }
+
+    fn builds(&self) -> bool {
+        false
+    }
+}
+
+/// Only checks the Rust files the diff changes, by running rustfmt on them directly.
+///
+/// It's much faster than `cargo fmt` on large workspaces.
+impl DiffAwareLinter for RustFmt {
+    fn lints_for_files(
+        &self,
+        working_dir: PathBuf,
+        changed_files: &[PathBuf],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        // Deleted files are part of the diff too
+        let files: Vec<&PathBuf> = changed_files
+            .iter()
+            .filter(|f| f.extension().is_some_and(|e| e == "rs"))
+            .filter(|f| f.starts_with(&working_dir) && f.exists())
+            .collect();
+        log::debug!(
+            "[RustFmt] - checking the formatting of {} files in directory {}",
+            files.len(),
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        if files.is_empty() {
+            return Ok(Vec::new());
+        }
+        let output = self
+            .rustfmt_command(&working_dir)
+            .args(files)
+            .output()
+            .map_err(|e| crate::error::Error::spawn("rustfmt", e))?;
+        checked(&output, &working_dir)
+    }
+}
+
+// The lints of rustfmt's output, or the error it failed with
+fn checked(output: &Output, working_dir: &Path) -> Result<Vec<Lint>, crate::error::Error> {
+    let lints = lints(
+        &String::from_utf8_lossy(&output.stdout),
+        &package_id(working_dir),
+    );
+    // rustfmt exits with 1 when it finds diffs, and fails the same way on syntax errors
+    if !output.status.success() && lints.is_empty() {
+        return Err(crate::error::Error::Command(format!(
+            "rustfmt failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(lints)
+}
+
+impl RustFmt {
+    pub fn set_toolchain(&mut self, toolchain: Option<String>) -> &mut Self {
+        self.toolchain = toolchain;
+        self
+    }
+
+    /// The `rustfmt.toml` to use instead of the one rustfmt finds in the package.
+    pub fn set_config_path(&mut self, config_path: Option<PathBuf>) -> &mut Self {
+        self.config_path = config_path;
+        self
+    }
+
+    /// The edition to parse the files with, when rustfmt checks them directly.
+    ///
+    /// Defaults to the edition of the package's manifest.
+    pub fn set_edition(&mut self, edition: Option<String>) -> &mut Self {
+        self.edition = edition;
+        self
+    }
+
+    fn check_parameters(&self) -> Vec<String> {
+        let mut params: Vec<String> = ["--check", "--color", "never"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        if let Some(config_path) = &self.config_path {
+            params.push("--config-path".to_string());
+            params.push(config_path.display().to_string());
+        }
+        params
+    }
+
+    // cargo fmt passes the edition of the manifest on its own, and refuses another one
+    fn cargo_parameters(&self) -> Vec<String> {
+        let mut params = Vec::new();
+        if let Some(toolchain) = &self.toolchain {
+            params.push(format!("+{toolchain}"));
+        }
+        params.push("fmt".to_string());
+        params.push("--".to_string());
+        params.extend(self.check_parameters());
+        params
+    }
+
+    fn rustfmt_parameters(&self, working_dir: &Path) -> Vec<String> {
+        let mut params = Vec::new();
+        if let Some(toolchain) = &self.toolchain {
+            params.push(format!("+{toolchain}"));
+        }
+        params.extend(self.check_parameters());
+        if let Some(edition) = self.edition.clone().or_else(|| edition(working_dir)) {
+            params.push("--edition".to_string());
+            params.push(edition);
+        }
+        params
+    }
+
+    fn cargo_command(&self, working_dir: &Path) -> Command {
+        let mut command = Command::new("cargo");
+        command
+            .current_dir(working_dir)
+            .args(self.cargo_parameters());
+        command
+    }
+
+    fn rustfmt_command(&self, working_dir: &Path) -> Command {
+        let mut command = Command::new("rustfmt");
+        command
+            .current_dir(working_dir)
+            .args(self.rustfmt_parameters(working_dir));
+        command
+    }
+}
+
+// The edition of the manifest in `working_dir`, if it sets one.
+// cargo_toml doesn't know the recent editions, so the manifest is read as plain TOML
+fn edition(working_dir: &Path) -> Option<String> {
+    let manifest: toml::Value = std::fs::read_to_string(working_dir.join("Cargo.toml"))
+        .ok()?
+        .parse()
+        .ok()?;
+    manifest
+        .get("package")?
+        .get("edition")?
+        .as_str()
+        .map(ToString::to_string)
+}
+
+/// Turns the output of `cargo fmt -- --check` into lints.
+///
+/// Each hunk starts with `Diff in <file>:<line>:`,
+/// or `Diff in <file> at line <line>:` with older versions of rustfmt.
+fn lints(output: &str, package_id: &str) -> Vec<Lint> {
+    let mut hunks: Vec<(String, u32, Vec<&str>)> = Vec::new();
+    for line in output.lines() {
+        if let Some((file_name, line_start)) = hunk_header(line) {
+            hunks.push((file_name, line_start, Vec::new()));
+        } else if let Some((_, _, lines)) = hunks.last_mut() {
+            lines.push(line);
+        }
+    }
+    hunks
+        .into_iter()
+        .map(|(file_name, first_line, lines)| lint(package_id, file_name, first_line, &lines))
+        .collect()
+}
+
+fn hunk_header(line: &str) -> Option<(String, u32)> {
+    let location = line.strip_prefix("Diff in ")?.strip_suffix(':')?;
+    let (file_name, line_start) = match location.rsplit_once(" at line ") {
+        Some(old_style) => old_style,
+        None => location.rsplit_once(':')?,
+    };
+    Some((file_name.to_string(), line_start.parse().ok()?))
+}
+
+// The span covers the lines the hunk removes, or the line it inserts before
+fn lint(package_id: &str, file_name: String, first_line: u32, lines: &[&str]) -> Lint {
+    let mut line = first_line;
+    let mut changed: Option<(u32, u32)> = None;
+    for diff_line in lines {
+        match diff_line.chars().next() {
+            Some('-') => {
+                changed = Some(changed.map_or((line, line), |(start, _)| (start, line)));
+                line += 1;
+            }
+            Some('+') => {
+                changed.get_or_insert((line, line));
+            }
+            _ => line += 1,
+        }
+    }
+    let (line_start, line_end) = changed.unwrap_or((first_line, first_line));
+    let message = "this code is not formatted like `cargo fmt` would".to_string();
+    let rendered = format!(
+        "warning: {}\n --> {}:{}\n{}\n",
+        message,
+        file_name,
+        line_start,
+        lines.join("\n").trim_end()
+    );
+    Lint {
+        package_id: package_id.to_string(),
+        src_path: Some(file_name.clone()),
+        reproducer: Some("cargo fmt -- --check".to_string()),
+        message: Some(Message {
+            message,
+            rendered,
+            code: Some(Code {
+                code: RUSTFMT_CODE.to_string(),
+                explanation: None,
+            }),
+            level: Some(Level::Warning),
+            spans: vec![Span {
+                file_name,
+                line_start,
+                line_end,
+                column_start: 1,
+                column_end: 1,
+                is_primary: true,
+                ..Span::default()
+            }],
+            children: Vec::new(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lint: &Lint) -> (&str, u32, u32) {
+        let span = &lint.message.as_ref().unwrap().spans[0];
+        (span.file_name.as_str(), span.line_start, span.line_end)
+    }
+
+    #[test]
+    fn test_lints() {
+        let output = r#"Diff in /repo/src/main.rs:1:
+ fn main() {
+-    let x=1;
+-    println!("{}",x);
+-
+-
++    let x = 1;
++    println!("{}", x);
+
+     let y = 2;
+ }
+Diff in /repo/src/lib.rs at line 10:
+ pub fn foo() {}
++
+ pub fn bar() {}
+"#;
+        let lints = lints(output, "foo");
+        assert_eq!(2, lints.len());
+        assert_eq!(("/repo/src/main.rs", 2, 5), lines(&lints[0]));
+        assert_eq!(("/repo/src/lib.rs", 11, 11), lines(&lints[1]));
+        assert_eq!(Some(RUSTFMT_CODE), lints[0].code());
+        assert!(lints[1]
+            .message
+            .as_ref()
+            .unwrap()
+            .rendered
+            .ends_with("--> /repo/src/lib.rs:11\n pub fn foo() {}\n+\n pub fn bar() {}\n"));
+    }
+
+    #[test]
+    fn test_no_diff() {
+        assert!(lints("", "foo").is_empty());
+    }
+
+    #[test]
+    fn test_cargo_parameters() {
+        let mut rustfmt = RustFmt::default();
+        assert_eq!(
+            vec!["fmt", "--", "--check", "--color", "never"],
+            rustfmt.cargo_parameters()
+        );
+        rustfmt
+            .set_toolchain(Some("nightly".to_string()))
+            .set_config_path(Some(PathBuf::from("/repo/rustfmt.toml")))
+            .set_edition(Some("2021".to_string()));
+        assert_eq!(
+            vec![
+                "+nightly",
+                "fmt",
+                "--",
+                "--check",
+                "--color",
+                "never",
+                "--config-path",
+                "/repo/rustfmt.toml"
+            ],
+            rustfmt.cargo_parameters()
+        );
+    }
+
+    #[test]
+    fn test_rustfmt_parameters() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"foo\"\nedition = \"2021\"\n",
+        )?;
+        let mut rustfmt = RustFmt::default();
+        assert_eq!(
+            vec!["--check", "--color", "never", "--edition", "2021"],
+            rustfmt.rustfmt_parameters(dir.path())
+        );
+        rustfmt.set_edition(Some("2018".to_string()));
+        assert_eq!("2018", rustfmt.rustfmt_parameters(dir.path())[4]);
+        Ok(())
+    }
+}
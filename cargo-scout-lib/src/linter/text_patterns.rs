@@ -0,0 +1,293 @@
+use crate::linter::{
+    package_id, shown_path, Code, DiffAwareLinter, Level, Lint, Linter, Message, Span,
+};
+use crate::vcs::Section;
+use regex::Regex;
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+
+/// A pattern `TextPatterns` looks for, as `[[text-patterns]]` in `scout.toml`
+///
+/// # Example
+/// ```toml
+/// [[text-patterns]]
+/// name = "dbg"
+/// pattern = '\bdbg!'
+/// message = "`dbg!` is left in the code"
+/// ```
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+pub struct TextPattern {
+    /// The findings have the code `pattern::<name>`
+    pub name: String,
+    /// A regular expression, matched against each line
+    pub pattern: String,
+    /// What the findings say
+    pub message: String,
+}
+
+impl TextPattern {
+    fn new(name: &str, pattern: &str, message: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[must_use]
+    pub fn code(&self) -> String {
+        format!("pattern::{}", self.name)
+    }
+}
+
+/// The patterns used when `scout.toml` doesn't configure any:
+/// debug macros, prints, unimplemented code and TODO comments.
+#[must_use]
+pub fn default_patterns() -> Vec<TextPattern> {
+    vec![
+        TextPattern::new("dbg", r"\bdbg!", "`dbg!` is left in the code"),
+        TextPattern::new(
+            "println",
+            r"\be?println!",
+            "printing to the terminal, use a logger instead",
+        ),
+        TextPattern::new(
+            "unimplemented",
+            r"\b(unimplemented|todo)!",
+            "this code is not implemented yet",
+        ),
+        TextPattern::new("todo", r"\b(TODO|FIXME)\b", "a TODO is left in the code"),
+    ]
+}
+
+/// Reports the lines matching regular expressions, such as leftover `dbg!` calls.
+///
+/// Only the lines the diff adds are checked when there's a diff,
+/// without building anything.
+pub struct TextPatterns {
+    patterns: Vec<(TextPattern, Regex)>,
+}
+
+impl TextPatterns {
+    /// Compiles the patterns.
+    ///
+    /// # Errors
+    /// Fails if one of the patterns isn't a valid regular expression.
+    pub fn new(patterns: Vec<TextPattern>) -> Result<Self, crate::error::Error> {
+        let patterns = patterns
+            .into_iter()
+            .map(|p| Regex::new(&p.pattern).map(|regex| (p, regex)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    // One finding per pattern matching each line
+    fn scan<'a>(
+        &self,
+        package_id: &str,
+        file: &Path,
+        lines: impl Iterator<Item = (u32, &'a str)>,
+    ) -> Vec<Lint> {
+        let mut findings = Vec::new();
+        for (line, text) in lines {
+            for (pattern, regex) in &self.patterns {
+                if let Some(found) = regex.find(text) {
+                    let columns = (column(text, found.start()), column(text, found.end()));
+                    findings.push(lint(package_id, file, pattern, line, columns));
+                }
+            }
+        }
+        findings
+    }
+
+    fn scan_file(&self, package_id: &str, file: &Path) -> Vec<Lint> {
+        match std::fs::read_to_string(file) {
+            Ok(contents) => self.scan(package_id, file, numbered(contents.lines())),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl Linter for TextPatterns {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        log::debug!(
+            "[TextPatterns] - looking for patterns in directory {}",
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        let package_id = package_id(&working_dir);
+        let pattern = working_dir.join("**").join("*.rs");
+        Ok(glob::glob(&pattern.to_string_lossy())?
+            .filter_map(Result::ok)
+            .filter(|f| {
+                !f.strip_prefix(&working_dir)
+                    .is_ok_and(|f| f.starts_with("target"))
+            })
+            .flat_map(|f| self.scan_file(&package_id, &f))
+            .collect())
+    }
+
+    fn configuration(&self) -> String {
+        self.patterns
+            .iter()
+            .map(|(p, _)| format!("{}={}", p.name, p.pattern))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+        Some(self)
+    }
+
+    fn builds(&self) -> bool {
+        false
+    }
+}
+
+impl DiffAwareLinter for TextPatterns {
+    fn lints_for_files(
+        &self,
+        working_dir: PathBuf,
+        changed_files: &[PathBuf],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let package_id = package_id(&working_dir);
+        Ok(changed_files
+            .iter()
+            .filter(|f| f.starts_with(&working_dir))
+            .flat_map(|f| self.scan_file(&package_id, f))
+            .collect())
+    }
+
+    /// Only checks the lines the sections add.
+    fn lints_for_sections(
+        &self,
+        working_dir: PathBuf,
+        sections: &[Section],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let package_id = package_id(&working_dir);
+        Ok(sections
+            .iter()
+            .filter(|s| Path::new(&s.file_name).starts_with(&working_dir))
+            .flat_map(|s| {
+                let added = s.added_lines.iter().map(|l| (l.line, l.text.as_str()));
+                self.scan(&package_id, Path::new(&s.file_name), added)
+            })
+            .collect())
+    }
+}
+
+fn numbered<'a>(lines: impl Iterator<Item = &'a str>) -> impl Iterator<Item = (u32, &'a str)> {
+    lines
+        .enumerate()
+        .map(|(i, line)| (u32::try_from(i + 1).unwrap_or(u32::MAX), line))
+}
+
+// The column of a byte offset, in characters starting at 1 like rustc's
+fn column(text: &str, offset: usize) -> u32 {
+    u32::try_from(text[..offset].chars().count() + 1).unwrap_or(u32::MAX)
+}
+
+fn lint(
+    package_id: &str,
+    file: &Path,
+    pattern: &TextPattern,
+    line: u32,
+    (column_start, column_end): (u32, u32),
+) -> Lint {
+    let file_name = file.to_string_lossy().to_string();
+    let code = pattern.code();
+    let rendered = format!(
+        "warning[{}]: {}\n --> {}:{}:{}\n",
+        code,
+        pattern.message,
+        shown_path(file).display(),
+        line,
+        column_start
+    );
+    Lint {
+        package_id: package_id.to_string(),
+        src_path: Some(file_name.clone()),
+        reproducer: None,
+        message: Some(Message {
+            message: pattern.message.clone(),
+            rendered,
+            code: Some(Code {
+                code,
+                explanation: None,
+            }),
+            level: Some(Level::Warning),
+            spans: vec![Span {
+                file_name,
+                line_start: line,
+                line_end: line,
+                column_start,
+                column_end,
+                is_primary: true,
+                ..Span::default()
+            }],
+            children: Vec::new(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vcs::AddedLine;
+
+    fn codes(lints: &[Lint]) -> Vec<(&str, u32, u32)> {
+        lints
+            .iter()
+            .map(|l| {
+                let span = l.primary_span().unwrap();
+                (l.code().unwrap(), span.line_start, span.column_start)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_default_patterns() -> Result<(), crate::error::Error> {
+        let patterns = TextPatterns::new(default_patterns())?;
+        let source =
+            "fn foo() {\n    dbg!(1); // TODO(jane): remove\n    println!(\"é\"); todo!()\n}\n";
+        let lints = patterns.scan("foo", Path::new("src/lib.rs"), numbered(source.lines()));
+        assert_eq!(
+            vec![
+                ("pattern::dbg", 2, 5),
+                ("pattern::todo", 2, 17),
+                ("pattern::println", 3, 5),
+                ("pattern::unimplemented", 3, 20)
+            ],
+            codes(&lints)
+        );
+        assert!(TextPatterns::new(vec![TextPattern::new("bad", "(", "")]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lints_for_sections() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let file = dir.path().join("lib.rs");
+        std::fs::write(&file, "dbg!(1);\ndbg!(2);\ndbg!(3);\n")?;
+        let section = Section {
+            file_name: file.to_string_lossy().to_string(),
+            line_start: 1,
+            line_end: 4,
+            added_lines: vec![AddedLine {
+                line: 2,
+                text: "dbg!(2);".to_string(),
+            }],
+        };
+        let patterns = TextPatterns::new(default_patterns())?;
+        // The lines around the added one are only context
+        let lints = patterns
+            .lints_for_sections(dir.path().to_path_buf(), std::slice::from_ref(&section))?;
+        assert_eq!(vec![("pattern::dbg", 2, 1)], codes(&lints));
+        let outside = tempfile::TempDir::new()?;
+        assert!(patterns
+            .lints_for_sections(outside.path().to_path_buf(), &[section])?
+            .is_empty());
+        assert_eq!(3, patterns.lints(dir.path().to_path_buf())?.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+use crate::linter::{
+    package_id, shown_path, Applicability, Code, DiffAwareLinter, Level, Lint, Linter, Message,
+    Span,
+};
+use crate::vcs::Section;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashSet};
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The code of the findings `Typos` reports
+pub const TYPOS_CODE: &str = "typos";
+
+/// Reports the spelling mistakes `typos` finds in doc comments, identifiers and strings.
+///
+/// Only the lines the diff adds are checked when there's a diff,
+/// so existing typos don't get in the way.
+/// The `typos` executable of the typos-cli crate must be installed.
+#[derive(Default)]
+pub struct Typos;
+
+impl Linter for Typos {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        log::debug!(
+            "[Typos] - checking the spelling in directory {}",
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        let output = typos(&working_dir, std::slice::from_ref(&working_dir))?;
+        Ok(lints(&output, &working_dir))
+    }
+
+    fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+        Some(self)
+    }
+
+    fn builds(&self) -> bool {
+        false
+    }
+}
+
+impl DiffAwareLinter for Typos {
+    fn lints_for_files(
+        &self,
+        working_dir: PathBuf,
+        changed_files: &[PathBuf],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        // Deleted files are part of the diff too
+        let files: Vec<PathBuf> = changed_files
+            .iter()
+            .filter(|f| f.starts_with(&working_dir) && f.exists())
+            .cloned()
+            .collect();
+        if files.is_empty() {
+            return Ok(Vec::new());
+        }
+        let output = typos(&working_dir, &files)?;
+        Ok(lints(&output, &working_dir))
+    }
+
+    /// Only keeps the typos on the lines the sections add.
+    fn lints_for_sections(
+        &self,
+        working_dir: PathBuf,
+        sections: &[Section],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let files: BTreeSet<&str> = sections
+            .iter()
+            .filter(|s| !s.added_lines.is_empty())
+            .map(|s| s.file_name.as_str())
+            .collect();
+        let files: Vec<PathBuf> = files.into_iter().map(PathBuf::from).collect();
+        let lints = self.lints_for_files(working_dir, &files)?;
+        Ok(on_added_lines(lints, sections))
+    }
+}
+
+fn on_added_lines(lints: Vec<Lint>, sections: &[Section]) -> Vec<Lint> {
+    let added: HashSet<(&str, u32)> = sections
+        .iter()
+        .flat_map(|s| {
+            s.added_lines
+                .iter()
+                .map(move |l| (s.file_name.as_str(), l.line))
+        })
+        .collect();
+    lints
+        .into_iter()
+        .filter(|l| {
+            l.primary_span()
+                .is_some_and(|s| added.contains(&(s.file_name.as_str(), s.line_start)))
+        })
+        .collect()
+}
+
+// Runs typos on the files, and returns what it prints.
+// It exits with 2 when it finds typos
+fn typos(working_dir: &Path, files: &[PathBuf]) -> Result<String, crate::error::Error> {
+    let output = Command::new("typos")
+        .current_dir(working_dir)
+        .args(["--format", "json"])
+        .args(files)
+        .output()
+        .map_err(|e| crate::error::Error::spawn("typos", e))?;
+    if !output.status.success() && output.stdout.is_empty() {
+        return Err(crate::error::Error::Command(format!(
+            "typos failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+// One JSON object per line, typos are the ones of type `typo`
+#[derive(Deserialize)]
+struct Typo {
+    #[serde(rename = "type")]
+    kind: String,
+    path: String,
+    line_num: u32,
+    byte_offset: usize,
+    #[serde(rename = "typo")]
+    word: String,
+    #[serde(default)]
+    corrections: Vec<String>,
+}
+
+fn lints(output: &str, working_dir: &Path) -> Vec<Lint> {
+    let package_id = package_id(working_dir);
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Typo>(line).ok())
+        .filter(|typo| typo.kind == "typo")
+        .map(|typo| {
+            let file = working_dir.join(&typo.path);
+            let line = std::fs::read_to_string(&file)
+                .ok()
+                .and_then(|c| {
+                    c.lines()
+                        .nth((typo.line_num as usize).saturating_sub(1))
+                        .map(String::from)
+                })
+                .unwrap_or_default();
+            lint(&package_id, &file, &typo, &line)
+        })
+        .collect()
+}
+
+// The column of a byte offset, in characters starting at 1 like rustc's
+fn column(text: &str, offset: usize) -> u32 {
+    let chars = text.get(..offset).map_or(offset, |t| t.chars().count());
+    u32::try_from(chars + 1).unwrap_or(u32::MAX)
+}
+
+fn lint(package_id: &str, file: &Path, typo: &Typo, line: &str) -> Lint {
+    let file_name = file.to_string_lossy().to_string();
+    let column_start = column(line, typo.byte_offset);
+    let column_end = column(line, typo.byte_offset + typo.word.len());
+    let message = match typo.corrections.as_slice() {
+        [] => format!("`{}` is misspelled", typo.word),
+        corrections => format!(
+            "`{}` should be {}",
+            typo.word,
+            corrections
+                .iter()
+                .map(|c| format!("`{c}`"))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        ),
+    };
+    let rendered = format!(
+        "warning[{}]: {}\n --> {}:{}:{}\n",
+        TYPOS_CODE,
+        message,
+        shown_path(file).display(),
+        typo.line_num,
+        column_start
+    );
+    // Only a single correction can be applied without asking
+    let (suggested_replacement, suggestion_applicability) = match typo.corrections.as_slice() {
+        [correction] => (
+            Some(correction.clone()),
+            Some(Applicability::MachineApplicable),
+        ),
+        _ => (None, None),
+    };
+    Lint {
+        package_id: package_id.to_string(),
+        src_path: Some(file_name.clone()),
+        reproducer: Some(format!("typos {}", shown_path(file).display())),
+        message: Some(Message {
+            message,
+            rendered,
+            code: Some(Code {
+                code: TYPOS_CODE.to_string(),
+                explanation: None,
+            }),
+            level: Some(Level::Warning),
+            spans: vec![Span {
+                file_name,
+                line_start: typo.line_num,
+                line_end: typo.line_num,
+                column_start,
+                column_end,
+                suggested_replacement,
+                suggestion_applicability,
+                is_primary: true,
+                ..Span::default()
+            }],
+            children: Vec::new(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lints() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        std::fs::create_dir(dir.path().join("src"))?;
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "/// Retruns the lenght\nfn len() {}\n",
+        )?;
+        let output = r#"{"type":"typo","path":"src/lib.rs","line_num":1,"byte_offset":4,"typo":"Retruns","corrections":["Returns"]}
+{"type":"typo","path":"src/lib.rs","line_num":1,"byte_offset":16,"typo":"lenght","corrections":["length","lent"]}
+{"type":"binary_file","path":"logo.png"}
+"#;
+        let lints = lints(output, dir.path());
+        assert_eq!(2, lints.len());
+        let message = lints[0].message.as_ref().unwrap();
+        assert_eq!("`Retruns` should be `Returns`", message.message);
+        let span = lints[0].primary_span().unwrap();
+        assert_eq!(
+            (1, 5, 12),
+            (span.line_start, span.column_start, span.column_end)
+        );
+        assert_eq!(Some("Returns"), span.suggested_replacement.as_deref());
+        assert_eq!(
+            "`lenght` should be `length` or `lent`",
+            lints[1].message.as_ref().unwrap().message
+        );
+        assert_eq!(None, lints[1].primary_span().unwrap().suggested_replacement);
+
+        let file_name = dir.path().join("src/lib.rs").to_string_lossy().to_string();
+        let section = |line| Section {
+            file_name: file_name.clone(),
+            line_start: 1,
+            line_end: 3,
+            added_lines: vec![crate::vcs::AddedLine {
+                line,
+                text: String::new(),
+            }],
+        };
+        assert_eq!(2, on_added_lines(lints.clone(), &[section(1)]).len());
+        assert!(on_added_lines(lints, &[section(2)]).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+use crate::linter::{
+    package_id, shown_path, Code, DiffAwareLinter, Level, Lint, Linter, Message, Span,
+};
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+use syn::visit::{self, Visit};
+
+/// The code of the findings `UnsafeCode` reports
+pub const UNSAFE_CODE: &str = "unsafe_usage";
+
+/// Reports every `unsafe` block, function, impl and trait,
+/// for teams that review each of them.
+///
+/// Only the changed files are parsed when there's a diff,
+/// so the findings are the `unsafe` keywords the diff adds.
+/// Files that can't be parsed have no findings, the compiler reports them.
+#[derive(Default)]
+pub struct UnsafeCode;
+
+impl Linter for UnsafeCode {
+    fn lints(&self, working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+        log::debug!(
+            "[UnsafeCode] - looking for unsafe code in directory {}",
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        let pattern = working_dir.join("**").join("*.rs");
+        let files: Vec<PathBuf> = glob::glob(&pattern.to_string_lossy())?
+            .filter_map(Result::ok)
+            .filter(|f| {
+                !f.strip_prefix(&working_dir)
+                    .is_ok_and(|f| f.starts_with("target"))
+            })
+            .collect();
+        Ok(lints(&working_dir, &files))
+    }
+
+    fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+        Some(self)
+    }
+
+    fn builds(&self) -> bool {
+        false
+    }
+}
+
+impl DiffAwareLinter for UnsafeCode {
+    fn lints_for_files(
+        &self,
+        working_dir: PathBuf,
+        changed_files: &[PathBuf],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let files: Vec<PathBuf> = changed_files
+            .iter()
+            .filter(|f| f.extension().is_some_and(|e| e == "rs"))
+            .filter(|f| f.starts_with(&working_dir))
+            .cloned()
+            .collect();
+        log::debug!(
+            "[UnsafeCode] - looking for unsafe code in {} files in directory {}",
+            files.len(),
+            &working_dir.to_str().unwrap_or("<no directory>")
+        );
+        Ok(lints(&working_dir, &files))
+    }
+}
+
+fn lints(working_dir: &Path, files: &[PathBuf]) -> Vec<Lint> {
+    let package_id = package_id(working_dir);
+    files
+        .iter()
+        .filter_map(|file| {
+            let source = std::fs::read_to_string(file).ok()?;
+            Some(
+                unsafe_keywords(&source)
+                    .into_iter()
+                    .map(|(kind, span)| lint(&package_id, file, kind, span))
+                    .collect::<Vec<Lint>>(),
+            )
+        })
+        .flatten()
+        .collect()
+}
+
+// What the `unsafe` keywords of a file apply to, and where they are
+#[derive(Default)]
+struct UnsafeKeywords(Vec<(&'static str, proc_macro2::Span)>);
+
+impl<'ast> Visit<'ast> for UnsafeKeywords {
+    fn visit_expr_unsafe(&mut self, expr: &'ast syn::ExprUnsafe) {
+        self.0.push(("block", expr.unsafe_token.span));
+        visit::visit_expr_unsafe(self, expr);
+    }
+
+    fn visit_signature(&mut self, sig: &'ast syn::Signature) {
+        if let Some(unsafety) = &sig.unsafety {
+            self.0.push(("function", unsafety.span));
+        }
+        visit::visit_signature(self, sig);
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        if let Some(unsafety) = &item.unsafety {
+            self.0.push(("impl", unsafety.span));
+        }
+        visit::visit_item_impl(self, item);
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast syn::ItemTrait) {
+        if let Some(unsafety) = &item.unsafety {
+            self.0.push(("trait", unsafety.span));
+        }
+        visit::visit_item_trait(self, item);
+    }
+}
+
+fn unsafe_keywords(source: &str) -> Vec<(&'static str, proc_macro2::Span)> {
+    let mut keywords = UnsafeKeywords::default();
+    if let Ok(file) = syn::parse_file(source) {
+        keywords.visit_file(&file);
+    }
+    keywords.0
+}
+
+fn to_u32(n: usize) -> u32 {
+    u32::try_from(n).unwrap_or(u32::MAX)
+}
+
+// The span is on the `unsafe` keyword, proc_macro2 columns start at 0
+fn lint(package_id: &str, file: &Path, kind: &str, span: proc_macro2::Span) -> Lint {
+    let file_name = file.to_string_lossy().to_string();
+    let (start, end) = (span.start(), span.end());
+    let message = format!("unsafe {kind}");
+    let rendered = format!(
+        "warning[{}]: {}\n --> {}:{}:{}\n  = note: unsafe code must be reviewed\n",
+        UNSAFE_CODE,
+        message,
+        shown_path(file).display(),
+        start.line,
+        start.column + 1
+    );
+    Lint {
+        package_id: package_id.to_string(),
+        src_path: Some(file_name.clone()),
+        reproducer: None,
+        message: Some(Message {
+            message,
+            rendered,
+            code: Some(Code {
+                code: UNSAFE_CODE.to_string(),
+                explanation: None,
+            }),
+            level: Some(Level::Warning),
+            spans: vec![Span {
+                file_name,
+                line_start: to_u32(start.line),
+                line_end: to_u32(end.line),
+                column_start: to_u32(start.column + 1),
+                column_end: to_u32(end.column + 1),
+                is_primary: true,
+                ..Span::default()
+            }],
+            children: Vec::new(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"unsafe trait Foo {}
+
+unsafe impl Foo for u8 {}
+
+pub unsafe fn bar() {}
+
+fn baz() {
+    let x = unsafe { bar() };
+}
+
+// unsafe in a comment, and "unsafe" in a string
+"#;
+
+    #[test]
+    fn test_unsafe_keywords() {
+        let keywords: Vec<(&str, usize, usize)> = unsafe_keywords(SOURCE)
+            .into_iter()
+            .map(|(kind, span)| (kind, span.start().line, span.start().column))
+            .collect();
+        assert_eq!(
+            vec![
+                ("trait", 1, 0),
+                ("impl", 3, 0),
+                ("function", 5, 4),
+                ("block", 8, 12)
+            ],
+            keywords
+        );
+        assert!(unsafe_keywords("fn unparsable(").is_empty());
+    }
+
+    #[test]
+    fn test_lints_for_files() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let src = dir.path().join("src");
+        std::fs::create_dir(&src)?;
+        std::fs::write(src.join("lib.rs"), SOURCE)?;
+        std::fs::write(src.join("safe.rs"), "fn safe() {}\n")?;
+        let outside = tempfile::TempDir::new()?;
+        std::fs::write(outside.path().join("lib.rs"), SOURCE)?;
+
+        let lints = UnsafeCode.lints_for_files(
+            dir.path().to_path_buf(),
+            &[src.join("safe.rs"), outside.path().join("lib.rs")],
+        )?;
+        assert!(lints.is_empty());
+
+        let lints = UnsafeCode.lints_for_files(dir.path().to_path_buf(), &[src.join("lib.rs")])?;
+        assert_eq!(4, lints.len());
+        let block = &lints[3];
+        assert_eq!(Some(UNSAFE_CODE), block.code());
+        assert_eq!("unsafe block", block.message.as_ref().unwrap().message);
+        let span = block.primary_span().unwrap();
+        assert_eq!(
+            (8, 13, 19),
+            (span.line_start, span.column_start, span.column_end)
+        );
+
+        assert_eq!(lints, UnsafeCode.lints(dir.path().to_path_buf())?);
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use crate::linter::Lint;
+use serde_json::{json, Value};
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Set by `Clippy` for the wrapper, the directory the diagnostics are captured in
+pub const CAPTURE_DIR_ENV: &str = "CARGO_SCOUT_CAPTURE_DIR";
+/// Overrides the `clippy-driver` the wrapper runs
+pub const CLIPPY_DRIVER_ENV: &str = "CARGO_SCOUT_CLIPPY_DRIVER";
+
+/// Returns true if the current process has been started by cargo
+/// as a `RUSTC_WORKSPACE_WRAPPER` for a scout run.
+#[must_use]
+pub fn is_wrapper_invocation() -> bool {
+    std::env::var_os(CAPTURE_DIR_ENV).is_some()
+}
+
+/// Compiles a crate with `clippy-driver`, and writes its diagnostics
+/// to the capture directory, tagged with the package being compiled.
+///
+/// `args` are the arguments cargo passes to the wrapper:
+/// the path to rustc, then the rustc arguments.
+/// The diagnostics are forwarded to cargo unchanged,
+/// and the exit code of `clippy-driver` is returned.
+///
+/// # Errors
+/// Fails if `clippy-driver` can't be run, or if the diagnostics can't be captured.
+pub fn wrap(args: impl IntoIterator<Item = OsString>) -> Result<i32, crate::error::Error> {
+    let driver = std::env::var_os(CLIPPY_DRIVER_ENV).unwrap_or_else(|| "clippy-driver".into());
+    let output = Command::new(driver)
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| crate::error::Error::spawn("clippy-driver", e))?;
+    std::io::stderr().write_all(&output.stderr)?;
+
+    if let Some(dir) = std::env::var_os(CAPTURE_DIR_ENV) {
+        let package_id = package_id(
+            &std::env::var("CARGO_PKG_NAME").unwrap_or_default(),
+            &std::env::var("CARGO_PKG_VERSION").unwrap_or_default(),
+            &std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default(),
+        );
+        let captured: String = String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .filter_map(|line| capture_line(line, &package_id))
+            .map(|line| line + "\n")
+            .collect();
+        if !captured.is_empty() {
+            let crate_name = std::env::var("CARGO_CRATE_NAME").unwrap_or_default();
+            let file_name = format!("{}-{}.json", crate_name, std::process::id());
+            std::fs::write(Path::new(&dir).join(file_name), captured)?;
+        }
+    }
+    Ok(output.status.code().unwrap_or(1))
+}
+
+// The package id format cargo used before the package id spec
+fn package_id(name: &str, version: &str, manifest_dir: &str) -> String {
+    format!(
+        "{} {} (path+file://{})",
+        name,
+        version,
+        manifest_dir.replace('\\', "/")
+    )
+}
+
+// Wraps a rustc diagnostic into the structure cargo emits for it
+fn capture_line(line: &str, package_id: &str) -> Option<String> {
+    if !line.starts_with('{') {
+        return None;
+    }
+    let mut message: Value = serde_json::from_str(line).ok()?;
+    // Artifact notifications and the like aren't diagnostics
+    message.get("level")?;
+    // Cargo always asks for colored renderings, and strips them unless asked not to
+    if let Some(rendered) = message.get("rendered").and_then(Value::as_str) {
+        message["rendered"] = Value::String(strip_ansi(rendered));
+    }
+    Some(
+        json!({
+            "package_id": package_id,
+            "src_path": null,
+            "message": message,
+        })
+        .to_string(),
+    )
+}
+
+fn strip_ansi(rendered: &str) -> String {
+    let mut stripped = String::with_capacity(rendered.len());
+    let mut chars = rendered.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip the escape sequence, up to its final letter
+            chars.by_ref().find(char::is_ascii_alphabetic);
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Reads back the lints the wrapper captured in `dir`.
+///
+/// # Errors
+/// Fails if a capture file can't be read or parsed.
+pub fn captured_lints(dir: impl AsRef<Path>) -> Result<Vec<Lint>, crate::error::Error> {
+    let mut lints = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let contents = std::fs::read_to_string(entry?.path())?;
+        lints.extend(
+            contents
+                .lines()
+                .filter_map(|line| serde_json::from_str::<Lint>(line).ok())
+                .filter(|lint| lint.message.as_ref().is_some_and(|m| !m.spans.is_empty())),
+        );
+    }
+    Ok(lints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIAGNOSTIC: &str = r#"{"message":"redundant clone","code":{"code":"clippy::redundant_clone","explanation":null},"level":"warning","spans":[{"file_name":"src/lib.rs","line_start":2,"line_end":2,"column_start":5,"column_end":9}],"children":[],"rendered":"warning: redundant clone"}"#;
+
+    #[test]
+    fn test_capture_line() {
+        let package_id = package_id("foo", "0.1.0", "/repo/foo");
+        assert_eq!("foo 0.1.0 (path+file:///repo/foo)", package_id);
+        assert!(capture_line("warning: unused variable", &package_id).is_none());
+        assert!(capture_line(r#"{"artifact":"foo.rmeta"}"#, &package_id).is_none());
+
+        let captured = capture_line(DIAGNOSTIC, &package_id).unwrap();
+        let lint: Lint = serde_json::from_str(&captured).unwrap();
+        assert_eq!(package_id, lint.package_id);
+        assert_eq!(Some("clippy::redundant_clone"), lint.code());
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(
+            "warning: 2 warnings emitted",
+            strip_ansi("\u{1b}[1m\u{1b}[33mwarning\u{1b}[0m\u{1b}[1m: 2 warnings emitted\u{1b}[0m")
+        );
+    }
+
+    #[test]
+    fn test_captured_lints() {
+        let dir = tempfile::tempdir().unwrap();
+        let package_id = package_id("foo", "0.1.0", "/repo/foo");
+        let without_spans = r#"{"message":"1 warning emitted","code":null,"level":"warning","spans":[],"children":[],"rendered":"warning: 1 warning emitted"}"#;
+        let contents: String = [DIAGNOSTIC, without_spans]
+            .iter()
+            .filter_map(|line| capture_line(line, &package_id))
+            .map(|line| line + "\n")
+            .collect();
+        std::fs::write(dir.path().join("foo-1.json"), contents).unwrap();
+
+        let lints = captured_lints(dir.path()).unwrap();
+        assert_eq!(1, lints.len());
+        assert_eq!(Some("clippy::redundant_clone"), lints[0].code());
+    }
+}
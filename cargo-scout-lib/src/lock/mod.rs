@@ -0,0 +1,99 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+
+const LOCK_FILE_NAME: &str = ".scout.lock";
+
+/// An advisory lock on a target directory,
+/// so simultaneous scout runs don't share it.
+///
+/// The lock is released when the `TargetLock` is dropped.
+#[derive(Debug)]
+pub struct TargetLock {
+    path: PathBuf,
+    _file: File,
+}
+
+impl TargetLock {
+    /// Locks the target directory.
+    ///
+    /// If another run holds the lock, this function either blocks until it is released,
+    /// or returns `Error::Locked` if `wait` is false.
+    ///
+    /// # Errors
+    /// Returns `Error::Locked` if another run holds the lock and `wait` is false,
+    /// and fails if the lock file can't be created.
+    pub fn acquire(target_dir: impl AsRef<Path>, wait: bool) -> Result<Self, crate::error::Error> {
+        let target_dir = target_dir.as_ref();
+        std::fs::create_dir_all(target_dir)?;
+        let path = target_dir.join(LOCK_FILE_NAME);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) if wait => {
+                log::info!(
+                    "Waiting for another scout run to release {}",
+                    path.display()
+                );
+                file.lock()?;
+            }
+            Err(TryLockError::WouldBlock) => return Err(crate::error::Error::Locked(path)),
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        Ok(Self { path, _file: file })
+    }
+
+    /// The path of the lock file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Returns the target directory cargo uses when run from `path`.
+///
+/// It is either `CARGO_TARGET_DIR`, or the closest `target` directory
+/// in `path` and its parents.
+#[must_use]
+pub fn target_dir(path: &Path) -> PathBuf {
+    if let Some(dir) = std::env::var_os("CARGO_TARGET_DIR") {
+        return PathBuf::from(dir);
+    }
+    path.ancestors()
+        .map(|p| p.join("target"))
+        .find(|p| p.is_dir())
+        .unwrap_or_else(|| path.join("target"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = TargetLock::acquire(dir.path(), false).unwrap();
+        assert_eq!(dir.path().join(LOCK_FILE_NAME), lock.path());
+        assert!(matches!(
+            TargetLock::acquire(dir.path(), false),
+            Err(crate::error::Error::Locked(_))
+        ));
+
+        drop(lock);
+        assert!(TargetLock::acquire(dir.path(), false).is_ok());
+    }
+
+    #[test]
+    fn test_target_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let member = dir.path().join("member");
+        std::fs::create_dir_all(member.join("src")).unwrap();
+        std::fs::create_dir(dir.path().join("target")).unwrap();
+        if std::env::var_os("CARGO_TARGET_DIR").is_none() {
+            assert_eq!(dir.path().join("target"), target_dir(&member));
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::linter::{Level, Lint};
+use crate::output::{headline, Formatter, RunMetadata};
+use crate::paths::PathResolver;
+use std::fmt::Write as _;
+
+/// Renders findings as GitHub Actions workflow commands,
+/// which show up as annotations on the pull request without any token.
+#[derive(Default, Debug, Clone)]
+pub struct ActionsFormatter {
+    resolver: PathResolver,
+}
+
+impl ActionsFormatter {
+    #[must_use]
+    pub fn new(resolver: PathResolver) -> Self {
+        Self { resolver }
+    }
+}
+
+impl Formatter for ActionsFormatter {
+    fn format(&self, lints: &[Lint], _metadata: &RunMetadata) -> String {
+        let mut output = String::new();
+        for lint in lints {
+            let command = match lint.level() {
+                Some(Level::Error) => "error",
+                Some(Level::Warning) | None => "warning",
+                _ => "notice",
+            };
+            let mut properties = Vec::new();
+            let span = lint.primary_span();
+            if let (Some(resolved), Some(span)) = (self.resolver.resolve_lint(lint), span) {
+                properties.push(format!("file={}", escape_property(&resolved.repo_path)));
+                properties.push(format!("line={}", span.line_start));
+                properties.push(format!("endLine={}", span.line_end));
+                // Columns only make sense on a single line
+                if span.column_start > 0 && span.line_start == span.line_end {
+                    properties.push(format!("col={}", span.column_start));
+                    properties.push(format!("endColumn={}", span.column_end));
+                }
+            }
+            if let Some(code) = lint.code() {
+                properties.push(format!("title={}", escape_property(code)));
+            }
+            let _ = writeln!(
+                output,
+                "::{} {}::{}",
+                command,
+                properties.join(","),
+                escape_data(&headline(lint))
+            );
+        }
+        output
+    }
+}
+
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::lint_at;
+
+    const CODE: &str = "clippy::redundant_clone";
+    const RENDERED: &str = "warning: 100% redundant: clone";
+
+    #[test]
+    fn test_actions() {
+        let lints = vec![
+            lint_at(CODE, Level::Warning, RENDERED, "src/main.rs", (3, 3)),
+            lint_at(CODE, Level::Error, RENDERED, "src/a,b.rs", (1, 2)),
+            lint_at(CODE, Level::Note, RENDERED, "src/lib.rs", (4, 4)),
+        ];
+        let expected = "::warning file=src/main.rs,line=3,endLine=3,col=5,endColumn=9,title=clippy%3A%3Aredundant_clone::100%25 redundant: clone
+::error file=src/a%2Cb.rs,line=1,endLine=2,title=clippy%3A%3Aredundant_clone::100%25 redundant: clone
+::notice file=src/lib.rs,line=4,endLine=4,col=5,endColumn=9,title=clippy%3A%3Aredundant_clone::100%25 redundant: clone
+";
+        assert_eq!(
+            expected,
+            ActionsFormatter::default().format(&lints, &RunMetadata::default())
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::linter::{Level, Lint};
+use crate::output::{headline, Formatter, RunMetadata};
+use crate::paths::PathResolver;
+use std::fmt::Write as _;
+
+/// Renders findings as Azure Pipelines logging commands,
+/// which the build summary shows as warnings and errors at their file and line.
+#[derive(Default, Debug, Clone)]
+pub struct AzureFormatter {
+    resolver: PathResolver,
+}
+
+impl AzureFormatter {
+    #[must_use]
+    pub fn new(resolver: PathResolver) -> Self {
+        Self { resolver }
+    }
+}
+
+impl Formatter for AzureFormatter {
+    fn format(&self, lints: &[Lint], _metadata: &RunMetadata) -> String {
+        let mut output = String::new();
+        for lint in lints {
+            // Azure only knows about errors and warnings
+            let kind = match lint.level() {
+                Some(Level::Error) => "error",
+                _ => "warning",
+            };
+            let mut properties = vec![format!("type={}", kind)];
+            let span = lint.primary_span();
+            if let (Some(resolved), Some(span)) = (self.resolver.resolve_lint(lint), span) {
+                properties.push(format!(
+                    "sourcepath={}",
+                    escape_property(&resolved.repo_path)
+                ));
+                properties.push(format!("linenumber={}", span.line_start));
+                if span.column_start > 0 {
+                    properties.push(format!("columnnumber={}", span.column_start));
+                }
+            }
+            if let Some(code) = lint.code() {
+                properties.push(format!("code={}", escape_property(code)));
+            }
+            let _ = writeln!(
+                output,
+                "##vso[task.logissue {};]{}",
+                properties.join(";"),
+                escape_message(&headline(lint))
+            );
+        }
+        // No task.complete, the exit status of cargo-scout is what tells whether the run failed
+        output
+    }
+}
+
+fn escape_message(s: &str) -> String {
+    s.replace('%', "%AZP25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(s: &str) -> String {
+    escape_message(s).replace(';', "%3B").replace(']', "%5D")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::lint_at;
+
+    const CODE: &str = "clippy::doc_markdown";
+    const RENDERED: &str = "warning: 100% of [this]; is wrong";
+
+    #[test]
+    fn test_azure() {
+        let lints = vec![
+            lint_at(CODE, Level::Warning, RENDERED, "src/main.rs", (3, 3)),
+            lint_at(CODE, Level::Error, RENDERED, "src/lib;1.rs", (1, 1)),
+        ];
+        let expected = "##vso[task.logissue type=warning;sourcepath=src/main.rs;linenumber=3;columnnumber=5;code=clippy::doc_markdown;]100%AZP25 of [this]; is wrong
+##vso[task.logissue type=error;sourcepath=src/lib%3B1.rs;linenumber=1;columnnumber=5;code=clippy::doc_markdown;]100%AZP25 of [this]; is wrong
+";
+        assert_eq!(
+            expected,
+            AzureFormatter::default().format(&lints, &RunMetadata::default())
+        );
+    }
+
+    #[test]
+    fn test_no_findings() {
+        assert_eq!(
+            "",
+            AzureFormatter::default().format(&[], &RunMetadata::default())
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use crate::linter::{Level, Lint};
+use crate::output::{escape_xml, headline, Formatter, RunMetadata};
+use crate::paths::PathResolver;
+use crate::stats::UNKNOWN_CODE;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// Renders findings as a Checkstyle XML report,
+/// which review tools like reviewdog or Jenkins warnings-ng consume natively.
+#[derive(Default, Debug, Clone)]
+pub struct CheckstyleFormatter {
+    resolver: PathResolver,
+}
+
+impl CheckstyleFormatter {
+    #[must_use]
+    pub fn new(resolver: PathResolver) -> Self {
+        Self { resolver }
+    }
+}
+
+impl Formatter for CheckstyleFormatter {
+    fn format(&self, lints: &[Lint], _metadata: &RunMetadata) -> String {
+        let mut by_file: BTreeMap<String, Vec<&Lint>> = BTreeMap::new();
+        for lint in lints {
+            let file_name = self
+                .resolver
+                .resolve_lint(lint)
+                .map_or_else(|| "<unknown>".to_string(), |r| r.repo_path);
+            by_file.entry(file_name).or_default().push(lint);
+        }
+        let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        output.push_str("<checkstyle version=\"4.3\">\n");
+        for (file_name, lints) in by_file {
+            let _ = writeln!(output, "  <file name=\"{}\">", escape_xml(&file_name));
+            for lint in lints {
+                let line = lint.primary_span().map_or(0, |s| s.line_start);
+                let severity = match lint.level() {
+                    Some(Level::Error) => "error",
+                    Some(Level::Warning) | None => "warning",
+                    _ => "info",
+                };
+                let _ = writeln!(
+                    output,
+                    "    <error line=\"{}\" severity=\"{}\" message=\"{}\" source=\"{}\"/>",
+                    line,
+                    severity,
+                    escape_xml(&headline(lint)),
+                    escape_xml(lint.code().unwrap_or(UNKNOWN_CODE))
+                );
+            }
+            output.push_str("  </file>\n");
+        }
+        output.push_str("</checkstyle>\n");
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::lint_at;
+
+    const CODE: &str = "clippy::doc_markdown";
+    const RENDERED: &str = "warning: use `<T>`";
+
+    #[test]
+    fn test_checkstyle() {
+        let lints = vec![
+            lint_at(CODE, Level::Warning, RENDERED, "src/main.rs", (3, 3)),
+            lint_at(CODE, Level::Error, RENDERED, "src/lib.rs", (1, 1)),
+            lint_at(CODE, Level::Note, RENDERED, "src/main.rs", (5, 5)),
+        ];
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<checkstyle version="4.3">
+  <file name="src/lib.rs">
+    <error line="1" severity="error" message="use `&lt;T&gt;`" source="clippy::doc_markdown"/>
+  </file>
+  <file name="src/main.rs">
+    <error line="3" severity="warning" message="use `&lt;T&gt;`" source="clippy::doc_markdown"/>
+    <error line="5" severity="info" message="use `&lt;T&gt;`" source="clippy::doc_markdown"/>
+  </file>
+</checkstyle>
+"#;
+        assert_eq!(
+            expected,
+            CheckstyleFormatter::default().format(&lints, &RunMetadata::default())
+        );
+    }
+}
@@ -0,0 +1,610 @@
+use crate::linter::{Level, Lint};
+use crate::output::{docs_url, Formatter, GroupBy, RunMetadata};
+use crate::policy::allow_failures::date;
+use crate::scout::TrackedLint;
+use crate::stats::UNKNOWN_CODE;
+use crate::vcs::{AddedLine, Origin, Section};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::Write as _;
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[1;36m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders findings for humans, grouped by file,
+/// with the severity of each finding colorized.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Default, Debug, Clone)]
+pub struct HumanFormatter {
+    color: bool,
+    group_by: GroupBy,
+    totals: bool,
+    reproducers: bool,
+    hunks: bool,
+}
+
+// A finding, the changes it was kept for, the commit that introduced it
+// and the oldest commit its lines come from
+type Entry<'a> = (
+    &'a Lint,
+    &'a [Section],
+    Option<&'a Origin>,
+    Option<&'a Origin>,
+);
+
+impl Formatter for HumanFormatter {
+    fn format(&self, lints: &[Lint], _metadata: &RunMetadata) -> String {
+        let entries: Vec<Entry> = lints.iter().map(|l| (l, &[][..], None, None)).collect();
+        self.render(&entries)
+    }
+
+    fn format_tracked(&self, lints: &[TrackedLint], _metadata: &RunMetadata) -> String {
+        let entries: Vec<Entry> = lints
+            .iter()
+            .map(|l| {
+                (
+                    &l.lint,
+                    &l.sections[..],
+                    l.introduced_by.as_ref(),
+                    l.origin.as_ref(),
+                )
+            })
+            .collect();
+        self.render(&entries)
+    }
+}
+
+impl HumanFormatter {
+    #[must_use]
+    pub fn new(color: bool) -> Self {
+        Self {
+            color,
+            group_by: GroupBy::default(),
+            totals: false,
+            reproducers: false,
+            hunks: false,
+        }
+    }
+
+    pub fn set_group_by(&mut self, group_by: GroupBy) -> &mut Self {
+        self.group_by = group_by;
+        self
+    }
+
+    /// Ends the output with the number of findings.
+    pub fn set_totals(&mut self, totals: bool) -> &mut Self {
+        self.totals = totals;
+        self
+    }
+
+    /// Follows each finding with the command that reproduces it, when there is one.
+    pub fn set_reproducers(&mut self, reproducers: bool) -> &mut Self {
+        self.reproducers = reproducers;
+        self
+    }
+
+    /// Follows each finding with the lines of the diff it was kept for,
+    /// when the findings come with their changes.
+    pub fn set_hunks(&mut self, hunks: bool) -> &mut Self {
+        self.hunks = hunks;
+        self
+    }
+
+    fn render(&self, lints: &[Entry]) -> String {
+        let mut output = match self.group_by {
+            GroupBy::File => self.format_by_file(lints),
+            GroupBy::Code => self.format_by_code(lints),
+        };
+        if self.totals {
+            if lints.is_empty() {
+                output.push_str(
+                    "No warnings raised by clippy::pedantic in your diff, you're good to go!\n",
+                );
+            } else {
+                let _ = writeln!(output, "Clippy::pedantic found {} warnings", lints.len());
+            }
+        }
+        output
+    }
+
+    fn format_by_file(&self, lints: &[Entry]) -> String {
+        let mut by_file: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+        for &entry in lints {
+            by_file.entry(file_name(entry.0)).or_default().push(entry);
+        }
+        let mut output = String::new();
+        for (file_name, lints) in by_file {
+            let header = format!("{} ({} findings)", file_name, lints.len());
+            output.push_str(&self.paint(BOLD, &header));
+            output.push('\n');
+            for (lint, sections, introduced_by, origin) in lints {
+                if let Some(m) = &lint.message {
+                    output.push_str(&self.colorize_level(&m.rendered, lint.level()));
+                    if !m.rendered.ends_with('\n') {
+                        output.push('\n');
+                    }
+                }
+                if let Some(reproducer) = lint.reproducer.as_ref().filter(|_| self.reproducers) {
+                    let _ = writeln!(output, "reproduce with: {reproducer}");
+                }
+                output.push_str(&introduced(introduced_by, origin, ""));
+                output.push_str(&self.hunk(lint, sections, ""));
+            }
+        }
+        output
+    }
+
+    // Renders the message once per lint code,
+    // followed by every location the lint fired on
+    fn format_by_code(&self, lints: &[Entry]) -> String {
+        let mut by_code: BTreeMap<&str, Vec<Entry>> = BTreeMap::new();
+        for &entry in lints {
+            by_code
+                .entry(entry.0.code().unwrap_or(UNKNOWN_CODE))
+                .or_default()
+                .push(entry);
+        }
+        let mut groups: Vec<(&str, Vec<Entry>)> = by_code.into_iter().collect();
+        groups.sort_by_key(|(_, lints)| std::cmp::Reverse(lints.len()));
+        let mut output = String::new();
+        for (code, lints) in groups {
+            let header = format!("{} ({} findings)", code, lints.len());
+            output.push_str(&self.paint(BOLD, &header));
+            output.push('\n');
+            let first = lints[0].0;
+            if let Some(m) = &first.message {
+                let headline = m.rendered.lines().next().unwrap_or_default();
+                output.push_str(&self.colorize_level(headline, first.level()));
+                output.push('\n');
+                // The rendered message is reduced to its headline,
+                // keep the help and notes that come with it
+                let children = m.render_children("  ");
+                output.push_str(&children);
+                // Clippy links its documentation in a help message already
+                if let Some(url) = docs_url(code).filter(|_| !children.contains("https://")) {
+                    let _ = writeln!(output, "  = help: for further information visit {url}");
+                }
+            }
+            for (lint, sections, introduced_by, origin) in lints {
+                let _ = writeln!(output, "  {}", location(lint));
+                if let Some(reproducer) = lint.reproducer.as_ref().filter(|_| self.reproducers) {
+                    let _ = writeln!(output, "    reproduce with: {reproducer}");
+                }
+                output.push_str(&introduced(introduced_by, origin, "    "));
+                output.push_str(&self.hunk(lint, sections, "    "));
+            }
+        }
+        output
+    }
+
+    // The lines of the diff the finding was kept for,
+    // with the columns the finding is about underlined
+    fn hunk(&self, lint: &Lint, sections: &[Section], indent: &str) -> String {
+        let lines: Vec<(&Section, &AddedLine)> = sections
+            .iter()
+            .flat_map(|s| s.added_lines.iter().map(move |l| (s, l)))
+            .collect();
+        if !self.hunks || lines.is_empty() {
+            return String::new();
+        }
+        let mut output = format!("{indent}added by your diff:\n");
+        for (section, line) in lines {
+            let _ = writeln!(
+                output,
+                "{}{:>5} {}",
+                indent,
+                line.line,
+                self.paint(GREEN, &format!("+ {}", line.text))
+            );
+            if let Some((offset, carets, label)) = underline(lint, &section.file_name, line) {
+                let _ = writeln!(
+                    output,
+                    "{}{:>8}{}{}{}",
+                    indent,
+                    "",
+                    offset,
+                    self.paint(level_color(lint.level()), &carets),
+                    label.map_or_else(String::new, |l| format!(" {l}"))
+                );
+            }
+        }
+        output
+    }
+
+    // Colorizes the `warning` in the first `warning: message` line
+    fn colorize_level(&self, rendered: &str, level: Option<Level>) -> String {
+        let color = level_color(level);
+        match rendered.find(':') {
+            Some(index) if self.color && !rendered[..index].contains('\n') => format!(
+                "{}{}",
+                self.paint(color, &rendered[..index]),
+                &rendered[index..]
+            ),
+            _ => rendered.to_string(),
+        }
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{color}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn level_color(level: Option<Level>) -> &'static str {
+    match level {
+        Some(Level::Error) => RED,
+        Some(Level::Warning) | None => YELLOW,
+        _ => CYAN,
+    }
+}
+
+// The whitespace before the columns of the first primary span on the line,
+// the carets under them, and the label of the span.
+// Spans without columns, or that are empty, aren't underlined
+fn underline<'a>(
+    lint: &'a Lint,
+    file_name: &str,
+    added: &AddedLine,
+) -> Option<(String, String, Option<&'a str>)> {
+    let message = lint.message.as_ref()?;
+    let span = message.primary_spans().into_iter().find(|s| {
+        s.file_name.replace('\\', "/") == file_name.replace('\\', "/")
+            && s.line_start <= added.line
+            && added.line <= s.line_end
+            && s.column_start > 0
+            && (s.line_start < s.line_end || s.column_start < s.column_end)
+    })?;
+    let width = added.text.chars().count() + 1;
+    let start = if added.line == span.line_start {
+        span.column_start as usize
+    } else {
+        // The lines a span continues on are underlined from their indentation
+        added.text.chars().take_while(|c| c.is_whitespace()).count() + 1
+    };
+    let end = if added.line == span.line_end {
+        span.column_end as usize
+    } else {
+        width
+    };
+    let end = end.min(width);
+    if end <= start {
+        return None;
+    }
+    // Tabs are kept, so the carets line up with the code
+    let offset: String = added
+        .text
+        .chars()
+        .take(start - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    Some((offset, "^".repeat(end - start), span.label.as_deref()))
+}
+
+fn introduced(introduced_by: Option<&Origin>, origin: Option<&Origin>, indent: &str) -> String {
+    let mut output = String::new();
+    if let Some(introduced_by) = introduced_by {
+        let _ = writeln!(
+            output,
+            "{}introduced in {} by {}",
+            indent,
+            short_id(&introduced_by.commit),
+            introduced_by.author
+        );
+    }
+    if let Some(origin) = origin {
+        let _ = writeln!(
+            output,
+            "{}exists since {}, first committed in {}",
+            indent,
+            date(u64::try_from(origin.time).unwrap_or_default()),
+            short_id(&origin.commit)
+        );
+    }
+    output
+}
+
+fn short_id(commit: &str) -> &str {
+    &commit[..commit.len().min(8)]
+}
+
+fn file_name(lint: &Lint) -> String {
+    lint.primary_span().map_or_else(
+        || "<unknown>".to_string(),
+        |s| s.file_name.replace('\\', "/"),
+    )
+}
+
+fn location(lint: &Lint) -> String {
+    match lint.primary_span() {
+        Some(s) => format!("{}:{}", s.file_name.replace('\\', "/"), s.line_start),
+        None => "<unknown>".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::span;
+    use crate::linter::{Code, Message};
+
+    fn lint(file_name: &str, rendered: &str, level: Level) -> Lint {
+        lint_with_code(file_name, rendered, level, None)
+    }
+
+    fn lint_with_code(file_name: &str, rendered: &str, level: Level, code: Option<&str>) -> Lint {
+        Lint {
+            package_id: "cargo-scout".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: rendered.to_string(),
+                code: code.map(|c| Code {
+                    code: c.to_string(),
+                    explanation: None,
+                }),
+                level: Some(level),
+                children: Vec::new(),
+                spans: vec![span(file_name, 1, 1)],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_grouped_by_file() {
+        let lints = vec![
+            lint(
+                "src/main.rs",
+                "warning: foo\n --> src/main.rs:1:1\n",
+                Level::Warning,
+            ),
+            lint("src/lib.rs", "error: bar", Level::Error),
+            lint("src/main.rs", "warning: baz\n", Level::Warning),
+        ];
+        let expected = "src/lib.rs (1 findings)
+error: bar
+src/main.rs (2 findings)
+warning: foo
+ --> src/main.rs:1:1
+warning: baz
+";
+        assert_eq!(
+            expected,
+            HumanFormatter::new(false).format(&lints, &RunMetadata::default())
+        );
+    }
+
+    #[test]
+    fn test_reproducers() {
+        let lints = vec![Lint {
+            reproducer: Some("cd /repo && cargo clippy -p foo".to_string()),
+            ..lint("src/lib.rs", "error: bar", Level::Error)
+        }];
+        let mut formatter = HumanFormatter::new(false);
+        assert_eq!(
+            "src/lib.rs (1 findings)\nerror: bar\n",
+            formatter.format(&lints, &RunMetadata::default())
+        );
+        formatter.set_reproducers(true);
+        assert_eq!(
+            "src/lib.rs (1 findings)\nerror: bar\nreproduce with: cd /repo && cargo clippy -p foo\n",
+            formatter.format(&lints, &RunMetadata::default())
+        );
+    }
+
+    #[test]
+    fn test_hunks() {
+        let lints = vec![TrackedLint {
+            lint: lint("src/lib.rs", "warning: bar", Level::Warning),
+            sections: vec![Section {
+                file_name: "src/lib.rs".to_string(),
+                line_start: 1,
+                line_end: 2,
+                added_lines: vec![AddedLine {
+                    line: 1,
+                    text: "let x = &Vec::new();".to_string(),
+                }],
+            }],
+            introduced_by: None,
+            origin: None,
+        }];
+        let mut formatter = HumanFormatter::new(false);
+        assert_eq!(
+            "src/lib.rs (1 findings)\nwarning: bar\n",
+            formatter.format_tracked(&lints, &RunMetadata::default())
+        );
+        formatter.set_hunks(true);
+        assert_eq!(
+            "src/lib.rs (1 findings)\nwarning: bar\nadded by your diff:\n    1 + let x = &Vec::new();\n",
+            formatter.format_tracked(&lints, &RunMetadata::default())
+        );
+        formatter.set_group_by(GroupBy::Code);
+        assert_eq!(
+            "<unknown> (1 findings)\nwarning: bar\n  src/lib.rs:1\n    added by your diff:\n        1 + let x = &Vec::new();\n",
+            formatter.format_tracked(&lints, &RunMetadata::default())
+        );
+    }
+
+    #[test]
+    fn test_hunks_underlined() {
+        let mut lint = lint("src/lib.rs", "warning: bar", Level::Warning);
+        if let Some(m) = lint.message.as_mut() {
+            m.spans[0].line_start = 2;
+            m.spans[0].line_end = 3;
+            m.spans[0].column_start = 10;
+            m.spans[0].column_end = 6;
+            m.spans[0].label = Some("help: use `&[u8]`".to_string());
+            // Secondary spans aren't underlined
+            let mut secondary = m.spans[0].clone();
+            secondary.line_start = 1;
+            secondary.is_primary = false;
+            m.spans.push(secondary);
+        }
+        let added_line = |line: u32, text: &str| AddedLine {
+            line,
+            text: text.to_string(),
+        };
+        let lints = vec![TrackedLint {
+            lint,
+            sections: vec![Section {
+                file_name: "src/lib.rs".to_string(),
+                line_start: 1,
+                line_end: 3,
+                added_lines: vec![
+                    added_line(1, "fn f() {"),
+                    added_line(2, "\tlet x = &Vec::new("),
+                    added_line(3, "    );"),
+                ],
+            }],
+            introduced_by: None,
+            origin: None,
+        }];
+        let mut formatter = HumanFormatter::new(false);
+        formatter.set_hunks(true);
+        let expected = "src/lib.rs (1 findings)
+warning: bar
+added by your diff:
+    1 + fn f() {
+    2 + \tlet x = &Vec::new(
+        \t        ^^^^^^^^^^ help: use `&[u8]`
+    3 +     );
+            ^ help: use `&[u8]`
+";
+        assert_eq!(
+            expected,
+            formatter.format_tracked(&lints, &RunMetadata::default())
+        );
+    }
+
+    #[test]
+    fn test_introduced_by() {
+        let lints = vec![TrackedLint {
+            lint: lint("src/lib.rs", "warning: bar", Level::Warning),
+            sections: Vec::new(),
+            introduced_by: Some(Origin {
+                commit: "5f0c6b7e1a2d3c4b".to_string(),
+                author: "jane".to_string(),
+                time: 0,
+            }),
+            origin: None,
+        }];
+        assert_eq!(
+            "src/lib.rs (1 findings)\nwarning: bar\nintroduced in 5f0c6b7e by jane\n",
+            HumanFormatter::new(false).format_tracked(&lints, &RunMetadata::default())
+        );
+
+        let lints = vec![TrackedLint {
+            origin: Some(Origin {
+                commit: "0a1b2c3d4e5f6a7b".to_string(),
+                author: "john".to_string(),
+                time: 1_577_836_800,
+            }),
+            ..lints[0].clone()
+        }];
+        assert_eq!(
+            "src/lib.rs (1 findings)\nwarning: bar\nintroduced in 5f0c6b7e by jane\nexists since 2020-01-01, first committed in 0a1b2c3d\n",
+            HumanFormatter::new(false).format_tracked(&lints, &RunMetadata::default())
+        );
+    }
+
+    #[test]
+    fn test_colored() {
+        let lints = vec![lint("src/lib.rs", "error: bar", Level::Error)];
+        let expected = "\x1b[1msrc/lib.rs (1 findings)\x1b[0m
+\x1b[1;31merror\x1b[0m: bar
+";
+        assert_eq!(
+            expected,
+            HumanFormatter::new(true).format(&lints, &RunMetadata::default())
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(
+            "",
+            HumanFormatter::default().format(&[], &RunMetadata::default())
+        );
+    }
+
+    #[test]
+    fn test_totals() {
+        let mut formatter = HumanFormatter::new(false);
+        formatter.set_totals(true);
+        assert_eq!(
+            "No warnings raised by clippy::pedantic in your diff, you're good to go!\n",
+            formatter.format(&[], &RunMetadata::default())
+        );
+        let lints = vec![lint("src/lib.rs", "error: bar", Level::Error)];
+        assert_eq!(
+            "src/lib.rs (1 findings)\nerror: bar\nClippy::pedantic found 1 warnings\n",
+            formatter.format(&lints, &RunMetadata::default())
+        );
+    }
+
+    #[test]
+    fn test_grouped_by_code() {
+        let lints = vec![
+            lint_with_code(
+                "src/main.rs",
+                "warning: missing backticks\n --> src/main.rs:1:1\n",
+                Level::Warning,
+                Some("clippy::doc_markdown"),
+            ),
+            lint_with_code("src/lib.rs", "error: bar", Level::Error, None),
+            lint_with_code(
+                "src/lib.rs",
+                "warning: missing backticks\n --> src/lib.rs:1:1\n",
+                Level::Warning,
+                Some("clippy::doc_markdown"),
+            ),
+        ];
+        let expected = "clippy::doc_markdown (2 findings)
+warning: missing backticks
+  = help: for further information visit https://rust-lang.github.io/rust-clippy/master/index.html#doc_markdown
+  src/main.rs:1
+  src/lib.rs:1
+<unknown> (1 findings)
+error: bar
+  src/lib.rs:1
+";
+        let mut formatter = HumanFormatter::new(false);
+        formatter.set_group_by(GroupBy::Code);
+        assert_eq!(expected, formatter.format(&lints, &RunMetadata::default()));
+    }
+
+    #[test]
+    fn test_grouped_by_code_with_children() {
+        let mut lint = lint_with_code(
+            "src/lib.rs",
+            "warning: redundant clone\n",
+            Level::Warning,
+            Some("clippy::redundant_clone"),
+        );
+        if let Some(m) = lint.message.as_mut() {
+            m.children.push(Message {
+                message: "remove this".to_string(),
+                level: Some(Level::Help),
+                spans: m.spans.clone(),
+                ..Message::default()
+            });
+        }
+        let expected = "clippy::redundant_clone (1 findings)
+warning: redundant clone
+  = help: remove this
+    --> src/lib.rs:1
+  = help: for further information visit https://rust-lang.github.io/rust-clippy/master/index.html#redundant_clone
+  src/lib.rs:1
+";
+        let mut formatter = HumanFormatter::new(false);
+        formatter.set_group_by(GroupBy::Code);
+        assert_eq!(expected, formatter.format(&[lint], &RunMetadata::default()));
+    }
+}
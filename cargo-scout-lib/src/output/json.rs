@@ -0,0 +1,169 @@
+use crate::linter::{Lint, Message};
+use crate::output::{headline, level_name, Formatter, RunMetadata};
+use crate::paths::PathResolver;
+use crate::scout::TrackedLint;
+use serde_json::{json, Value};
+
+/// Renders findings as a JSON document.
+///
+/// Every finding carries both its repository-relative
+/// and crate-relative path, along with its crate name.
+#[derive(Default, Debug, Clone)]
+pub struct JsonFormatter {
+    resolver: PathResolver,
+}
+
+impl JsonFormatter {
+    #[must_use]
+    pub fn new(resolver: PathResolver) -> Self {
+        Self { resolver }
+    }
+
+    #[must_use]
+    pub fn finding(&self, lint: &Lint) -> Value {
+        let resolved = self.resolver.resolve_lint(lint);
+        let span = lint.primary_span();
+        json!({
+            "code": lint.code(),
+            "fingerprint": lint.fingerprint(),
+            "level": level_name(lint.level()),
+            "message": headline(lint),
+            "rendered": lint.message.as_ref().map(|m| m.rendered.as_str()),
+            "repo_path": resolved.as_ref().map(|r| r.repo_path.as_str()),
+            "crate_path": resolved.as_ref().map(|r| r.crate_path.as_str()),
+            "crate_name": resolved.as_ref().and_then(|r| r.crate_name.as_deref()),
+            "line_start": span.map(|s| s.line_start),
+            "line_end": span.map(|s| s.line_end),
+            "children": lint.message.as_ref().map_or_else(Vec::new, |m| {
+                m.children.iter().map(child).collect()
+            }),
+            "reproducer": lint.reproducer,
+        })
+    }
+}
+
+fn child(message: &Message) -> Value {
+    let spans: Vec<Value> = message
+        .spans
+        .iter()
+        .map(|s| {
+            json!({
+                "file_name": s.file_name,
+                "line_start": s.line_start,
+                "line_end": s.line_end,
+                "column_start": s.column_start,
+                "column_end": s.column_end,
+                "suggested_replacement": s.suggested_replacement,
+            })
+        })
+        .collect();
+    json!({
+        "level": level_name(message.level),
+        "message": message.message,
+        "spans": spans,
+    })
+}
+
+impl Formatter for JsonFormatter {
+    fn format(&self, lints: &[Lint], metadata: &RunMetadata) -> String {
+        let findings: Vec<Value> = lints.iter().map(|l| self.finding(l)).collect();
+        document(&findings, metadata)
+    }
+
+    fn format_tracked(&self, lints: &[TrackedLint], metadata: &RunMetadata) -> String {
+        let findings: Vec<Value> = lints
+            .iter()
+            .map(|tracked| {
+                let mut finding = self.finding(&tracked.lint);
+                if let Some(origin) = &tracked.introduced_by {
+                    finding["introduced_by"] = json!(origin);
+                }
+                if let Some(origin) = &tracked.origin {
+                    finding["origin"] = json!(origin);
+                }
+                finding
+            })
+            .collect();
+        document(&findings, metadata)
+    }
+}
+
+fn document(findings: &[Value], metadata: &RunMetadata) -> String {
+    let document = json!({
+        "metadata": metadata,
+        "findings": findings,
+    });
+    format!("{document:#}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::span;
+    use crate::linter::{Code, Level, Message, Span};
+
+    #[test]
+    fn test_json() {
+        let lint = Lint {
+            package_id: "foo 0.1.0 (path+file:///foo)".to_string(),
+            src_path: None,
+            reproducer: Some("cd foo && cargo clippy -p foo@0.1.0".to_string()),
+            message: Some(Message {
+                message: String::new(),
+                rendered: "warning: missing backticks\n --> foo/src/lib.rs:1:1\n".to_string(),
+                code: Some(Code {
+                    code: "clippy::doc_markdown".to_string(),
+                    explanation: None,
+                }),
+                level: Some(Level::Warning),
+                children: Vec::new(),
+                spans: vec![span("foo/src/lib.rs", 1, 2)],
+            }),
+        };
+        let fingerprint = lint.fingerprint();
+        let mut with_secondary_span = lint.clone();
+        if let Some(m) = &mut with_secondary_span.message {
+            m.spans.insert(
+                0,
+                Span {
+                    is_primary: false,
+                    ..span("foo/src/main.rs", 1, 2)
+                },
+            );
+        }
+        assert_eq!(fingerprint, with_secondary_span.fingerprint());
+        let formatter = JsonFormatter::new(PathResolver::new(vec!["foo".to_string()]));
+        let output: Value =
+            serde_json::from_str(&formatter.format(&[lint], &RunMetadata::new("1.0.0"))).unwrap();
+        assert_eq!(
+            json!({"metadata": {
+                "schema_version": 1,
+                "tool_version": "1.0.0",
+                "target_branch": null,
+            },
+            "findings": [{
+                "code": "clippy::doc_markdown",
+                "fingerprint": fingerprint,
+                "level": "warning",
+                "message": "missing backticks",
+                "rendered": "warning: missing backticks\n --> foo/src/lib.rs:1:1\n",
+                "repo_path": "foo/src/lib.rs",
+                "crate_path": "src/lib.rs",
+                "crate_name": "foo",
+                "line_start": 1,
+                "line_end": 2,
+                "children": [],
+                "reproducer": "cd foo && cargo clippy -p foo@0.1.0",
+            }]}),
+            output
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        let output: Value =
+            serde_json::from_str(&JsonFormatter::default().format(&[], &RunMetadata::default()))
+                .unwrap();
+        assert_eq!(json!([]), output["findings"]);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::linter::Lint;
+use crate::output::{escape_xml, headline, Formatter, RunMetadata};
+use crate::stats::UNKNOWN_CODE;
+use std::fmt::Write as _;
+
+/// Renders findings as a `JUnit` XML report, with one failing testcase per finding,
+/// so CI servers can display them in their test tabs.
+#[derive(Default, Debug, Clone)]
+pub struct JunitFormatter {}
+
+impl Formatter for JunitFormatter {
+    fn format(&self, lints: &[Lint], _metadata: &RunMetadata) -> String {
+        let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            output,
+            "<testsuites name=\"cargo-scout\" tests=\"{0}\" failures=\"{0}\">",
+            lints.len()
+        );
+        let _ = writeln!(
+            output,
+            "  <testsuite name=\"cargo-scout\" tests=\"{0}\" failures=\"{0}\">",
+            lints.len()
+        );
+        for lint in lints {
+            let code = lint.code().unwrap_or(UNKNOWN_CODE);
+            let span = lint.primary_span();
+            let file_name = span.map_or_else(
+                || "<unknown>".to_string(),
+                |s| s.file_name.replace('\\', "/"),
+            );
+            let line = span.map_or(0, |s| s.line_start);
+            let _ = writeln!(
+                output,
+                "    <testcase name=\"{}\" classname=\"{}\">",
+                escape_xml(&format!("{code} {file_name}:{line}")),
+                escape_xml(&file_name)
+            );
+            let _ = writeln!(
+                output,
+                "      <failure message=\"{}\" type=\"{}\">{}</failure>",
+                escape_xml(&headline(lint)),
+                escape_xml(code),
+                escape_xml(lint.message.as_ref().map_or("", |m| m.rendered.as_str()))
+            );
+            output.push_str("    </testcase>\n");
+        }
+        output.push_str("  </testsuite>\n</testsuites>\n");
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::span;
+    use crate::linter::{Code, Message};
+
+    #[test]
+    fn test_junit() {
+        let lint = Lint {
+            package_id: "cargo-scout".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "warning: use `<T>`".to_string(),
+                code: Some(Code {
+                    code: "clippy::doc_markdown".to_string(),
+                    explanation: None,
+                }),
+                level: None,
+                children: Vec::new(),
+                spans: vec![span("src/lib.rs", 3, 4)],
+            }),
+        };
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="cargo-scout" tests="1" failures="1">
+  <testsuite name="cargo-scout" tests="1" failures="1">
+    <testcase name="clippy::doc_markdown src/lib.rs:3" classname="src/lib.rs">
+      <failure message="use `&lt;T&gt;`" type="clippy::doc_markdown">warning: use `&lt;T&gt;`</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#;
+        assert_eq!(
+            expected,
+            JunitFormatter::default().format(&[lint], &RunMetadata::default())
+        );
+    }
+
+    #[test]
+    fn test_no_findings() {
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="cargo-scout" tests="0" failures="0">
+  <testsuite name="cargo-scout" tests="0" failures="0">
+  </testsuite>
+</testsuites>
+"#;
+        assert_eq!(
+            expected,
+            JunitFormatter::default().format(&[], &RunMetadata::default())
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use crate::linter::{Level, Lint};
+use crate::output::{Formatter, RunMetadata};
+use crate::paths::PathResolver;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// The hidden comment every Markdown report starts with,
+/// bots look for it to update their previous comment instead of adding one.
+pub const REPORT_MARKER: &str = "<!-- cargo-scout-report -->";
+
+/// Renders findings as a compact Markdown summary,
+/// meant to be posted as a single comment on a pull request.
+#[derive(Default, Debug, Clone)]
+pub struct MarkdownFormatter {
+    resolver: PathResolver,
+}
+
+impl MarkdownFormatter {
+    #[must_use]
+    pub fn new(resolver: PathResolver) -> Self {
+        Self { resolver }
+    }
+}
+
+#[derive(Default)]
+struct FileCounts {
+    errors: usize,
+    warnings: usize,
+    others: usize,
+}
+
+impl Formatter for MarkdownFormatter {
+    fn format(&self, lints: &[Lint], metadata: &RunMetadata) -> String {
+        let mut by_file: BTreeMap<String, Vec<&Lint>> = BTreeMap::new();
+        for lint in lints {
+            let file_name = self
+                .resolver
+                .resolve_lint(lint)
+                .map_or_else(|| "<unknown>".to_string(), |r| r.repo_path);
+            by_file.entry(file_name).or_default().push(lint);
+        }
+
+        let mut output = format!("{REPORT_MARKER}\n");
+        if lints.is_empty() {
+            output.push_str("### cargo-scout: no findings\n");
+        } else {
+            let _ = writeln!(
+                output,
+                "### cargo-scout: {} {} in {} {}",
+                lints.len(),
+                plural(lints.len(), "finding"),
+                by_file.len(),
+                plural(by_file.len(), "file")
+            );
+        }
+        if let Some(branch) = &metadata.target_branch {
+            let _ = writeln!(output, "\nCompared against `{branch}`.");
+        }
+        if lints.is_empty() {
+            return output;
+        }
+
+        output.push_str("\n| File | Errors | Warnings | Other |\n|---|---:|---:|---:|\n");
+        for (file_name, lints) in &by_file {
+            let mut counts = FileCounts::default();
+            for lint in lints {
+                match lint.level() {
+                    Some(Level::Error) => counts.errors += 1,
+                    Some(Level::Warning) | None => counts.warnings += 1,
+                    _ => counts.others += 1,
+                }
+            }
+            let _ = writeln!(
+                output,
+                "| `{}` | {} | {} | {} |",
+                file_name.replace('|', "\\|"),
+                counts.errors,
+                counts.warnings,
+                counts.others
+            );
+        }
+
+        for (file_name, lints) in &by_file {
+            let _ = writeln!(
+                output,
+                "\n<details>\n<summary><code>{}</code> ({})</summary>\n",
+                escape_html(file_name),
+                lints.len()
+            );
+            for lint in lints {
+                let rendered = lint
+                    .message
+                    .as_ref()
+                    .map(|m| m.rendered.trim_end())
+                    .unwrap_or_default();
+                let _ = writeln!(output, "```text\n{rendered}\n```");
+            }
+            output.push_str("\n</details>\n");
+        }
+        output
+    }
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        word.to_string()
+    } else {
+        format!("{word}s")
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::lint_at;
+
+    const CODE: &str = "clippy::doc_markdown";
+
+    #[test]
+    fn test_markdown() {
+        let lints = vec![
+            lint_at(
+                CODE,
+                Level::Warning,
+                "warning: use `<T>`\n --> src/main.rs:3:1\n",
+                "src/main.rs",
+                (3, 3),
+            ),
+            lint_at(
+                CODE,
+                Level::Error,
+                "warning: use `<T>`\n --> src/lib.rs:1:1\n",
+                "src/lib.rs",
+                (1, 1),
+            ),
+            lint_at(
+                CODE,
+                Level::Note,
+                "warning: use `<T>`\n --> src/main.rs:5:1\n",
+                "src/main.rs",
+                (5, 5),
+            ),
+        ];
+        let mut metadata = RunMetadata::default();
+        metadata.set_target_branch(Some("master".to_string()));
+        let expected = r#"<!-- cargo-scout-report -->
+### cargo-scout: 3 findings in 2 files
+
+Compared against `master`.
+
+| File | Errors | Warnings | Other |
+|---|---:|---:|---:|
+| `src/lib.rs` | 1 | 0 | 0 |
+| `src/main.rs` | 0 | 1 | 1 |
+
+<details>
+<summary><code>src/lib.rs</code> (1)</summary>
+
+```text
+warning: use `<T>`
+ --> src/lib.rs:1:1
+```
+
+</details>
+
+<details>
+<summary><code>src/main.rs</code> (2)</summary>
+
+```text
+warning: use `<T>`
+ --> src/main.rs:3:1
+```
+```text
+warning: use `<T>`
+ --> src/main.rs:5:1
+```
+
+</details>
+"#;
+        assert_eq!(
+            expected,
+            MarkdownFormatter::default().format(&lints, &metadata)
+        );
+    }
+
+    #[test]
+    fn test_no_findings() {
+        let output = MarkdownFormatter::default().format(&[], &RunMetadata::default());
+        assert_eq!(
+            "<!-- cargo-scout-report -->\n### cargo-scout: no findings\n",
+            output
+        );
+    }
+}
@@ -0,0 +1,439 @@
+use crate::error::Error;
+use crate::linter::{Level, Lint};
+use crate::schema::SCHEMA_VERSION;
+use crate::scout::TrackedLint;
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub mod actions;
+pub mod azure;
+pub mod checkstyle;
+pub mod human;
+pub mod json;
+pub mod junit;
+pub mod markdown;
+pub mod rdjson;
+pub mod sarif;
+pub mod teamcity;
+pub mod vscode;
+
+/// A `Formatter` renders the final set of findings,
+/// for humans or for other tools to consume.
+pub trait Formatter {
+    fn format(&self, lints: &[Lint], metadata: &RunMetadata) -> String;
+
+    /// Renders findings along with the changes they were kept for.
+    /// Formatters that don't show the changes render the findings alone.
+    fn format_tracked(&self, lints: &[TrackedLint], metadata: &RunMetadata) -> String {
+        let lints: Vec<Lint> = lints.iter().map(|l| l.lint.clone()).collect();
+        self.format(&lints, metadata)
+    }
+}
+
+/// Information about the run, shared by every output.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct RunMetadata {
+    /// The version of the schema the output follows, see [`SCHEMA_VERSION`]
+    pub schema_version: u32,
+    /// The version of cargo-scout that produced the findings
+    pub tool_version: String,
+    /// The branch the changes were compared against
+    pub target_branch: Option<String>,
+}
+
+impl RunMetadata {
+    #[must_use]
+    pub fn new(tool_version: impl Into<String>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            tool_version: tool_version.into(),
+            target_branch: None,
+        }
+    }
+
+    pub fn set_target_branch(&mut self, target_branch: Option<String>) -> &mut Self {
+        self.target_branch = target_branch;
+        self
+    }
+}
+
+/// An output requested on the command line,
+/// either `format` (printed to stdout) or `format=path`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct OutputSpec {
+    pub format: OutputFormat,
+    pub path: Option<PathBuf>,
+}
+
+impl FromStr for OutputSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((_, "")) => Err(format!("missing output path in {s}")),
+            Some((format, path)) => Ok(Self {
+                format: format.parse()?,
+                path: Some(PathBuf::from(path)),
+            }),
+            None => Ok(Self {
+                format: s.parse()?,
+                path: None,
+            }),
+        }
+    }
+}
+
+/// Sends the same findings and run metadata to every registered output.
+#[derive(Default)]
+pub struct Reporter {
+    sinks: Vec<(Box<dyn Formatter>, Option<PathBuf>)>,
+}
+
+impl Reporter {
+    /// Registers an output, written to `path` or printed to stdout if there is none.
+    pub fn add_sink(&mut self, formatter: Box<dyn Formatter>, path: Option<PathBuf>) -> &mut Self {
+        self.sinks.push((formatter, path));
+        self
+    }
+
+    /// Returns true if at least one output is printed to stdout.
+    #[must_use]
+    pub fn writes_to_stdout(&self) -> bool {
+        self.sinks.iter().any(|(_, path)| path.is_none())
+    }
+
+    /// Renders the findings with every output, in the order they were added.
+    ///
+    /// # Errors
+    /// Fails if an output file can't be written.
+    pub fn report(&self, lints: &[Lint], metadata: &RunMetadata) -> Result<(), Error> {
+        let lints: Vec<TrackedLint> = lints
+            .iter()
+            .map(|lint| TrackedLint {
+                lint: lint.clone(),
+                sections: Vec::new(),
+                introduced_by: None,
+                origin: None,
+            })
+            .collect();
+        self.report_tracked(&lints, metadata)
+    }
+
+    /// Renders the findings and the changes they were kept for with every output,
+    /// in the order they were added.
+    ///
+    /// # Errors
+    /// Fails if an output file can't be written.
+    pub fn report_tracked(
+        &self,
+        lints: &[TrackedLint],
+        metadata: &RunMetadata,
+    ) -> Result<(), Error> {
+        for (formatter, path) in &self.sinks {
+            let report = formatter.format_tracked(lints, metadata);
+            match path {
+                Some(path) => {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(path, report)?;
+                }
+                None => print!("{report}"),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The available output formats
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+    Sarif,
+    Junit,
+    Checkstyle,
+    Rdjson,
+    Vscode,
+    Markdown,
+    Azure,
+    Teamcity,
+    Actions,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" | "text" => Ok(OutputFormat::Human),
+            "json" => Ok(OutputFormat::Json),
+            "sarif" => Ok(OutputFormat::Sarif),
+            "junit" => Ok(OutputFormat::Junit),
+            "checkstyle" => Ok(OutputFormat::Checkstyle),
+            "rdjson" => Ok(OutputFormat::Rdjson),
+            "vscode" => Ok(OutputFormat::Vscode),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "azure" => Ok(OutputFormat::Azure),
+            "teamcity" => Ok(OutputFormat::Teamcity),
+            "actions" => Ok(OutputFormat::Actions),
+            _ => Err(format!(
+                "unknown output format {s}, expected one of human, json, sarif, junit, checkstyle, rdjson, vscode, markdown, azure, teamcity, actions"
+            )),
+        }
+    }
+}
+
+/// Whether the output should be colored
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum ColorChoice {
+    /// Color the output if stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "unknown color choice {s}, expected one of auto, always, never"
+            )),
+        }
+    }
+}
+
+/// How findings are grouped in the output
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum GroupBy {
+    /// One group per file, with every finding rendered
+    #[default]
+    File,
+    /// One group per lint code, with the list of locations it fired on
+    Code,
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(GroupBy::File),
+            "code" => Ok(GroupBy::Code),
+            _ => Err(format!("unknown grouping {s}, expected one of file, code")),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Returns true if the output should be colored.
+    #[must_use]
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Returns the first line of the rendered message, without the level prefix.
+///
+/// Example:
+/// "warning: unused variable" becomes "unused variable"
+#[must_use]
+pub fn headline(lint: &Lint) -> String {
+    let first_line = lint
+        .message
+        .as_ref()
+        .and_then(|m| m.rendered.lines().next())
+        .unwrap_or_default();
+    match first_line.find(": ") {
+        Some(index) => first_line[index + 2..].to_string(),
+        None => first_line.to_string(),
+    }
+}
+
+/// Returns where the lint code is documented, for clippy lints, rustc errors and `RustSec` advisories.
+///
+/// Example:
+/// `"clippy::needless_pass_by_value"` becomes
+/// `"https://rust-lang.github.io/rust-clippy/master/index.html#needless_pass_by_value"`
+#[must_use]
+pub fn docs_url(code: &str) -> Option<String> {
+    if let Some(name) = code.strip_prefix("clippy::") {
+        return Some(format!(
+            "https://rust-lang.github.io/rust-clippy/master/index.html#{name}"
+        ));
+    }
+    if code.starts_with("RUSTSEC-") {
+        return Some(format!("https://rustsec.org/advisories/{code}"));
+    }
+    let is_error_code =
+        code.len() == 5 && code.starts_with('E') && code[1..].chars().all(|c| c.is_ascii_digit());
+    if is_error_code {
+        Some(format!("https://doc.rust-lang.org/error_codes/{code}.html"))
+    } else {
+        None
+    }
+}
+
+/// Returns the name of the level, as reported by the linter.
+#[must_use]
+pub fn level_name(level: Option<Level>) -> &'static str {
+    match level {
+        Some(Level::Error) => "error",
+        Some(Level::Warning) | None => "warning",
+        Some(Level::Note) => "note",
+        Some(Level::Help) => "help",
+        Some(Level::Other) => "other",
+    }
+}
+
+/// Escapes the characters that can't appear in XML text and attributes.
+#[must_use]
+pub fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::Message;
+
+    #[test]
+    fn test_output_format() {
+        assert_eq!(Ok(OutputFormat::Human), "human".parse());
+        assert_eq!(Ok(OutputFormat::Human), "text".parse());
+        assert_eq!(Ok(OutputFormat::Json), "json".parse());
+        assert_eq!(Ok(OutputFormat::Sarif), "sarif".parse());
+        assert_eq!(Ok(OutputFormat::Junit), "junit".parse());
+        assert_eq!(Ok(OutputFormat::Checkstyle), "checkstyle".parse());
+        assert_eq!(Ok(OutputFormat::Rdjson), "rdjson".parse());
+        assert_eq!(Ok(OutputFormat::Vscode), "vscode".parse());
+        assert_eq!(Ok(OutputFormat::Markdown), "md".parse());
+        assert_eq!(Ok(OutputFormat::Azure), "azure".parse());
+        assert_eq!(Ok(OutputFormat::Teamcity), "teamcity".parse());
+        assert_eq!(Ok(OutputFormat::Actions), "actions".parse());
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_output_spec() {
+        assert_eq!(
+            Ok(OutputSpec {
+                format: OutputFormat::Json,
+                path: Some(PathBuf::from("scout.json")),
+            }),
+            "json=scout.json".parse()
+        );
+        assert_eq!(
+            Ok(OutputSpec {
+                format: OutputFormat::Human,
+                path: None,
+            }),
+            "text".parse()
+        );
+        assert!("json=".parse::<OutputSpec>().is_err());
+        assert!("yaml=scout.yaml".parse::<OutputSpec>().is_err());
+    }
+
+    #[test]
+    fn test_reporter_writes_every_sink() {
+        struct Count;
+        impl Formatter for Count {
+            fn format(&self, lints: &[Lint], metadata: &RunMetadata) -> String {
+                format!("{} {}", metadata.tool_version, lints.len())
+            }
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("nested").join("first.txt");
+        let second = dir.path().join("second.txt");
+        let mut reporter = Reporter::default();
+        reporter
+            .add_sink(Box::new(Count), Some(first.clone()))
+            .add_sink(Box::new(Count), Some(second.clone()));
+        assert!(!reporter.writes_to_stdout());
+
+        reporter.report(&[], &RunMetadata::new("1.0.0")).unwrap();
+        assert_eq!("1.0.0 0", std::fs::read_to_string(first).unwrap());
+        assert_eq!("1.0.0 0", std::fs::read_to_string(second).unwrap());
+    }
+
+    #[test]
+    fn test_headline() {
+        let lint = Lint {
+            package_id: "cargo-scout".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "warning: unused variable: `x`\n --> src/main.rs:2:9".to_string(),
+                code: None,
+                level: None,
+                children: Vec::new(),
+                spans: Vec::new(),
+            }),
+        };
+        assert_eq!("unused variable: `x`", headline(&lint));
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            "&lt;a href=&quot;b&quot;&gt;&amp;&apos;&lt;/a&gt;",
+            escape_xml("<a href=\"b\">&'</a>")
+        );
+    }
+
+    #[test]
+    fn test_color_choice() {
+        assert_eq!(Ok(ColorChoice::Auto), "auto".parse());
+        assert_eq!(Ok(ColorChoice::Always), "always".parse());
+        assert_eq!(Ok(ColorChoice::Never), "never".parse());
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+        assert!(ColorChoice::Always.enabled());
+        assert!(!ColorChoice::Never.enabled());
+    }
+
+    #[test]
+    fn test_docs_url() {
+        assert_eq!(
+            Some("https://rust-lang.github.io/rust-clippy/master/index.html#redundant_clone"),
+            docs_url("clippy::redundant_clone").as_deref()
+        );
+        assert_eq!(
+            Some("https://doc.rust-lang.org/error_codes/E0308.html"),
+            docs_url("E0308").as_deref()
+        );
+        assert_eq!(
+            Some("https://rustsec.org/advisories/RUSTSEC-2020-0071"),
+            docs_url("RUSTSEC-2020-0071").as_deref()
+        );
+        assert_eq!(None, docs_url("unused_variables"));
+        assert_eq!(None, docs_url("rustfmt"));
+    }
+
+    #[test]
+    fn test_group_by() {
+        assert_eq!(Ok(GroupBy::File), "file".parse());
+        assert_eq!(Ok(GroupBy::Code), "code".parse());
+        assert!("crate".parse::<GroupBy>().is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::linter::{Level, Lint, Span};
+use crate::output::{headline, Formatter, RunMetadata};
+use crate::paths::PathResolver;
+use serde_json::{json, Value};
+
+/// Renders findings in the Reviewdog Diagnostic Format,
+/// with clippy's machine applicable fixes as suggestions.
+#[derive(Default, Debug, Clone)]
+pub struct RdjsonFormatter {
+    resolver: PathResolver,
+}
+
+impl RdjsonFormatter {
+    #[must_use]
+    pub fn new(resolver: PathResolver) -> Self {
+        Self { resolver }
+    }
+
+    fn diagnostic(&self, lint: &Lint) -> Value {
+        let resolved = self.resolver.resolve_lint(lint);
+        let span = lint.primary_span();
+        let severity = match lint.level() {
+            Some(Level::Error) => "ERROR",
+            Some(Level::Warning) | None => "WARNING",
+            _ => "INFO",
+        };
+        // Reviewdog applies suggestions to the file of the diagnostic
+        let suggestions: Vec<Value> = lint
+            .message
+            .as_ref()
+            .map(|m| m.machine_applicable_suggestions())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|s| span.is_some_and(|primary| primary.file_name == s.file_name))
+            .map(|s| {
+                json!({
+                    "range": range(s),
+                    "text": s.suggested_replacement,
+                })
+            })
+            .collect();
+        let mut diagnostic = json!({
+            "message": headline(lint),
+            "location": {
+                "path": resolved.as_ref().map(|r| r.repo_path.as_str()),
+                "range": span.map(range),
+            },
+            "severity": severity,
+            "originalOutput": lint.message.as_ref().map(|m| m.rendered.as_str()),
+        });
+        if let Some(code) = lint.code() {
+            diagnostic["code"] = json!({ "value": code });
+        }
+        if !suggestions.is_empty() {
+            diagnostic["suggestions"] = Value::Array(suggestions);
+        }
+        diagnostic
+    }
+}
+
+// Columns are only known when the linter reported them
+fn range(span: &Span) -> Value {
+    let position = |line: u32, column: u32| {
+        if column == 0 {
+            json!({ "line": line })
+        } else {
+            json!({ "line": line, "column": column })
+        }
+    };
+    json!({
+        "start": position(span.line_start, span.column_start),
+        "end": position(span.line_end, span.column_end),
+    })
+}
+
+impl Formatter for RdjsonFormatter {
+    fn format(&self, lints: &[Lint], _metadata: &RunMetadata) -> String {
+        let diagnostics: Vec<Value> = lints.iter().map(|l| self.diagnostic(l)).collect();
+        let result = json!({
+            "source": {
+                "name": "cargo-scout",
+                "url": "https://github.com/o0Ignition0o/cargo-scout",
+            },
+            "diagnostics": diagnostics,
+        });
+        format!("{result:#}\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::{Applicability, Code, Message};
+
+    fn span(line: u32, replacement: Option<&str>) -> Span {
+        Span {
+            file_name: "foo/src/lib.rs".to_string(),
+            line_start: line,
+            line_end: line,
+            column_start: 5,
+            column_end: 9,
+            suggested_replacement: replacement.map(str::to_string),
+            suggestion_applicability: replacement.map(|_| Applicability::MachineApplicable),
+            is_primary: true,
+            ..Span::default()
+        }
+    }
+
+    #[test]
+    fn test_rdjson() {
+        let lint = Lint {
+            package_id: "foo 0.1.0 (path+file:///foo)".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "warning: redundant clone".to_string(),
+                code: Some(Code {
+                    code: "clippy::redundant_clone".to_string(),
+                    explanation: None,
+                }),
+                level: Some(Level::Warning),
+                spans: vec![span(3, None)],
+                children: vec![Message {
+                    message: String::new(),
+                    rendered: "help: remove this".to_string(),
+                    code: None,
+                    level: Some(Level::Help),
+                    spans: vec![span(3, Some(""))],
+                    children: Vec::new(),
+                }],
+            }),
+        };
+        let formatter = RdjsonFormatter::new(PathResolver::new(vec!["foo".to_string()]));
+        let result: Value =
+            serde_json::from_str(&formatter.format(&[lint], &RunMetadata::default())).unwrap();
+        let range = json!({
+            "start": { "line": 3, "column": 5 },
+            "end": { "line": 3, "column": 9 },
+        });
+        assert_eq!(
+            json!([{
+                "message": "redundant clone",
+                "location": { "path": "foo/src/lib.rs", "range": range },
+                "severity": "WARNING",
+                "originalOutput": "warning: redundant clone",
+                "code": { "value": "clippy::redundant_clone" },
+                "suggestions": [{ "range": range, "text": "" }],
+            }]),
+            result["diagnostics"]
+        );
+    }
+
+    #[test]
+    fn test_without_suggestions() {
+        let lint = Lint {
+            package_id: "foo 0.1.0 (path+file:///foo)".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "error: bar".to_string(),
+                code: None,
+                level: Some(Level::Error),
+                spans: vec![span(1, None)],
+                children: Vec::new(),
+            }),
+        };
+        let result: Value = serde_json::from_str(
+            &RdjsonFormatter::default().format(&[lint], &RunMetadata::default()),
+        )
+        .unwrap();
+        let diagnostic = &result["diagnostics"][0];
+        assert_eq!("ERROR", diagnostic["severity"]);
+        assert!(diagnostic.get("suggestions").is_none());
+        assert!(diagnostic.get("code").is_none());
+    }
+}
@@ -0,0 +1,127 @@
+use crate::linter::{Level, Lint};
+use crate::output::{headline, Formatter, RunMetadata};
+use crate::paths::PathResolver;
+use crate::stats::UNKNOWN_CODE;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Renders findings as a SARIF 2.1.0 log,
+/// which code scanning tools consume natively.
+#[derive(Default, Debug, Clone)]
+pub struct SarifFormatter {
+    resolver: PathResolver,
+}
+
+impl SarifFormatter {
+    #[must_use]
+    pub fn new(resolver: PathResolver) -> Self {
+        Self { resolver }
+    }
+
+    fn result(&self, lint: &Lint) -> Value {
+        let resolved = self.resolver.resolve_lint(lint);
+        let span = lint.primary_span();
+        let level = match lint.level() {
+            Some(Level::Error) => "error",
+            Some(Level::Warning) | None => "warning",
+            _ => "note",
+        };
+        json!({
+            "ruleId": lint.code().unwrap_or(UNKNOWN_CODE),
+            "level": level,
+            "message": { "text": headline(lint) },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": {
+                        "uri": resolved.as_ref().map(|r| r.repo_path.as_str()),
+                    },
+                    "region": {
+                        "startLine": span.map(|s| s.line_start),
+                        "endLine": span.map(|s| s.line_end),
+                    },
+                },
+            }],
+            "properties": {
+                "cratePath": resolved.as_ref().map(|r| r.crate_path.as_str()),
+                "crateName": resolved.as_ref().and_then(|r| r.crate_name.as_deref()),
+            },
+        })
+    }
+}
+
+impl Formatter for SarifFormatter {
+    fn format(&self, lints: &[Lint], metadata: &RunMetadata) -> String {
+        let rules: BTreeSet<&str> = lints
+            .iter()
+            .map(|l| l.code().unwrap_or(UNKNOWN_CODE))
+            .collect();
+        let rules: Vec<Value> = rules.into_iter().map(|id| json!({ "id": id })).collect();
+        let results: Vec<Value> = lints.iter().map(|l| self.result(l)).collect();
+        let log = json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "cargo-scout",
+                        "informationUri": "https://github.com/o0Ignition0o/cargo-scout",
+                        "version": metadata.tool_version,
+                        "rules": rules,
+                    },
+                },
+                "properties": {
+                    "targetBranch": metadata.target_branch,
+                },
+                "results": results,
+            }],
+        });
+        format!("{log:#}\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::span;
+    use crate::linter::{Code, Message};
+
+    #[test]
+    fn test_sarif() {
+        let lint = Lint {
+            package_id: "foo 0.1.0 (path+file:///foo)".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "error: bar".to_string(),
+                code: Some(Code {
+                    code: "clippy::panic".to_string(),
+                    explanation: None,
+                }),
+                level: Some(Level::Error),
+                children: Vec::new(),
+                spans: vec![span("foo/src/lib.rs", 3, 4)],
+            }),
+        };
+        let formatter = SarifFormatter::new(PathResolver::new(vec!["foo".to_string()]));
+        let log: Value =
+            serde_json::from_str(&formatter.format(&[lint], &RunMetadata::default())).unwrap();
+        assert_eq!("2.1.0", log["version"]);
+        let run = &log["runs"][0];
+        assert_eq!(
+            json!([{"id": "clippy::panic"}]),
+            run["tool"]["driver"]["rules"]
+        );
+        let result = &run["results"][0];
+        assert_eq!("clippy::panic", result["ruleId"]);
+        assert_eq!("error", result["level"]);
+        assert_eq!("bar", result["message"]["text"]);
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!("foo/src/lib.rs", location["artifactLocation"]["uri"]);
+        assert_eq!(3, location["region"]["startLine"]);
+        assert_eq!("src/lib.rs", result["properties"]["cratePath"]);
+        assert_eq!("foo", result["properties"]["crateName"]);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::linter::{Level, Lint};
+use crate::output::{headline, Formatter, RunMetadata};
+use crate::paths::PathResolver;
+use crate::stats::UNKNOWN_CODE;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+/// Renders findings as `TeamCity` service messages,
+/// which the build's Inspections tab lists natively.
+///
+/// Errors are also reported as build problems, so they fail the build.
+#[derive(Default, Debug, Clone)]
+pub struct TeamcityFormatter {
+    resolver: PathResolver,
+}
+
+impl TeamcityFormatter {
+    #[must_use]
+    pub fn new(resolver: PathResolver) -> Self {
+        Self { resolver }
+    }
+}
+
+impl Formatter for TeamcityFormatter {
+    fn format(&self, lints: &[Lint], _metadata: &RunMetadata) -> String {
+        let mut output = String::new();
+        // Inspections must reference a type declared beforehand
+        let codes: BTreeSet<&str> = lints
+            .iter()
+            .map(|l| l.code().unwrap_or(UNKNOWN_CODE))
+            .collect();
+        for code in codes {
+            let category = if code.starts_with("clippy::") {
+                "clippy"
+            } else {
+                "rustc"
+            };
+            let _ = writeln!(
+                output,
+                "##teamcity[inspectionType id='{0}' name='{0}' description='{0}' category='{1}']",
+                escape(code),
+                category
+            );
+        }
+        for lint in lints {
+            let severity = match lint.level() {
+                Some(Level::Error) => "ERROR",
+                Some(Level::Warning) | None => "WARNING",
+                _ => "INFO",
+            };
+            let file = self
+                .resolver
+                .resolve_lint(lint)
+                .map_or_else(|| UNKNOWN_CODE.to_string(), |r| r.repo_path);
+            let line = lint.primary_span().map_or(0, |s| s.line_start);
+            let _ = writeln!(
+                output,
+                "##teamcity[inspection typeId='{}' message='{}' file='{}' line='{}' SEVERITY='{}']",
+                escape(lint.code().unwrap_or(UNKNOWN_CODE)),
+                escape(&headline(lint)),
+                escape(&file),
+                line,
+                severity
+            );
+            if lint.level() == Some(Level::Error) {
+                let _ = writeln!(
+                    output,
+                    "##teamcity[buildProblem description='{}' identity='{}']",
+                    escape(&format!("{}:{}: {}", file, line, headline(lint))),
+                    lint.fingerprint()
+                );
+            }
+        }
+        output
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('|', "||")
+        .replace('\'', "|'")
+        .replace('\n', "|n")
+        .replace('\r', "|r")
+        .replace('[', "|[")
+        .replace(']', "|]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::lint_at;
+
+    const CODE: &str = "clippy::doc_markdown";
+    const RENDERED: &str = "warning: item in documentation is missing backticks: 'a[0]'";
+
+    #[test]
+    fn test_teamcity() {
+        let error = lint_at(CODE, Level::Error, RENDERED, "src/lib.rs", (1, 1));
+        let lints = vec![
+            lint_at(CODE, Level::Warning, RENDERED, "src/main.rs", (3, 3)),
+            error.clone(),
+        ];
+        let expected = format!(
+            "##teamcity[inspectionType id='clippy::doc_markdown' name='clippy::doc_markdown' description='clippy::doc_markdown' category='clippy']
+##teamcity[inspection typeId='clippy::doc_markdown' message='item in documentation is missing backticks: |'a|[0|]|'' file='src/main.rs' line='3' SEVERITY='WARNING']
+##teamcity[inspection typeId='clippy::doc_markdown' message='item in documentation is missing backticks: |'a|[0|]|'' file='src/lib.rs' line='1' SEVERITY='ERROR']
+##teamcity[buildProblem description='src/lib.rs:1: item in documentation is missing backticks: |'a|[0|]|'' identity='{}']
+",
+            error.fingerprint()
+        );
+        assert_eq!(
+            expected,
+            TeamcityFormatter::default().format(&lints, &RunMetadata::default())
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!("a||b|nc|r", escape("a|b\nc\r"));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::linter::{Level, Lint, Span};
+use crate::output::{headline, Formatter, RunMetadata};
+use crate::paths::PathResolver;
+use serde_json::{json, Value};
+
+/// Where `--emit vscode` writes the diagnostics, relative to the directory scout runs in
+pub const VSCODE_DIAGNOSTICS_PATH: &str = ".scout/vscode-diagnostics.json";
+
+/// Renders findings as a list of VS Code diagnostics,
+/// with zero-based lines and characters like the editor API.
+///
+/// Paths are relative to the repository root and always use forward slashes.
+#[derive(Default, Debug, Clone)]
+pub struct VscodeFormatter {
+    resolver: PathResolver,
+}
+
+impl VscodeFormatter {
+    #[must_use]
+    pub fn new(resolver: PathResolver) -> Self {
+        Self { resolver }
+    }
+
+    fn diagnostic(&self, lint: &Lint) -> Value {
+        let resolved = self.resolver.resolve_lint(lint);
+        let span = lint.primary_span();
+        let severity = match lint.level() {
+            Some(Level::Error) => "Error",
+            Some(Level::Warning) | None => "Warning",
+            Some(Level::Help) => "Hint",
+            _ => "Information",
+        };
+        json!({
+            "file": resolved.as_ref().map(|r| r.repo_path.as_str()),
+            "range": span.map(range),
+            "severity": severity,
+            "message": headline(lint),
+            "source": "cargo-scout",
+            "code": lint.code(),
+        })
+    }
+}
+
+// Converts the one-based span positions to zero-based ones,
+// unknown columns cover the whole lines
+fn range(span: &Span) -> Value {
+    let start_character = span.column_start.saturating_sub(1);
+    let end_character = if span.column_end == 0 {
+        u32::MAX
+    } else {
+        span.column_end - 1
+    };
+    json!({
+        "start": {
+            "line": span.line_start.saturating_sub(1),
+            "character": start_character,
+        },
+        "end": {
+            "line": span.line_end.saturating_sub(1),
+            "character": end_character,
+        },
+    })
+}
+
+impl Formatter for VscodeFormatter {
+    fn format(&self, lints: &[Lint], _metadata: &RunMetadata) -> String {
+        let diagnostics: Vec<Value> = lints.iter().map(|l| self.diagnostic(l)).collect();
+        format!("{:#}\n", Value::Array(diagnostics))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::{Code, Message};
+
+    #[test]
+    fn test_vscode() {
+        let lint = Lint {
+            package_id: "foo 0.1.0 (path+file:///foo)".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "warning: missing backticks".to_string(),
+                code: Some(Code {
+                    code: "clippy::doc_markdown".to_string(),
+                    explanation: None,
+                }),
+                level: Some(Level::Warning),
+                spans: vec![Span {
+                    file_name: "foo\\src\\lib.rs".to_string(),
+                    line_start: 3,
+                    line_end: 4,
+                    column_start: 5,
+                    column_end: 9,
+                    is_primary: true,
+                    ..Span::default()
+                }],
+                children: Vec::new(),
+            }),
+        };
+        let formatter = VscodeFormatter::new(PathResolver::new(vec!["foo".to_string()]));
+        let output: Value =
+            serde_json::from_str(&formatter.format(&[lint], &RunMetadata::default())).unwrap();
+        assert_eq!(
+            json!([{
+                "file": "foo/src/lib.rs",
+                "range": {
+                    "start": { "line": 2, "character": 4 },
+                    "end": { "line": 3, "character": 8 },
+                },
+                "severity": "Warning",
+                "message": "missing backticks",
+                "source": "cargo-scout",
+                "code": "clippy::doc_markdown",
+            }]),
+            output
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use crate::linter::{Lint, Message};
+use std::path::{Component, Path, PathBuf};
+
+/// Resolves the paths reported by linters into the paths
+/// the different consumers of scout need.
+///
+/// Annotations on code hosts need paths relative to the repository root,
+/// whereas IDEs usually want paths relative to the crate.
+/// Every emitter should use this service so they all agree.
+#[derive(Debug, Clone, Default)]
+pub struct PathResolver {
+    // Member directories, relative to the repository root,
+    // sorted from the longest to the shortest.
+    members: Vec<String>,
+}
+
+/// A finding location, relative to both the repository and its crate.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ResolvedPath {
+    /// The path relative to the repository root
+    /// Example:
+    /// "cargo-scout-lib/src/lib.rs"
+    pub repo_path: String,
+    /// The path relative to the crate directory
+    /// Example:
+    /// "src/lib.rs"
+    pub crate_path: String,
+    /// The crate name, if known
+    /// Example:
+    /// Some("cargo-scout-lib")
+    pub crate_name: Option<String>,
+}
+
+impl PathResolver {
+    /// Creates a resolver out of the member directories,
+    /// as returned by `Config::members`.
+    #[must_use]
+    pub fn new(members: Vec<String>) -> Self {
+        let mut members: Vec<String> = members.into_iter().map(|m| normalize(&m)).collect();
+        members.sort_by_key(|m| std::cmp::Reverse(m.len()));
+        Self { members }
+    }
+
+    /// Resolves a file name reported by a linter.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::paths::PathResolver;
+    /// let resolver = PathResolver::new(vec!["cargo-scout".to_string(), "cargo-scout-lib".to_string()]);
+    /// let resolved = resolver.resolve("cargo-scout-lib\\src\\lib.rs");
+    /// assert_eq!("cargo-scout-lib/src/lib.rs", resolved.repo_path);
+    /// assert_eq!("src/lib.rs", resolved.crate_path);
+    /// ```
+    #[must_use]
+    pub fn resolve(&self, file_name: &str) -> ResolvedPath {
+        let repo_path = normalize(file_name);
+        let crate_path = self
+            .members
+            .iter()
+            .filter(|m| !m.is_empty())
+            .find_map(|m| {
+                repo_path
+                    .strip_prefix(m.as_str())
+                    .and_then(|p| p.strip_prefix('/'))
+            })
+            .unwrap_or(&repo_path)
+            .to_string();
+        ResolvedPath {
+            repo_path,
+            crate_path,
+            crate_name: None,
+        }
+    }
+
+    /// Resolves the primary location of a lint, along with its crate name.
+    #[must_use]
+    pub fn resolve_lint(&self, lint: &Lint) -> Option<ResolvedPath> {
+        let span = lint.primary_span()?;
+        let mut resolved = self.resolve(&span.file_name);
+        resolved.crate_name = crate_name(&lint.package_id).map(String::from);
+        Some(resolved)
+    }
+}
+
+/// Rewrites the paths reported by linters relative to the repository root,
+/// which is how the VCS reports changed sections.
+///
+/// Linters may report absolute paths, or paths relative to the workspace root,
+/// which isn't the repository root when the workspace lives in a subdirectory.
+#[derive(Debug, Clone, Default)]
+pub struct PathNormalizer {
+    repo_root: PathBuf,
+    workspace_root: PathBuf,
+}
+
+impl PathNormalizer {
+    /// Both roots should be absolute.
+    #[must_use]
+    pub fn new(repo_root: impl AsRef<Path>, workspace_root: impl AsRef<Path>) -> Self {
+        let canonical = |p: &Path| canonical(p).unwrap_or_else(|| lexical(p));
+        Self {
+            repo_root: canonical(repo_root.as_ref()),
+            workspace_root: canonical(workspace_root.as_ref()),
+        }
+    }
+
+    /// Returns the file name relative to the repository root, with forward slashes.
+    ///
+    /// Files outside of the repository keep their absolute path.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::paths::PathNormalizer;
+    /// let normalizer = PathNormalizer::new("/repo", "/repo/rust");
+    /// assert_eq!("rust/foo/src/lib.rs", normalizer.normalize("foo/src/lib.rs"));
+    /// assert_eq!("rust/src/main.rs", normalizer.normalize("/repo/rust/./src/main.rs"));
+    /// ```
+    #[must_use]
+    pub fn normalize(&self, file_name: &str) -> String {
+        let file_name = file_name.replace('\\', "/");
+        let path = Path::new(&file_name);
+        let absolute = if path.is_absolute() {
+            canonical(path).unwrap_or_else(|| lexical(&simplify(path)))
+        } else {
+            lexical(&self.workspace_root.join(path))
+        };
+        match absolute.strip_prefix(&self.repo_root) {
+            Ok(relative) => normalize(&relative.to_string_lossy()),
+            Err(_) => normalize(&absolute.to_string_lossy()),
+        }
+    }
+
+    /// The root of the repository, file names are relative to it.
+    #[must_use]
+    pub fn repo_root(&self) -> &Path {
+        &self.repo_root
+    }
+
+    /// Returns where a member directory lives in the repository,
+    /// given the project directory its path is relative to.
+    ///
+    /// The repository root itself is an empty string.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::paths::PathNormalizer;
+    /// # use std::path::Path;
+    /// let normalizer = PathNormalizer::new("/repo", "/repo/rust");
+    /// assert_eq!("rust/foo", normalizer.repo_dir(Path::new("/repo/rust"), "foo"));
+    /// assert_eq!("rust", normalizer.repo_dir(Path::new("/repo/rust"), "."));
+    /// assert_eq!("", normalizer.repo_dir(Path::new("/repo"), "."));
+    /// ```
+    #[must_use]
+    pub fn repo_dir(&self, project_dir: &Path, member: &str) -> String {
+        self.normalize(&project_dir.join(member).to_string_lossy())
+    }
+
+    /// Normalizes the paths of every span of the lint, including its children's.
+    #[must_use]
+    pub fn normalize_lint(&self, mut lint: Lint) -> Lint {
+        if let Some(message) = lint.message.as_mut() {
+            self.normalize_message(message);
+        }
+        lint
+    }
+
+    fn normalize_message(&self, message: &mut Message) {
+        for span in &mut message.spans {
+            span.file_name = self.normalize(&span.file_name);
+        }
+        for child in &mut message.children {
+            self.normalize_message(child);
+        }
+    }
+}
+
+// Canonicalizes the path, without the verbatim prefix Windows adds
+fn canonical(path: &Path) -> Option<PathBuf> {
+    std::fs::canonicalize(path).ok().map(|p| simplify(&p))
+}
+
+/// Turns the verbatim paths `std::fs::canonicalize` returns on Windows,
+/// such as `\\?\C:\repo`, into the paths linters and git report,
+/// with an uppercase drive letter so they compare equal.
+///
+/// # Example
+/// ```
+/// # use cargo_scout_lib::paths::simplify;
+/// # use std::path::{Path, PathBuf};
+/// assert_eq!(PathBuf::from("C:\\repo"), simplify(Path::new("\\\\?\\c:\\repo")));
+/// assert_eq!(PathBuf::from("\\\\server\\share"), simplify(Path::new("\\\\?\\UNC\\server\\share")));
+/// assert_eq!(PathBuf::from("/repo"), simplify(Path::new("/repo")));
+/// ```
+#[must_use]
+pub fn simplify(path: &Path) -> PathBuf {
+    let path = path.to_string_lossy();
+    let path = match path.strip_prefix(r"\\?\") {
+        Some(unc) if unc.starts_with(r"UNC\") => format!(r"\\{}", &unc[4..]),
+        Some(rest) if has_drive(rest) => rest.to_string(),
+        _ => path.into_owned(),
+    };
+    if has_drive(&path) {
+        PathBuf::from(format!("{}{}", path[..1].to_ascii_uppercase(), &path[1..]))
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+fn has_drive(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+// Resolves `.` and `..` without touching the file system
+fn lexical(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            c => result.push(c.as_os_str()),
+        }
+    }
+    result
+}
+
+/// Extracts the crate name out of a cargo package id,
+/// or out of the package id spec recent cargo versions report instead.
+///
+/// # Example
+/// ```
+/// # use cargo_scout_lib::paths::crate_name;
+/// assert_eq!(Some("cargo-scout-lib"), crate_name("cargo-scout-lib 0.5.0 (path+file:///cargo-scout-lib)"));
+/// assert_eq!(Some("cargo-scout-lib"), crate_name("path+file:///repo/cargo-scout-lib#0.5.0"));
+/// assert_eq!(Some("scout"), crate_name("path+file:///repo/cargo-scout#scout@0.5.0"));
+/// ```
+#[must_use]
+pub fn crate_name(package_id: &str) -> Option<&str> {
+    // Specs have no spaces, paths in their URL are percent-encoded
+    match package_id.rsplit_once('#') {
+        Some((url, fragment)) if !package_id.contains(' ') => match fragment.split_once('@') {
+            Some((name, _)) => Some(name),
+            None => url.trim_end_matches('/').rsplit('/').next(),
+        },
+        _ => package_id.split_whitespace().next(),
+    }
+}
+
+// Use forward slashes, and get rid of the leading `./`
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut path = path.as_str();
+    while let Some(stripped) = path.strip_prefix("./") {
+        path = stripped;
+    }
+    if path == "." {
+        String::new()
+    } else {
+        path.trim_end_matches('/').to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::span;
+    use crate::linter::Message;
+
+    #[test]
+    fn test_normalize_nested_workspace() {
+        let normalizer = PathNormalizer::new("/does-not-exist/repo", "/does-not-exist/repo/rust");
+        assert_eq!(
+            "rust/member/src/lib.rs",
+            normalizer.normalize("member\\src\\lib.rs")
+        );
+        assert_eq!(
+            "rust/src/lib.rs",
+            normalizer.normalize("/does-not-exist/repo/rust/member/../src/lib.rs")
+        );
+        assert_eq!(
+            "/does-not-exist/elsewhere/lib.rs",
+            normalizer.normalize("/does-not-exist/elsewhere/lib.rs")
+        );
+    }
+
+    #[test]
+    fn test_normalize_lint() {
+        let lint = Lint {
+            package_id: "foo".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                spans: vec![span("src/lib.rs", 1, 1)],
+                children: vec![Message {
+                    spans: vec![span("/does-not-exist/repo/src/main.rs", 1, 1)],
+                    ..Message::default()
+                }],
+                ..Message::default()
+            }),
+        };
+        let normalizer = PathNormalizer::new("/does-not-exist", "/does-not-exist/repo");
+        let message = normalizer.normalize_lint(lint).message.unwrap();
+        assert_eq!("repo/src/lib.rs", message.spans[0].file_name);
+        assert_eq!("repo/src/main.rs", message.children[0].spans[0].file_name);
+    }
+
+    #[test]
+    fn test_resolve_not_workspace() {
+        let resolver = PathResolver::new(vec![".".to_string()]);
+        let resolved = resolver.resolve("./src/lib.rs");
+        assert_eq!("src/lib.rs", resolved.repo_path);
+        assert_eq!("src/lib.rs", resolved.crate_path);
+    }
+
+    #[test]
+    fn test_resolve_nested_members() {
+        let resolver = PathResolver::new(vec![
+            "crates".to_string(),
+            "crates/foo/".to_string(),
+            "crates/foobar".to_string(),
+        ]);
+        assert_eq!(
+            "src/lib.rs",
+            resolver.resolve("crates/foo/src/lib.rs").crate_path
+        );
+        assert_eq!(
+            "src/lib.rs",
+            resolver.resolve("crates/foobar/src/lib.rs").crate_path
+        );
+        assert_eq!("build.rs", resolver.resolve("crates/build.rs").crate_path);
+    }
+
+    #[test]
+    fn test_resolve_outside_members() {
+        let resolver = PathResolver::new(vec!["foo".to_string()]);
+        let resolved = resolver.resolve("bar/src/lib.rs");
+        assert_eq!("bar/src/lib.rs", resolved.repo_path);
+        assert_eq!("bar/src/lib.rs", resolved.crate_path);
+    }
+
+    #[test]
+    fn test_resolve_lint() {
+        let resolver = PathResolver::new(vec!["cargo-scout-lib".to_string()]);
+        let lint = Lint {
+            package_id: "cargo-scout-lib 0.5.0 (path+file:///cargo-scout-lib)".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "this is a test lint".to_string(),
+                code: None,
+                level: None,
+                children: Vec::new(),
+                spans: vec![span("cargo-scout-lib/src/lib.rs", 1, 1)],
+            }),
+        };
+        assert_eq!(
+            Some(ResolvedPath {
+                repo_path: "cargo-scout-lib/src/lib.rs".to_string(),
+                crate_path: "src/lib.rs".to_string(),
+                crate_name: Some("cargo-scout-lib".to_string()),
+            }),
+            resolver.resolve_lint(&lint)
+        );
+    }
+}
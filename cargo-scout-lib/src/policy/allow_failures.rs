@@ -0,0 +1,286 @@
+use crate::linter::Lint;
+use glob::Pattern;
+use serde::Deserialize;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_ALLOW_FAILURES_PATH: &str = "scout-allow-failures.toml";
+
+/// This struct represents the optional `scout-allow-failures.toml` file,
+/// which lists findings that temporarily don't fail the run.
+///
+/// The findings are still reported, only the exit status is affected.
+///
+/// # Example
+/// ```toml
+/// [[allow]]
+/// fingerprint = "5f0c6b7e1a2d3c4b"
+/// owner = "jane"
+/// expires = "2020-06-30"
+///
+/// [[allow]]
+/// file = "src/legacy/**"
+/// owner = "john"
+/// expires = "2020-07-15"
+/// ```
+#[derive(Deserialize, Debug, Default, PartialEq, Clone)]
+#[serde(default)]
+pub struct AllowFailures {
+    pub allow: Vec<AllowedFailure>,
+}
+
+/// A finding, or the findings of a set of files, allowed to fail until a date.
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct AllowedFailure {
+    /// The fingerprint of the finding, as reported by the json output
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    /// A glob matching the files whose findings are allowed to fail
+    /// Example:
+    /// "src/legacy/**"
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Who is responsible for fixing the findings
+    pub owner: String,
+    /// The last day the findings are allowed to fail, formatted as YYYY-MM-DD
+    pub expires: String,
+}
+
+impl AllowedFailure {
+    /// Returns true if the entry is past its expiry date.
+    #[must_use]
+    pub fn is_expired(&self, today: &str) -> bool {
+        self.expires.as_str() < today
+    }
+
+    /// Returns true if the lint is covered by this entry.
+    #[must_use]
+    pub fn matches(&self, lint: &Lint) -> bool {
+        let fingerprint_matches = self
+            .fingerprint
+            .as_ref()
+            .is_none_or(|f| *f == lint.fingerprint());
+        let file_matches = self.file.as_ref().is_none_or(|file| {
+            let pattern = Pattern::new(file);
+            lint.primary_span().is_some_and(|s| {
+                pattern
+                    .as_ref()
+                    .is_ok_and(|p| p.matches(&s.file_name.replace('\\', "/")))
+            })
+        });
+        fingerprint_matches && file_matches
+    }
+
+    fn validate(&self) -> Result<(), crate::error::Error> {
+        if self.fingerprint.is_none() && self.file.is_none() {
+            return Err(crate::error::Error::AllowFailures(format!(
+                "the entry owned by {} needs a fingerprint or a file",
+                self.owner
+            )));
+        }
+        if !is_date(&self.expires) {
+            return Err(crate::error::Error::AllowFailures(format!(
+                "invalid expiry date {}, expected YYYY-MM-DD",
+                self.expires
+            )));
+        }
+        if let Some(file) = &self.file {
+            Pattern::new(file)?;
+        }
+        Ok(())
+    }
+}
+
+impl AllowFailures {
+    /// This function will read the allowed failures from a path.
+    ///
+    /// If the file doesn't exist, nothing is allowed to fail.
+    ///
+    /// # Errors
+    /// Fails if the file can't be read, isn't valid TOML, or has an invalid date.
+    pub fn from_path(p: impl AsRef<Path>) -> Result<Self, crate::error::Error> {
+        let p = p.as_ref();
+        if p.exists() {
+            std::fs::read_to_string(p)?.parse()
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Returns the lints that aren't covered by an unexpired entry,
+    /// which are the ones that can fail the run.
+    #[must_use]
+    pub fn failing(&self, lints: &[Lint], today: &str) -> Vec<Lint> {
+        lints
+            .iter()
+            .filter(|l| {
+                !self
+                    .allow
+                    .iter()
+                    .any(|a| !a.is_expired(today) && a.matches(l))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the entries past their expiry date.
+    #[must_use]
+    pub fn expired(&self, today: &str) -> Vec<&AllowedFailure> {
+        self.allow.iter().filter(|a| a.is_expired(today)).collect()
+    }
+}
+
+impl FromStr for AllowFailures {
+    type Err = crate::error::Error;
+
+    /// This function will parse the contents of a `scout-allow-failures.toml` file.
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let allow_failures: Self = toml::from_str(contents)?;
+        for entry in &allow_failures.allow {
+            entry.validate()?;
+        }
+        Ok(allow_failures)
+    }
+}
+
+fn is_date(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    parts.len() == 3
+        && [4, 2, 2]
+            .iter()
+            .zip(&parts)
+            .all(|(len, part)| part.len() == *len && part.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Returns the current UTC date, formatted as YYYY-MM-DD.
+#[must_use]
+pub fn today() -> String {
+    date(now())
+}
+
+/// Returns the number of seconds since the Unix epoch.
+#[must_use]
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Returns the UTC date of a number of seconds since the Unix epoch, formatted as YYYY-MM-DD.
+#[must_use]
+pub fn date(seconds: u64) -> String {
+    let (year, month, day) = civil_from_days(seconds / 86_400);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+// Converts a number of days since 1970-01-01 to a (year, month, day) date,
+// see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::span;
+    use crate::linter::Message;
+
+    fn lint(file_name: &str) -> Lint {
+        Lint {
+            package_id: "cargo-scout".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "warning: foo".to_string(),
+                code: None,
+                level: None,
+                spans: vec![span(file_name, 1, 1)],
+                children: Vec::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_allowed_by_file() {
+        let allow_failures: AllowFailures = r#"
+            [[allow]]
+            file = "src/legacy/**"
+            owner = "jane"
+            expires = "2020-06-30"
+        "#
+        .parse()
+        .unwrap();
+        let lints = vec![lint("src/legacy/old.rs"), lint("src/new.rs")];
+
+        let failing = allow_failures.failing(&lints, "2020-06-30");
+        assert_eq!(vec![lint("src/new.rs")], failing);
+        assert!(allow_failures.expired("2020-06-30").is_empty());
+
+        // The day after the expiry date, everything fails again
+        assert_eq!(lints, allow_failures.failing(&lints, "2020-07-01"));
+        assert_eq!(1, allow_failures.expired("2020-07-01").len());
+    }
+
+    #[test]
+    fn test_allowed_by_fingerprint() {
+        let allowed = lint("src/lib.rs");
+        let allow_failures: AllowFailures = format!(
+            "[[allow]]\nfingerprint = \"{}\"\nowner = \"jane\"\nexpires = \"2020-06-30\"\n",
+            allowed.fingerprint()
+        )
+        .parse()
+        .unwrap();
+        let lints = vec![allowed, lint("src/main.rs")];
+
+        assert_eq!(
+            vec![lint("src/main.rs")],
+            allow_failures.failing(&lints, "2020-01-01")
+        );
+    }
+
+    #[test]
+    fn test_invalid_entries() {
+        assert!("[[allow]]\nowner = \"jane\"\nexpires = \"2020-06-30\""
+            .parse::<AllowFailures>()
+            .is_err());
+        assert!(
+            "[[allow]]\nfile = \"src/**\"\nowner = \"jane\"\nexpires = \"next week\""
+                .parse::<AllowFailures>()
+                .is_err()
+        );
+        assert!(
+            "[[allow]]\nfile = \"src/***\"\nowner = \"jane\"\nexpires = \"2020-06-30\""
+                .parse::<AllowFailures>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_missing_file() {
+        assert_eq!(
+            AllowFailures::default(),
+            AllowFailures::from_path("does-not-exist.toml").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!((1970, 1, 1), civil_from_days(0));
+        assert_eq!((2000, 3, 1), civil_from_days(11_017));
+        assert_eq!((2020, 2, 29), civil_from_days(18_321));
+        assert_eq!("2020-01-01", date(1_577_836_800));
+    }
+}
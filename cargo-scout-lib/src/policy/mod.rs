@@ -0,0 +1,223 @@
+use crate::linter::{Level, Lint};
+use serde::Deserialize;
+use std::str::FromStr;
+
+pub mod allow_failures;
+pub mod ratchet;
+
+/// Decides which lint severities make a run fail.
+///
+/// Lints that don't report a level are considered warnings.
+#[derive(Deserialize, PartialEq, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FailOn {
+    /// Only fail if an error has been found
+    Error,
+    /// Fail if a warning or an error has been found
+    Warning,
+    /// Fail if anything has been found
+    #[default]
+    Any,
+}
+
+impl FromStr for FailOn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(FailOn::Error),
+            "warning" => Ok(FailOn::Warning),
+            "any" => Ok(FailOn::Any),
+            _ => Err(format!(
+                "unknown severity {s}, expected one of error, warning, any"
+            )),
+        }
+    }
+}
+
+impl FailOn {
+    /// Returns true if the lint is severe enough to fail the run.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::linter::Level;
+    /// # use cargo_scout_lib::policy::FailOn;
+    /// assert!(FailOn::Warning.fails_on(Some(Level::Error)));
+    /// assert!(!FailOn::Error.fails_on(Some(Level::Warning)));
+    /// assert!(FailOn::Any.fails_on(Some(Level::Note)));
+    /// ```
+    #[must_use]
+    pub fn fails_on(self, level: Option<Level>) -> bool {
+        let level = level.unwrap_or(Level::Warning);
+        match self {
+            FailOn::Error => level == Level::Error,
+            FailOn::Warning => level == Level::Error || level == Level::Warning,
+            FailOn::Any => true,
+        }
+    }
+
+    /// Returns true if at least one of the lints should fail the run.
+    #[must_use]
+    pub fn should_fail(self, lints: &[Lint]) -> bool {
+        lints.iter().any(|l| self.fails_on(l.level()))
+    }
+}
+
+/// Lint codes that fail the run when the diff introduces them,
+/// the other findings are only advisory.
+///
+/// It overrides the severity of the findings once they're intersected with the diff:
+/// the denied ones become errors, and the other ones notes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DenyNew {
+    codes: Vec<String>,
+}
+
+impl DenyNew {
+    #[must_use]
+    pub fn new(codes: Vec<String>) -> Self {
+        Self { codes }
+    }
+
+    /// Returns true if some codes are denied, no severity is overridden otherwise.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        !self.codes.is_empty()
+    }
+
+    /// Returns the lints with their severity overridden.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::linter::{Code, Level, Lint, Message};
+    /// # use cargo_scout_lib::policy::DenyNew;
+    /// # let lint = |code: &str| Lint {
+    /// #     package_id: "foo".to_string(),
+    /// #     src_path: None,
+    /// #     reproducer: None,
+    /// #     message: Some(Message {
+    /// #         code: Some(Code { code: code.to_string(), explanation: None }),
+    /// #         level: Some(Level::Warning),
+    /// #         ..Message::default()
+    /// #     }),
+    /// # };
+    /// let deny_new = DenyNew::new(vec!["clippy::unwrap_used".to_string()]);
+    /// let lints = deny_new.apply(vec![lint("clippy::unwrap_used"), lint("clippy::doc_markdown")]);
+    /// assert_eq!(Some(Level::Error), lints[0].level());
+    /// assert_eq!(Some(Level::Note), lints[1].level());
+    /// ```
+    #[must_use]
+    pub fn apply(&self, mut lints: Vec<Lint>) -> Vec<Lint> {
+        for lint in &mut lints {
+            self.override_level(lint);
+        }
+        lints
+    }
+
+    /// Overrides the severity of a single lint, see `apply`.
+    pub fn override_level(&self, lint: &mut Lint) {
+        if !self.is_enabled() {
+            return;
+        }
+        let denied = lint
+            .code()
+            .is_some_and(|code| self.codes.iter().any(|c| c == code));
+        if let Some(message) = lint.message.as_mut() {
+            message.level = Some(if denied { Level::Error } else { Level::Note });
+        }
+    }
+
+    /// The severity that makes the run fail, only the denied lints do when it's enabled.
+    #[must_use]
+    pub fn fail_on(&self, fail_on: FailOn) -> FailOn {
+        if self.is_enabled() {
+            FailOn::Error
+        } else {
+            fail_on
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::Message;
+
+    fn lint(level: Option<Level>) -> Lint {
+        Lint {
+            package_id: "cargo-scout".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "this is a test lint".to_string(),
+                code: None,
+                level,
+                children: Vec::new(),
+                spans: Vec::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Ok(FailOn::Error), "error".parse());
+        assert_eq!(Ok(FailOn::Warning), "warning".parse());
+        assert_eq!(Ok(FailOn::Any), "any".parse());
+        assert!("foo".parse::<FailOn>().is_err());
+    }
+
+    #[test]
+    fn test_no_lints_never_fail() {
+        assert!(!FailOn::Any.should_fail(&[]));
+        assert!(!FailOn::Warning.should_fail(&[]));
+        assert!(!FailOn::Error.should_fail(&[]));
+    }
+
+    #[test]
+    fn test_fail_on_error() {
+        assert!(!FailOn::Error.should_fail(&[lint(Some(Level::Warning)), lint(None)]));
+        assert!(FailOn::Error.should_fail(&[lint(Some(Level::Warning)), lint(Some(Level::Error))]));
+    }
+
+    #[test]
+    fn test_fail_on_warning() {
+        assert!(!FailOn::Warning.should_fail(&[lint(Some(Level::Note)), lint(Some(Level::Help))]));
+        assert!(FailOn::Warning.should_fail(&[lint(Some(Level::Note)), lint(None)]));
+        assert!(FailOn::Warning.should_fail(&[lint(Some(Level::Error))]));
+    }
+
+    #[test]
+    fn test_fail_on_any() {
+        assert!(FailOn::Any.should_fail(&[lint(Some(Level::Note))]));
+        assert!(FailOn::Any.should_fail(&[lint(Some(Level::Other))]));
+    }
+
+    #[test]
+    fn test_deny_new() {
+        let with_code = |code: &str, level: Level| {
+            let mut lint = lint(Some(level));
+            if let Some(m) = lint.message.as_mut() {
+                m.code = Some(crate::linter::Code {
+                    code: code.to_string(),
+                    explanation: None,
+                });
+            }
+            lint
+        };
+        let lints = vec![
+            with_code("clippy::unwrap_used", Level::Warning),
+            with_code("clippy::panic", Level::Warning),
+            // Errors of other lints are advisory too
+            with_code("clippy::doc_markdown", Level::Error),
+        ];
+        let deny_new = DenyNew::new(vec!["clippy::panic".to_string()]);
+        let fail_on = deny_new.fail_on(FailOn::Any);
+        assert_eq!(FailOn::Error, fail_on);
+        assert!(fail_on.should_fail(&deny_new.apply(lints.clone())));
+        assert!(!fail_on.should_fail(&deny_new.apply(vec![lints[0].clone(), lints[2].clone()])));
+        // Without denied codes, nothing changes
+        assert_eq!(lints, DenyNew::default().apply(lints.clone()));
+        assert_eq!(FailOn::Any, DenyNew::default().fail_on(FailOn::Any));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::linter::Lint;
+use crate::stats::UNKNOWN_CODE;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const DEFAULT_RATCHET_PATH: &str = "scout-ratchet.toml";
+
+/// This struct represents the optional `scout-ratchet.toml` file,
+/// the amount of findings of each lint code the whole project may have.
+///
+/// A run fails when one of the amounts goes up, and the budgets are lowered
+/// as findings get fixed, so they never go back up.
+///
+/// # Example
+/// ```toml
+/// [budgets]
+/// "clippy::doc_markdown" = 12
+/// "clippy::must_use_candidate" = 3
+/// ```
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+#[serde(default)]
+pub struct Ratchet {
+    pub budgets: BTreeMap<String, usize>,
+}
+
+/// The amount of findings of a lint code, compared to its budget
+#[derive(Debug, PartialEq, Clone)]
+pub struct BudgetChange {
+    pub code: String,
+    pub budget: usize,
+    pub count: usize,
+}
+
+/// How the findings of the whole project compare to the budgets
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct RatchetCheck {
+    /// The codes with more findings than their budget, they fail the run
+    pub exceeded: Vec<BudgetChange>,
+    /// The codes with less findings than their budget, the budget can be lowered
+    pub decreased: Vec<BudgetChange>,
+}
+
+impl Ratchet {
+    /// Reads the ratchet file, or returns None if there is none.
+    ///
+    /// # Errors
+    /// Fails if the file can't be read or isn't valid TOML.
+    pub fn from_path(p: impl AsRef<Path>) -> Result<Option<Self>, crate::error::Error> {
+        let p = p.as_ref();
+        if p.exists() {
+            Ok(Some(toml::from_str(&std::fs::read_to_string(p)?)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// A ratchet whose budgets are the current amount of findings of each code.
+    #[must_use]
+    pub fn from_lints(lints: &[Lint]) -> Self {
+        Self {
+            budgets: counts(lints),
+        }
+    }
+
+    /// Writes the ratchet file.
+    ///
+    /// # Errors
+    /// Fails if the file can't be written.
+    pub fn save(&self, p: impl AsRef<Path>) -> Result<(), crate::error::Error> {
+        std::fs::write(p, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Compares the findings of the whole project to the budgets.
+    ///
+    /// Codes without a budget have a budget of 0.
+    #[must_use]
+    pub fn check(&self, lints: &[Lint]) -> RatchetCheck {
+        let counts = counts(lints);
+        let mut check = RatchetCheck::default();
+        let codes: std::collections::BTreeSet<&String> =
+            self.budgets.keys().chain(counts.keys()).collect();
+        for code in codes {
+            let change = BudgetChange {
+                code: code.clone(),
+                budget: self.budgets.get(code).copied().unwrap_or_default(),
+                count: counts.get(code).copied().unwrap_or_default(),
+            };
+            if change.count > change.budget {
+                check.exceeded.push(change);
+            } else if change.count < change.budget {
+                check.decreased.push(change);
+            }
+        }
+        check
+    }
+
+    /// Lowers the budgets to the current amount of findings, and never raises them.
+    #[must_use]
+    pub fn tightened(&self, lints: &[Lint]) -> Self {
+        let counts = counts(lints);
+        Self {
+            budgets: self
+                .budgets
+                .iter()
+                .map(|(code, budget)| {
+                    let count = counts.get(code).copied().unwrap_or_default();
+                    (code.clone(), count.min(*budget))
+                })
+                .filter(|(_, budget)| *budget > 0)
+                .collect(),
+        }
+    }
+}
+
+fn counts(lints: &[Lint]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for lint in lints {
+        *counts
+            .entry(lint.code().unwrap_or(UNKNOWN_CODE).to_string())
+            .or_insert(0) += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::lint_at;
+    use crate::linter::Level;
+
+    #[test]
+    fn test_check() {
+        let ratchet = Ratchet::from_lints(&[
+            lint_at("a", Level::Warning, "", "src/lib.rs", (1, 1)),
+            lint_at("a", Level::Warning, "", "src/lib.rs", (1, 1)),
+            lint_at("b", Level::Warning, "", "src/lib.rs", (1, 1)),
+        ]);
+        assert_eq!(
+            RatchetCheck::default(),
+            ratchet.check(&[
+                lint_at("b", Level::Warning, "", "src/lib.rs", (1, 1)),
+                lint_at("a", Level::Warning, "", "src/lib.rs", (1, 1)),
+                lint_at("a", Level::Warning, "", "src/lib.rs", (1, 1))
+            ])
+        );
+        let check = ratchet.check(&[
+            lint_at("a", Level::Warning, "", "src/lib.rs", (1, 1)),
+            lint_at("c", Level::Warning, "", "src/lib.rs", (1, 1)),
+        ]);
+        assert_eq!(
+            vec![BudgetChange {
+                code: "c".to_string(),
+                budget: 0,
+                count: 1
+            }],
+            check.exceeded
+        );
+        let decreased: Vec<(&str, usize, usize)> = check
+            .decreased
+            .iter()
+            .map(|c| (c.code.as_str(), c.budget, c.count))
+            .collect();
+        assert_eq!(vec![("a", 2, 1), ("b", 1, 0)], decreased);
+    }
+
+    #[test]
+    fn test_tightened() {
+        let ratchet = Ratchet::from_lints(&[
+            lint_at("a", Level::Warning, "", "src/lib.rs", (1, 1)),
+            lint_at("a", Level::Warning, "", "src/lib.rs", (1, 1)),
+            lint_at("b", Level::Warning, "", "src/lib.rs", (1, 1)),
+        ]);
+        // b is fixed, a is fixed once, c is new and doesn't get a budget
+        let tightened = ratchet.tightened(&[
+            lint_at("a", Level::Warning, "", "src/lib.rs", (1, 1)),
+            lint_at("c", Level::Warning, "", "src/lib.rs", (1, 1)),
+            lint_at("c", Level::Warning, "", "src/lib.rs", (1, 1)),
+        ]);
+        assert_eq!(
+            vec![("a".to_string(), 1)],
+            tightened.budgets.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_save() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join(DEFAULT_RATCHET_PATH);
+        assert_eq!(None, Ratchet::from_path(&path)?);
+        let ratchet = Ratchet::from_lints(&[lint_at(
+            "clippy::doc_markdown",
+            Level::Warning,
+            "",
+            "src/lib.rs",
+            (1, 1),
+        )]);
+        ratchet.save(&path)?;
+        assert_eq!(
+            "[budgets]\n\"clippy::doc_markdown\" = 1\n",
+            std::fs::read_to_string(&path)?
+        );
+        assert_eq!(Some(ratchet), Ratchet::from_path(&path)?);
+        Ok(())
+    }
+}
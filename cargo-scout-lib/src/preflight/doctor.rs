@@ -0,0 +1,278 @@
+use crate::baseline::{Baseline, DEFAULT_BASELINE_PATH};
+use crate::config::rust::CargoConfig;
+use crate::config::scout::ScoutConfig;
+use crate::config::Config;
+use crate::policy::allow_failures::{AllowFailures, DEFAULT_ALLOW_FAILURES_PATH};
+use crate::preflight::toolchain::ToolchainFile;
+use crate::preflight::Preflight;
+use crate::vcs::git::repo_root;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How a check of the environment went
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Ok,
+    /// Only some features of scout are affected
+    Warning,
+    /// Runs can't succeed
+    Error,
+}
+
+/// A single line of the doctor report
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    /// The version or path found, or what went wrong
+    pub detail: String,
+}
+
+/// Everything the doctor found out about the environment.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// Returns true if no check failed, warnings aside.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(|c| c.status != Status::Error)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        for check in &self.checks {
+            let status = match check.status {
+                Status::Ok => "ok",
+                Status::Warning => "warning",
+                Status::Error => "error",
+            };
+            writeln!(
+                f,
+                "{:<width$}  {:<7}  {}",
+                check.name,
+                status,
+                check.detail,
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Inspects the toolchains, the tools and the configuration scout relies on,
+/// for bug reports and for debugging CI images.
+#[derive(Debug, Clone)]
+pub struct Doctor {
+    dir: PathBuf,
+    target_branch: String,
+    manifest: PathBuf,
+    config: PathBuf,
+    allow_failures: PathBuf,
+    baseline: PathBuf,
+}
+
+impl Doctor {
+    #[must_use]
+    pub fn new(dir: PathBuf, target_branch: String) -> Self {
+        Self {
+            manifest: dir.join("Cargo.toml"),
+            config: dir.join("scout.toml"),
+            allow_failures: dir.join(DEFAULT_ALLOW_FAILURES_PATH),
+            baseline: dir.join(DEFAULT_BASELINE_PATH),
+            dir,
+            target_branch,
+        }
+    }
+
+    pub fn set_manifest(&mut self, manifest: PathBuf) -> &mut Self {
+        self.manifest = manifest;
+        self
+    }
+
+    pub fn set_config(&mut self, config: PathBuf) -> &mut Self {
+        self.config = config;
+        self
+    }
+
+    pub fn set_allow_failures(&mut self, allow_failures: PathBuf) -> &mut Self {
+        self.allow_failures = allow_failures;
+        self
+    }
+
+    pub fn set_baseline(&mut self, baseline: PathBuf) -> &mut Self {
+        self.baseline = baseline;
+        self
+    }
+
+    #[must_use]
+    pub fn report(&self) -> Report {
+        let mut checks = vec![
+            self.command(
+                "active toolchain",
+                "rustup",
+                &["show", "active-toolchain"],
+                Status::Error,
+            ),
+            self.command(
+                "toolchains",
+                "rustup",
+                &["toolchain", "list"],
+                Status::Warning,
+            ),
+            check(
+                "pinned toolchain",
+                Status::Error,
+                ToolchainFile::find(&self.dir).map(|file| {
+                    file.map_or_else(
+                        || "none".to_string(),
+                        |f| format!("{} in {}", f.channel, f.path.display()),
+                    )
+                }),
+            ),
+            self.command("rustc", "rustc", &["--version"], Status::Error),
+            self.command("cargo", "cargo", &["--version"], Status::Error),
+            self.command("clippy", "cargo", &["clippy", "--version"], Status::Error),
+            self.command(
+                "nightly clippy",
+                "cargo",
+                &["+nightly", "clippy", "--version"],
+                Status::Warning,
+            ),
+            self.command("rustfmt", "rustfmt", &["--version"], Status::Warning),
+            self.command("git", "git", &["--version"], Status::Warning),
+        ];
+        checks.push(check(
+            "repository",
+            Status::Error,
+            repo_root(&self.dir).map(|r| r.display().to_string()),
+        ));
+        let git_problem =
+            Preflight::new(self.dir.clone(), self.target_branch.clone()).git_problem();
+        checks.push(check(
+            "target branch",
+            Status::Error,
+            git_problem.map_or_else(|| Ok(self.target_branch.clone()), Err),
+        ));
+        checks.push(check(
+            "workspace",
+            Status::Error,
+            CargoConfig::from_manifest_path(&self.manifest).and_then(|config| {
+                let (root, members) = (config.workspace_root()?, config.members());
+                Ok(format!(
+                    "{} ({} members: {})",
+                    root.display(),
+                    members.len(),
+                    members.join(", ")
+                ))
+            }),
+        ));
+        checks.push(file_check(
+            "scout config",
+            &self.config,
+            ScoutConfig::from_path,
+        ));
+        checks.push(file_check(
+            "allowed failures",
+            &self.allow_failures,
+            AllowFailures::from_path,
+        ));
+        checks.push(file_check("baseline", &self.baseline, Baseline::from_path));
+        Report { checks }
+    }
+
+    // Runs a tool, and reports what it prints on a single line
+    fn command(&self, name: &str, program: &str, args: &[&str], on_failure: Status) -> Check {
+        let output = Command::new(program)
+            .current_dir(&self.dir)
+            .args(args)
+            .output()
+            .map_err(|e| crate::error::Error::spawn(program, e))
+            .and_then(|output| {
+                if output.status.success() {
+                    Ok(String::from_utf8(output.stdout)?)
+                } else {
+                    Err(crate::error::Error::Command(String::from_utf8(
+                        output.stderr,
+                    )?))
+                }
+            })
+            .map(|stdout| stdout.trim().lines().collect::<Vec<_>>().join(", "));
+        check(name, on_failure, output)
+    }
+}
+
+fn check(name: &str, on_failure: Status, result: Result<String, impl fmt::Display>) -> Check {
+    let (status, detail) = match result {
+        Ok(detail) => (Status::Ok, detail),
+        Err(e) => (on_failure, e.to_string().trim().to_string()),
+    };
+    Check {
+        name: name.to_string(),
+        status,
+        detail,
+    }
+}
+
+// Configuration files are optional, but must be valid when they exist
+fn file_check<'a, T>(
+    name: &str,
+    path: &'a Path,
+    parse: impl FnOnce(&'a Path) -> Result<T, crate::error::Error>,
+) -> Check {
+    let result = if path.exists() {
+        parse(path).map(|_| format!("{} is valid", path.display()))
+    } else {
+        Ok(format!("{} not found, using the defaults", path.display()))
+    };
+    check(name, Status::Error, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scout.toml");
+        let report = file_check("scout config", &path, ScoutConfig::from_path);
+        assert_eq!(Status::Ok, report.status);
+        assert!(report.detail.ends_with("not found, using the defaults"));
+
+        std::fs::write(&path, "ignore-lints = 3").unwrap();
+        let report = file_check("scout config", &path, ScoutConfig::from_path);
+        assert_eq!(Status::Error, report.status);
+        assert!(report.detail.starts_with("Toml error"));
+    }
+
+    #[test]
+    fn test_report_display() {
+        let report = Report {
+            checks: vec![
+                Check {
+                    name: "git".to_string(),
+                    status: Status::Ok,
+                    detail: "git version 2.39.0".to_string(),
+                },
+                Check {
+                    name: "nightly clippy".to_string(),
+                    status: Status::Warning,
+                    detail: "toolchain 'nightly' is not installed".to_string(),
+                },
+            ],
+        };
+        assert_eq!(
+            "git             ok       git version 2.39.0
+nightly clippy  warning  toolchain 'nightly' is not installed
+",
+            report.to_string()
+        );
+        assert!(report.is_healthy());
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
+use toolchain::ToolchainFile;
+
+pub mod doctor;
+pub mod toolchain;
+
+/// A prerequisite of a run that isn't met.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// What is missing
+    pub description: String,
+    /// How to fix it
+    pub fix: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.description, self.fix)
+    }
+}
+
+/// Checks that a run has everything it needs before starting it,
+/// so every missing prerequisite is reported at once.
+#[derive(Debug, Clone)]
+pub struct Preflight {
+    repo_path: PathBuf,
+    git_root: Option<PathBuf>,
+    target_branch: String,
+    nightly: bool,
+    install_missing: bool,
+}
+
+impl Preflight {
+    #[must_use]
+    pub fn new(repo_path: PathBuf, target_branch: String) -> Self {
+        Self {
+            repo_path,
+            git_root: None,
+            target_branch,
+            nightly: false,
+            install_missing: false,
+        }
+    }
+
+    /// Check the repository at `git_root`, instead of the one the project belongs to
+    pub fn set_git_root(&mut self, git_root: Option<PathBuf>) -> &mut Self {
+        self.git_root = git_root;
+        self
+    }
+
+    /// Require clippy on the nightly toolchain, for `--preview`
+    pub fn set_nightly(&mut self, nightly: bool) -> &mut Self {
+        self.nightly = nightly;
+        self
+    }
+
+    /// Install the toolchain pinned by the project and its components if they're missing
+    pub fn set_install_missing(&mut self, install_missing: bool) -> &mut Self {
+        self.install_missing = install_missing;
+        self
+    }
+
+    /// Returns every prerequisite that isn't met.
+    #[must_use]
+    pub fn problems(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        problems.extend(self.git_problem());
+        problems.extend(self.toolchain_problem());
+        problems
+    }
+
+    /// Checks every prerequisite.
+    ///
+    /// # Errors
+    /// Returns `Error::Preflight`, listing every prerequisite that isn't met.
+    pub fn check(&self) -> Result<(), crate::error::Error> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::error::Error::Preflight(problems))
+        }
+    }
+
+    fn git_problem(&self) -> Option<Problem> {
+        let repo = match &self.git_root {
+            Some(root) => git2::Repository::open(root),
+            None => git2::Repository::discover(&self.repo_path),
+        };
+        let Ok(repo) = repo else {
+            return Some(Problem {
+                description: format!("{} isn't in a git repository", self.repo_path.display()),
+                fix: "run scout from a git checkout".to_string(),
+            });
+        };
+        if repo.revparse_single(&self.target_branch).is_err() {
+            return Some(Problem {
+                description: format!("the target branch {} doesn't exist", self.target_branch),
+                fix: "run with --fetch to fetch it, or pick another one with --branch".to_string(),
+            });
+        }
+        None
+    }
+
+    fn toolchain_problem(&self) -> Option<Problem> {
+        // --preview runs nightly whatever the project pins
+        if !self.nightly {
+            match ToolchainFile::find(&self.repo_path) {
+                Ok(Some(file)) => return self.pinned_toolchain_problem(&file),
+                Ok(None) => {}
+                Err(e) => {
+                    return Some(Problem {
+                        description: format!("the toolchain file can't be read: {e}"),
+                        fix: "fix rust-toolchain.toml".to_string(),
+                    })
+                }
+            }
+            return if self.cargo_succeeds(&["clippy", "--version"]) {
+                None
+            } else {
+                Some(Problem {
+                    description: "clippy isn't installed".to_string(),
+                    fix: "rustup component add clippy".to_string(),
+                })
+            };
+        }
+        if !self.cargo_succeeds(&["+nightly", "--version"]) {
+            Some(Problem {
+                description: "the nightly toolchain isn't installed".to_string(),
+                fix: "rustup toolchain install nightly --component clippy".to_string(),
+            })
+        } else if !self.cargo_succeeds(&["+nightly", "clippy", "--version"]) {
+            Some(Problem {
+                description: "clippy isn't installed on the nightly toolchain".to_string(),
+                fix: "rustup component add clippy --toolchain nightly".to_string(),
+            })
+        } else {
+            None
+        }
+    }
+
+    // rustup runs the toolchain pinned by the project, which must have clippy
+    fn pinned_toolchain_problem(&self, file: &ToolchainFile) -> Option<Problem> {
+        log::info!(
+            "[Preflight] - using toolchain {} from {}",
+            file.channel,
+            file.path.display()
+        );
+        let mut missing = file.missing_components();
+        if self.install_missing {
+            let installed = match &missing {
+                None => file.install(),
+                Some(components) if !components.is_empty() => file.install_components(components),
+                Some(_) => Ok(()),
+            };
+            if let Err(e) = installed {
+                log::warn!("{e}");
+            }
+            missing = file.missing_components();
+        }
+        match missing {
+            None => Some(Problem {
+                description: format!(
+                    "the toolchain {} pinned in {} isn't installed",
+                    file.channel,
+                    file.path.display()
+                ),
+                fix: "run with --install-missing".to_string(),
+            }),
+            Some(components) if !components.is_empty() => Some(Problem {
+                description: format!(
+                    "{} isn't installed for the toolchain {}",
+                    components.join(", "),
+                    file.channel
+                ),
+                fix: format!(
+                    "rustup component add {} --toolchain {}, or run with --install-missing",
+                    components.join(" "),
+                    file.channel
+                ),
+            }),
+            Some(_) => None,
+        }
+    }
+
+    fn cargo_succeeds(&self, args: &[&str]) -> bool {
+        Command::new("cargo")
+            .current_dir(&self.repo_path)
+            .args(args)
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let preflight = Preflight::new(dir.path().to_path_buf(), "master".to_string());
+        let problem = preflight.git_problem().unwrap();
+        assert!(problem.description.ends_with("isn't in a git repository"));
+
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let problem = preflight.git_problem().unwrap();
+        assert_eq!(
+            "the target branch master doesn't exist",
+            problem.description
+        );
+
+        let signature = git2::Signature::now("scout", "scout@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let commit = repo
+            .commit(None, &signature, &signature, "Initial commit", &tree, &[])
+            .unwrap();
+        repo.branch("master", &repo.find_commit(commit).unwrap(), true)
+            .unwrap();
+        assert_eq!(None, preflight.git_problem());
+    }
+
+    #[test]
+    fn test_problem_display() {
+        let problem = Problem {
+            description: "clippy isn't installed".to_string(),
+            fix: "rustup component add clippy".to_string(),
+        };
+        assert_eq!(
+            "clippy isn't installed (rustup component add clippy)",
+            problem.to_string()
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The toolchain a project pins in `rust-toolchain.toml` or `rust-toolchain`.
+///
+/// rustup picks it whenever cargo runs in the project,
+/// scout reads it to check the components it needs are installed.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ToolchainFile {
+    /// The file the toolchain is pinned in
+    pub path: PathBuf,
+    /// The toolchain name, such as `1.80.0` or `nightly-2024-06-01`
+    pub channel: String,
+    /// The components the project requires, besides clippy
+    pub components: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Contents {
+    toolchain: Toolchain,
+}
+
+#[derive(Deserialize)]
+struct Toolchain {
+    channel: Option<String>,
+    #[serde(default)]
+    components: Vec<String>,
+}
+
+impl ToolchainFile {
+    /// Looks for a toolchain file in `dir` and its parents, as rustup does.
+    ///
+    /// # Errors
+    /// Fails if a toolchain file can't be read or parsed.
+    pub fn find(dir: impl AsRef<Path>) -> Result<Option<Self>, crate::error::Error> {
+        for dir in dir.as_ref().ancestors() {
+            // rustup prefers the legacy name when both exist
+            for name in &["rust-toolchain", "rust-toolchain.toml"] {
+                let path = dir.join(name);
+                if path.is_file() {
+                    let contents = std::fs::read_to_string(&path)?;
+                    return Self::parse(path, &contents);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // A legacy `rust-toolchain` file may only hold the toolchain name.
+    // A file without a channel, such as one that only lists components, pins nothing
+    fn parse(path: PathBuf, contents: &str) -> Result<Option<Self>, crate::error::Error> {
+        let trimmed = contents.trim();
+        if !trimmed.is_empty() && !trimmed.contains('\n') && !trimmed.contains('[') {
+            return Ok(Some(Self {
+                path,
+                channel: trimmed.to_string(),
+                components: Vec::new(),
+            }));
+        }
+        let Toolchain {
+            channel,
+            components,
+        } = toml::from_str::<Contents>(contents)?.toolchain;
+        Ok(channel.map(|channel| Self {
+            path,
+            channel,
+            components,
+        }))
+    }
+
+    /// Returns the components scout needs that aren't installed for the toolchain,
+    /// or `None` if the toolchain itself isn't installed.
+    #[must_use]
+    pub fn missing_components(&self) -> Option<Vec<String>> {
+        let output = Command::new("rustup")
+            .args([
+                "component",
+                "list",
+                "--installed",
+                "--toolchain",
+                &self.channel,
+            ])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let installed = String::from_utf8_lossy(&output.stdout);
+        Some(
+            self.required_components()
+                .filter(|component| {
+                    !installed
+                        .lines()
+                        .any(|line| is_component(line.trim(), component))
+                })
+                .collect(),
+        )
+    }
+
+    /// Installs the toolchain, along with the components scout needs.
+    ///
+    /// # Errors
+    /// Fails if rustup can't be run or doesn't install it.
+    pub fn install(&self) -> Result<(), crate::error::Error> {
+        let mut args = vec!["toolchain", "install", self.channel.as_str()];
+        let components: Vec<String> = self.required_components().collect();
+        for component in &components {
+            args.extend(&["--component", component.as_str()]);
+        }
+        rustup(&args)
+    }
+
+    /// Installs `components` for the toolchain.
+    ///
+    /// # Errors
+    /// Fails if rustup can't be run or doesn't install them.
+    pub fn install_components(&self, components: &[String]) -> Result<(), crate::error::Error> {
+        let mut args = vec!["component", "add"];
+        args.extend(components.iter().map(String::as_str));
+        args.extend(&["--toolchain", self.channel.as_str()]);
+        rustup(&args)
+    }
+
+    fn required_components(&self) -> impl Iterator<Item = String> + '_ {
+        std::iter::once("clippy".to_string()).chain(
+            self.components
+                .iter()
+                .filter(|c| c.as_str() != "clippy")
+                .cloned(),
+        )
+    }
+}
+
+// rustup lists installed components with their target, e.g. `clippy-x86_64-unknown-linux-gnu`
+fn is_component(installed: &str, component: &str) -> bool {
+    installed == component
+        || installed
+            .strip_prefix(component)
+            .is_some_and(|target| target.starts_with('-') && target.matches('-').count() >= 2)
+}
+
+fn rustup(args: &[&str]) -> Result<(), crate::error::Error> {
+    log::info!("[Preflight] - running rustup {}", args.join(" "));
+    let status = Command::new("rustup")
+        .args(args)
+        .status()
+        .map_err(|e| crate::error::Error::spawn("rustup", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(crate::error::Error::Command(format!(
+            "rustup {} failed",
+            args.join(" ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let path = PathBuf::from("rust-toolchain");
+        assert_eq!(
+            Some(ToolchainFile {
+                path: path.clone(),
+                channel: "1.80.0".to_string(),
+                components: Vec::new(),
+            }),
+            ToolchainFile::parse(path.clone(), "1.80.0\n").unwrap()
+        );
+        let toml = r#"
+            [toolchain]
+            channel = "nightly-2024-06-01"
+            components = ["rustfmt", "rust-src"]
+        "#;
+        assert_eq!(
+            Some(ToolchainFile {
+                path: path.clone(),
+                channel: "nightly-2024-06-01".to_string(),
+                components: vec!["rustfmt".to_string(), "rust-src".to_string()],
+            }),
+            ToolchainFile::parse(path.clone(), toml).unwrap()
+        );
+        let without_channel = "[toolchain]\ncomponents = [\"rustfmt\"]\n";
+        assert_eq!(None, ToolchainFile::parse(path, without_channel).unwrap());
+    }
+
+    #[test]
+    fn test_find() {
+        let dir = tempfile::tempdir().unwrap();
+        let member = dir.path().join("member");
+        std::fs::create_dir(&member).unwrap();
+        assert_eq!(None, ToolchainFile::find(&member).unwrap());
+        std::fs::write(
+            dir.path().join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"stable\"\n",
+        )
+        .unwrap();
+        let found = ToolchainFile::find(&member).unwrap().unwrap();
+        assert_eq!("stable", found.channel);
+        assert_eq!(dir.path().join("rust-toolchain.toml"), found.path);
+    }
+
+    #[test]
+    fn test_is_component() {
+        assert!(is_component("clippy-x86_64-unknown-linux-gnu", "clippy"));
+        assert!(is_component("rust-src", "rust-src"));
+        assert!(!is_component("rust-src", "rust"));
+        assert!(!is_component("rustfmt-x86_64-unknown-linux-gnu", "rust"));
+    }
+}
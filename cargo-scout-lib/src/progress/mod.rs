@@ -0,0 +1,76 @@
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// A step of a `Scout` run
+#[derive(PartialEq, Debug, Clone)]
+pub enum Stage {
+    /// Computing the changed sections against the target branch
+    Diffing,
+    /// Running the linter on a workspace member, or on several at once
+    Linting {
+        /// The member, or the members linted at once separated by commas
+        member: String,
+        /// The position of the member, starting at 1
+        index: usize,
+        total: usize,
+    },
+    /// Keeping the lints that intersect with the diff
+    Intersecting,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stage::Diffing => write!(f, "diffing"),
+            Stage::Linting {
+                member,
+                index,
+                total,
+            } => write!(f, "linting {member} ({index}/{total})"),
+            Stage::Intersecting => write!(f, "checking for intersections"),
+        }
+    }
+}
+
+/// A `Progress` is notified when a stage of a run starts and finishes,
+/// so the library doesn't need to know how progress is displayed.
+pub trait Progress {
+    fn started(&self, _stage: &Stage) {}
+    fn finished(&self, _stage: &Stage, _elapsed: Duration) {}
+}
+
+/// Doesn't report anything
+#[derive(Default, Debug, Clone, Copy)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {}
+
+impl<P: Progress + ?Sized> Progress for Rc<P> {
+    fn started(&self, stage: &Stage) {
+        (**self).started(stage);
+    }
+
+    fn finished(&self, stage: &Stage, elapsed: Duration) {
+        (**self).finished(stage, elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_display() {
+        assert_eq!("diffing", Stage::Diffing.to_string());
+        assert_eq!(
+            "linting cargo-scout-lib (2/3)",
+            Stage::Linting {
+                member: "cargo-scout-lib".to_string(),
+                index: 2,
+                total: 3,
+            }
+            .to_string()
+        );
+    }
+}
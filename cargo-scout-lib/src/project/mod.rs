@@ -0,0 +1,239 @@
+use cargo_metadata::MetadataCommand;
+use std::path::{Path, PathBuf};
+
+/// The packages of a Cargo workspace, as `cargo metadata` describes them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    workspace_root: PathBuf,
+    // The manifest the project was loaded from
+    manifest_dir: PathBuf,
+    packages: Vec<Package>,
+    // The crates the workspace manifest replaces with a local copy, and where the copy is
+    patches: Vec<(String, PathBuf)>,
+}
+
+/// A workspace member
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    pub name: String,
+    /// The package id, as cargo reports it in its messages
+    pub id: String,
+    /// The directory of the package's manifest
+    pub dir: PathBuf,
+    pub targets: Vec<Target>,
+    /// The names of the dependencies, including the dev and build ones
+    pub dependencies: Vec<String>,
+    /// The directories of the path dependencies, including the dev and build ones
+    pub path_dependencies: Vec<PathBuf>,
+}
+
+/// A library, binary, test, example, bench or build script of a package
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub name: String,
+    /// Example:
+    /// `vec!["lib".to_string()]`
+    pub kind: Vec<String>,
+    /// The root source file of the target
+    pub src_path: PathBuf,
+}
+
+impl Project {
+    /// Runs `cargo metadata` on the manifest, without resolving the dependencies.
+    ///
+    /// The paths are canonical, so they can be compared to each other.
+    ///
+    /// # Errors
+    /// Fails if `cargo metadata` fails, e.g. on an invalid manifest.
+    pub fn from_manifest_path(
+        manifest_path: impl AsRef<Path>,
+    ) -> Result<Self, crate::error::Error> {
+        let manifest_path = std::fs::canonicalize(manifest_path)?;
+        let metadata = MetadataCommand::new()
+            .manifest_path(&manifest_path)
+            .no_deps()
+            .exec()?;
+        let canonical = |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.into());
+        let packages = metadata
+            .workspace_packages()
+            .into_iter()
+            .map(|package| Package {
+                name: package.name.clone(),
+                id: package.id.repr.clone(),
+                dir: canonical(
+                    package
+                        .manifest_path
+                        .parent()
+                        .map_or_else(|| Path::new(""), |dir| dir.as_std_path()),
+                ),
+                targets: package
+                    .targets
+                    .iter()
+                    .map(|target| Target {
+                        name: target.name.clone(),
+                        kind: target.kind.clone(),
+                        src_path: canonical(target.src_path.as_std_path()),
+                    })
+                    .collect(),
+                dependencies: package
+                    .dependencies
+                    .iter()
+                    .map(|d| d.name.clone())
+                    .collect(),
+                path_dependencies: package
+                    .dependencies
+                    .iter()
+                    .filter_map(|d| d.path.as_ref())
+                    .map(|path| canonical(path.as_std_path()))
+                    .collect(),
+            })
+            .collect();
+        let workspace_root = canonical(metadata.workspace_root.as_std_path());
+        Ok(Self {
+            patches: patches(&workspace_root)
+                .into_iter()
+                .map(|(name, dir)| (name, canonical(&dir)))
+                .collect(),
+            workspace_root,
+            manifest_dir: manifest_path
+                .parent()
+                .map_or_else(PathBuf::new, Path::to_path_buf),
+            packages,
+        })
+    }
+
+    /// The directory of the workspace manifest, or of the package if it isn't in a workspace
+    #[must_use]
+    pub fn workspace_root(&self) -> &Path {
+        &self.workspace_root
+    }
+
+    /// Whether the project was loaded from the workspace manifest,
+    /// rather than from the manifest of one of its members
+    #[must_use]
+    pub fn is_workspace_root(&self) -> bool {
+        self.manifest_dir == self.workspace_root
+    }
+
+    /// The members of the workspace, in the order cargo lists them
+    #[must_use]
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+
+    /// The member whose manifest the project was loaded from, if it isn't a virtual manifest
+    #[must_use]
+    pub fn current_package(&self) -> Option<&Package> {
+        self.packages.iter().find(|p| p.dir == self.manifest_dir)
+    }
+
+    /// The member the file belongs to, the innermost one when packages are nested.
+    #[must_use]
+    pub fn package_of(&self, file: &Path) -> Option<&Package> {
+        self.packages
+            .iter()
+            .filter(|p| file.starts_with(&p.dir))
+            .max_by_key(|p| p.dir.components().count())
+    }
+
+    /// The members that depend on `package` directly
+    #[must_use]
+    pub fn dependents(&self, package: &Package) -> Vec<&Package> {
+        self.packages
+            .iter()
+            .filter(|p| p.path_dependencies.contains(&package.dir))
+            .collect()
+    }
+
+    /// The members that use the file through a local crate that isn't a member,
+    /// a path dependency or a crate patched with `[patch]`.
+    ///
+    /// Empty when the file belongs to a member.
+    #[must_use]
+    pub fn users_of(&self, file: &Path) -> Vec<&Package> {
+        let member_dir = self.package_of(file).map(|p| p.dir.as_path());
+        // The crates containing the file, nested in its member if it has one
+        let in_crate = |dir: &Path| {
+            file.starts_with(dir) && member_dir.is_none_or(|m| dir != m && dir.starts_with(m))
+        };
+        let patched: Vec<&str> = self
+            .patches
+            .iter()
+            .filter(|(_, dir)| in_crate(dir))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        self.packages
+            .iter()
+            .filter(|p| {
+                p.path_dependencies.iter().any(|dir| in_crate(dir))
+                    || p.dependencies.iter().any(|d| patched.contains(&d.as_str()))
+            })
+            .collect()
+    }
+}
+
+// The `path` entries of the `[patch]` tables of the workspace manifest.
+// `cargo metadata --no-deps` doesn't resolve them
+fn patches(workspace_root: &Path) -> Vec<(String, PathBuf)> {
+    let manifest: toml::Value = match std::fs::read_to_string(workspace_root.join("Cargo.toml"))
+        .ok()
+        .and_then(|m| m.parse().ok())
+    {
+        Some(manifest) => manifest,
+        None => return Vec::new(),
+    };
+    manifest
+        .get("patch")
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flat_map(|registries| registries.values())
+        .filter_map(toml::Value::as_table)
+        .flatten()
+        .filter_map(|(name, patch)| {
+            let path = patch.get("path")?.as_str()?;
+            Some((name.clone(), workspace_root.join(path)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace() -> Result<(), crate::error::Error> {
+        let project = Project::from_manifest_path("../Cargo.toml")?;
+        assert!(project.is_workspace_root());
+        assert_eq!(None, project.current_package());
+        let mut names: Vec<&str> = project.packages().iter().map(|p| p.name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(vec!["cargo-scout", "cargo-scout-lib"], names);
+
+        let lib = project
+            .package_of(&std::fs::canonicalize("src/lib.rs")?)
+            .unwrap();
+        assert_eq!("cargo-scout-lib", lib.name);
+        assert!(lib
+            .targets
+            .iter()
+            .any(|t| t.kind == ["lib"] && t.src_path.ends_with("cargo-scout-lib/src/lib.rs")));
+        let dependents: Vec<&str> = project
+            .dependents(lib)
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(vec!["cargo-scout"], dependents);
+        Ok(())
+    }
+
+    #[test]
+    fn test_member() -> Result<(), crate::error::Error> {
+        let project = Project::from_manifest_path("Cargo.toml")?;
+        assert!(!project.is_workspace_root());
+        assert_eq!(
+            Some("cargo-scout-lib"),
+            project.current_package().map(|p| p.name.as_str())
+        );
+        Ok(())
+    }
+}
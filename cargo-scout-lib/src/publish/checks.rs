@@ -0,0 +1,205 @@
+use crate::linter::{Level, Lint};
+use crate::output::headline;
+use crate::paths::PathResolver;
+use crate::publish::comment::PullRequest;
+use crate::publish::{AnnotationSink, PostError};
+use serde::Deserialize;
+use serde_json::json;
+
+// The name the check run shows up with on the pull request
+const CHECK_NAME: &str = "cargo-scout";
+
+#[derive(Deserialize)]
+struct CreatedRun {
+    id: u64,
+}
+
+/// The findings as the annotations of a GitHub check run, on the head commit of the pull request.
+///
+/// The check run is created with the first batch, the next batches are added to it.
+/// Its conclusion is neutral, the exit code of the job tells whether the run fails.
+/// The token needs the `checks: write` permission.
+pub struct CheckRun {
+    pull_request: PullRequest,
+    head_sha: String,
+    resolver: PathResolver,
+    summary: String,
+    id: Option<u64>,
+}
+
+impl CheckRun {
+    #[must_use]
+    pub fn new(
+        pull_request: PullRequest,
+        head_sha: String,
+        resolver: PathResolver,
+        summary: String,
+    ) -> Self {
+        Self {
+            pull_request,
+            head_sha,
+            resolver,
+            summary,
+            id: None,
+        }
+    }
+
+    fn runs_url(&self) -> String {
+        format!(
+            "{}/repos/{}/check-runs",
+            self.pull_request.api_url(),
+            self.pull_request.project()
+        )
+    }
+
+    // Creates the check run with the output, or adds the output to the one created before
+    fn send(&mut self, output: &serde_json::Value) -> Result<(), PostError> {
+        if let Some(id) = self.id {
+            let url = format!("{}/{}", self.runs_url(), id);
+            return self
+                .pull_request
+                .request("PATCH", &url, Some(&json!({ "output": output })))
+                .map(|_| ());
+        }
+        let run = json!({
+            "name": CHECK_NAME,
+            "head_sha": self.head_sha,
+            "status": "completed",
+            "conclusion": "neutral",
+            "output": output,
+        });
+        let response = self
+            .pull_request
+            .request("POST", &self.runs_url(), Some(&run))?;
+        let created: CreatedRun = serde_json::from_str(&response)
+            .map_err(|e| PostError::Failed(format!("unexpected check run: {e}")))?;
+        self.id = Some(created.id);
+        Ok(())
+    }
+}
+
+impl AnnotationSink for CheckRun {
+    fn post(&mut self, batch: &[Lint]) -> Result<(), PostError> {
+        let annotations: Vec<serde_json::Value> = batch
+            .iter()
+            .filter_map(|lint| annotation(&self.resolver, lint))
+            .collect();
+        let output = json!({
+            "title": CHECK_NAME,
+            "summary": self.summary,
+            "annotations": annotations,
+        });
+        self.send(&output)
+    }
+
+    fn post_summary(&mut self, summary: &str) -> Result<(), PostError> {
+        let output = json!({
+            "title": CHECK_NAME,
+            "summary": format!("{}\n\n{}", self.summary, summary),
+        });
+        self.send(&output)
+    }
+}
+
+// The annotation of a finding, None when it isn't located in the repository
+fn annotation(resolver: &PathResolver, lint: &Lint) -> Option<serde_json::Value> {
+    let span = lint.primary_span()?;
+    let located = resolver.resolve_lint(lint)?;
+    let level = match lint.level() {
+        Some(Level::Error) => "failure",
+        Some(Level::Warning) | None => "warning",
+        _ => "notice",
+    };
+    let mut annotation = json!({
+        "path": located.repo_path,
+        "start_line": span.line_start,
+        "end_line": span.line_end,
+        "annotation_level": level,
+        "message": headline(lint),
+    });
+    // GitHub only takes columns on a single line
+    if span.column_start > 0 && span.line_start == span.line_end {
+        annotation["start_column"] = span.column_start.into();
+        annotation["end_column"] = span.column_end.into();
+    }
+    if let Some(code) = lint.code() {
+        annotation["title"] = code.into();
+    }
+    Some(annotation)
+}
+
+/// The commit a GitHub Actions job runs for, the head of the pull request.
+///
+/// It's read from the event the workflow runs on, `GITHUB_SHA` is the merge commit
+/// GitHub creates for the pull request, and only used when there's no event.
+#[must_use]
+pub fn head_sha(var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    var("GITHUB_EVENT_PATH")
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|event| serde_json::from_str::<serde_json::Value>(&event).ok())
+        .and_then(|event| {
+            event["pull_request"]["head"]["sha"]
+                .as_str()
+                .map(String::from)
+        })
+        .or_else(|| var("GITHUB_SHA"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::{self, span};
+    use crate::linter::Span;
+
+    #[test]
+    fn test_annotation() {
+        let lint = fixtures::lint(
+            "clippy::redundant_clone",
+            Level::Warning,
+            "redundant clone",
+            vec![Span {
+                column_start: 5,
+                column_end: 9,
+                ..span("src/lib.rs", 3, 3)
+            }],
+        );
+        assert_eq!(
+            Some(json!({
+                "path": "src/lib.rs",
+                "start_line": 3,
+                "end_line": 3,
+                "start_column": 5,
+                "end_column": 9,
+                "annotation_level": "warning",
+                "message": headline(&lint),
+                "title": "clippy::redundant_clone",
+            })),
+            annotation(&PathResolver::default(), &lint)
+        );
+        let error = fixtures::lint(
+            "E0425",
+            Level::Error,
+            "cannot find value",
+            vec![span("src/lib.rs", 3, 4)],
+        );
+        let annotation = annotation(&PathResolver::default(), &error).unwrap();
+        assert_eq!("failure", annotation["annotation_level"]);
+        assert!(annotation.get("start_column").is_none());
+    }
+
+    #[test]
+    fn test_head_sha() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let event = dir.path().join("event.json");
+        std::fs::write(&event, r#"{"pull_request": {"head": {"sha": "abc123"}}}"#)?;
+        let vars = |name: &str| match name {
+            "GITHUB_EVENT_PATH" => Some(event.to_string_lossy().to_string()),
+            "GITHUB_SHA" => Some("merge".to_string()),
+            _ => None,
+        };
+        assert_eq!(Some("abc123".to_string()), head_sha(vars));
+        let without_event = |name: &str| (name == "GITHUB_SHA").then(|| "merge".to_string());
+        assert_eq!(Some("merge".to_string()), head_sha(without_event));
+        Ok(())
+    }
+}
@@ -0,0 +1,565 @@
+use crate::error::Error;
+use crate::output::markdown::REPORT_MARKER;
+use crate::publish::{PostError, Publisher};
+use serde::Deserialize;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+// The largest page the GitHub and GitLab APIs return
+const PAGE_SIZE: usize = 100;
+
+/// The code hosts scout can comment on
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Provider {
+    Github,
+    Gitlab,
+}
+
+impl FromStr for Provider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "github" => Ok(Provider::Github),
+            "gitlab" => Ok(Provider::Gitlab),
+            _ => Err(format!(
+                "unknown provider {s}, expected one of github, gitlab"
+            )),
+        }
+    }
+}
+
+/// A comment on a pull request
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+pub struct Comment {
+    pub id: u64,
+    #[serde(default)]
+    pub body: String,
+}
+
+/// The comments of a pull request, or of a merge request on GitLab.
+pub trait CommentThread {
+    /// Lists every comment, oldest first.
+    ///
+    /// # Errors
+    /// Fails if the request can't be sent or is rejected.
+    fn comments(&mut self) -> Result<Vec<Comment>, PostError>;
+    /// Posts a new comment.
+    ///
+    /// # Errors
+    /// Fails if the request can't be sent or is rejected.
+    fn create(&mut self, body: &str) -> Result<(), PostError>;
+    /// Replaces the body of the comment `id`.
+    ///
+    /// # Errors
+    /// Fails if the request can't be sent or is rejected.
+    fn update(&mut self, id: u64, body: &str) -> Result<(), PostError>;
+}
+
+/// What posting the report did
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CommentAction {
+    Created,
+    Updated(u64),
+    /// The previous comment already had the same report
+    Unchanged(u64),
+}
+
+/// Posts the report, or updates the comment a previous run posted,
+/// found by the hidden marker the report starts with.
+///
+/// The requests are retried by the publisher while the host rate limits them.
+///
+/// # Errors
+/// Fails if the comments can't be listed, posted or updated.
+pub fn upsert(
+    publisher: &Publisher,
+    thread: &mut impl CommentThread,
+    report: &str,
+) -> Result<CommentAction, Error> {
+    let body = if report.contains(REPORT_MARKER) {
+        report.to_string()
+    } else {
+        format!("{REPORT_MARKER}\n{report}")
+    };
+    let previous = publisher
+        .send(|| thread.comments())?
+        .into_iter()
+        .find(|c| c.body.contains(REPORT_MARKER));
+    match previous {
+        Some(comment) if comment.body.trim_end() == body.trim_end() => {
+            Ok(CommentAction::Unchanged(comment.id))
+        }
+        Some(comment) => {
+            publisher.send(|| thread.update(comment.id, &body))?;
+            Ok(CommentAction::Updated(comment.id))
+        }
+        None => {
+            publisher.send(|| thread.create(&body))?;
+            Ok(CommentAction::Created)
+        }
+    }
+}
+
+/// The pull request a CI job runs for, as told by the CI variables.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PullRequest {
+    provider: Provider,
+    api_url: String,
+    project: String,
+    number: u64,
+    token: String,
+}
+
+impl PullRequest {
+    /// Reads the pull request from the CI variables, with `var` looking them up.
+    ///
+    /// Returns `None` when the job doesn't run for a pull request,
+    /// e.g. on a push to a branch.
+    ///
+    /// GitHub Actions jobs need `GITHUB_TOKEN`, GitLab jobs need `GITLAB_TOKEN`,
+    /// a token allowed to comment, since job tokens are not.
+    ///
+    /// # Errors
+    /// Fails if the CI variables of a pull request are set but incomplete,
+    /// or if the token is missing.
+    pub fn from_env(
+        provider: Provider,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Option<Self>, Error> {
+        let required = |name: &str| {
+            var(name)
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| Error::Publish(format!("{name} is not set")))
+        };
+        let (number, api_url, project, token) = match provider {
+            Provider::Github => {
+                // refs/pull/<number>/merge on pull_request events
+                let number = var("GITHUB_REF").and_then(|r| {
+                    r.strip_prefix("refs/pull/")
+                        .and_then(|r| r.split('/').next())
+                        .and_then(|n| n.parse().ok())
+                });
+                let Some(number) = number else {
+                    return Ok(None);
+                };
+                let api_url =
+                    var("GITHUB_API_URL").unwrap_or_else(|| "https://api.github.com".to_string());
+                (
+                    number,
+                    api_url,
+                    required("GITHUB_REPOSITORY")?,
+                    required("GITHUB_TOKEN")?,
+                )
+            }
+            Provider::Gitlab => {
+                let Some(number) = var("CI_MERGE_REQUEST_IID").and_then(|n| n.parse().ok()) else {
+                    return Ok(None);
+                };
+                let api_url =
+                    var("CI_API_V4_URL").unwrap_or_else(|| "https://gitlab.com/api/v4".to_string());
+                (
+                    number,
+                    api_url,
+                    required("CI_PROJECT_ID")?,
+                    required("GITLAB_TOKEN")?,
+                )
+            }
+        };
+        Ok(Some(Self {
+            provider,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            project,
+            number,
+            token,
+        }))
+    }
+
+    #[must_use]
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+
+    fn comments_url(&self) -> String {
+        match self.provider {
+            Provider::Github => format!(
+                "{}/repos/{}/issues/{}/comments",
+                self.api_url, self.project, self.number
+            ),
+            Provider::Gitlab => format!(
+                "{}/projects/{}/merge_requests/{}/notes",
+                self.api_url, self.project, self.number
+            ),
+        }
+    }
+
+    fn comment_url(&self, id: u64) -> String {
+        match self.provider {
+            Provider::Github => format!(
+                "{}/repos/{}/issues/comments/{}",
+                self.api_url, self.project, id
+            ),
+            Provider::Gitlab => format!("{}/{}", self.comments_url(), id),
+        }
+    }
+
+    pub(crate) fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    pub(crate) fn project(&self) -> &str {
+        &self.project
+    }
+
+    // Sends a request to the API of the code host, and returns the body of the response.
+    // Requests are sent with curl, which CI images ship with. The token is passed
+    // on curl's standard input, so it doesn't show up in the process list
+    pub(crate) fn request(
+        &self,
+        method: &str,
+        url: &str,
+        json: Option<&serde_json::Value>,
+    ) -> Result<String, PostError> {
+        let mut config = vec![
+            format!("url = \"{}\"", escape_config(url)),
+            format!("request = \"{}\"", method),
+            "silent".to_string(),
+            "show-error".to_string(),
+            // The headers tell a rate limit from a permission error
+            "include".to_string(),
+            "write-out = \"\\n%{http_code}\"".to_string(),
+        ];
+        for header in self.headers() {
+            config.push(format!("header = \"{}\"", escape_config(&header)));
+        }
+        if let Some(json) = json {
+            let json = json.to_string();
+            config.push("header = \"Content-Type: application/json\"".to_string());
+            config.push(format!("data-binary = \"{}\"", escape_config(&json)));
+        }
+
+        let mut child = Command::new("curl")
+            .args(["--config", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| PostError::Failed(format!("could not run curl: {e}")))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(config.join("\n").as_bytes())
+                .map_err(|e| PostError::Failed(e.to_string()))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| PostError::Failed(e.to_string()))?;
+        if !output.status.success() {
+            return Err(PostError::Failed(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        response(&String::from_utf8_lossy(&output.stdout), url)
+    }
+
+    fn headers(&self) -> Vec<String> {
+        match self.provider {
+            Provider::Github => vec![
+                format!("Authorization: Bearer {}", self.token),
+                "Accept: application/vnd.github+json".to_string(),
+                "User-Agent: cargo-scout".to_string(),
+            ],
+            Provider::Gitlab => vec![format!("PRIVATE-TOKEN: {}", self.token)],
+        }
+    }
+}
+
+/// The comments of a pull request, through the code host API with curl.
+pub struct CurlThread {
+    pull_request: PullRequest,
+}
+
+impl CurlThread {
+    #[must_use]
+    pub fn new(pull_request: PullRequest) -> Self {
+        Self { pull_request }
+    }
+
+    fn request(&self, method: &str, url: &str, body: Option<&str>) -> Result<String, PostError> {
+        let json = body.map(|body| serde_json::json!({ "body": body }));
+        self.pull_request.request(method, url, json.as_ref())
+    }
+}
+
+// The body of a successful response, from what curl writes: the headers,
+// the body, and the status code on the last line
+fn response(stdout: &str, url: &str) -> Result<String, PostError> {
+    let (response, status) = stdout.rsplit_once('\n').unwrap_or(("", stdout));
+    let Ok(status) = status.trim().parse::<u16>() else {
+        return Err(PostError::Failed(format!("unexpected response from {url}")));
+    };
+    // Informational responses, like 100 Continue, come with their own headers
+    let mut body = response;
+    let mut headers = Vec::new();
+    while body.starts_with("HTTP/") {
+        let (head, rest) = body
+            .split_once("\r\n\r\n")
+            .or_else(|| body.split_once("\n\n"))
+            .unwrap_or((body, ""));
+        headers = head
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+        body = rest;
+    }
+    if status < 400 {
+        Ok(body.to_string())
+    } else {
+        Err(PostError::from_response(status, &headers, body))
+    }
+}
+
+impl CommentThread for CurlThread {
+    fn comments(&mut self) -> Result<Vec<Comment>, PostError> {
+        let mut comments = Vec::new();
+        for page in 1.. {
+            let url = format!(
+                "{}?per_page={}&page={}&sort=asc",
+                self.pull_request.comments_url(),
+                PAGE_SIZE,
+                page
+            );
+            let response = self.request("GET", &url, None)?;
+            let batch: Vec<Comment> = serde_json::from_str(&response)
+                .map_err(|e| PostError::Failed(format!("unexpected comments: {e}")))?;
+            let last = batch.len() < PAGE_SIZE;
+            comments.extend(batch);
+            if last {
+                break;
+            }
+        }
+        Ok(comments)
+    }
+
+    fn create(&mut self, body: &str) -> Result<(), PostError> {
+        self.request("POST", &self.pull_request.comments_url(), Some(body))
+            .map(|_| ())
+    }
+
+    fn update(&mut self, id: u64, body: &str) -> Result<(), PostError> {
+        let method = match self.pull_request.provider {
+            Provider::Github => "PATCH",
+            Provider::Gitlab => "PUT",
+        };
+        self.request(method, &self.pull_request.comment_url(id), Some(body))
+            .map(|_| ())
+    }
+}
+
+// Quotes a value for a curl config file
+fn escape_config(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct TestThread {
+        // The amount of requests rate limited before the comments are listed
+        rate_limited: usize,
+        comments: Vec<Comment>,
+        created: Vec<String>,
+        updated: Vec<(u64, String)>,
+    }
+
+    impl CommentThread for TestThread {
+        fn comments(&mut self) -> Result<Vec<Comment>, PostError> {
+            if self.rate_limited > 0 {
+                self.rate_limited -= 1;
+                return Err(PostError::RateLimited);
+            }
+            Ok(self.comments.clone())
+        }
+
+        fn create(&mut self, body: &str) -> Result<(), PostError> {
+            self.created.push(body.to_string());
+            Ok(())
+        }
+
+        fn update(&mut self, id: u64, body: &str) -> Result<(), PostError> {
+            self.updated.push((id, body.to_string()));
+            Ok(())
+        }
+    }
+
+    fn comment(id: u64, body: &str) -> Comment {
+        Comment {
+            id,
+            body: body.to_string(),
+        }
+    }
+
+    fn publisher() -> Publisher {
+        Publisher {
+            sleep: |_| {},
+            ..Publisher::default()
+        }
+    }
+
+    #[test]
+    fn test_upsert() -> Result<(), Error> {
+        let report = format!("{}\n### cargo-scout: no findings\n", REPORT_MARKER);
+
+        let mut thread = TestThread::default();
+        thread.comments.push(comment(1, "LGTM"));
+        assert_eq!(
+            CommentAction::Created,
+            upsert(&publisher(), &mut thread, &report)?
+        );
+        assert_eq!(vec![report.clone()], thread.created);
+
+        let mut thread = TestThread::default();
+        thread.comments.push(comment(1, "LGTM"));
+        thread.comments.push(comment(2, REPORT_MARKER));
+        assert_eq!(
+            CommentAction::Updated(2),
+            upsert(&publisher(), &mut thread, &report)?
+        );
+        assert_eq!(vec![(2, report.clone())], thread.updated);
+        assert!(thread.created.is_empty());
+
+        let mut thread = TestThread::default();
+        thread.comments.push(comment(3, report.trim_end()));
+        assert_eq!(
+            CommentAction::Unchanged(3),
+            upsert(&publisher(), &mut thread, &report)?
+        );
+        assert!(thread.updated.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_upsert_when_rate_limited() -> Result<(), Error> {
+        let mut thread = TestThread {
+            rate_limited: 2,
+            ..TestThread::default()
+        };
+        assert_eq!(
+            CommentAction::Created,
+            upsert(&publisher(), &mut thread, "no findings")?
+        );
+        thread.rate_limited = 10;
+        assert!(matches!(
+            upsert(&publisher(), &mut thread, "no findings"),
+            Err(Error::Publish(ref reason)) if reason == "rate limited"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_upsert_adds_the_marker() -> Result<(), Error> {
+        let mut thread = TestThread::default();
+        upsert(&publisher(), &mut thread, "no findings")?;
+        assert_eq!(
+            vec![format!("{}\nno findings", REPORT_MARKER)],
+            thread.created
+        );
+        Ok(())
+    }
+
+    fn pull_request(
+        provider: Provider,
+        vars: &[(&str, &str)],
+    ) -> Result<Option<PullRequest>, Error> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        PullRequest::from_env(provider, |name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_github_pull_request() -> Result<(), Error> {
+        let vars = [
+            ("GITHUB_REF", "refs/pull/42/merge"),
+            ("GITHUB_REPOSITORY", "ghclara/cargo-scout"),
+            ("GITHUB_TOKEN", "secret"),
+        ];
+        let request = pull_request(Provider::Github, &vars)?.unwrap();
+        assert_eq!(42, request.number());
+        assert_eq!(
+            "https://api.github.com/repos/ghclara/cargo-scout/issues/42/comments",
+            request.comments_url()
+        );
+        assert_eq!(
+            "https://api.github.com/repos/ghclara/cargo-scout/issues/comments/7",
+            request.comment_url(7)
+        );
+
+        let push = [
+            ("GITHUB_REF", "refs/heads/main"),
+            ("GITHUB_TOKEN", "secret"),
+        ];
+        assert_eq!(None, pull_request(Provider::Github, &push)?);
+        assert!(pull_request(Provider::Github, &vars[..2]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gitlab_merge_request() -> Result<(), Error> {
+        let vars = [
+            ("CI_MERGE_REQUEST_IID", "12"),
+            ("CI_PROJECT_ID", "345"),
+            ("CI_API_V4_URL", "https://gitlab.example.com/api/v4/"),
+            ("GITLAB_TOKEN", "secret"),
+        ];
+        let merge_request = pull_request(Provider::Gitlab, &vars)?.unwrap();
+        assert_eq!(
+            "https://gitlab.example.com/api/v4/projects/345/merge_requests/12/notes/7",
+            merge_request.comment_url(7)
+        );
+        assert_eq!(None, pull_request(Provider::Gitlab, &vars[1..])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_response() {
+        let url = "https://api.github.com/repos/ghclara/cargo-scout/issues/42/comments";
+        let ok = "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 201 Created\r\nLocation: x\r\n\r\n{}\n201";
+        assert_eq!(Ok("{}".to_string()), response(ok, url));
+        let throttled =
+            "HTTP/2 403\r\nx-ratelimit-remaining: 0\r\n\r\n{\"message\":\"slow down\"}\n403";
+        assert_eq!(Err(PostError::RateLimited), response(throttled, url));
+        // The token of a pull request from a fork can't comment
+        let forbidden = "HTTP/2 403\r\nx-ratelimit-remaining: 4999\r\n\r\n{\"message\":\"Resource not accessible by integration\"}\n403";
+        assert_eq!(
+            Err(PostError::Forbidden(
+                "403: {\"message\":\"Resource not accessible by integration\"}".to_string()
+            )),
+            response(forbidden, url)
+        );
+        assert!(response("curl: (6) Could not resolve host", url).is_err());
+    }
+
+    #[test]
+    fn test_escape_config() {
+        assert_eq!(
+            r#"{\"body\":\"a\\nb\"}"#,
+            escape_config(&serde_json::json!({ "body": "a\nb" }).to_string())
+        );
+        assert_eq!(
+            r#"line\nbreak\t\"quoted\""#,
+            escape_config("line\nbreak\t\"quoted\"")
+        );
+    }
+}
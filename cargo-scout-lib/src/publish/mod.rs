@@ -0,0 +1,329 @@
+use crate::linter::Lint;
+use std::time::Duration;
+
+pub mod checks;
+pub mod comment;
+
+/// The default amount of annotations posted in a single request
+pub const DEFAULT_BATCH_SIZE: usize = 50;
+
+/// A code host that findings can be posted to as annotations.
+///
+/// Implementations only need to send a single request,
+/// batching and retries are handled by the `Publisher`.
+pub trait AnnotationSink {
+    /// Posts a batch of findings as annotations.
+    ///
+    /// # Errors
+    /// Fails if the request can't be sent, is rate limited or rejected.
+    fn post(&mut self, batch: &[Lint]) -> Result<(), PostError>;
+    /// Posts a plain text summary, used when not every finding could be annotated.
+    ///
+    /// # Errors
+    /// Fails if the request can't be sent, is rate limited or rejected.
+    fn post_summary(&mut self, summary: &str) -> Result<(), PostError>;
+}
+
+/// The reasons a request to a code host can fail
+#[derive(Debug, PartialEq, Clone)]
+pub enum PostError {
+    /// The host asked us to slow down
+    RateLimited,
+    /// The token isn't allowed to make the request,
+    /// e.g. the token of a pull request opened from a fork
+    Forbidden(String),
+    /// Any other failure
+    Failed(String),
+}
+
+impl PostError {
+    /// Maps an HTTP response that isn't successful to a `PostError`.
+    ///
+    /// Code hosts rate limit with a 429, or with a 403 whose body or headers say so.
+    /// Any other 403 is a permission error.
+    #[must_use]
+    pub fn from_response(status: u16, headers: &[(String, String)], body: &str) -> Self {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.trim())
+        };
+        let rate_limited = body.to_lowercase().contains("rate limit")
+            || header("x-ratelimit-remaining") == Some("0")
+            || header("retry-after").is_some();
+        match status {
+            429 => PostError::RateLimited,
+            403 if rate_limited => PostError::RateLimited,
+            403 => PostError::Forbidden(format!("{status}: {body}")),
+            _ => PostError::Failed(format!("{status}: {body}")),
+        }
+    }
+}
+
+/// What has been published
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PublishReport {
+    /// The amount of findings posted as annotations
+    pub annotated: usize,
+    /// The amount of findings only mentioned in the summary
+    pub not_annotated: usize,
+}
+
+/// Posts findings to an `AnnotationSink` in chunks,
+/// with an exponential backoff when the host rate limits us.
+///
+/// If the host keeps rate limiting us, the remaining findings
+/// are summarized instead of failing the publishing step.
+pub struct Publisher {
+    batch_size: usize,
+    max_retries: u32,
+    initial_backoff: Duration,
+    sleep: fn(Duration),
+}
+
+impl Default for Publisher {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_BATCH_SIZE,
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            sleep: std::thread::sleep,
+        }
+    }
+}
+
+impl Publisher {
+    pub fn set_batch_size(&mut self, batch_size: usize) -> &mut Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn set_max_retries(&mut self, max_retries: u32) -> &mut Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn set_initial_backoff(&mut self, initial_backoff: Duration) -> &mut Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Posts the findings in batches, retrying the failed requests with an increasing backoff,
+    /// and posts a summary of the ones that couldn't be annotated.
+    ///
+    /// # Errors
+    /// Fails if a request still fails after the retries.
+    pub fn publish(
+        &self,
+        sink: &mut impl AnnotationSink,
+        lints: &[Lint],
+    ) -> Result<PublishReport, crate::error::Error> {
+        let mut annotated = 0;
+        for batch in lints.chunks(self.batch_size) {
+            if self.post_with_backoff(sink, batch)? {
+                annotated += batch.len();
+            } else {
+                break;
+            }
+        }
+        let not_annotated = lints.len() - annotated;
+        if not_annotated > 0 {
+            let summary = format!("{not_annotated} more findings not annotated due to API limits");
+            sink.post_summary(&summary).map_err(publish_error)?;
+        }
+        Ok(PublishReport {
+            annotated,
+            not_annotated,
+        })
+    }
+
+    /// Sends a single request, retrying it with an increasing backoff while we are rate limited.
+    ///
+    /// # Errors
+    /// Fails if the request fails, or is still rate limited after the retries.
+    pub fn send<T>(
+        &self,
+        request: impl FnMut() -> Result<T, PostError>,
+    ) -> Result<T, crate::error::Error> {
+        self.with_backoff(request)?
+            .ok_or_else(|| publish_error(PostError::RateLimited))
+    }
+
+    // Returns false if we are still rate limited after all the retries
+    fn post_with_backoff(
+        &self,
+        sink: &mut impl AnnotationSink,
+        batch: &[Lint],
+    ) -> Result<bool, crate::error::Error> {
+        Ok(self.with_backoff(|| sink.post(batch))?.is_some())
+    }
+
+    // Returns None if we are still rate limited after all the retries
+    fn with_backoff<T>(
+        &self,
+        mut request: impl FnMut() -> Result<T, PostError>,
+    ) -> Result<Option<T>, crate::error::Error> {
+        let mut backoff = self.initial_backoff;
+        for attempt in 0..=self.max_retries {
+            match request() {
+                Ok(response) => return Ok(Some(response)),
+                Err(PostError::RateLimited) => {
+                    if attempt < self.max_retries {
+                        (self.sleep)(backoff);
+                        backoff *= 2;
+                    }
+                }
+                Err(e) => return Err(publish_error(e)),
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn publish_error(e: PostError) -> crate::error::Error {
+    match e {
+        PostError::RateLimited => crate::error::Error::Publish("rate limited".to_string()),
+        PostError::Forbidden(reason) => crate::error::Error::Publish(format!(
+            "permission denied, {reason}. Check that the token is allowed to comment"
+        )),
+        PostError::Failed(reason) => crate::error::Error::Publish(reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fails with the given errors first, then succeeds
+    struct TestSink {
+        errors: Vec<PostError>,
+        posted: Vec<usize>,
+        summaries: Vec<String>,
+    }
+
+    impl TestSink {
+        fn new(errors: Vec<PostError>) -> Self {
+            Self {
+                errors,
+                posted: Vec::new(),
+                summaries: Vec::new(),
+            }
+        }
+    }
+
+    impl AnnotationSink for TestSink {
+        fn post(&mut self, batch: &[Lint]) -> Result<(), PostError> {
+            if self.errors.is_empty() {
+                self.posted.push(batch.len());
+                Ok(())
+            } else {
+                Err(self.errors.remove(0))
+            }
+        }
+
+        fn post_summary(&mut self, summary: &str) -> Result<(), PostError> {
+            self.summaries.push(summary.to_string());
+            Ok(())
+        }
+    }
+
+    fn lints(count: usize) -> Vec<Lint> {
+        (0..count)
+            .map(|_| Lint {
+                package_id: "cargo-scout".to_string(),
+                src_path: None,
+                reproducer: None,
+                message: None,
+            })
+            .collect()
+    }
+
+    fn publisher() -> Publisher {
+        let mut publisher = Publisher {
+            sleep: |_| {},
+            ..Publisher::default()
+        };
+        publisher.set_batch_size(2);
+        publisher
+    }
+
+    #[test]
+    fn test_from_response() {
+        let header = |name: &str, value: &str| vec![(name.to_string(), value.to_string())];
+        assert_eq!(
+            PostError::RateLimited,
+            PostError::from_response(429, &[], "")
+        );
+        assert_eq!(
+            PostError::RateLimited,
+            PostError::from_response(403, &[], r#"{"message":"API rate limit exceeded"}"#)
+        );
+        assert_eq!(
+            PostError::RateLimited,
+            PostError::from_response(403, &header("X-RateLimit-Remaining", "0"), "")
+        );
+        assert_eq!(
+            PostError::RateLimited,
+            PostError::from_response(403, &header("retry-after", "60"), "")
+        );
+        assert_eq!(
+            PostError::Failed("500: oops".to_string()),
+            PostError::from_response(500, &[], "oops")
+        );
+    }
+
+    #[test]
+    fn test_permission_denied() {
+        let body = r#"{"message":"Resource not accessible by integration"}"#;
+        // GitHub tells the remaining requests with every response
+        let headers = [("X-RateLimit-Remaining".to_string(), "4999".to_string())];
+        let error = PostError::from_response(403, &headers, body);
+        assert_eq!(PostError::Forbidden(format!("403: {body}")), error);
+        let message = publish_error(error).to_string();
+        assert!(message.contains("permission denied"));
+        assert!(message.contains("Resource not accessible by integration"));
+    }
+
+    #[test]
+    fn test_publish_in_batches() -> Result<(), crate::error::Error> {
+        let mut sink = TestSink::new(Vec::new());
+        let report = publisher().publish(&mut sink, &lints(5))?;
+        assert_eq!(vec![2, 2, 1], sink.posted);
+        assert_eq!(5, report.annotated);
+        assert_eq!(0, report.not_annotated);
+        assert!(sink.summaries.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_when_rate_limited() -> Result<(), crate::error::Error> {
+        let mut sink = TestSink::new(vec![PostError::RateLimited, PostError::RateLimited]);
+        let report = publisher().publish(&mut sink, &lints(3))?;
+        assert_eq!(vec![2, 1], sink.posted);
+        assert_eq!(3, report.annotated);
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_fallback() -> Result<(), crate::error::Error> {
+        let mut sink = TestSink::new(vec![PostError::RateLimited; 10]);
+        let mut publisher = publisher();
+        publisher.set_max_retries(1);
+        let report = publisher.publish(&mut sink, &lints(3))?;
+        assert!(sink.posted.is_empty());
+        assert_eq!(0, report.annotated);
+        assert_eq!(3, report.not_annotated);
+        assert_eq!(
+            vec!["3 more findings not annotated due to API limits".to_string()],
+            sink.summaries
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_other_errors_fail() {
+        let mut sink = TestSink::new(vec![PostError::Failed("500: oops".to_string())]);
+        assert!(publisher().publish(&mut sink, &lints(3)).is_err());
+    }
+}
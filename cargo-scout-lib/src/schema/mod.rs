@@ -0,0 +1,239 @@
+use std::str::FromStr;
+
+/// The version of the documents scout produces,
+/// bumped whenever a change breaks their published schema.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The JSON documents scout produces, that have a published schema
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SchemaKind {
+    /// The document written by `--output json`
+    Findings,
+    /// The changed sections, as returned by a `VCS`
+    Sections,
+    /// The information about a run, shared by every output
+    RunMetadata,
+}
+
+impl FromStr for SchemaKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "findings" => Ok(SchemaKind::Findings),
+            "sections" => Ok(SchemaKind::Sections),
+            "run-metadata" => Ok(SchemaKind::RunMetadata),
+            _ => Err(format!(
+                "unknown schema {s}, expected one of findings, sections, run-metadata"
+            )),
+        }
+    }
+}
+
+impl SchemaKind {
+    /// Returns the JSON Schema document describing this kind of output.
+    #[must_use]
+    pub fn schema(self) -> &'static str {
+        match self {
+            SchemaKind::Findings => include_str!("../../schemas/findings.schema.json"),
+            SchemaKind::Sections => include_str!("../../schemas/sections.schema.json"),
+            SchemaKind::RunMetadata => include_str!("../../schemas/run-metadata.schema.json"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::{Code, Level, Lint, Message, Span};
+    use crate::output::json::JsonFormatter;
+    use crate::output::{Formatter, RunMetadata};
+    use crate::paths::PathResolver;
+    use crate::scout::TrackedLint;
+    use crate::vcs::{AddedLine, Origin, Section};
+    use serde_json::Value;
+
+    // Validates the subset of JSON Schema the published schemas use
+    fn validate(root: &Value, schema: &Value, instance: &Value, path: &str) -> Result<(), String> {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let name = reference.trim_start_matches("#/$defs/");
+            return validate(root, &root["$defs"][name], instance, path);
+        }
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match types {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(t) => t.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            let matches = types.iter().any(|t| match *t {
+                "object" => instance.is_object(),
+                "array" => instance.is_array(),
+                "string" => instance.is_string(),
+                "integer" => instance.is_u64() || instance.is_i64(),
+                "null" => instance.is_null(),
+                _ => false,
+            });
+            if !matches {
+                return Err(format!("{}: expected {:?}, got {}", path, types, instance));
+            }
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            if !values.contains(instance) {
+                return Err(format!("{}: {} is not one of {:?}", path, instance, values));
+            }
+        }
+        if let (Some(minimum), Some(n)) = (schema.get("minimum"), instance.as_i64()) {
+            if minimum.as_i64().is_some_and(|m| n < m) {
+                return Err(format!("{}: {} is below {}", path, n, minimum));
+            }
+        }
+        if let Some(object) = instance.as_object() {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for required in schema["required"].as_array().into_iter().flatten() {
+                let required = required.as_str().unwrap_or_default();
+                if !object.contains_key(required) {
+                    return Err(format!("{}: missing {}", path, required));
+                }
+            }
+            for (key, value) in object {
+                match properties.and_then(|p| p.get(key)) {
+                    Some(property) => {
+                        validate(root, property, value, &format!("{}.{}", path, key))?
+                    }
+                    None if schema["additionalProperties"] == Value::Bool(false) => {
+                        return Err(format!("{}: unexpected {}", path, key))
+                    }
+                    None => {}
+                }
+            }
+        }
+        if let (Some(items), Some(array)) = (schema.get("items"), instance.as_array()) {
+            for (i, item) in array.iter().enumerate() {
+                validate(root, items, item, &format!("{}[{}]", path, i))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn assert_valid(kind: SchemaKind, instance: &Value) {
+        let schema: Value = serde_json::from_str(kind.schema()).unwrap();
+        if let Err(e) = validate(&schema, &schema, instance, "$") {
+            panic!("{:?} schema violation: {}", kind, e);
+        }
+    }
+
+    fn lint() -> Lint {
+        let span = Span {
+            file_name: "foo/src/lib.rs".to_string(),
+            line_start: 1,
+            line_end: 2,
+            column_start: 1,
+            column_end: 4,
+            is_primary: true,
+            ..Span::default()
+        };
+        Lint {
+            package_id: "foo 0.1.0 (path+file:///foo)".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: "redundant clone".to_string(),
+                rendered: "warning: redundant clone".to_string(),
+                code: Some(Code {
+                    code: "clippy::redundant_clone".to_string(),
+                    explanation: None,
+                }),
+                level: Some(Level::Warning),
+                spans: vec![span.clone()],
+                children: vec![Message {
+                    message: "remove this".to_string(),
+                    level: Some(Level::Help),
+                    spans: vec![Span {
+                        suggested_replacement: Some(String::new()),
+                        ..span
+                    }],
+                    ..Message::default()
+                }],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_findings_schema() {
+        let formatter = JsonFormatter::new(PathResolver::new(vec!["foo".to_string()]));
+        let mut metadata = RunMetadata::new("1.0.0");
+        metadata.set_target_branch(Some("master".to_string()));
+        let lints = vec![
+            lint(),
+            Lint {
+                package_id: "foo".to_string(),
+                src_path: None,
+                reproducer: None,
+                message: None,
+            },
+        ];
+        let output: Value = serde_json::from_str(&formatter.format(&lints, &metadata)).unwrap();
+        assert_valid(SchemaKind::Findings, &output);
+
+        let tracked = vec![TrackedLint {
+            lint: lint(),
+            sections: Vec::new(),
+            introduced_by: Some(Origin {
+                commit: "5f0c6b7e1a2d3c4b".to_string(),
+                author: "jane".to_string(),
+                time: 1_577_836_800,
+            }),
+            origin: Some(Origin {
+                commit: "0a1b2c3d4e5f6a7b".to_string(),
+                author: "john".to_string(),
+                time: 1_500_000_000,
+            }),
+        }];
+        let output: Value =
+            serde_json::from_str(&formatter.format_tracked(&tracked, &metadata)).unwrap();
+        assert_eq!("jane", output["findings"][0]["introduced_by"]["author"]);
+        assert_eq!("john", output["findings"][0]["origin"]["author"]);
+        assert_valid(SchemaKind::Findings, &output);
+    }
+
+    #[test]
+    fn test_sections_schema() {
+        let sections = vec![Section {
+            file_name: "src/lib.rs".to_string(),
+            line_start: 1,
+            line_end: 3,
+            added_lines: vec![AddedLine {
+                line: 2,
+                text: "let x = 1;".to_string(),
+            }],
+        }];
+        assert_valid(
+            SchemaKind::Sections,
+            &serde_json::to_value(sections).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_run_metadata_schema() {
+        let metadata = RunMetadata::new("1.0.0");
+        assert_valid(
+            SchemaKind::RunMetadata,
+            &serde_json::to_value(metadata).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_validation_fails() {
+        let schema: Value = serde_json::from_str(SchemaKind::RunMetadata.schema()).unwrap();
+        let instance = serde_json::json!({ "tool_version": 1, "target_branch": null });
+        assert!(validate(&schema, &schema, &instance, "$").is_err());
+    }
+
+    #[test]
+    fn test_schema_kind() {
+        assert_eq!(Ok(SchemaKind::Findings), "findings".parse());
+        assert_eq!(Ok(SchemaKind::Sections), "sections".parse());
+        assert_eq!(Ok(SchemaKind::RunMetadata), "run-metadata".parse());
+        assert!("lints".parse::<SchemaKind>().is_err());
+    }
+}
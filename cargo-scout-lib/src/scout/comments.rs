@@ -0,0 +1,96 @@
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use std::str::FromStr;
+
+/// Returns true if the two versions of a Rust source file
+/// only differ in their comments, doc comments and whitespace.
+///
+/// Line endings don't matter, a checkout with CRLF endings
+/// compares equal to the LF endings git stores.
+/// Sources that can't be tokenized are considered different.
+///
+/// # Example
+/// ```
+/// # use cargo_scout_lib::scout::comments::only_comments_changed;
+/// let old = "/// Adds one\nfn add_one(x: u32) -> u32 { x + 1 }";
+/// let new = "/// Adds 1 to x\n// TODO: overflow\nfn add_one(x: u32) -> u32 {\n    x + 1\n}";
+/// assert!(only_comments_changed(old, new));
+/// assert!(!only_comments_changed(old, "fn add_one(x: u32) -> u32 { x + 2 }"));
+/// ```
+#[must_use]
+pub fn only_comments_changed(old: &str, new: &str) -> bool {
+    let (old, new) = (old.replace("\r\n", "\n"), new.replace("\r\n", "\n"));
+    match (TokenStream::from_str(&old), TokenStream::from_str(&new)) {
+        (Ok(old), Ok(new)) => without_docs(old).to_string() == without_docs(new).to_string(),
+        _ => false,
+    }
+}
+
+// Regular comments aren't tokens, but doc comments are `#[doc = "..."]` attributes
+fn without_docs(stream: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let mut kept = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let is_pound = matches!(&tokens[i], TokenTree::Punct(p) if p.as_char() == '#');
+        let bang = matches!(tokens.get(i + 1), Some(TokenTree::Punct(p)) if p.as_char() == '!');
+        let attribute = if bang { i + 2 } else { i + 1 };
+        if is_pound && tokens.get(attribute).is_some_and(is_doc_attribute) {
+            i = attribute + 1;
+            continue;
+        }
+        kept.push(match &tokens[i] {
+            TokenTree::Group(g) => {
+                let mut group = proc_macro2::Group::new(g.delimiter(), without_docs(g.stream()));
+                group.set_span(g.span());
+                TokenTree::Group(group)
+            }
+            token => token.clone(),
+        });
+        i += 1;
+    }
+    kept.into_iter().collect()
+}
+
+fn is_doc_attribute(token: &TokenTree) -> bool {
+    match token {
+        TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket => {
+            matches!(g.stream().into_iter().next(), Some(TokenTree::Ident(i)) if i == "doc")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doc_comments() {
+        let old = "//! Crate docs\nstruct Foo {\n    /// A field\n    bar: u32,\n}";
+        let new = "//! Better crate docs\nstruct Foo {\n    /** The field */\n    bar: u32,\n}";
+        assert!(only_comments_changed(old, new));
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let old = "const GREETING: &str = \"Hello\nWorld\";\n";
+        let new = "// Greets\r\nconst GREETING: &str = \"Hello\r\nWorld\";\r\n";
+        assert!(only_comments_changed(old, new));
+    }
+
+    #[test]
+    fn test_code_changes() {
+        let old = "struct Foo {\n    bar: u32,\n}";
+        assert!(!only_comments_changed(
+            old,
+            "struct Foo {\n    bar: u64,\n}"
+        ));
+        // Other attributes matter
+        assert!(!only_comments_changed(
+            old,
+            "#[derive(Debug)]\nstruct Foo {\n    bar: u32,\n}"
+        ));
+        // Code that doesn't tokenize can't be compared
+        assert!(!only_comments_changed(old, "struct Foo {"));
+    }
+}
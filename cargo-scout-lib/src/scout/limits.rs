@@ -0,0 +1,98 @@
+use crate::vcs::Section;
+use std::collections::BTreeSet;
+
+/// The size of a diff beyond which the linters that build the code aren't run.
+///
+/// Large imports of vendored or generated code would otherwise
+/// compile and lint for a long time, for findings nobody reviews.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DiffLimits {
+    /// The number of changed files the diff may have
+    pub max_files: Option<usize>,
+    /// The number of changed lines the diff may have
+    pub max_lines: Option<usize>,
+}
+
+impl DiffLimits {
+    /// Returns which limit `sections` exceed, or `None` if they fit in every limit.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::scout::limits::DiffLimits;
+    /// # use cargo_scout_lib::vcs::Section;
+    /// let limits = DiffLimits { max_files: None, max_lines: Some(5) };
+    /// // Lines 1 to 10, the end is excluded
+    /// let section = Section { file_name: "foo.rs".to_string(), line_start: 1, line_end: 11, added_lines: Vec::new() };
+    /// assert_eq!(
+    ///     Some("10 changed lines, more than the limit of 5".to_string()),
+    ///     limits.exceeded(&[section])
+    /// );
+    /// ```
+    #[must_use]
+    pub fn exceeded(&self, sections: &[Section]) -> Option<String> {
+        let files = sections
+            .iter()
+            .map(|s| s.file_name.as_str())
+            .collect::<BTreeSet<_>>()
+            .len();
+        if let Some(max_files) = self.max_files.filter(|max| files > *max) {
+            return Some(format!(
+                "{files} changed files, more than the limit of {max_files}"
+            ));
+        }
+        let lines: usize = sections
+            .iter()
+            .map(|s| s.line_end.saturating_sub(s.line_start) as usize)
+            .sum();
+        self.max_lines
+            .filter(|max| lines > *max)
+            .map(|max_lines| format!("{lines} changed lines, more than the limit of {max_lines}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(file_name: &str, line_start: u32, line_end: u32) -> Section {
+        Section {
+            file_name: file_name.to_string(),
+            line_start,
+            line_end,
+            added_lines: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_exceeded() {
+        let sections = vec![
+            section("src/lib.rs", 1, 4),
+            section("src/lib.rs", 10, 11),
+            section("src/main.rs", 5, 6),
+            // Only removes lines
+            section("src/main.rs", 20, 20),
+        ];
+        assert_eq!(None, DiffLimits::default().exceeded(&sections));
+        let limits = DiffLimits {
+            max_files: Some(2),
+            max_lines: Some(5),
+        };
+        assert_eq!(None, limits.exceeded(&sections));
+        let limits = DiffLimits {
+            max_files: Some(1),
+            max_lines: Some(4),
+        };
+        assert_eq!(
+            Some("2 changed files, more than the limit of 1".to_string()),
+            limits.exceeded(&sections)
+        );
+        let limits = DiffLimits {
+            max_files: None,
+            max_lines: Some(4),
+        };
+        assert_eq!(
+            Some("5 changed lines, more than the limit of 4".to_string()),
+            limits.exceeded(&sections)
+        );
+    }
+}
@@ -1,7 +1,51 @@
-use crate::config::*;
-use crate::linter::*;
-use crate::vcs::*;
+use crate::baseline::Baseline;
+use crate::cancel::CancellationToken;
+use crate::config::Config;
+use crate::filter::{LintFilter, PathFilter};
+#[cfg(feature = "async")]
+use crate::linter::AsyncLinter;
+use crate::linter::{dedup, Lint, Linter, Span};
+use crate::paths::PathNormalizer;
+use crate::progress::{NoProgress, Progress, Stage};
+use crate::vcs::{normalize_sections, Origin, Section, VCS};
+use limits::DiffLimits;
+use scope::Scope;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::time::Instant;
 
+pub mod comments;
+pub mod limits;
+pub mod scope;
+
+/// A lint the diff is responsible for, along with the changes it overlaps.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrackedLint {
+    pub lint: Lint,
+    /// The changed sections the lint is on, after context and scope expansion
+    pub sections: Vec<Section>,
+    /// The last commit that changed the lines of the lint, when blaming is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub introduced_by: Option<Origin>,
+    /// The oldest commit the lines of the lint come from, when blaming is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Origin>,
+}
+
+impl From<TrackedLint> for Lint {
+    fn from(tracked: TrackedLint) -> Self {
+        tracked.lint
+    }
+}
+
+/// Gates lints on a diff: runs the linter on the members the diff changes,
+/// and keeps the lints that overlap the changes.
+///
+/// This is what the `cargo-scout` binary runs, other tools can embed it
+/// with their own `VCS`, `Config` and `Linter` implementations.
+/// Several linters can run at once with a `Vec<Box<dyn Linter>>`.
+#[allow(clippy::struct_excessive_bools)]
 pub struct Scout<V, C, L>
 where
     V: VCS,
@@ -11,6 +55,22 @@ where
     vcs: V,
     config: C,
     linter: L,
+    filter: LintFilter,
+    path_filter: PathFilter,
+    baseline: Baseline,
+    progress: Box<dyn Progress>,
+    context: u32,
+    scope: Scope,
+    skip_comment_changes: bool,
+    always_run: bool,
+    whole_workspace: bool,
+    lint_dependents: bool,
+    members: Option<Vec<String>>,
+    limits: DiffLimits,
+    path_normalizer: Option<PathNormalizer>,
+    cancellation: CancellationToken,
+    blame: bool,
+    include_macro_defs: bool,
 }
 
 impl<V, C, L> Scout<V, C, L>
@@ -24,30 +84,631 @@ where
             vcs,
             config,
             linter,
+            filter: LintFilter::default(),
+            path_filter: PathFilter::default(),
+            baseline: Baseline::default(),
+            progress: Box::new(NoProgress),
+            context: 0,
+            scope: Scope::default(),
+            skip_comment_changes: false,
+            always_run: false,
+            whole_workspace: false,
+            lint_dependents: false,
+            members: None,
+            limits: DiffLimits::default(),
+            path_normalizer: None,
+            cancellation: CancellationToken::default(),
+            blame: false,
+            include_macro_defs: false,
+        }
+    }
+    /// Run the linter even if no Rust source or manifest changed
+    pub fn set_always_run(&mut self, always_run: bool) -> &mut Self {
+        self.always_run = always_run;
+        self
+    }
+    /// Lint every member, not only the members the diff changes
+    pub fn set_whole_workspace(&mut self, whole_workspace: bool) -> &mut Self {
+        self.whole_workspace = whole_workspace;
+        self
+    }
+    /// Also lint the members that depend on the changed ones, directly or not.
+    ///
+    /// Their compile errors are then blamed on the diff.
+    pub fn set_lint_dependents(&mut self, lint_dependents: bool) -> &mut Self {
+        self.lint_dependents = lint_dependents;
+        self
+    }
+    /// Only lint these members, among the ones the diff concerns. All of them when `None`
+    pub fn set_members(&mut self, members: Option<Vec<String>>) -> &mut Self {
+        self.members = members;
+        self
+    }
+    /// Set the size of a diff beyond which the linters that build the code aren't run
+    pub fn set_limits(&mut self, limits: DiffLimits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+    /// Set the paths the changes and the lints are restricted to
+    pub fn set_path_filter(&mut self, path_filter: PathFilter) -> &mut Self {
+        self.path_filter = path_filter;
+        self
+    }
+    /// Set the filter that is applied to the lints found in the diff
+    pub fn set_filter(&mut self, filter: LintFilter) -> &mut Self {
+        self.filter = filter;
+        self
+    }
+    /// Set the pre-existing findings that are removed from the lints found in the diff
+    pub fn set_baseline(&mut self, baseline: Baseline) -> &mut Self {
+        self.baseline = baseline;
+        self
+    }
+    /// Set what is notified of the progress of the run
+    pub fn set_progress(&mut self, progress: Box<dyn Progress>) -> &mut Self {
+        self.progress = progress;
+        self
+    }
+    /// Set how many lines around a change still count as changed
+    pub fn set_context(&mut self, context: u32) -> &mut Self {
+        self.context = context;
+        self
+    }
+    /// Set what a change is attributed to
+    pub fn set_scope(&mut self, scope: Scope) -> &mut Self {
+        self.scope = scope;
+        self
+    }
+    /// Skip the linters that build the code when the diff only changes comments
+    pub fn set_skip_comment_changes(&mut self, skip_comment_changes: bool) -> &mut Self {
+        self.skip_comment_changes = skip_comment_changes;
+        self
+    }
+    /// Set how the paths reported by the linter are made relative to the repository root
+    pub fn set_path_normalizer(&mut self, path_normalizer: PathNormalizer) -> &mut Self {
+        self.path_normalizer = Some(path_normalizer);
+        self
+    }
+    /// Set the token that stops the run between members
+    pub fn set_cancellation(&mut self, cancellation: CancellationToken) -> &mut Self {
+        self.cancellation = cancellation;
+        self
+    }
+    /// Record the last commit that changed the lines of each lint
+    pub fn set_blame(&mut self, blame: bool) -> &mut Self {
+        self.blame = blame;
+        self
+    }
+    /// Also keep the lints in macro definitions the diff changes.
+    ///
+    /// By default, a lint in a macro expansion is on the diff when the macro invocation is.
+    pub fn set_include_macro_defs(&mut self, include_macro_defs: bool) -> &mut Self {
+        self.include_macro_defs = include_macro_defs;
+        self
+    }
+    /// Returns the lints the diff is responsible for,
+    /// minus the baselined and filtered out ones.
+    ///
+    /// # Errors
+    /// Fails if the diff can't be read, or if a linter fails.
+    pub fn run(&self) -> Result<Vec<TrackedLint>, crate::error::Error> {
+        let Some(plan) = self.plan()? else {
+            return Ok(Vec::new());
+        };
+        let changed_sections = self.changed_sections(&plan.diff_sections);
+        let lints = self.lints_for_members(&plan.members, Some(&changed_sections), plan.build);
+        self.tracked(&plan, lints)
+    }
+    /// Same as `run`, without blocking the thread while the linter runs,
+    /// so a service can check many repositories at once.
+    ///
+    /// The diff is still computed synchronously, it doesn't wait on the linter.
+    ///
+    /// # Errors
+    /// Fails if the diff can't be read, or if a linter fails.
+    #[cfg(feature = "async")]
+    pub async fn run_async(&self) -> Result<Vec<TrackedLint>, crate::error::Error>
+    where
+        L: AsyncLinter,
+    {
+        let Some(plan) = self.plan()? else {
+            return Ok(Vec::new());
+        };
+        let changed_sections = self.changed_sections(&plan.diff_sections);
+        let lints = if plan.build {
+            self.lints_for_members_async(&plan.members, &changed_sections)
+                .await
+        } else {
+            // Those linters don't take long to run
+            self.lints_for_members(&plan.members, Some(&changed_sections), false)
+        };
+        self.tracked(&plan, lints)
+    }
+    /// Returns the members the files concern, as `Config::members` names them.
+    ///
+    /// The file names are relative to the repository root, like the diff's.
+    /// A file belongs to the innermost member containing it, a file of a crate that isn't
+    /// a member concerns the members using it, and a `Cargo.lock` the members it resolves.
+    ///
+    /// # Errors
+    /// Fails if the current directory can't be read.
+    pub fn members_of(&self, file_names: &[String]) -> Result<Vec<String>, crate::error::Error> {
+        let members = self.member_dirs()?;
+        let mut concerned = BTreeSet::new();
+        for file_name in file_names {
+            let (owners, users) = self.owners_of(&members, file_name);
+            concerned.extend(owners);
+            concerned.extend(users);
+        }
+        Ok(members
+            .into_iter()
+            .map(|(_, m)| m)
+            .filter(|m| concerned.contains(m))
+            .collect())
+    }
+    /// Returns every lint found in the project, regardless of the diff.
+    ///
+    /// # Errors
+    /// Fails if a linter fails.
+    pub fn lints(&self) -> Result<Vec<Lint>, crate::error::Error> {
+        self.lints_for_members(&self.config.members(), None, true)
+            .map(dedup)
+    }
+    // The sections the diff changes in this project, or in the crates it uses.
+    // In a monorepo, the changes made to other projects don't concern this one
+    fn changes(&self) -> Result<Vec<Section>, crate::error::Error> {
+        let changes = self.stage(&Stage::Diffing, || {
+            self.vcs
+                .sections(".")
+                .map(|sections| self.path_filter.filter_sections(sections))
+        })?;
+        let project = self.repo_dir(".")?;
+        Ok(changes
+            .into_iter()
+            .filter(|s| {
+                in_member(&project, s)
+                    || !self
+                        .config
+                        .users_of(&self.repo_file(&s.file_name))
+                        .is_empty()
+            })
+            .collect())
+    }
+    // Returns None if the linter doesn't need to run
+    fn plan(&self) -> Result<Option<Plan>, crate::error::Error> {
+        let changes = self.changes()?;
+        // The linters that don't build the code still check the changes
+        let mut build = true;
+        if let Some(exceeded) = self.limits.exceeded(&changes) {
+            log::warn!(
+                "[Scout] - the diff has {exceeded}, skipping the linters that build the code. These changes need a manual review"
+            );
+            build = false;
+        }
+        let sections: Vec<Section> = changes.iter().map(|s| s.expand(self.context)).collect();
+        let sections = scope::expand_to_scope(sections, self.scope, |file_name| {
+            std::fs::read_to_string(self.repo_file(file_name)).ok()
+        });
+        let diff_sections = normalize_sections(sections);
+        if build && self.skip_comment_changes && self.only_comments_changed(&diff_sections)? {
+            log::info!("[Scout] - only comments changed, skipping the linters that build the code");
+            build = false;
+        }
+        if !self.always_run && !diff_sections.iter().any(Section::is_rust_relevant) {
+            log::info!("[Scout] - no Rust sources or manifests changed, nothing to do");
+            return Ok(None);
+        }
+        let members = self.member_dirs()?;
+        let mut owners: BTreeSet<String> = BTreeSet::new();
+        let mut users: BTreeSet<String> = BTreeSet::new();
+        let mut outside: BTreeSet<&str> = BTreeSet::new();
+        for s in &diff_sections {
+            let (file_owners, file_users) = self.owners_of(&members, &s.file_name);
+            if file_owners.is_empty() && file_users.is_empty() && s.is_rust_relevant() {
+                outside.insert(s.file_name.as_str());
+            }
+            owners.extend(file_owners);
+            users.extend(file_users);
+        }
+        if !outside.is_empty() {
+            log::info!(
+                "[Scout] - ignoring changes outside the linted members: {}",
+                outside.into_iter().collect::<Vec<_>>().join(", ")
+            );
+        }
+        // There's no need to run the linter on members where no changes have been made
+        let selected = |m: &String| self.members.as_ref().is_none_or(|only| only.contains(m));
+        let changed_members: BTreeSet<String> = members
+            .iter()
+            .filter(|(_, m)| (self.whole_workspace || owners.contains(m)) && selected(m))
+            .map(|(_, m)| m.clone())
+            .collect();
+        // The members using a changed crate that isn't a member can break like dependents
+        let mut dependents = if self.lint_dependents {
+            self.dependents(&changed_members)
+        } else {
+            BTreeSet::new()
+        };
+        dependents.extend(
+            users
+                .into_iter()
+                .filter(|m| !changed_members.contains(m) && selected(m)),
+        );
+        if !dependents.is_empty() {
+            log::info!(
+                "[Scout] - also linting the members that depend on the changes: {}",
+                dependents.iter().cloned().collect::<Vec<_>>().join(", ")
+            );
+        }
+        Ok(Some(Plan {
+            dependents: members
+                .iter()
+                .filter(|(_, m)| dependents.contains(m))
+                .map(|(dir, _)| dir.clone())
+                .collect(),
+            members: members
+                .into_iter()
+                .filter(|(_, m)| changed_members.contains(m) || dependents.contains(m))
+                .map(|(_, m)| m)
+                .collect(),
+            diff_sections,
+            build,
+        }))
+    }
+    // The members, with their directory relative to the repository root
+    fn member_dirs(&self) -> Result<Vec<(String, String)>, crate::error::Error> {
+        self.config
+            .members()
+            .into_iter()
+            .map(|m| Ok((self.repo_dir(&m)?, m)))
+            .collect()
+    }
+    // The members a changed file belongs to, and the members using it.
+    // A change to a crate that isn't a member concerns the members using it,
+    // else it belongs to the innermost member, when members are nested
+    fn owners_of(
+        &self,
+        members: &[(String, String)],
+        file_name: &str,
+    ) -> (Vec<String>, Vec<String>) {
+        let users = self.config.users_of(&self.repo_file(file_name));
+        if !users.is_empty() {
+            return (Vec::new(), users);
+        }
+        if let Some(owner) = owner(members, file_name) {
+            return (vec![owner.clone()], Vec::new());
         }
+        // The lockfile of a workspace sits above its members, and resolves
+        // the dependencies of all of them
+        let owners = lockfile_dir(file_name).map_or_else(Vec::new, |lockfile_dir| {
+            members
+                .iter()
+                .filter(|(dir, _)| {
+                    in_dir(lockfile_dir, &format!("{}/", dir.trim_start_matches("./")))
+                })
+                .map(|(_, m)| m.clone())
+                .collect()
+        });
+        (owners, Vec::new())
     }
-    pub fn run(&self) -> Result<Vec<Lint>, crate::error::Error> {
-        let diff_sections = self.vcs.sections(".")?;
+    // The members that depend on the changed ones, directly or not
+    fn dependents(&self, changed: &BTreeSet<String>) -> BTreeSet<String> {
+        let mut dependents = BTreeSet::new();
+        let mut queue: Vec<String> = changed.iter().cloned().collect();
+        while let Some(member) = queue.pop() {
+            for dependent in self.config.dependents(&member) {
+                if !changed.contains(&dependent) && dependents.insert(dependent.clone()) {
+                    queue.push(dependent);
+                }
+            }
+        }
+        dependents
+    }
+    // Diff aware linters are given the changed sections, when there's a diff.
+    // Without build, only the diff aware linters that don't build the code run
+    fn lints_for_members(
+        &self,
+        members: &[String],
+        changed_sections: Option<&[Section]>,
+        build: bool,
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        if members.is_empty() {
+            return Ok(Vec::new());
+        }
+        let dirs = Self::dirs(members)?;
+        let lints = match changed_sections {
+            Some(sections) if !build => self.lints_without_build(members, &dirs, sections),
+            _ => {
+                // The members are linted at once, clippy builds them with a single cargo run
+                self.cancellation.check()?;
+                self.stage(&Self::linting(members), || {
+                    self.linter.lints_for_members(&dirs, changed_sections)
+                })
+            }
+        };
+        self.normalized(lints)
+    }
+    // Same as `lints_for_members` with build, without blocking the thread
+    #[cfg(feature = "async")]
+    async fn lints_for_members_async(
+        &self,
+        members: &[String],
+        changed_sections: &[Section],
+    ) -> Result<Vec<Lint>, crate::error::Error>
+    where
+        L: AsyncLinter,
+    {
+        if members.is_empty() {
+            return Ok(Vec::new());
+        }
+        let dirs = Self::dirs(members)?;
+        self.cancellation.check()?;
+        let stage = Self::linting(members);
+        self.progress.started(&stage);
+        let start = Instant::now();
+        let lints = self
+            .linter
+            .lints_for_members_async(&dirs, Some(changed_sections))
+            .await;
+        self.progress.finished(&stage, start.elapsed());
+        self.normalized(lints)
+    }
+    fn dirs(members: &[String]) -> Result<Vec<PathBuf>, crate::error::Error> {
         let current_dir = std::fs::canonicalize(".")?;
+        Ok(members.iter().map(|m| current_dir.join(m)).collect())
+    }
+    fn linting(members: &[String]) -> Stage {
+        Stage::Linting {
+            member: members.join(", "),
+            index: 1,
+            total: 1,
+        }
+    }
+    // Normalizes the lints, or the compile errors the linter failed with
+    fn normalized(
+        &self,
+        lints: Result<Vec<Lint>, crate::error::Error>,
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        match lints {
+            Err(crate::error::Error::Compilation { errors }) => {
+                Err(crate::error::Error::Compilation {
+                    errors: self.normalize_paths(errors),
+                })
+            }
+            lints => Ok(self.normalize_lints(lints?)),
+        }
+    }
+    // Keeps the lints on the diff, or tells whether the diff broke the build
+    fn tracked(
+        &self,
+        plan: &Plan,
+        lints: Result<Vec<Lint>, crate::error::Error>,
+    ) -> Result<Vec<TrackedLint>, crate::error::Error> {
+        match lints {
+            Err(crate::error::Error::Compilation { errors }) => {
+                Err(self.compilation_error(errors, &plan.diff_sections, &plan.dependents))
+            }
+            lints => Ok(self.blame(self.intersect(lints?, &plan.diff_sections))),
+        }
+    }
+    // The linters that don't build the code run on each member in turn
+    fn lints_without_build(
+        &self,
+        members: &[String],
+        dirs: &[PathBuf],
+        sections: &[Section],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
         let mut lints = Vec::new();
-        let members = self.config.members();
-        // There's no need to run the linter on members where no changes have been made
-        let relevant_members = members.iter().filter(|m| diff_in_member(m, &diff_sections));
-        for m in relevant_members {
-            lints.extend(self.linter.lints(current_dir.join(m))?);
+        for (i, (m, dir)) in members.iter().zip(dirs).enumerate() {
+            self.cancellation.check()?;
+            let stage = Stage::Linting {
+                member: m.clone(),
+                index: i + 1,
+                total: members.len(),
+            };
+            lints.extend(self.stage(&stage, || {
+                self.linter.lints_without_build(dir.clone(), sections)
+            })?);
+        }
+        Ok(lints)
+    }
+    // Makes the paths of the lints relative to the repository root, and filters them
+    fn normalize_lints(&self, lints: Vec<Lint>) -> Vec<Lint> {
+        self.path_filter.filter(self.normalize_paths(lints))
+    }
+    fn normalize_paths(&self, lints: Vec<Lint>) -> Vec<Lint> {
+        match &self.path_normalizer {
+            Some(normalizer) => lints
+                .into_iter()
+                .map(|l| normalizer.normalize_lint(l))
+                .collect(),
+            None => lints,
         }
-        println!("[Scout] - checking for intersections");
-        Ok(lints_from_diff(&lints, &diff_sections))
+    }
+    // Tells whether the diff is responsible for the compile errors,
+    // the members that depend on the changes only break because of them
+    fn compilation_error(
+        &self,
+        errors: Vec<Lint>,
+        diff_sections: &[Section],
+        dependents: &[String],
+    ) -> crate::error::Error {
+        let errors = dedup(errors);
+        let in_dependents: Vec<Lint> = errors
+            .iter()
+            .filter(|e| {
+                e.message
+                    .iter()
+                    .flat_map(|m| &m.spans)
+                    .any(|s| dependents.iter().any(|dir| in_dir(dir, &s.file_name)))
+            })
+            .cloned()
+            .collect();
+        if !in_dependents.is_empty() {
+            return crate::error::Error::CompilationInDiff {
+                errors: in_dependents,
+            };
+        }
+        let in_diff: Vec<Lint> = lints_from_diff(&errors, diff_sections, self.include_macro_defs)
+            .into_iter()
+            .map(Lint::from)
+            .collect();
+        if in_diff.is_empty() {
+            crate::error::Error::Compilation { errors }
+        } else {
+            crate::error::Error::CompilationInDiff { errors: in_diff }
+        }
+    }
+    // Keeps the lints that overlap the changes, minus the baselined and filtered out ones
+    fn intersect(&self, lints: Vec<Lint>, diff_sections: &[Section]) -> Vec<TrackedLint> {
+        log::debug!("[Scout] - checking for intersections");
+        self.stage(&Stage::Intersecting, || {
+            // A file compiled for several targets gets the same lints several times
+            let lints = dedup(lints);
+            let (lints, sections): (Vec<Lint>, Vec<Vec<Section>>) =
+                lints_from_diff(&lints, diff_sections, self.include_macro_defs)
+                    .into_iter()
+                    .map(|t| (t.lint, t.sections))
+                    .unzip();
+            let baselined = self.baseline.baselined(&lints);
+            lints
+                .into_iter()
+                .zip(sections)
+                .zip(baselined)
+                .filter(|((lint, _), baselined)| !baselined && self.filter.is_allowed(lint))
+                .map(|((lint, sections), _)| TrackedLint {
+                    lint,
+                    sections,
+                    introduced_by: None,
+                    origin: None,
+                })
+                .collect()
+        })
+    }
+    fn blame(&self, mut lints: Vec<TrackedLint>) -> Vec<TrackedLint> {
+        if !self.blame {
+            return lints;
+        }
+        for tracked in &mut lints {
+            let Some(span) = tracked.lint.primary_span() else {
+                continue;
+            };
+            tracked.introduced_by = self
+                .vcs
+                .introduced_by(".", &span.file_name, span.line_start, span.line_end)
+                .unwrap_or_else(|e| {
+                    log::debug!("[Scout] - could not blame {}: {}", span.file_name, e);
+                    None
+                });
+            tracked.origin = self
+                .vcs
+                .origin(".", &span.file_name, span.line_start, span.line_end)
+                .unwrap_or_else(|e| {
+                    log::debug!(
+                        "[Scout] - could not find the origin of {}: {}",
+                        span.file_name,
+                        e
+                    );
+                    None
+                });
+        }
+        lints
+    }
+    // Returns true if every changed file only has comment changes
+    fn only_comments_changed(&self, sections: &[Section]) -> Result<bool, crate::error::Error> {
+        let file_names: BTreeSet<&str> = sections.iter().map(|s| s.file_name.as_str()).collect();
+        for file_name in file_names {
+            let original = self.vcs.original(".", file_name)?;
+            let current = std::fs::read_to_string(self.repo_file(file_name)).ok();
+            match (original, current) {
+                (Some(original), Some(current))
+                    if comments::only_comments_changed(&original, &current) => {}
+                _ => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+    // Where a directory relative to the current one lives in the repository,
+    // diff sections are relative to the repository root
+    fn repo_dir(&self, dir: &str) -> Result<String, crate::error::Error> {
+        match &self.path_normalizer {
+            Some(normalizer) => Ok(normalizer.repo_dir(&std::fs::canonicalize(".")?, dir)),
+            None => Ok(dir.to_string()),
+        }
+    }
+    // The sections with absolute file names, when the repository root is known
+    fn changed_sections(&self, sections: &[Section]) -> Vec<Section> {
+        sections
+            .iter()
+            .map(|s| Section {
+                file_name: self.repo_file(&s.file_name).to_string_lossy().to_string(),
+                ..s.clone()
+            })
+            .collect()
+    }
+    fn repo_file(&self, file_name: &str) -> PathBuf {
+        match &self.path_normalizer {
+            Some(normalizer) => normalizer.repo_root().join(file_name),
+            None => PathBuf::from(file_name),
+        }
+    }
+    // Runs f, notifying the progress of its start and duration
+    fn stage<T>(&self, stage: &Stage, f: impl FnOnce() -> T) -> T {
+        self.progress.started(stage);
+        let start = Instant::now();
+        let result = f();
+        self.progress.finished(stage, start.elapsed());
+        result
     }
 }
 
-fn diff_in_member(member: &str, sections: &[Section]) -> bool {
-    for s in sections {
-        if s.file_name.starts_with(&member) {
-            return true;
-        }
+// What a run lints, and what it keeps lints on
+struct Plan {
+    members: Vec<String>,
+    diff_sections: Vec<Section>,
+    // The directories of the members linted because they depend on the changes
+    dependents: Vec<String>,
+    // False when only the linters that don't build the code run
+    build: bool,
+}
+
+fn in_member(member: &str, section: &Section) -> bool {
+    in_dir(member, &section.file_name)
+}
+
+// The member with the deepest directory containing the file
+fn owner<'a>(members: &'a [(String, String)], file_name: &str) -> Option<&'a String> {
+    members
+        .iter()
+        .filter(|(dir, _)| in_dir(dir, file_name))
+        .max_by_key(|(dir, _)| {
+            dir.split(['/', '\\'])
+                .filter(|c| !c.is_empty() && *c != ".")
+                .count()
+        })
+        .map(|(_, m)| m)
+}
+
+// The directory of a `Cargo.lock`, "." at the repository root
+fn lockfile_dir(file_name: &str) -> Option<&str> {
+    let file_name = file_name.trim_start_matches("./");
+    if file_name == "Cargo.lock" {
+        return Some(".");
     }
-    false
+    file_name
+        .strip_suffix("Cargo.lock")
+        .and_then(|dir| dir.strip_suffix(['/', '\\']))
+}
+
+// The root member, "." or "", contains every file
+fn in_dir(member: &str, file_name: &str) -> bool {
+    let member = member.replace('\\', "/");
+    let member = member.trim_start_matches("./").trim_end_matches('/');
+    member == "."
+        || member.is_empty()
+        || file_name
+            .strip_prefix(member)
+            .is_some_and(|rest| rest.starts_with('/'))
 }
 
 // Check if clippy_lint and git_section have overlapped lines
@@ -60,26 +721,59 @@ fn lines_in_range(clippy_lint: &Span, git_section: &Section) -> bool {
 
 fn files_match(clippy_lint: &Span, git_section: &Section) -> bool {
     // Git diff paths and clippy paths don't get along too well on Windows...
-    clippy_lint.file_name.replace("\\", "/") == git_section.file_name.replace("\\", "/")
+    clippy_lint.file_name.replace('\\', "/") == git_section.file_name.replace('\\', "/")
 }
 
-fn lints_from_diff(lints: &[Lint], diffs: &[Section]) -> Vec<Lint> {
-    let mut lints_in_diff = Vec::new();
+// The spans a lint is on, as far as the diff is concerned.
+// A span in a macro expansion usually points at the macro definition,
+// the code that triggered the lint is where the macro is invoked
+fn diff_spans(span: &Span, include_macro_defs: bool) -> Vec<&Span> {
+    let mut spans = span.call_sites();
+    if spans.is_empty() || include_macro_defs {
+        spans.insert(0, span);
+    }
+    spans
+}
+
+fn lints_from_diff(
+    lints: &[Lint],
+    diffs: &[Section],
+    include_macro_defs: bool,
+) -> Vec<TrackedLint> {
+    let mut lints_in_diff: Vec<TrackedLint> = Vec::new();
+    // The position of each lint in lints_in_diff, once it's in there
+    let mut tracked: HashMap<usize, usize> = HashMap::new();
     for diff in diffs {
-        let diff_lints = lints.iter().filter(|lint| {
+        let diff_lints = lints.iter().enumerate().filter(|(_, lint)| {
             if let Some(m) = &lint.message {
-                for s in &m.spans {
-                    if files_match(&s, &diff) && lines_in_range(&s, &diff) {
+                // The other spans only show where the code the lint is about comes from
+                for s in m
+                    .primary_spans()
+                    .into_iter()
+                    .flat_map(|s| diff_spans(s, include_macro_defs))
+                {
+                    if files_match(s, diff) && lines_in_range(s, diff) {
                         return true;
-                    };
+                    }
                 }
                 false
             } else {
                 false
             }
         });
-        for l in diff_lints {
-            lints_in_diff.push(l.clone());
+        for (index, l) in diff_lints {
+            // A lint that overlaps several sections is reported once
+            if let Some(position) = tracked.get(&index) {
+                lints_in_diff[*position].sections.push(diff.clone());
+            } else {
+                tracked.insert(index, lints_in_diff.len());
+                lints_in_diff.push(TrackedLint {
+                    lint: l.clone(),
+                    sections: vec![diff.clone()],
+                    introduced_by: None,
+                    origin: None,
+                });
+            }
         }
     }
     lints_in_diff
@@ -90,7 +784,10 @@ mod scout_tests {
     use super::*;
     use crate::config::Config;
     use crate::error::Error;
-    use crate::linter::{Lint, Linter};
+    use crate::linter::fixtures::{self, span};
+    #[cfg(feature = "async")]
+    use crate::linter::AsyncLinter;
+    use crate::linter::{DiffAwareLinter, Level, Lint, Linter};
     use std::cell::RefCell;
     use std::clone::Clone;
     use std::path::{Path, PathBuf};
@@ -98,12 +795,15 @@ mod scout_tests {
     struct TestVCS {
         sections: Vec<Section>,
         sections_called: RefCell<bool>,
+        // The contents of every file before the changes
+        original: Option<String>,
     }
     impl TestVCS {
         pub fn new(sections: Vec<Section>) -> Self {
             Self {
                 sections,
                 sections_called: RefCell::new(false),
+                original: None,
             }
         }
     }
@@ -112,6 +812,35 @@ mod scout_tests {
             *self.sections_called.borrow_mut() = true;
             Ok(self.sections.clone())
         }
+        fn original<P: AsRef<Path>>(&self, _: P, _: &str) -> Result<Option<String>, Error> {
+            Ok(self.original.clone())
+        }
+    }
+    // A diff aware linter that doesn't build the code, like TextPatterns
+    #[derive(Default)]
+    struct HygieneLinter {
+        times_called: Rc<RefCell<usize>>,
+    }
+    impl Linter for HygieneLinter {
+        fn lints(&self, _working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+            Ok(Vec::new())
+        }
+        fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+            Some(self)
+        }
+        fn builds(&self) -> bool {
+            false
+        }
+    }
+    impl DiffAwareLinter for HygieneLinter {
+        fn lints_for_files(
+            &self,
+            _working_dir: PathBuf,
+            _changed_files: &[PathBuf],
+        ) -> Result<Vec<Lint>, crate::error::Error> {
+            *self.times_called.borrow_mut() += 1;
+            Ok(Vec::new())
+        }
     }
     struct TestLinter {
         // Using a RefCell here becauselints
@@ -132,6 +861,15 @@ mod scout_tests {
             Ok(Vec::new())
         }
     }
+    #[cfg(feature = "async")]
+    impl AsyncLinter for TestLinter {
+        async fn lints_async(
+            &self,
+            working_dir: PathBuf,
+        ) -> Result<Vec<Lint>, crate::error::Error> {
+            self.lints(working_dir)
+        }
+    }
     struct TestConfig {
         members: Vec<String>,
     }
@@ -169,6 +907,7 @@ mod scout_tests {
             file_name: "foo/bar.rs".to_string(),
             line_start: 0,
             line_end: 10,
+            added_lines: Vec::new(),
         }];
         let linter = TestLinter::new();
         let vcs = TestVCS::new(diff);
@@ -191,6 +930,7 @@ mod scout_tests {
             file_name: "baz/bar.rs".to_string(),
             line_start: 0,
             line_end: 10,
+            added_lines: Vec::new(),
         }];
         let linter = TestLinter::new();
         let vcs = TestVCS::new(diff);
@@ -207,6 +947,192 @@ mod scout_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_scout_excluded_diff() -> Result<(), crate::error::Error> {
+        let diff = vec![Section {
+            file_name: "foo/generated/bar.rs".to_string(),
+            line_start: 0,
+            line_end: 10,
+            added_lines: Vec::new(),
+        }];
+        let linter = TestLinter::new();
+        let vcs = TestVCS::new(diff);
+        let config = TestConfig::new(vec!["foo".to_string()]);
+        let actual_times_called = Rc::clone(&linter.lints_times_called);
+        let mut path_filter = PathFilter::default();
+        path_filter.set_exclude(vec!["*/generated/**".to_string()]);
+        let mut scout = Scout::new(vcs, config, linter);
+        scout.set_path_filter(path_filter);
+        // The only change is excluded, so there's no need to lint the member
+        assert!(scout.run()?.is_empty());
+        assert_eq!(0, *actual_times_called.borrow());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_skips_non_rust_changes() -> Result<(), crate::error::Error> {
+        let diff = vec![Section {
+            file_name: "foo/README.md".to_string(),
+            line_start: 0,
+            line_end: 10,
+            added_lines: Vec::new(),
+        }];
+        let linter = TestLinter::new();
+        let actual_times_called = Rc::clone(&linter.lints_times_called);
+        let config = TestConfig::new(vec!["foo".to_string()]);
+        let mut scout = Scout::new(TestVCS::new(diff), config, linter);
+        assert!(scout.run()?.is_empty());
+        assert_eq!(0, *actual_times_called.borrow());
+
+        scout.set_always_run(true);
+        let _ = scout.run()?;
+        assert_eq!(1, *actual_times_called.borrow());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_skips_large_diffs() -> Result<(), crate::error::Error> {
+        let diff = vec![Section {
+            file_name: "foo/bar.rs".to_string(),
+            line_start: 1,
+            line_end: 100,
+            added_lines: Vec::new(),
+        }];
+        let linter = TestLinter::new();
+        let actual_times_called = Rc::clone(&linter.lints_times_called);
+        let hygiene = HygieneLinter::default();
+        let hygiene_times_called = Rc::clone(&hygiene.times_called);
+        let linters: Vec<Box<dyn Linter>> = vec![Box::new(linter), Box::new(hygiene)];
+        let config = TestConfig::new(vec!["foo".to_string()]);
+        let mut scout = Scout::new(TestVCS::new(diff), config, linters);
+        scout.set_limits(DiffLimits {
+            max_files: None,
+            max_lines: Some(50),
+        });
+        assert!(scout.run()?.is_empty());
+        // The linters that don't build the code still check the changes
+        assert_eq!(0, *actual_times_called.borrow());
+        assert_eq!(1, *hygiene_times_called.borrow());
+
+        // Lines 1 to 99
+        scout.set_limits(DiffLimits {
+            max_files: Some(1),
+            max_lines: Some(99),
+        });
+        let _ = scout.run()?;
+        assert_eq!(1, *actual_times_called.borrow());
+        assert_eq!(2, *hygiene_times_called.borrow());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_comment_changes() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let file = dir.path().join("lib.rs");
+        std::fs::write(&file, "// Adds the numbres\nfn add() {}\n")?;
+        let diff = vec![Section {
+            file_name: file.to_string_lossy().to_string(),
+            line_start: 1,
+            line_end: 2,
+            added_lines: Vec::new(),
+        }];
+        let mut vcs = TestVCS::new(diff);
+        vcs.original = Some("// Adds numbers\nfn add() {}\n".to_string());
+        let clippy = TestLinter::new();
+        let clippy_times_called = Rc::clone(&clippy.lints_times_called);
+        let typos = HygieneLinter::default();
+        let typos_times_called = Rc::clone(&typos.times_called);
+        let linters: Vec<Box<dyn Linter>> = vec![Box::new(clippy), Box::new(typos)];
+        let mut scout = Scout::new(vcs, TestConfig::new(vec![".".to_string()]), linters);
+        scout.set_skip_comment_changes(true);
+        let _ = scout.run()?;
+        // Only the linters that build the code are skipped
+        assert_eq!(0, *clippy_times_called.borrow());
+        assert_eq!(1, *typos_times_called.borrow());
+
+        scout.set_skip_comment_changes(false);
+        let _ = scout.run()?;
+        assert_eq!(1, *clippy_times_called.borrow());
+        assert_eq!(2, *typos_times_called.borrow());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_member_selection() -> Result<(), crate::error::Error> {
+        let diff = vec![Section {
+            file_name: "member10/bar.rs".to_string(),
+            line_start: 0,
+            line_end: 10,
+            added_lines: Vec::new(),
+        }];
+        let linter = TestLinter::new();
+        let actual_times_called = Rc::clone(&linter.lints_times_called);
+        let config = TestConfig::new(vec!["member1".to_string(), "member2".to_string()]);
+        let mut scout = Scout::new(TestVCS::new(diff.clone()), config, linter);
+        // member10 isn't in member1
+        assert!(scout.run()?.is_empty());
+        assert_eq!(0, *actual_times_called.borrow());
+
+        scout.set_whole_workspace(true);
+        let _ = scout.run()?;
+        assert_eq!(2, *actual_times_called.borrow());
+
+        let linter = TestLinter::new();
+        let actual_times_called = Rc::clone(&linter.lints_times_called);
+        let config = TestConfig::new(vec![".".to_string()]);
+        let _ = Scout::new(TestVCS::new(diff), config, linter).run()?;
+        assert_eq!(1, *actual_times_called.borrow());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_members_of() -> Result<(), crate::error::Error> {
+        let config = TestConfig::new(vec![
+            "member1".to_string(),
+            "member1/nested".to_string(),
+            "member2".to_string(),
+        ]);
+        let scout = Scout::new(TestVCS::new(Vec::new()), config, TestLinter::new());
+        let members_of = |files: &[&str]| {
+            scout.members_of(&files.iter().map(|f| (*f).to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            vec!["member1/nested".to_string(), "member2".to_string()],
+            members_of(&["member2/src/lib.rs", "member1/nested/src/lib.rs"])?
+        );
+        assert_eq!(
+            vec!["member1".to_string()],
+            members_of(&["member1/build.rs"])?
+        );
+        assert!(members_of(&["README.md"])?.is_empty());
+        assert_eq!(3, members_of(&["Cargo.lock"])?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_selected_members() -> Result<(), crate::error::Error> {
+        let diff = ["member1/bar.rs", "member2/baz.rs"]
+            .iter()
+            .map(|f| Section {
+                file_name: (*f).to_string(),
+                line_start: 0,
+                line_end: 10,
+                added_lines: Vec::new(),
+            })
+            .collect();
+        let linter = TestLinter::new();
+        let times_called = Rc::clone(&linter.lints_times_called);
+        let config = TestConfig::new(vec!["member1".to_string(), "member2".to_string()]);
+        let mut scout = Scout::new(TestVCS::new(diff), config, linter);
+        scout.set_members(Some(vec!["member2".to_string()]));
+        let _ = scout.run()?;
+        assert_eq!(1, *times_called.borrow());
+        scout.set_members(None);
+        let _ = scout.run()?;
+        assert_eq!(3, *times_called.borrow());
+        Ok(())
+    }
+
     #[test]
     fn test_scout_in_workspace() -> Result<(), crate::error::Error> {
         let diff = vec![
@@ -214,11 +1140,13 @@ mod scout_tests {
                 file_name: "member1/bar.rs".to_string(),
                 line_start: 0,
                 line_end: 10,
+                added_lines: Vec::new(),
             },
             Section {
                 file_name: "member2/baz.rs".to_string(),
                 line_start: 0,
                 line_end: 10,
+                added_lines: Vec::new(),
             },
         ];
         let linter = TestLinter::new();
@@ -241,11 +1169,483 @@ mod scout_tests {
         assert_eq!(expected_times_called, *actual_times_called.borrow());
         Ok(())
     }
+
+    #[test]
+    fn test_scout_lints_members_at_once() -> Result<(), crate::error::Error> {
+        #[derive(Default)]
+        struct MembersLinter {
+            calls: Rc<RefCell<Vec<Vec<PathBuf>>>>,
+        }
+        impl Linter for MembersLinter {
+            fn lints(&self, _working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+                Ok(Vec::new())
+            }
+            fn lints_for_members(
+                &self,
+                members: &[PathBuf],
+                _sections: Option<&[Section]>,
+            ) -> Result<Vec<Lint>, crate::error::Error> {
+                self.calls.borrow_mut().push(members.to_vec());
+                Ok(Vec::new())
+            }
+        }
+        let diff = ["member1/bar.rs", "member2/baz.rs"]
+            .iter()
+            .map(|f| Section {
+                file_name: (*f).to_string(),
+                line_start: 0,
+                line_end: 10,
+                added_lines: Vec::new(),
+            })
+            .collect();
+        let config = TestConfig::new(vec![
+            "member1".to_string(),
+            "member2".to_string(),
+            "member3".to_string(),
+        ]);
+        let linter = MembersLinter::default();
+        let calls = Rc::clone(&linter.calls);
+        let _ = Scout::new(TestVCS::new(diff), config, linter).run()?;
+        // A single call, for the two changed members
+        let calls = calls.borrow();
+        assert_eq!(1, calls.len());
+        let current_dir = std::fs::canonicalize(".")?;
+        assert_eq!(
+            vec![current_dir.join("member1"), current_dir.join("member2")],
+            calls[0]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_lints_dependents() -> Result<(), crate::error::Error> {
+        // member3 uses member2, which uses member1
+        struct ChainConfig;
+        impl Config for ChainConfig {
+            fn members(&self) -> Vec<String> {
+                vec![
+                    "member1".to_string(),
+                    "member2".to_string(),
+                    "member3".to_string(),
+                    "member4".to_string(),
+                ]
+            }
+            fn dependents(&self, member: &str) -> Vec<String> {
+                match member {
+                    "member1" => vec!["member2".to_string()],
+                    "member2" => vec!["member3".to_string()],
+                    _ => Vec::new(),
+                }
+            }
+        }
+        let diff = vec![Section {
+            file_name: "member1/lib.rs".to_string(),
+            line_start: 0,
+            line_end: 10,
+            added_lines: Vec::new(),
+        }];
+        let linter = TestLinter::new();
+        let times_called = Rc::clone(&linter.lints_times_called);
+        let mut scout = Scout::new(TestVCS::new(diff), ChainConfig, linter);
+        let _ = scout.run()?;
+        assert_eq!(1, *times_called.borrow());
+        scout.set_lint_dependents(true);
+        let _ = scout.run()?;
+        assert_eq!(1 + 3, *times_called.borrow());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_dedups_lints() -> Result<(), crate::error::Error> {
+        // Clippy reports the lint for the library, and again for its unit tests
+        struct TargetsLinter;
+        impl Linter for TargetsLinter {
+            fn lints(&self, _working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+                let lint: Lint = serde_json::from_str(
+                    r#"{"package_id": "foo", "src_path": "src/lib.rs", "message": {
+                        "message": "redundant clone", "rendered": "warning: redundant clone",
+                        "code": {"code": "clippy::redundant_clone", "explanation": null},
+                        "spans": [{"file_name": "src/lib.rs", "line_start": 3, "line_end": 3,
+                                   "column_start": 9, "column_end": 17, "is_primary": true}]}}"#,
+                )
+                .unwrap();
+                let mut test_target = lint.clone();
+                test_target.package_id = "foo#test".to_string();
+                Ok(vec![lint, test_target])
+            }
+        }
+        let diff = vec![Section {
+            file_name: "src/lib.rs".to_string(),
+            line_start: 1,
+            line_end: 5,
+            added_lines: Vec::new(),
+        }];
+        let config = TestConfig::new(vec![".".to_string()]);
+        let scout = Scout::new(TestVCS::new(diff), config, TargetsLinter);
+        assert_eq!(1, scout.run()?.len());
+        assert_eq!(1, scout.lints()?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_nested_members() -> Result<(), crate::error::Error> {
+        // The root package has a member in crates/foo, and member2 uses vendor/bar
+        struct NestedConfig;
+        impl Config for NestedConfig {
+            fn members(&self) -> Vec<String> {
+                vec![
+                    ".".to_string(),
+                    "crates/foo".to_string(),
+                    "member2".to_string(),
+                ]
+            }
+            fn users_of(&self, file: &std::path::Path) -> Vec<String> {
+                if file.starts_with("vendor/bar") {
+                    vec!["member2".to_string()]
+                } else {
+                    Vec::new()
+                }
+            }
+        }
+        let section = |file_name: &str| Section {
+            file_name: file_name.to_string(),
+            line_start: 0,
+            line_end: 10,
+            added_lines: Vec::new(),
+        };
+        let scout = Scout::new(
+            TestVCS::new(vec![section("crates/foo/src/lib.rs")]),
+            NestedConfig,
+            TestLinter::new(),
+        );
+        let plan = scout.plan()?.unwrap();
+        assert_eq!(vec!["crates/foo"], plan.members);
+        let scout = Scout::new(
+            TestVCS::new(vec![section("vendor/bar/src/lib.rs")]),
+            NestedConfig,
+            TestLinter::new(),
+        );
+        let plan = scout.plan()?.unwrap();
+        assert_eq!(vec!["member2"], plan.members);
+        assert_eq!(vec!["member2"], plan.dependents);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_lints_all_members() -> Result<(), crate::error::Error> {
+        let linter = TestLinter::new();
+        // No diff, we still want every member to be linted
+        let vcs = TestVCS::new(Vec::new());
+        let config = TestConfig::new(vec!["member1".to_string(), "member2".to_string()]);
+        let expected_times_called = 2;
+        let actual_times_called = Rc::clone(&linter.lints_times_called);
+        let scout = Scout::new(vcs, config, linter);
+        let _ = scout.lints()?;
+
+        assert_eq!(expected_times_called, *actual_times_called.borrow());
+        assert!(!*scout.vcs.sections_called.borrow());
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct TestProgress {
+        stages: RefCell<Vec<Stage>>,
+    }
+    impl Progress for TestProgress {
+        fn finished(&self, stage: &Stage, _elapsed: std::time::Duration) {
+            self.stages.borrow_mut().push(stage.clone());
+        }
+    }
+
+    #[test]
+    fn test_scout_runs_several_linters() -> Result<(), crate::error::Error> {
+        let diff = vec![Section {
+            file_name: "foo/bar.rs".to_string(),
+            line_start: 0,
+            line_end: 10,
+            added_lines: Vec::new(),
+        }];
+        let (first, second) = (TestLinter::new(), TestLinter::new());
+        let times_called = vec![
+            Rc::clone(&first.lints_times_called),
+            Rc::clone(&second.lints_times_called),
+        ];
+        let linters: Vec<Box<dyn Linter>> = vec![Box::new(first), Box::new(second)];
+        let config = TestConfig::new(vec!["foo".to_string()]);
+        let _ = Scout::new(TestVCS::new(diff), config, linters).run()?;
+        for times_called in times_called {
+            assert_eq!(1, *times_called.borrow());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_gives_changed_files() -> Result<(), crate::error::Error> {
+        #[derive(Default)]
+        struct FilesLinter {
+            changed_files: RefCell<Vec<PathBuf>>,
+        }
+        impl Linter for FilesLinter {
+            fn lints(&self, _working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+                Ok(Vec::new())
+            }
+            fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+                Some(self)
+            }
+        }
+        impl DiffAwareLinter for FilesLinter {
+            fn lints_for_files(
+                &self,
+                _working_dir: PathBuf,
+                changed_files: &[PathBuf],
+            ) -> Result<Vec<Lint>, crate::error::Error> {
+                self.changed_files.replace(changed_files.to_vec());
+                Ok(Vec::new())
+            }
+        }
+        let section = |file_name: &str, line_start| Section {
+            file_name: file_name.to_string(),
+            line_start,
+            line_end: line_start + 2,
+            added_lines: Vec::new(),
+        };
+        let diff = vec![
+            section("foo/lib.rs", 1),
+            section("foo/main.rs", 1),
+            section("foo/lib.rs", 20),
+        ];
+        // Through a Vec, as the binary runs them
+        let linters = vec![FilesLinter::default()];
+        let scout = Scout::new(
+            TestVCS::new(diff),
+            TestConfig::new(vec![".".to_string()]),
+            linters,
+        );
+        let _ = scout.run()?;
+        // Without a PathNormalizer, they stay relative to the repository root
+        assert_eq!(
+            vec![PathBuf::from("foo/lib.rs"), PathBuf::from("foo/main.rs")],
+            *scout.linter[0].changed_files.borrow()
+        );
+        // Without a diff, there are no changed files to give
+        scout.linter[0].changed_files.replace(Vec::new());
+        let _ = scout.lints()?;
+        assert!(scout.linter[0].changed_files.borrow().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_lockfile_changes() -> Result<(), crate::error::Error> {
+        // Reports a vulnerable dependency on its Cargo.lock lines, as CargoAudit does
+        struct AuditLinter {
+            times_called: RefCell<usize>,
+        }
+        impl Linter for AuditLinter {
+            fn lints(&self, _working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+                *self.times_called.borrow_mut() += 1;
+                Ok(vec![fixtures::lint(
+                    "RUSTSEC-2020-0071",
+                    Level::Error,
+                    "error[RUSTSEC-2020-0071]: Potential segfault in the time crate",
+                    vec![span("Cargo.lock", 12, 13)],
+                )])
+            }
+        }
+        let diff = vec![Section {
+            file_name: "Cargo.lock".to_string(),
+            line_start: 12,
+            line_end: 14,
+            added_lines: Vec::new(),
+        }];
+        let linter = AuditLinter {
+            times_called: RefCell::new(0),
+        };
+        let config = TestConfig::new(vec!["member1".to_string(), "member2".to_string()]);
+        let scout = Scout::new(TestVCS::new(diff), config, linter);
+        let lints = scout.run()?;
+        // The workspace lockfile belongs to every member, the finding is reported once
+        assert_eq!(2, *scout.linter.times_called.borrow());
+        assert_eq!(1, lints.len());
+        assert_eq!(Some("RUSTSEC-2020-0071"), lints[0].lint.code());
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_scout_run_async() -> Result<(), crate::error::Error> {
+        let diff = vec![Section {
+            file_name: "foo/bar.rs".to_string(),
+            line_start: 0,
+            line_end: 10,
+            added_lines: Vec::new(),
+        }];
+        let linter = TestLinter::new();
+        let times_called = Rc::clone(&linter.lints_times_called);
+        let config = TestConfig::new(vec!["foo".to_string(), "baz".to_string()]);
+        let scout = Scout::new(TestVCS::new(diff), config, linter);
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        assert!(runtime.block_on(scout.run_async())?.is_empty());
+        // Only the changed member is linted, as with run
+        assert_eq!(1, *times_called.borrow());
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_scout_run_async_diff_aware() -> Result<(), crate::error::Error> {
+        // Finds more when it doesn't know the changes
+        struct SectionsLinter;
+        impl Linter for SectionsLinter {
+            fn lints(&self, _working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+                Ok(vec![fixtures::lint(
+                    "whole_file",
+                    Level::Warning,
+                    "",
+                    vec![span("foo/bar.rs", 2, 2)],
+                )])
+            }
+            fn diff_aware(&self) -> Option<&dyn DiffAwareLinter> {
+                Some(self)
+            }
+        }
+        impl DiffAwareLinter for SectionsLinter {
+            fn lints_for_files(
+                &self,
+                _working_dir: PathBuf,
+                _changed_files: &[PathBuf],
+            ) -> Result<Vec<Lint>, crate::error::Error> {
+                Ok(vec![fixtures::lint(
+                    "changed_lines",
+                    Level::Warning,
+                    "",
+                    vec![span("foo/bar.rs", 3, 3)],
+                )])
+            }
+        }
+        impl AsyncLinter for SectionsLinter {
+            async fn lints_async(
+                &self,
+                working_dir: PathBuf,
+            ) -> Result<Vec<Lint>, crate::error::Error> {
+                self.lints(working_dir)
+            }
+        }
+        let diff = vec![Section {
+            file_name: "foo/bar.rs".to_string(),
+            line_start: 0,
+            line_end: 10,
+            added_lines: Vec::new(),
+        }];
+        let config = TestConfig::new(vec!["foo".to_string()]);
+        let scout = Scout::new(TestVCS::new(diff), config, SectionsLinter);
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let lints = runtime.block_on(scout.run_async())?;
+        assert_eq!(scout.run()?, lints);
+        assert_eq!(1, lints.len());
+        assert_eq!(
+            "changed_lines",
+            lints[0]
+                .lint
+                .message
+                .as_ref()
+                .unwrap()
+                .code
+                .as_ref()
+                .unwrap()
+                .code
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_compile_errors() {
+        struct BrokenLinter;
+        impl Linter for BrokenLinter {
+            fn lints(&self, _working_dir: PathBuf) -> Result<Vec<Lint>, crate::error::Error> {
+                let error = |line| Lint {
+                    package_id: "foo".to_string(),
+                    src_path: None,
+                    reproducer: None,
+                    message: Some(crate::linter::Message {
+                        spans: vec![span("foo/bar.rs", line, line)],
+                        ..crate::linter::Message::default()
+                    }),
+                };
+                Err(crate::error::Error::Compilation {
+                    errors: vec![error(3), error(30)],
+                })
+            }
+        }
+        let run = |line_start, line_end| {
+            let diff = vec![Section {
+                file_name: "foo/bar.rs".to_string(),
+                line_start,
+                line_end,
+                added_lines: Vec::new(),
+            }];
+            let config = TestConfig::new(vec!["foo".to_string()]);
+            Scout::new(TestVCS::new(diff), config, BrokenLinter).run()
+        };
+        assert!(matches!(
+            run(0, 10),
+            Err(crate::error::Error::CompilationInDiff { ref errors }) if errors.len() == 1
+        ));
+        assert!(matches!(
+            run(10, 20),
+            Err(crate::error::Error::Compilation { ref errors }) if errors.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_scout_cancellation() {
+        let diff = vec![Section {
+            file_name: "foo/bar.rs".to_string(),
+            line_start: 0,
+            line_end: 10,
+            added_lines: Vec::new(),
+        }];
+        let linter = TestLinter::new();
+        let times_called = Rc::clone(&linter.lints_times_called);
+        let config = TestConfig::new(vec!["foo".to_string()]);
+        let cancellation = CancellationToken::new();
+        let mut scout = Scout::new(TestVCS::new(diff), config, linter);
+        scout.set_cancellation(cancellation.clone());
+        cancellation.cancel();
+        assert!(matches!(scout.run(), Err(crate::error::Error::Cancelled)));
+        assert_eq!(0, *times_called.borrow());
+    }
+
+    #[test]
+    fn test_scout_reports_progress() -> Result<(), crate::error::Error> {
+        let diff = vec![Section {
+            file_name: "foo/bar.rs".to_string(),
+            line_start: 0,
+            line_end: 10,
+            added_lines: Vec::new(),
+        }];
+        let config = TestConfig::new(vec!["foo".to_string(), "baz".to_string()]);
+        let progress = Rc::new(TestProgress::default());
+        let mut scout = Scout::new(TestVCS::new(diff), config, TestLinter::new());
+        scout.set_progress(Box::new(Rc::clone(&progress)));
+        let _ = scout.run()?;
+
+        let expected_stages = vec![
+            Stage::Diffing,
+            Stage::Linting {
+                member: "foo".to_string(),
+                index: 1,
+                total: 1,
+            },
+            Stage::Intersecting,
+        ];
+        assert_eq!(expected_stages, *progress.stages.borrow());
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod intersections_tests {
-    use crate::linter::Span;
+    use crate::linter::fixtures::span;
     use crate::vcs::Section;
 
     type TestSection = (&'static str, u32, u32);
@@ -299,24 +1699,165 @@ mod intersections_tests {
         assert_all_not_in_range(ranges_to_test);
     }
 
+    #[test]
+    fn test_in_member() {
+        use crate::scout::in_member;
+        let section = Section {
+            file_name: "crates/foo/src/lib.rs".to_string(),
+            line_start: 1,
+            line_end: 2,
+            added_lines: Vec::new(),
+        };
+        assert!(in_member(".", &section));
+        // The repository root, relative to itself
+        assert!(in_member("", &section));
+        assert!(in_member("crates/foo", &section));
+        assert!(in_member("./crates/foo/", &section));
+        // Members listed with Windows separators
+        assert!(in_member("crates\\foo", &section));
+        assert!(!in_member("crates/fo", &section));
+    }
+
+    #[test]
+    fn test_lints_from_diff_tracks_sections() {
+        use crate::linter::{Lint, Message};
+        use crate::scout::{lints_from_diff, TrackedLint};
+        // The lint spans both changes
+        let lint = Lint {
+            package_id: "foo".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                spans: vec![span("foo.rs", 1, 10)],
+                ..Message::default()
+            }),
+        };
+        let sections = vec![
+            Section {
+                file_name: "foo.rs".to_string(),
+                line_start: 2,
+                line_end: 3,
+                added_lines: Vec::new(),
+            },
+            Section {
+                file_name: "foo.rs".to_string(),
+                line_start: 8,
+                line_end: 8,
+                added_lines: Vec::new(),
+            },
+            Section {
+                file_name: "bar.rs".to_string(),
+                line_start: 2,
+                line_end: 3,
+                added_lines: Vec::new(),
+            },
+        ];
+        let tracked = lints_from_diff(std::slice::from_ref(&lint), &sections, false);
+        assert_eq!(
+            vec![TrackedLint {
+                lint,
+                sections: sections[..2].to_vec(),
+                introduced_by: None,
+                origin: None,
+            }],
+            tracked
+        );
+        // Tracked lints can be persisted and read back
+        let json = serde_json::to_string(&tracked).unwrap();
+        assert_eq!(
+            tracked,
+            serde_json::from_str::<Vec<TrackedLint>>(&json).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_lints_from_diff_in_macro() {
+        use crate::linter::Lint;
+        use crate::scout::lints_from_diff;
+        // The lint fires in the definition of a macro, invoked in main.rs
+        let lint: Lint = serde_json::from_str(
+            r#"{"package_id": "foo", "src_path": null, "message": {"rendered": "", "spans": [{
+                "file_name": "src/macros.rs", "line_start": 3, "line_end": 3,
+                "expansion": {"macro_decl_name": "square!", "def_site_span": null, "span": {
+                    "file_name": "src/main.rs", "line_start": 10, "line_end": 10}}}]}}"#,
+        )
+        .unwrap();
+        let section = |file_name: &str, line: u32| Section {
+            file_name: file_name.to_string(),
+            line_start: line,
+            line_end: line,
+            added_lines: Vec::new(),
+        };
+        let lints = std::slice::from_ref(&lint);
+        assert_eq!(
+            1,
+            lints_from_diff(lints, &[section("src/main.rs", 10)], false).len()
+        );
+        assert!(lints_from_diff(lints, &[section("src/macros.rs", 3)], false).is_empty());
+        assert_eq!(
+            1,
+            lints_from_diff(lints, &[section("src/macros.rs", 3)], true).len()
+        );
+    }
+
+    #[test]
+    fn test_lints_from_diff_primary_spans() {
+        use crate::linter::Lint;
+        use crate::scout::lints_from_diff;
+        // The value is moved on line 2 and borrowed on line 5, the error is about the borrow
+        let lint: Lint = serde_json::from_str(
+            r#"{"package_id": "foo", "src_path": null, "message": {"rendered": "", "spans": [
+                {"file_name": "src/lib.rs", "line_start": 2, "line_end": 2,
+                 "is_primary": false, "label": "value moved here"},
+                {"file_name": "src/lib.rs", "line_start": 5, "line_end": 5,
+                 "is_primary": true, "label": "value borrowed here after move"}]}}"#,
+        )
+        .unwrap();
+        let section = |line: u32| Section {
+            file_name: "src/lib.rs".to_string(),
+            line_start: line,
+            line_end: line,
+            added_lines: Vec::new(),
+        };
+        let lints = std::slice::from_ref(&lint);
+        assert!(lints_from_diff(lints, &[section(2)], false).is_empty());
+        assert_eq!(1, lints_from_diff(lints, &[section(5)], false).len());
+        assert_eq!(Some(5), lint.primary_span().map(|s| s.line_start));
+    }
+
+    #[test]
+    fn test_lines_in_expanded_range() {
+        use crate::scout::lines_in_range;
+        // The lint fires on the function header, the change is in its body
+        let clippy_lint = span("foo.rs", 1, 1);
+        let git_section = Section {
+            file_name: "foo.rs".to_string(),
+            line_start: 5,
+            line_end: 6,
+            added_lines: Vec::new(),
+        };
+        assert!(!lines_in_range(&clippy_lint, &git_section.expand(0)));
+        assert!(!lines_in_range(&clippy_lint, &git_section.expand(3)));
+        assert!(lines_in_range(&clippy_lint, &git_section.expand(4)));
+    }
+
     fn assert_all_files_match(ranges: Vec<(TestSection, TestSection)>) {
         use crate::scout::files_match;
         for range in ranges {
             let lint = range.0;
             let section = range.1;
-            let clippy_lint = Span {
-                file_name: String::from(lint.0),
-                line_start: lint.1,
-                line_end: lint.2,
-            };
+            let clippy_lint = span(lint.0, lint.1, lint.2);
             let git_section = Section {
                 file_name: String::from(section.0),
                 line_start: section.1,
                 line_end: section.2,
+                added_lines: Vec::new(),
             };
             assert!(
                 files_match(&clippy_lint, &git_section),
-                print!("Expected files match for {} and {}", lint.0, section.0)
+                "Expected files match for {} and {}",
+                lint.0,
+                section.0
             );
         }
     }
@@ -326,19 +1867,18 @@ mod intersections_tests {
         for range in ranges {
             let lint = range.0;
             let section = range.1;
-            let clippy_lint = Span {
-                file_name: String::from(lint.0),
-                line_start: lint.1,
-                line_end: lint.2,
-            };
+            let clippy_lint = span(lint.0, lint.1, lint.2);
             let git_section = Section {
                 file_name: String::from(section.0),
                 line_start: section.1,
                 line_end: section.2,
+                added_lines: Vec::new(),
             };
             assert!(
                 !files_match(&clippy_lint, &git_section),
-                print!("Expected files match for {} and {}", lint.0, section.0)
+                "Expected files match for {} and {}",
+                lint.0,
+                section.0
             );
         }
     }
@@ -349,10 +1889,9 @@ mod intersections_tests {
             let section = range.1;
             assert!(
                 in_range(lint, section),
-                print!(
-                    "Expected in range, found not in range for \n {:#?} and {:#?}",
-                    lint, section
-                )
+                "Expected in range, found not in range for \n {:#?} and {:#?}",
+                lint,
+                section
             );
         }
     }
@@ -363,26 +1902,22 @@ mod intersections_tests {
             let section = range.1;
             assert!(
                 !in_range(lint, section),
-                print!(
-                    "Expected not in range, found in range for \n {:#?} and {:#?}",
-                    lint, section
-                )
+                "Expected not in range, found in range for \n {:#?} and {:#?}",
+                lint,
+                section
             );
         }
     }
 
     fn in_range(lint: (&str, u32, u32), section: (&str, u32, u32)) -> bool {
         use crate::scout::lines_in_range;
-        let clippy_lint = Span {
-            file_name: String::from(lint.0),
-            line_start: lint.1,
-            line_end: lint.2,
-        };
+        let clippy_lint = span(lint.0, lint.1, lint.2);
 
         let git_section = Section {
             file_name: String::from(section.0),
             line_start: section.1,
             line_end: section.2,
+            added_lines: Vec::new(),
         };
         lines_in_range(&clippy_lint, &git_section)
     }
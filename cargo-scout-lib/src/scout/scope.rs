@@ -0,0 +1,248 @@
+use crate::vcs::Section;
+use std::convert::TryFrom;
+use std::path::Path;
+use std::str::FromStr;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+/// What a change is attributed to when looking for the lints it caused
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum Scope {
+    /// Only the changed lines
+    #[default]
+    Line,
+    /// The whole functions the changed lines belong to
+    Function,
+    /// The whole items the changed lines belong to, such as functions,
+    /// structs, or the methods and constants of an impl block
+    Item,
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "line" => Ok(Scope::Line),
+            "function" => Ok(Scope::Function),
+            "item" => Ok(Scope::Item),
+            _ => Err(format!(
+                "unknown scope {s}, expected one of line, function, item"
+            )),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Ranges {
+    ranges: Vec<(u32, u32)>,
+}
+
+impl Ranges {
+    fn push(&mut self, span: proc_macro2::Span) {
+        self.ranges
+            .push((to_u32(span.start().line), to_u32(span.end().line)));
+    }
+}
+
+fn to_u32(line: usize) -> u32 {
+    u32::try_from(line).unwrap_or(u32::MAX)
+}
+
+#[derive(Default)]
+struct FunctionRanges(Ranges);
+
+#[derive(Default)]
+struct ItemRanges(Ranges);
+
+impl<'ast> Visit<'ast> for FunctionRanges {
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        self.0.push(item.span());
+        visit::visit_item_fn(self, item);
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast syn::ImplItemFn) {
+        self.0.push(item.span());
+        visit::visit_impl_item_fn(self, item);
+    }
+
+    fn visit_trait_item_fn(&mut self, item: &'ast syn::TraitItemFn) {
+        self.0.push(item.span());
+        visit::visit_trait_item_fn(self, item);
+    }
+}
+
+// Modules, impl and trait blocks are too wide to be useful,
+// so their own items are recorded instead
+impl<'ast> Visit<'ast> for ItemRanges {
+    fn visit_item(&mut self, item: &'ast syn::Item) {
+        match item {
+            syn::Item::Mod(_) | syn::Item::Impl(_) | syn::Item::Trait(_) => {
+                visit::visit_item(self, item);
+            }
+            _ => self.0.push(item.span()),
+        }
+    }
+
+    fn visit_impl_item(&mut self, item: &'ast syn::ImplItem) {
+        self.0.push(item.span());
+    }
+
+    fn visit_trait_item(&mut self, item: &'ast syn::TraitItem) {
+        self.0.push(item.span());
+    }
+}
+
+/// Returns the first and last lines of every function in a Rust source file,
+/// including methods and nested functions.
+///
+/// Files that can't be parsed have no functions.
+///
+/// # Example
+/// ```
+/// # use cargo_scout_lib::scout::scope::function_ranges;
+/// let source = "fn foo() {\n    bar();\n}\n";
+/// assert_eq!(vec![(1, 3)], function_ranges(source));
+/// ```
+#[must_use]
+pub fn function_ranges(source: &str) -> Vec<(u32, u32)> {
+    let mut ranges = FunctionRanges::default();
+    if let Ok(file) = syn::parse_file(source) {
+        ranges.visit_file(&file);
+    }
+    ranges.0.ranges
+}
+
+/// Returns the first and last lines of every item in a Rust source file.
+///
+/// The items of modules, impl and trait blocks are returned
+/// instead of the blocks themselves.
+/// Files that can't be parsed have no items.
+///
+/// # Example
+/// ```
+/// # use cargo_scout_lib::scout::scope::item_ranges;
+/// let source = "struct Foo;\n\nimpl Foo {\n    const BAR: u32 = 1;\n}\n";
+/// assert_eq!(vec![(1, 1), (4, 4)], item_ranges(source));
+/// ```
+#[must_use]
+pub fn item_ranges(source: &str) -> Vec<(u32, u32)> {
+    let mut ranges = ItemRanges::default();
+    if let Ok(file) = syn::parse_file(source) {
+        ranges.visit_file(&file);
+    }
+    ranges.0.ranges
+}
+
+/// Widens every section to the functions or items it overlaps with, depending on `scope`.
+///
+/// `source` returns the contents of a file, sections in files it can't read are kept as is.
+pub fn expand_to_scope(
+    sections: Vec<Section>,
+    scope: Scope,
+    source: impl Fn(&str) -> Option<String>,
+) -> Vec<Section> {
+    let ranges: fn(&str) -> Vec<(u32, u32)> = match scope {
+        Scope::Line => return sections,
+        Scope::Function => function_ranges,
+        Scope::Item => item_ranges,
+    };
+    sections
+        .into_iter()
+        .map(|mut section| {
+            if Path::new(&section.file_name)
+                .extension()
+                .is_none_or(|e| e != "rs")
+            {
+                return section;
+            }
+            let ranges = source(&section.file_name)
+                .map(|s| ranges(&s))
+                .unwrap_or_default();
+            let (line_start, line_end) = (section.line_start, section.line_end);
+            for (start, end) in ranges {
+                if start <= line_end && line_start <= end {
+                    section.line_start = section.line_start.min(start);
+                    section.line_end = section.line_end.max(end);
+                }
+            }
+            section
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "/// Foo
+fn foo() {
+    let x = 1;
+}
+
+struct Bar;
+
+impl Bar {
+    fn bar(&self) {
+        println!();
+    }
+}
+";
+
+    fn section(line_start: u32, line_end: u32) -> Section {
+        Section {
+            file_name: "src/lib.rs".to_string(),
+            line_start,
+            line_end,
+            added_lines: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_function_ranges() {
+        assert_eq!(vec![(1, 4), (9, 11)], function_ranges(SOURCE));
+        assert!(function_ranges("fn {").is_empty());
+    }
+
+    #[test]
+    fn test_item_ranges() {
+        assert_eq!(vec![(1, 4), (6, 6), (9, 11)], item_ranges(SOURCE));
+        assert_eq!(
+            vec![(2, 2), (3, 3)],
+            item_ranges("mod foo {\n    struct Bar;\n    fn baz() {}\n}\n")
+        );
+    }
+
+    #[test]
+    fn test_expand_to_functions() {
+        let sections = vec![section(3, 3), section(6, 6), section(10, 10)];
+        let expected = vec![section(1, 4), section(6, 6), section(9, 11)];
+        assert_eq!(
+            expected,
+            expand_to_scope(sections, Scope::Function, |_| Some(SOURCE.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_expand_to_items() {
+        let source = |_: &str| Some(SOURCE.to_string());
+        let sections = vec![section(2, 6), section(10, 10)];
+        assert_eq!(
+            sections,
+            expand_to_scope(sections.clone(), Scope::Line, source)
+        );
+        // The method is expanded, not the impl block it belongs to
+        assert_eq!(
+            vec![section(1, 6), section(9, 11)],
+            expand_to_scope(sections, Scope::Item, source)
+        );
+    }
+
+    #[test]
+    fn test_scope() {
+        assert_eq!(Ok(Scope::Line), "line".parse());
+        assert_eq!(Ok(Scope::Function), "function".parse());
+        assert_eq!(Ok(Scope::Item), "item".parse());
+        assert!("crate".parse::<Scope>().is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::output::escape_xml;
+use crate::stats::trend::TrendRecord;
+use serde_json::json;
+
+// The average width of a character of Verdana 11px, which badges are rendered with
+const CHAR_WIDTH: f64 = 6.5;
+const PADDING: f64 = 10.0;
+
+/// A shields.io-like badge telling how many findings the project has
+#[derive(Debug, PartialEq, Clone)]
+pub struct Badge {
+    pub label: String,
+    pub message: String,
+    color: Color,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Color {
+    Green,
+    Yellow,
+    Orange,
+    Grey,
+}
+
+impl Color {
+    // The names shields.io knows, and their colors
+    fn name(self) -> &'static str {
+        match self {
+            Color::Green => "brightgreen",
+            Color::Yellow => "yellow",
+            Color::Orange => "orange",
+            Color::Grey => "lightgrey",
+        }
+    }
+
+    fn hex(self) -> &'static str {
+        match self {
+            Color::Green => "#4c1",
+            Color::Yellow => "#dfb317",
+            Color::Orange => "#fe7d37",
+            Color::Grey => "#9f9f9f",
+        }
+    }
+}
+
+impl Badge {
+    /// The badge of the latest record, `unknown` if nothing was recorded.
+    #[must_use]
+    pub fn from_record(record: Option<&TrendRecord>) -> Self {
+        let (message, color) = match record.map(|r| r.total) {
+            None => ("unknown".to_string(), Color::Grey),
+            Some(0) => ("clean".to_string(), Color::Green),
+            Some(1) => ("1 warning".to_string(), Color::Yellow),
+            Some(total) if total < 10 => (format!("{total} warnings"), Color::Yellow),
+            Some(total) => (format!("{total} warnings"), Color::Orange),
+        };
+        Self {
+            label: "scout".to_string(),
+            message,
+            color,
+        }
+    }
+
+    /// Renders the badge as an SVG image, in the flat style of shields.io.
+    #[must_use]
+    pub fn svg(&self) -> String {
+        // The texts are a few characters long
+        #[allow(clippy::cast_precision_loss)]
+        let width = |text: &str| (text.chars().count() as f64 * CHAR_WIDTH + PADDING).round();
+        let (label_width, message_width) = (width(&self.label), width(&self.message));
+        let total_width = label_width + message_width;
+        let (label, message) = (escape_xml(&self.label), escape_xml(&self.message));
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{total}" height="20" role="img" aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+  <clipPath id="r"><rect width="{total}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+    <rect width="{total}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##,
+            total = total_width,
+            label = label,
+            message = message,
+            label_width = label_width,
+            message_width = message_width,
+            color = self.color.hex(),
+            label_x = label_width / 2.0,
+            message_x = label_width + message_width / 2.0,
+        )
+    }
+
+    /// Renders the badge as the JSON a shields.io endpoint badge reads.
+    #[must_use]
+    pub fn endpoint(&self) -> String {
+        json!({
+            "schemaVersion": 1,
+            "label": self.label,
+            "message": self.message,
+            "color": self.color.name(),
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn record(total: usize) -> TrendRecord {
+        TrendRecord {
+            commit: "a1".to_string(),
+            timestamp: 0,
+            total,
+            by_code: BTreeMap::new(),
+            by_crate: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_messages() {
+        let message = |record: Option<&TrendRecord>| Badge::from_record(record).message;
+        assert_eq!("unknown", message(None));
+        assert_eq!("clean", message(Some(&record(0))));
+        assert_eq!("1 warning", message(Some(&record(1))));
+        assert_eq!("12 warnings", message(Some(&record(12))));
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = Badge::from_record(Some(&record(12))).svg();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"125\""));
+        assert!(svg.contains("<title>scout: 12 warnings</title>"));
+        assert!(svg.contains("fill=\"#fe7d37\""));
+    }
+
+    #[test]
+    fn test_endpoint() {
+        let endpoint: serde_json::Value =
+            serde_json::from_str(&Badge::from_record(Some(&record(0))).endpoint()).unwrap();
+        assert_eq!(
+            json!({
+                "schemaVersion": 1,
+                "label": "scout",
+                "message": "clean",
+                "color": "brightgreen",
+            }),
+            endpoint
+        );
+    }
+}
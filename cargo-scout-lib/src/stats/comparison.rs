@@ -0,0 +1,107 @@
+use crate::linter::Lint;
+use crate::stats::UNKNOWN_CODE;
+use std::collections::BTreeSet;
+
+/// How the findings of a project differ between two runs,
+/// such as before and after a toolchain upgrade.
+///
+/// Findings are matched by fingerprint, so they're compared
+/// regardless of the lines they are on.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Comparison {
+    /// Findings only raised by the second run
+    pub added: Vec<Lint>,
+    /// Findings only raised by the first run
+    pub removed: Vec<Lint>,
+    /// Lint codes only raised by the second run
+    pub new_codes: BTreeSet<String>,
+    /// Lint codes only raised by the first run
+    pub gone_codes: BTreeSet<String>,
+}
+
+impl Comparison {
+    #[must_use]
+    pub fn new(before: &[Lint], after: &[Lint]) -> Self {
+        let fingerprints =
+            |lints: &[Lint]| -> BTreeSet<String> { lints.iter().map(Lint::fingerprint).collect() };
+        let codes = |lints: &[Lint]| -> BTreeSet<String> {
+            lints
+                .iter()
+                .map(|l| l.code().unwrap_or(UNKNOWN_CODE).to_string())
+                .collect()
+        };
+        let (before_fingerprints, after_fingerprints) = (fingerprints(before), fingerprints(after));
+        let (before_codes, after_codes) = (codes(before), codes(after));
+        Self {
+            added: after
+                .iter()
+                .filter(|l| !before_fingerprints.contains(&l.fingerprint()))
+                .cloned()
+                .collect(),
+            removed: before
+                .iter()
+                .filter(|l| !after_fingerprints.contains(&l.fingerprint()))
+                .cloned()
+                .collect(),
+            new_codes: after_codes.difference(&before_codes).cloned().collect(),
+            gone_codes: before_codes.difference(&after_codes).cloned().collect(),
+        }
+    }
+
+    /// Returns true if both runs raised the same findings.
+    #[must_use]
+    pub fn is_unchanged(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::span;
+    use crate::linter::{Code, Message};
+
+    fn lint(code: &str, file_name: &str, line: u32) -> Lint {
+        Lint {
+            package_id: "foo".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                rendered: format!("warning: {}", code),
+                code: Some(Code {
+                    code: code.to_string(),
+                    explanation: None,
+                }),
+                spans: vec![span(file_name, line, line)],
+                ..Message::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_comparison() {
+        let before = vec![
+            lint("clippy::doc_markdown", "src/lib.rs", 1),
+            lint("clippy::redundant_clone", "src/lib.rs", 5),
+        ];
+        let after = vec![
+            // Moved, but the same finding
+            lint("clippy::doc_markdown", "src/lib.rs", 2),
+            lint("clippy::doc_markdown", "src/main.rs", 1),
+            lint("clippy::manual_let_else", "src/main.rs", 3),
+        ];
+        let comparison = Comparison::new(&before, &after);
+        assert_eq!(after[1..].to_vec(), comparison.added);
+        assert_eq!(before[1..].to_vec(), comparison.removed);
+        assert_eq!(
+            vec!["clippy::manual_let_else"],
+            comparison.new_codes.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["clippy::redundant_clone"],
+            comparison.gone_codes.iter().collect::<Vec<_>>()
+        );
+        assert!(!comparison.is_unchanged());
+        assert!(Comparison::new(&before, &before).is_unchanged());
+    }
+}
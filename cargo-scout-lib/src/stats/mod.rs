@@ -0,0 +1,127 @@
+use crate::linter::Lint;
+use crate::paths::crate_name;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub mod badge;
+pub mod comparison;
+pub mod trend;
+
+/// The code used for lints that don't report one
+pub const UNKNOWN_CODE: &str = "<unknown>";
+
+/// Aggregated statistics over a set of lints
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Statistics {
+    /// Statistics per lint code, the most frequent first
+    pub by_code: Vec<CodeStatistics>,
+    /// The amount of lints per crate
+    pub by_crate: BTreeMap<String, usize>,
+    /// The total amount of lints
+    pub total: usize,
+}
+
+/// Statistics for a single lint code
+#[derive(Debug, PartialEq, Clone)]
+pub struct CodeStatistics {
+    /// The lint code
+    /// Example:
+    /// `"clippy::doc_markdown"`
+    pub code: String,
+    /// How many times the lint has been raised
+    pub count: usize,
+    /// The files the lint has been raised in
+    pub files: BTreeSet<String>,
+}
+
+impl Statistics {
+    #[must_use]
+    pub fn from_lints(lints: &[Lint]) -> Self {
+        let mut by_code: BTreeMap<&str, CodeStatistics> = BTreeMap::new();
+        let mut by_crate = BTreeMap::new();
+        for lint in lints {
+            let code = lint.code().unwrap_or(UNKNOWN_CODE);
+            let stats = by_code.entry(code).or_insert_with(|| CodeStatistics {
+                code: code.to_string(),
+                count: 0,
+                files: BTreeSet::new(),
+            });
+            stats.count += 1;
+            if let Some(span) = lint.primary_span() {
+                stats.files.insert(span.file_name.replace('\\', "/"));
+            }
+            if let Some(name) = crate_name(&lint.package_id) {
+                *by_crate.entry(name.to_string()).or_insert(0) += 1;
+            }
+        }
+        let mut by_code: Vec<CodeStatistics> = by_code.into_values().collect();
+        // Sort is stable, codes with the same count stay in alphabetical order
+        by_code.sort_by_key(|s| std::cmp::Reverse(s.count));
+        Self {
+            by_code,
+            by_crate,
+            total: lints.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linter::fixtures::span;
+    use crate::linter::{Code, Message};
+
+    fn lint(package_id: &str, code: Option<&str>, file_name: &str) -> Lint {
+        Lint {
+            package_id: package_id.to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "this is a test lint".to_string(),
+                code: code.map(|c| Code {
+                    code: c.to_string(),
+                    explanation: None,
+                }),
+                level: None,
+                children: Vec::new(),
+                spans: vec![span(file_name, 1, 1)],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(Statistics::default(), Statistics::from_lints(&[]));
+    }
+
+    #[test]
+    fn test_statistics() {
+        let lints = vec![
+            lint("foo 0.1.0", Some("clippy::doc_markdown"), "foo/src/lib.rs"),
+            lint("foo 0.1.0", Some("clippy::doc_markdown"), "foo/src/lib.rs"),
+            lint("bar 0.1.0", Some("clippy::doc_markdown"), "bar/src/lib.rs"),
+            lint(
+                "bar 0.1.0",
+                Some("clippy::cast_lossless"),
+                "bar/src/main.rs",
+            ),
+            lint("bar 0.1.0", None, "bar/src/main.rs"),
+        ];
+        let stats = Statistics::from_lints(&lints);
+        assert_eq!(5, stats.total);
+        assert_eq!(
+            vec![
+                ("clippy::doc_markdown", 3, 2),
+                ("<unknown>", 1, 1),
+                ("clippy::cast_lossless", 1, 1)
+            ],
+            stats
+                .by_code
+                .iter()
+                .map(|s| (s.code.as_str(), s.count, s.files.len()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&2), stats.by_crate.get("foo"));
+        assert_eq!(Some(&3), stats.by_crate.get("bar"));
+    }
+}
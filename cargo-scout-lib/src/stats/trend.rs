@@ -0,0 +1,188 @@
+use crate::stats::Statistics;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Where `cargo scout stats record` appends its records by default
+pub const DEFAULT_TREND_PATH: &str = ".scout/trend.jsonl";
+
+/// The findings of the whole project at a commit
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct TrendRecord {
+    /// The commit that was checked out
+    pub commit: String,
+    /// When the findings were recorded, in seconds since the Unix epoch
+    pub timestamp: u64,
+    pub total: usize,
+    /// The amount of findings per lint code
+    pub by_code: BTreeMap<String, usize>,
+    /// The amount of findings per crate
+    pub by_crate: BTreeMap<String, usize>,
+}
+
+impl TrendRecord {
+    #[must_use]
+    pub fn new(commit: impl Into<String>, timestamp: u64, stats: &Statistics) -> Self {
+        Self {
+            commit: commit.into(),
+            timestamp,
+            total: stats.total,
+            by_code: stats
+                .by_code
+                .iter()
+                .map(|s| (s.code.clone(), s.count))
+                .collect(),
+            by_crate: stats.by_crate.clone(),
+        }
+    }
+}
+
+/// A file of records, one JSON document per line, oldest first.
+///
+/// Appending a line keeps the file easy to merge when it's committed.
+#[derive(Debug, Clone)]
+pub struct TrendStore {
+    path: PathBuf,
+}
+
+impl TrendStore {
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Adds a record at the end of the file, creating it if needed.
+    ///
+    /// # Errors
+    /// Fails if the file can't be written.
+    pub fn append(&self, record: &TrendRecord) -> Result<(), crate::error::Error> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    /// Reads every record.
+    ///
+    /// If the file doesn't exist, there are no records.
+    ///
+    /// # Errors
+    /// Fails if the file can't be read or a record can't be parsed.
+    pub fn records(&self) -> Result<Vec<TrendRecord>, crate::error::Error> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        std::fs::read_to_string(&self.path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(Into::into))
+            .collect()
+    }
+}
+
+/// How the amount of findings of a lint code changed
+#[derive(Debug, PartialEq, Clone)]
+pub struct CodeTrend {
+    pub code: String,
+    pub before: usize,
+    pub after: usize,
+}
+
+impl CodeTrend {
+    #[must_use]
+    pub fn change(&self) -> i64 {
+        if self.after >= self.before {
+            i64::try_from(self.after - self.before).unwrap_or(i64::MAX)
+        } else {
+            -i64::try_from(self.before - self.after).unwrap_or(i64::MAX)
+        }
+    }
+}
+
+/// Returns the lint codes whose amount of findings changed between two records,
+/// the largest changes first.
+#[must_use]
+pub fn code_trends(before: &TrendRecord, after: &TrendRecord) -> Vec<CodeTrend> {
+    let codes: BTreeSet<&String> = before.by_code.keys().chain(after.by_code.keys()).collect();
+    let mut trends: Vec<CodeTrend> = codes
+        .into_iter()
+        .map(|code| CodeTrend {
+            code: code.clone(),
+            before: before.by_code.get(code).copied().unwrap_or_default(),
+            after: after.by_code.get(code).copied().unwrap_or_default(),
+        })
+        .filter(|t| t.before != t.after)
+        .collect();
+    // Sort is stable, codes with the same change stay in alphabetical order
+    trends.sort_by_key(|t| std::cmp::Reverse(t.change().abs()));
+    trends
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(commit: &str, by_code: &[(&str, usize)]) -> TrendRecord {
+        TrendRecord {
+            commit: commit.to_string(),
+            timestamp: 1_577_836_800,
+            total: by_code.iter().map(|(_, count)| count).sum(),
+            by_code: by_code.iter().map(|(c, n)| (c.to_string(), *n)).collect(),
+            by_crate: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_append_and_read() -> Result<(), crate::error::Error> {
+        let dir = tempfile::TempDir::new()?;
+        let store = TrendStore::new(dir.path().join(DEFAULT_TREND_PATH));
+        assert!(store.records()?.is_empty());
+
+        let first = record("a1", &[("clippy::doc_markdown", 3)]);
+        let second = record("b2", &[("clippy::doc_markdown", 1)]);
+        store.append(&first)?;
+        store.append(&second)?;
+        assert_eq!(vec![first, second], store.records()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_code_trends() {
+        let before = record(
+            "a1",
+            &[
+                ("clippy::doc_markdown", 12),
+                ("clippy::cast_lossless", 2),
+                ("clippy::unused_self", 1),
+            ],
+        );
+        let after = record(
+            "b2",
+            &[
+                ("clippy::doc_markdown", 5),
+                ("clippy::unused_self", 1),
+                ("clippy::redundant_clone", 2),
+            ],
+        );
+        let trends: Vec<(String, i64)> = code_trends(&before, &after)
+            .into_iter()
+            .map(|t| (t.code.clone(), t.change()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("clippy::doc_markdown".to_string(), -7),
+                ("clippy::cast_lossless".to_string(), -2),
+                ("clippy::redundant_clone".to_string(), 2),
+            ],
+            trends
+        );
+    }
+}
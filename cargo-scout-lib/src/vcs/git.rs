@@ -1,26 +1,455 @@
-use super::*;
+use super::{is_rust_relevant, AddedLine, Section, VCS};
 use crate::error::Error;
-use git2::{Delta, DiffOptions, Repository};
-use std::path::Path;
+use git2::{
+    BlameOptions, BranchType, Delta, Diff, DiffFindOptions, DiffOptions, ErrorCode, Oid,
+    Repository, Tree,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The minimum amount of consecutive lines
+/// a block must have to be considered moved, not counting trivial lines
+const MIN_MOVED_LINES: usize = 3;
 
 pub struct Git {
     target_branch: String,
+    detect_moves: bool,
+    root: Option<PathBuf>,
 }
 
 impl Default for Git {
-    #[must_use]
     fn default() -> Self {
         Self {
             target_branch: "master".to_string(),
+            detect_moves: false,
+            root: None,
         }
     }
 }
 
+pub use super::Origin;
+
 impl Git {
     #[must_use]
     pub fn with_target(target_branch: String) -> Self {
-        Self { target_branch }
+        Self {
+            target_branch,
+            ..Self::default()
+        }
+    }
+
+    /// Treat moved or copied code as not new.
+    ///
+    /// Renamed and copied files are detected with git's similarity detection,
+    /// and blocks of added lines that have been removed elsewhere in the diff
+    /// are considered moved, so relocated legacy code doesn't show up in the diff.
+    pub fn set_detect_moves(&mut self, detect_moves: bool) -> &mut Self {
+        self.detect_moves = detect_moves;
+        self
+    }
+
+    /// Use the repository at `root`, instead of the one the given paths belong to.
+    pub fn set_root(&mut self, root: Option<PathBuf>) -> &mut Self {
+        self.root = root;
+        self
+    }
+
+    fn open<P: AsRef<Path>>(&self, repo_path: P) -> Result<Repository, git2::Error> {
+        match &self.root {
+            Some(root) => Repository::open(root),
+            None => Repository::discover(repo_path),
+        }
+    }
+
+    /// Returns how many commits the local target branch is behind
+    /// the remote branch it tracks, as of the last fetch.
+    ///
+    /// Targets that aren't local branches, or that don't track a remote branch, return `None`.
+    ///
+    /// # Errors
+    /// Fails if the repository can't be opened.
+    pub fn behind_upstream<P: AsRef<Path>>(&self, repo_path: P) -> Result<Option<usize>, Error> {
+        let repo = self.open(repo_path)?;
+        let branch = match repo.find_branch(&self.target_branch, BranchType::Local) {
+            Ok(branch) => branch,
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let upstream = match branch.upstream() {
+            Ok(upstream) => upstream,
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        match (branch.get().target(), upstream.get().target()) {
+            (Some(local), Some(upstream)) => {
+                let (_ahead, behind) = repo.graph_ahead_behind(local, upstream)?;
+                Ok(Some(behind))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Fetches the remote branch the target branch tracks,
+    /// and fast-forwards the target branch to it.
+    ///
+    /// This runs `git fetch`, so the user's credentials and transports are available.
+    /// Targets that don't track a remote branch are left untouched.
+    ///
+    /// # Errors
+    /// Fails if the repository can't be opened, or if `git fetch` or the fast-forward fails.
+    pub fn fetch_target<P: AsRef<Path>>(&self, repo_path: P) -> Result<(), Error> {
+        let repo = self.open(repo_path)?;
+        let repo_path = repo
+            .workdir()
+            .ok_or_else(|| git2::Error::from_str("the repository has no working directory"))?;
+        let local = format!("refs/heads/{}", self.target_branch);
+        let config = repo.config()?;
+        let setting =
+            |key: &str| config.get_string(&format!("branch.{}.{}", self.target_branch, key));
+        let (Ok(remote), Ok(merge)) = (setting("remote"), setting("merge")) else {
+            log::warn!(
+                "{} doesn't track a remote branch, it can't be fetched",
+                self.target_branch
+            );
+            return Ok(());
+        };
+        log::info!("[VCS] - Fetching {merge} from {remote}");
+        fetch(repo_path, &remote, &format!("{merge}:{local}"), None)
     }
+
+    /// Fetches the target branch when it doesn't exist locally,
+    /// as in the shallow clones CI services make.
+    ///
+    /// A target such as `origin/main` is fetched into its remote-tracking branch,
+    /// and other targets into a local branch, from `origin`.
+    /// Only the last `depth` commits are fetched when a depth is given.
+    /// Returns whether the target had to be fetched.
+    ///
+    /// # Errors
+    /// Fails if the repository can't be opened, or if `git fetch` fails.
+    pub fn fetch_missing_target<P: AsRef<Path>>(
+        &self,
+        repo_path: P,
+        depth: Option<u32>,
+    ) -> Result<bool, Error> {
+        let repo = self.open(repo_path)?;
+        if repo.revparse_single(&self.target_branch).is_ok() {
+            return Ok(false);
+        }
+        let repo_path = repo
+            .workdir()
+            .ok_or_else(|| git2::Error::from_str("the repository has no working directory"))?;
+        let (remote, refspec) = match self.target_branch.split_once('/') {
+            Some((remote, branch)) if repo.find_remote(remote).is_ok() => (
+                remote.to_string(),
+                format!("refs/heads/{branch}:refs/remotes/{remote}/{branch}"),
+            ),
+            _ => (
+                "origin".to_string(),
+                format!("refs/heads/{0}:refs/heads/{0}", self.target_branch),
+            ),
+        };
+        log::info!(
+            "[VCS] - {} doesn't exist, fetching it from {}",
+            self.target_branch,
+            remote
+        );
+        fetch(repo_path, &remote, &refspec, depth)?;
+        Ok(true)
+    }
+}
+
+// Commits made within the same second are ordered by history
+fn is_newer(repo: &Repository, a: &Origin, b: &Origin) -> bool {
+    let descendant =
+        |a: &Origin, b: &Origin| match (Oid::from_str(&a.commit), Oid::from_str(&b.commit)) {
+            (Ok(a), Ok(b)) => a != b && repo.graph_descendant_of(a, b).unwrap_or(false),
+            _ => false,
+        };
+    descendant(a, b) || (a.time > b.time && !descendant(b, a))
+}
+
+// The commits that last changed each of the lines, as of HEAD
+fn blamed_commits<I>(repo: &Repository, file_name: &str, lines: I) -> Result<Vec<Origin>, Error>
+where
+    I: IntoIterator<Item = u32>,
+{
+    let blame = repo.blame_file(Path::new(file_name), Some(&mut BlameOptions::new()))?;
+    let mut commits = Vec::new();
+    for line in lines {
+        let Some(hunk) = blame.get_line(line as usize) else {
+            continue;
+        };
+        let commit = repo.find_commit(hunk.final_commit_id())?;
+        commits.push(Origin {
+            commit: commit.id().to_string(),
+            author: commit.author().name().unwrap_or_default().to_string(),
+            time: commit.time().seconds(),
+        });
+    }
+    Ok(commits)
+}
+
+// The lines between `line_start` and `line_end` in the working copy of `file_name`
+// that are unchanged since HEAD, numbered as they are in HEAD
+fn committed_lines(
+    repo: &Repository,
+    file_name: &str,
+    line_start: u32,
+    line_end: u32,
+) -> Result<Vec<u32>, Error> {
+    let head = repo.head()?.peel_to_tree()?;
+    let mut options = DiffOptions::new();
+    options.pathspec(file_name).context_lines(0);
+    let diff = repo.diff_tree_to_workdir_with_index(Some(&head), Some(&mut options))?;
+    // (old_lines, new_start, new_lines) of each change
+    let hunks: RefCell<Vec<(u32, u32, u32)>> = RefCell::new(Vec::new());
+    diff.foreach(
+        &mut |_, _| true,
+        None,
+        Some(&mut |_, hunk| {
+            hunks
+                .borrow_mut()
+                .push((hunk.old_lines(), hunk.new_start(), hunk.new_lines()));
+            true
+        }),
+        None,
+    )?;
+    let hunks = hunks.into_inner();
+    let mut lines = Vec::new();
+    for line in line_start.max(1)..=line_end.max(line_start) {
+        let mut offset: i64 = 0;
+        let mut changed = false;
+        for &(old_lines, new_start, new_lines) in &hunks {
+            // A change that only removes lines starts after `new_start`
+            if new_start + new_lines.max(1) <= line {
+                offset += i64::from(old_lines) - i64::from(new_lines);
+            } else if new_lines > 0 && new_start <= line {
+                changed = true;
+            }
+        }
+        if !changed {
+            lines.extend(u32::try_from(i64::from(line) + offset).ok());
+        }
+    }
+    Ok(lines)
+}
+
+/// A branch of a remote that isn't configured locally,
+/// fetched into a temporary reference to diff with.
+///
+/// The reference is deleted when the `RemoteTarget` is dropped.
+#[derive(Debug)]
+pub struct RemoteTarget {
+    repo_path: PathBuf,
+    reference: String,
+}
+
+impl RemoteTarget {
+    /// Fetches `branch` from the repository at `url`.
+    ///
+    /// Only the last `depth` commits are fetched when a depth is given.
+    ///
+    /// # Errors
+    /// Returns `Error::Fetch` if `git fetch` fails.
+    pub fn fetch<P: AsRef<Path>>(
+        repo_path: P,
+        url: &str,
+        branch: &str,
+        depth: Option<u32>,
+    ) -> Result<Self, Error> {
+        let repo = Repository::discover(repo_path)?;
+        let repo_path = repo
+            .workdir()
+            .ok_or_else(|| git2::Error::from_str("the repository has no working directory"))?
+            .to_path_buf();
+        // Simultaneous runs in the same repository get their own reference
+        let reference = format!("refs/scout/target-{}", std::process::id());
+        log::info!("[VCS] - Fetching {branch} from {url}");
+        fetch(&repo_path, url, &format!("+{branch}:{reference}"), depth)?;
+        Ok(Self {
+            repo_path,
+            reference,
+        })
+    }
+
+    /// The reference to use as the target branch
+    #[must_use]
+    pub fn reference(&self) -> &str {
+        &self.reference
+    }
+}
+
+impl Drop for RemoteTarget {
+    fn drop(&mut self) {
+        let deleted = Repository::open(&self.repo_path)
+            .and_then(|repo| repo.find_reference(&self.reference)?.delete());
+        if let Err(e) = deleted {
+            log::warn!("Could not delete {}: {}", self.reference, e);
+        }
+    }
+}
+
+/// A checkout of the target branch in a temporary directory, made with `git worktree`,
+/// to build and run the code as it was before the changes.
+///
+/// The worktree is removed when the `Worktree` is dropped.
+#[derive(Debug)]
+pub struct Worktree {
+    repo_path: PathBuf,
+    path: PathBuf,
+}
+
+impl Worktree {
+    /// Checks `target` out, detached, in a temporary directory.
+    ///
+    /// # Errors
+    /// Fails if `repo_path` isn't in a git repository, or if `git worktree add` fails.
+    pub fn add<P: AsRef<Path>>(repo_path: P, target: &str) -> Result<Self, Error> {
+        let repo = Repository::discover(repo_path)?;
+        let repo_path = repo
+            .workdir()
+            .ok_or_else(|| git2::Error::from_str("the repository has no working directory"))?
+            .to_path_buf();
+        // Simultaneous runs in the same repository get their own worktree
+        let path = std::env::temp_dir().join(format!("scout-worktree-{}", std::process::id()));
+        log::info!("[VCS] - Checking {} out in {}", target, path.display());
+        let output = Command::new("git")
+            .current_dir(&repo_path)
+            .args(["worktree", "add", "--detach"])
+            .arg(&path)
+            .arg(target)
+            .output()
+            .map_err(|e| Error::spawn("git", e))?;
+        if !output.status.success() {
+            return Err(Error::Command(format!(
+                "git worktree add failed:\n{}",
+                String::from_utf8(output.stderr)?
+            )));
+        }
+        Ok(Self { repo_path, path })
+    }
+
+    /// The directory the target branch is checked out in
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let removed = Command::new("git")
+            .current_dir(&self.repo_path)
+            .args(["worktree", "remove", "--force"])
+            .arg(&self.path)
+            .status();
+        if !removed.is_ok_and(|status| status.success()) {
+            log::warn!("Could not remove the worktree {}", self.path.display());
+        }
+    }
+}
+
+// Runs `git fetch`, telling why it failed when the remote is at fault
+fn fetch(repo_path: &Path, remote: &str, refspec: &str, depth: Option<u32>) -> Result<(), Error> {
+    let mut command = Command::new("git");
+    command.current_dir(repo_path).args(["fetch", "--no-tags"]);
+    if let Some(depth) = depth {
+        command.arg(format!("--depth={depth}"));
+    }
+    let output = command
+        .args([remote, refspec])
+        .output()
+        .map_err(|e| Error::spawn("git", e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8(output.stderr)?;
+    let reason = if stderr.contains("couldn't find remote ref") {
+        "the remote has no such branch".to_string()
+    } else if [
+        "Could not resolve host",
+        "Could not read from remote repository",
+        "unable to access",
+        "does not appear to be a git repository",
+        "Connection refused",
+        "timed out",
+    ]
+    .iter()
+    .any(|cause| stderr.contains(cause))
+    {
+        "the remote is unreachable, check the network and your credentials".to_string()
+    } else {
+        stderr.trim().to_string()
+    };
+    Err(Error::Fetch {
+        remote: remote.to_string(),
+        refspec: refspec.to_string(),
+        reason,
+    })
+}
+
+/// Picks the target branch: the first of the candidates that exists in the repository.
+///
+/// Without candidates, the default branch is the one `origin/HEAD` points to,
+/// or `main` or `master`, whichever exists. Remote branches that have no local
+/// branch are compared with their remote-tracking branch, such as `origin/main`.
+/// When nothing exists, the first candidate is returned for the preflight checks to report.
+pub fn target_branch<P: AsRef<Path>>(repo_path: P, candidates: &[String]) -> String {
+    let fallback = candidates
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "master".to_string());
+    let Ok(repo) = Repository::discover(repo_path) else {
+        return fallback;
+    };
+    let exists = |name: &str| repo.revparse_single(name).is_ok();
+    if !candidates.is_empty() {
+        return candidates
+            .iter()
+            .find(|c| exists(c))
+            .cloned()
+            .unwrap_or(fallback);
+    }
+    let remote_head = repo
+        .find_reference("refs/remotes/origin/HEAD")
+        .ok()
+        .and_then(|r| r.symbolic_target().map(str::to_string))
+        .and_then(|t| t.strip_prefix("refs/remotes/origin/").map(str::to_string));
+    if let Some(branch) = remote_head {
+        if repo.find_branch(&branch, BranchType::Local).is_ok() {
+            return branch;
+        }
+        return format!("origin/{branch}");
+    }
+    ["main", "master"]
+        .iter()
+        .find(|b| exists(b))
+        .map_or(fallback, |b| (*b).to_string())
+}
+
+/// Returns the root of the working directory of the repository `path` belongs to.
+///
+/// # Errors
+/// Fails if `path` isn't in a repository with a working directory.
+pub fn repo_root<P: AsRef<Path>>(path: P) -> Result<PathBuf, Error> {
+    let repo = Repository::discover(path)?;
+    repo.workdir()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| git2::Error::from_str("the repository has no working directory").into())
+}
+
+/// Returns the id of the commit checked out in the repository `path` belongs to.
+///
+/// # Errors
+/// Fails if `path` isn't in a git repository, or if nothing is checked out.
+pub fn head_commit<P: AsRef<Path>>(path: P) -> Result<String, Error> {
+    let repo = Repository::discover(path)?;
+    let commit = repo.head()?.peel_to_commit()?;
+    Ok(commit.id().to_string())
 }
 
 impl VCS for Git {
@@ -28,16 +457,151 @@ impl VCS for Git {
     where
         P: AsRef<Path>,
     {
-        println!("[VCS] - Getting diff with target {}", &self.target_branch);
-        let repo = Repository::discover(repo_path)?;
+        log::info!("[VCS] - Getting diff with target {}", &self.target_branch);
+        let repo = self.open(repo_path)?;
+        let tree = repo.revparse_single(&self.target_branch)?.peel_to_tree()?;
+        self.repo_sections(&repo, Some(&tree), "")
+    }
+
+    fn introduced_by<P>(
+        &self,
+        repo_path: P,
+        file_name: &str,
+        line_start: u32,
+        line_end: u32,
+    ) -> Result<Option<Origin>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let repo = self.open(repo_path)?;
+        // Blame reads HEAD, the lines of a modified file may not match it anymore
+        match repo.status_file(Path::new(file_name)) {
+            Ok(status) if status.is_empty() => {}
+            _ => return Ok(None),
+        }
+        let mut introduced_by: Option<Origin> = None;
+        for commit in blamed_commits(
+            &repo,
+            file_name,
+            line_start.max(1)..=line_end.max(line_start),
+        )? {
+            if introduced_by
+                .as_ref()
+                .is_none_or(|o| is_newer(&repo, &commit, o))
+            {
+                introduced_by = Some(commit);
+            }
+        }
+        Ok(introduced_by)
+    }
+
+    fn origin<P>(
+        &self,
+        repo_path: P,
+        file_name: &str,
+        line_start: u32,
+        line_end: u32,
+    ) -> Result<Option<Origin>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let repo = self.open(repo_path)?;
+        match repo.head()?.peel_to_tree()?.get_path(Path::new(file_name)) {
+            Ok(_) => {}
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let lines = committed_lines(&repo, file_name, line_start, line_end)?;
+        let mut origin: Option<Origin> = None;
+        for commit in blamed_commits(&repo, file_name, lines)? {
+            if origin.as_ref().is_none_or(|o| commit.time < o.time) {
+                origin = Some(commit);
+            }
+        }
+        Ok(origin)
+    }
+
+    fn original<P>(&self, repo_path: P, file_name: &str) -> Result<Option<String>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let repo = self.open(repo_path)?;
         let tree = repo.revparse_single(&self.target_branch)?.peel_to_tree()?;
+        let entry = match tree.get_path(Path::new(file_name)) {
+            Ok(entry) => entry,
+            Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let blob = entry.to_object(&repo)?.peel_to_blob()?;
+        Ok(std::str::from_utf8(blob.content()).ok().map(str::to_string))
+    }
+}
+
+impl Git {
+    // The sections of the repository and of its submodules, with file names
+    // relative to the top repository. `prefix` is where the repository lives in it.
+    fn repo_sections(
+        &self,
+        repo: &Repository,
+        tree: Option<&Tree>,
+        prefix: &str,
+    ) -> Result<Vec<Section>, Error> {
+        let mut sections = self.diff_sections(repo, tree)?;
+        for section in &mut sections {
+            section.file_name = format!("{}{}", prefix, section.file_name);
+        }
+        for submodule in repo.submodules()? {
+            let path = submodule.path().to_string_lossy().replace('\\', "/");
+            let name = format!("{prefix}{path}");
+            let Ok(sub_repo) = submodule.open() else {
+                log::warn!("Submodule {name} isn't checked out, its changes are left out");
+                continue;
+            };
+            // The commit the target pins, a submodule added by the diff is entirely new
+            let pinned = match tree.map(|t| t.get_path(submodule.path())).transpose() {
+                Ok(entry) => entry.map(|e| e.id()),
+                Err(e) if e.code() == ErrorCode::NotFound => None,
+                Err(e) => return Err(e.into()),
+            };
+            let sub_tree = if let Ok(commit) = pinned.map(|id| sub_repo.find_commit(id)).transpose()
+            {
+                commit.map(|c| c.tree()).transpose()?
+            } else {
+                log::warn!(
+                    "Submodule {} doesn't have the commit {} pins, its changes are left out",
+                    name,
+                    self.target_branch
+                );
+                continue;
+            };
+            sections.extend(self.repo_sections(
+                &sub_repo,
+                sub_tree.as_ref(),
+                &format!("{name}/"),
+            )?);
+        }
+        Ok(sections)
+    }
+
+    fn diff_sections(&self, repo: &Repository, tree: Option<&Tree>) -> Result<Vec<Section>, Error> {
         let mut config = DiffOptions::default();
         config
             .context_lines(0)
             .show_untracked_content(true)
             .recurse_untracked_dirs(true);
-        let diff = repo.diff_tree_to_workdir_with_index(Some(&tree), Some(&mut config))?;
-        let mut sections = Vec::new();
+        let mut diff = repo.diff_tree_to_workdir_with_index(tree, Some(&mut config))?;
+        warn_nested_repos(&diff);
+        if self.detect_moves {
+            diff.find_similar(Some(
+                DiffFindOptions::new()
+                    .renames(true)
+                    .copies(true)
+                    .for_untracked(true),
+            ))?;
+            return sections_without_moves(&diff);
+        }
+        // Both callbacks add to the sections, the line one to the last hunk's
+        let sections = RefCell::new(Vec::new());
         diff.foreach(
             &mut |_delta, _progress| true,
             None,
@@ -46,11 +610,12 @@ impl VCS for Git {
                     Delta::Modified | Delta::Added | Delta::Untracked => {
                         if let Some(file_path) = delta.new_file().path() {
                             let file_path = file_path.to_string_lossy().to_string();
-                            if file_path.ends_with(".rs") {
-                                sections.push(Section {
+                            if is_rust_relevant(&file_path) {
+                                sections.borrow_mut().push(Section {
                                     file_name: file_path,
                                     line_start: hunk.new_start(),
                                     line_end: hunk.new_start() + hunk.new_lines(),
+                                    added_lines: Vec::new(),
                                 });
                             }
                         }
@@ -59,12 +624,198 @@ impl VCS for Git {
                 }
                 true
             }),
-            None,
+            Some(&mut |delta, _hunk, line| {
+                let mut sections = sections.borrow_mut();
+                let Some(last) = sections.last_mut() else {
+                    return true;
+                };
+                let in_last = delta
+                    .new_file()
+                    .path()
+                    .is_some_and(|p| p.to_string_lossy() == last.file_name);
+                if let (true, '+', Some(line_number)) = (in_last, line.origin(), line.new_lineno())
+                {
+                    last.added_lines
+                        .push(added_line(line_number, line.content()));
+                }
+                true
+            }),
         )?;
-        Ok(sections)
+        Ok(sections.into_inner())
+    }
+}
+
+fn added_line(line: u32, content: &[u8]) -> AddedLine {
+    let text = String::from_utf8_lossy(content);
+    AddedLine {
+        line,
+        text: text.trim_end_matches(['\r', '\n']).to_string(),
     }
 }
 
+// git doesn't look into repositories nested in the working directory
+// unless they are submodules, they show up as a single untracked directory
+fn warn_nested_repos(diff: &Diff) {
+    for delta in diff.deltas() {
+        if delta.status() != Delta::Untracked {
+            continue;
+        }
+        if let Some(path) = delta.new_file().path() {
+            if path.to_string_lossy().ends_with('/') {
+                log::warn!(
+                    "{} is a nested repository, its changes are left out",
+                    path.display()
+                );
+            }
+        }
+    }
+}
+
+// Builds sections out of the added lines,
+// leaving out the blocks of lines that have been removed somewhere else in the diff.
+fn sections_without_moves(diff: &Diff) -> Result<Vec<Section>, Error> {
+    // (file name, line number, trimmed content), in the old version of the files
+    let mut removed: Vec<(String, u32, String)> = Vec::new();
+    // (file name, line number, trimmed content, line)
+    let mut added: Vec<(String, u32, String, AddedLine)> = Vec::new();
+    diff.foreach(
+        &mut |_delta, _progress| true,
+        None,
+        None,
+        Some(&mut |delta, _hunk, line| {
+            let content = String::from_utf8_lossy(line.content()).trim().to_string();
+            match line.origin() {
+                '-' => {
+                    if let (Some(file_path), Some(line_number)) =
+                        (delta.old_file().path(), line.old_lineno())
+                    {
+                        let file_path = file_path.to_string_lossy().to_string();
+                        removed.push((file_path, line_number, content));
+                    }
+                }
+                '+' => match delta.status() {
+                    Delta::Modified
+                    | Delta::Added
+                    | Delta::Untracked
+                    | Delta::Renamed
+                    | Delta::Copied => {
+                        if let (Some(file_path), Some(line_number)) =
+                            (delta.new_file().path(), line.new_lineno())
+                        {
+                            let file_path = file_path.to_string_lossy().to_string();
+                            if is_rust_relevant(&file_path) {
+                                let line = added_line(line_number, line.content());
+                                added.push((file_path, line_number, content, line));
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+            true
+        }),
+    )?;
+
+    let moved = moved_lines(&removed, &added);
+
+    let mut sections: Vec<Section> = Vec::new();
+    for ((file_name, line, _, added_line), _) in added.into_iter().zip(moved).filter(|(_, m)| !m) {
+        match sections.last_mut() {
+            Some(last) if last.file_name == file_name && last.line_end == line => {
+                last.line_end += 1;
+                last.added_lines.push(added_line);
+            }
+            _ => sections.push(Section {
+                file_name,
+                line_start: line,
+                line_end: line + 1,
+                added_lines: vec![added_line],
+            }),
+        }
+    }
+    Ok(sections)
+}
+
+// Whether each added line belongs to a block of lines that was removed somewhere else
+fn moved_lines(
+    removed: &[(String, u32, String)],
+    added: &[(String, u32, String, AddedLine)],
+) -> Vec<bool> {
+    // Blank lines and lone braces are everywhere, they can't tell a block moved.
+    // Blocks are matched on their other lines, which must follow each other on both sides
+    let removed_lines: Vec<usize> = (0..removed.len())
+        .filter(|&i| !is_trivial(&removed[i].2))
+        .collect();
+    let added_lines: Vec<usize> = (0..added.len())
+        .filter(|&i| !is_trivial(&added[i].2))
+        .collect();
+    let mut candidates: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (position, &i) in removed_lines.iter().enumerate() {
+        candidates
+            .entry(removed[i].2.as_str())
+            .or_default()
+            .push(position);
+    }
+    let removed_follows = |a: usize, b: usize| {
+        let (a, b) = (removed_lines[a], removed_lines[b]);
+        removed[a].0 == removed[b].0
+            && u32::try_from(b - a).is_ok_and(|d| removed[b].1.checked_sub(removed[a].1) == Some(d))
+    };
+    let added_follows = |a: usize, b: usize| {
+        let (a, b) = (added_lines[a], added_lines[b]);
+        added[a].0 == added[b].0
+            && u32::try_from(b - a).is_ok_and(|d| added[b].1.checked_sub(added[a].1) == Some(d))
+    };
+    let mut moved: Vec<Option<bool>> = vec![None; added.len()];
+    let mut position = 0;
+    while position < added_lines.len() {
+        let content = added[added_lines[position]].2.as_str();
+        let longest = candidates
+            .get(content)
+            .into_iter()
+            .flatten()
+            .map(|&start| {
+                let mut len = 1;
+                while position + len < added_lines.len()
+                    && start + len < removed_lines.len()
+                    && added[added_lines[position + len]].2 == removed[removed_lines[start + len]].2
+                    && added_follows(position + len - 1, position + len)
+                    && removed_follows(start + len - 1, start + len)
+                {
+                    len += 1;
+                }
+                len
+            })
+            .max()
+            .unwrap_or_default();
+        let len = longest.max(1);
+        for &i in &added_lines[position..position + len] {
+            moved[i] = Some(longest >= MIN_MOVED_LINES);
+        }
+        position += len;
+    }
+    // A trivial line goes with the line before it, or the line after it
+    // when it starts a run of added lines
+    let follows = |a: usize, b: usize| added[a].0 == added[b].0 && added[a].1 + 1 == added[b].1;
+    for i in 1..added.len() {
+        if moved[i].is_none() && follows(i - 1, i) {
+            moved[i] = moved[i - 1];
+        }
+    }
+    for i in (0..added.len().saturating_sub(1)).rev() {
+        if moved[i].is_none() && follows(i, i + 1) {
+            moved[i] = moved[i + 1];
+        }
+    }
+    moved.into_iter().map(|m| m.unwrap_or(false)).collect()
+}
+
+// Blank lines and lines made of punctuation only, such as `}` or `);`
+fn is_trivial(content: &str) -> bool {
+    !content.chars().any(char::is_alphanumeric)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,20 +846,39 @@ mod tests {
                 file_name: "bar.rs".into(),
                 line_start: 1,
                 line_end: 5,
+                added_lines: Vec::new(),
             },
             Section {
                 file_name: "foo.rs".into(),
                 line_start: 1,
                 line_end: 7,
+                added_lines: Vec::new(),
             },
         ];
 
         let git = Git::default();
-        let actual = git.sections(repo.path())?;
+        let actual = locations(git.sections(repo.path())?);
         assert_eq!(expected, actual);
         Ok(())
     }
 
+    #[test]
+    fn manifest_files() -> Result<()> {
+        // Only the file names matter
+        let repo = RepoFixture::new()?
+            .write("Cargo.toml", "test_files/git/added/bar.rs")?
+            .write("README.md", "test_files/git/added/foo.rs")?;
+
+        let expected = vec![Section {
+            file_name: "Cargo.toml".into(),
+            line_start: 1,
+            line_end: 5,
+            added_lines: Vec::new(),
+        }];
+        assert_eq!(expected, locations(Git::default().sections(repo.path())?));
+        Ok(())
+    }
+
     #[test]
     fn untracked_files() -> Result<()> {
         let repo = RepoFixture::new()?
@@ -120,16 +890,18 @@ mod tests {
                 file_name: "foo.rs".into(),
                 line_start: 1,
                 line_end: 7,
+                added_lines: Vec::new(),
             },
             Section {
                 file_name: "inside/some/dir/bar.rs".into(),
                 line_start: 1,
                 line_end: 5,
+                added_lines: Vec::new(),
             },
         ];
 
         let git = Git::default();
-        let actual = git.sections(repo.path())?;
+        let actual = locations(git.sections(repo.path())?);
         assert_eq!(expected, actual);
         Ok(())
     }
@@ -150,26 +922,30 @@ mod tests {
                 file_name: "bar.rs".into(),
                 line_start: 1,
                 line_end: 2,
+                added_lines: Vec::new(),
             },
             Section {
                 file_name: "bar.rs".into(),
                 line_start: 5,
                 line_end: 9,
+                added_lines: Vec::new(),
             },
             Section {
                 file_name: "foo.rs".into(),
                 line_start: 3,
                 line_end: 4,
+                added_lines: Vec::new(),
             },
             Section {
                 file_name: "foo.rs".into(),
                 line_start: 6,
                 line_end: 7,
+                added_lines: Vec::new(),
             },
         ];
 
         let git = Git::default();
-        let actual = git.sections(repo.path())?;
+        let actual = locations(git.sections(repo.path())?);
         assert_eq!(expected, actual);
         Ok(())
     }
@@ -185,10 +961,11 @@ mod tests {
             file_name: "foo.rs".into(),
             line_start: 1,
             line_end: 7,
+            added_lines: Vec::new(),
         }];
 
         let git = Git::default();
-        let actual = git.sections(repo.path())?;
+        let actual = locations(git.sections(repo.path())?);
         assert_eq!(expected, actual);
         Ok(())
     }
@@ -207,20 +984,414 @@ mod tests {
                 file_name: "foo.rs".into(),
                 line_start: 3,
                 line_end: 4,
+                added_lines: Vec::new(),
             },
             Section {
                 file_name: "foo.rs".into(),
                 line_start: 6,
                 line_end: 7,
+                added_lines: Vec::new(),
             },
         ];
 
         let git = Git::with_target("other".to_string());
-        let actual = git.sections(repo.path())?;
+        let actual = locations(git.sections(repo.path())?);
         assert_eq!(expected, actual);
         Ok(())
     }
 
+    #[test]
+    fn origin_of_committed_lines() -> Result<()> {
+        let repo = RepoFixture::new()?
+            .write("foo.rs", "test_files/git/modified/old/foo.rs")?
+            .stage(&["foo.rs"])?
+            .commit("master", &["foo.rs"])?;
+
+        let git = Git::default();
+        let origin = git.origin(repo.path(), "foo.rs", 1, 3)?.unwrap();
+        let head = repo.repo.head()?.target().unwrap().to_string();
+        assert_eq!(head, origin.commit);
+        assert_eq!("name", origin.author);
+        Ok(())
+    }
+
+    #[test]
+    fn origin_of_uncommitted_file() -> Result<()> {
+        let repo = RepoFixture::new()?.write("foo.rs", "test_files/git/added/foo.rs")?;
+
+        let git = Git::default();
+        // The file doesn't exist in any commit yet
+        assert_eq!(None, git.origin(repo.path(), "foo.rs", 1, 3)?);
+        Ok(())
+    }
+
+    #[test]
+    fn origin_of_modified_file() -> Result<()> {
+        let repo = RepoFixture::new()?;
+        fs::write(repo.path().join("foo.rs"), "a\nb\nc\nd\n")?;
+        let repo = repo.stage(&["foo.rs"])?.commit("master", &["foo.rs"])?;
+
+        let git = Git::default();
+        // Two lines inserted before the committed ones, one removed after them
+        fs::write(repo.path().join("foo.rs"), "x\ny\na\nb\nd\n")?;
+        assert_eq!(None, git.origin(repo.path(), "foo.rs", 1, 2)?);
+        let head = repo.repo.head()?.target().unwrap().to_string();
+        assert_eq!(
+            head,
+            git.origin(repo.path(), "foo.rs", 2, 3)?.unwrap().commit
+        );
+        assert_eq!(
+            head,
+            git.origin(repo.path(), "foo.rs", 5, 5)?.unwrap().commit
+        );
+        assert_eq!(None, git.origin(repo.path(), "foo.rs", 6, 6)?);
+        Ok(())
+    }
+
+    #[test]
+    fn moved_between_files() -> Result<()> {
+        let files = &["foo.rs", "bar.rs"];
+        let repo = RepoFixture::new()?
+            .write("foo.rs", "test_files/git/moved/old/foo.rs")?
+            .write("bar.rs", "test_files/git/moved/old/bar.rs")?
+            .stage(files)?
+            .commit("master", files)?
+            .write("foo.rs", "test_files/git/moved/new/foo.rs")?
+            .write("bar.rs", "test_files/git/moved/new/bar.rs")?;
+
+        // The impl block moved from foo.rs to bar.rs,
+        // only the answer function is new
+        let expected = vec![Section {
+            file_name: "bar.rs".into(),
+            line_start: 11,
+            line_end: 14,
+            added_lines: Vec::new(),
+        }];
+
+        let mut git = Git::default();
+        git.set_detect_moves(true);
+        let actual = locations(git.sections(repo.path())?);
+        assert_eq!(expected, actual);
+
+        // Without move detection, the whole block is new
+        let actual = locations(Git::default().sections(repo.path())?);
+        assert!(actual.contains(&Section {
+            file_name: "bar.rs".into(),
+            line_start: 4,
+            line_end: 14,
+            added_lines: Vec::new(),
+        }));
+        Ok(())
+    }
+
+    #[test]
+    fn braces_are_not_moved() -> Result<()> {
+        let files = &["foo.rs", "bar.rs"];
+        let repo = RepoFixture::new()?
+            .write("foo.rs", "test_files/git/braces/old/foo.rs")?
+            .write("bar.rs", "test_files/git/braces/old/bar.rs")?
+            .stage(files)?
+            .commit("master", files)?
+            .write("foo.rs", "test_files/git/braces/new/foo.rs")?
+            .write("bar.rs", "test_files/git/braces/new/bar.rs")?;
+
+        // The new function ends with braces and a blank line like the removed one,
+        // and shares a single line with it
+        let expected = vec![Section {
+            file_name: "bar.rs".into(),
+            line_start: 2,
+            line_end: 13,
+            added_lines: Vec::new(),
+        }];
+
+        let mut git = Git::default();
+        git.set_detect_moves(true);
+        let actual = locations(git.sections(repo.path())?);
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn renamed_file() -> Result<()> {
+        let repo = RepoFixture::new()?
+            .write("foo.rs", "test_files/git/moved/old/foo.rs")?
+            .stage(&["foo.rs"])?
+            .commit("master", &["foo.rs"])?
+            .remove("foo.rs")?
+            .write("baz.rs", "test_files/git/moved/new/baz.rs")?
+            .stage(&["baz.rs"])?;
+
+        // Only the bar function is new
+        let expected = vec![Section {
+            file_name: "baz.rs".into(),
+            line_start: 9,
+            line_end: 13,
+            added_lines: Vec::new(),
+        }];
+
+        let mut git = Git::default();
+        git.set_detect_moves(true);
+        let actual = locations(git.sections(repo.path())?);
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn original_files() -> Result<()> {
+        let files = &["foo.rs"];
+        let repo = RepoFixture::new()?
+            .write("foo.rs", "test_files/git/modified/old/foo.rs")?
+            .stage(files)?
+            .commit("master", files)?
+            .write("foo.rs", "test_files/git/modified/new/foo.rs")?;
+
+        let git = Git::default();
+        let expected = fs::read_to_string("test_files/git/modified/old/foo.rs")?;
+        assert_eq!(Some(expected), git.original(repo.path(), "foo.rs")?);
+        assert_eq!(None, git.original(repo.path(), "bar.rs")?);
+        Ok(())
+    }
+
+    #[test]
+    fn stale_target() -> Result<()> {
+        let fixture = RepoFixture::new()?
+            .branch("target")?
+            .write("foo.rs", "test_files/git/added/foo.rs")?
+            .commit("newer", &["foo.rs"])?;
+        let git = Git::with_target("target".to_string());
+        assert_eq!(None, git.behind_upstream(fixture.path())?);
+        assert_eq!(
+            None,
+            Git::with_target("unknown".to_string()).behind_upstream(fixture.path())?
+        );
+
+        let repo = &fixture.repo;
+        let newer = repo.refname_to_id("refs/heads/newer")?;
+        repo.reference("refs/remotes/origin/target", newer, false, "fetch")?;
+        repo.remote("origin", "https://example.invalid/repo.git")?;
+        repo.find_branch("target", BranchType::Local)?
+            .set_upstream(Some("origin/target"))?;
+        assert_eq!(Some(1), git.behind_upstream(fixture.path())?);
+        Ok(())
+    }
+
+    #[test]
+    fn repository_root() -> Result<()> {
+        let repo = RepoFixture::new()?.write("inside/foo.rs", "test_files/git/added/foo.rs")?;
+        let root = repo_root(repo.path().join("inside"))?;
+        assert_eq!(fs::canonicalize(repo.path())?, fs::canonicalize(root)?);
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_missing_target() -> Result<()> {
+        let remote = RepoFixture::new()?.branch("main")?;
+        let repo = RepoFixture::new()?;
+        repo.repo
+            .remote("origin", &remote.path().to_string_lossy())?;
+
+        let git = Git::with_target("origin/main".to_string());
+        assert!(git.fetch_missing_target(repo.path(), Some(1))?);
+        assert!(repo.repo.revparse_single("origin/main").is_ok());
+        assert!(!git.fetch_missing_target(repo.path(), Some(1))?);
+
+        let git = Git::with_target("main".to_string());
+        assert!(git.fetch_missing_target(repo.path(), None)?);
+        assert!(repo.repo.find_branch("main", BranchType::Local).is_ok());
+
+        let git = Git::with_target("develop".to_string());
+        match git.fetch_missing_target(repo.path(), None) {
+            Err(Error::Fetch { reason, .. }) => assert_eq!("the remote has no such branch", reason),
+            other => panic!("unexpected {:?}", other),
+        }
+        repo.repo.remote_set_url("origin", "/does-not-exist")?;
+        match git.fetch_missing_target(repo.path(), None) {
+            Err(Error::Fetch { reason, .. }) => assert!(reason.contains("unreachable")),
+            other => panic!("unexpected {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn remote_target() -> Result<()> {
+        let remote = RepoFixture::new()?.branch("main")?;
+        let repo = RepoFixture::new()?;
+        let url = remote.path().to_string_lossy().to_string();
+
+        let target = RemoteTarget::fetch(repo.path(), &url, "main", None)?;
+        let reference = target.reference().to_string();
+        let git = Git::with_target(reference.clone());
+        assert_eq!(Vec::<Section>::new(), git.sections(repo.path())?);
+        drop(target);
+        assert!(repo.repo.find_reference(&reference).is_err());
+
+        match RemoteTarget::fetch(repo.path(), &url, "develop", None) {
+            Err(Error::Fetch { reason, .. }) => assert_eq!("the remote has no such branch", reason),
+            other => panic!("unexpected {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn worktree() -> Result<()> {
+        let repo = RepoFixture::new()?
+            .write("foo.rs", "test_files/git/modified/old/foo.rs")?
+            .commit("main", &["foo.rs"])?;
+        fs::write(repo.path().join("foo.rs"), "fn changed() {}\n")?;
+
+        let worktree = Worktree::add(repo.path(), "main")?;
+        let path = worktree.path().to_path_buf();
+        assert_eq!(
+            fs::read_to_string("test_files/git/modified/old/foo.rs")?,
+            fs::read_to_string(path.join("foo.rs"))?
+        );
+        drop(worktree);
+        assert!(!path.exists());
+        assert!(Worktree::add(repo.path(), "develop").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn introduced_by() -> Result<()> {
+        let repo = RepoFixture::new()?
+            .write("foo.rs", "test_files/git/modified/old/foo.rs")?
+            .stage(&["foo.rs"])?
+            .commit("master", &["foo.rs"])?;
+        let first = repo.repo.head()?.target().unwrap().to_string();
+        let repo = repo
+            .write("foo.rs", "test_files/git/modified/new/foo.rs")?
+            .stage(&["foo.rs"])?
+            .commit("master", &["foo.rs"])?;
+        let second = repo.repo.head()?.target().unwrap().to_string();
+
+        let git = Git::default();
+        let commit = |start, end| -> Result<String> {
+            Ok(git
+                .introduced_by(repo.path(), "foo.rs", start, end)?
+                .unwrap()
+                .commit)
+        };
+        assert_eq!(first, commit(1, 1)?);
+        // Line 3 changed in the second commit
+        assert_eq!(second, commit(1, 3)?);
+        assert_eq!(
+            first,
+            git.origin(repo.path(), "foo.rs", 1, 3)?.unwrap().commit
+        );
+
+        // The lines of a modified file may not be the ones blame reads
+        fs::write(repo.path().join("foo.rs"), "")?;
+        assert_eq!(None, git.introduced_by(repo.path(), "foo.rs", 1, 3)?);
+        Ok(())
+    }
+
+    #[test]
+    fn target_branches() -> Result<()> {
+        let repo = RepoFixture::new()?.branch("develop")?;
+        let candidates = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            "develop",
+            target_branch(repo.path(), &candidates(&["release", "develop", "master"]))
+        );
+        assert_eq!(
+            "release",
+            target_branch(repo.path(), &candidates(&["release"]))
+        );
+
+        // The default branch of the remote
+        let head = repo.repo.head()?.target().unwrap();
+        repo.repo
+            .reference("refs/remotes/origin/trunk", head, false, "fetch")?;
+        repo.repo.reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/trunk",
+            false,
+            "clone",
+        )?;
+        assert_eq!("origin/trunk", target_branch(repo.path(), &[]));
+        let repo = repo.branch("trunk")?;
+        assert_eq!("trunk", target_branch(repo.path(), &[]));
+        Ok(())
+    }
+
+    #[test]
+    fn crlf_working_copy() -> Result<()> {
+        // Checkouts on Windows have CRLF endings, git stores LF
+        let repo = RepoFixture::new()?;
+        repo.repo.config()?.set_str("core.autocrlf", "true")?;
+        fs::write(repo.path().join("lib.rs"), "fn foo() {}\r\nfn bar() {}\r\n")?;
+        let repo = repo.stage(&["lib.rs"])?.commit("master", &["lib.rs"])?;
+        fs::write(
+            repo.path().join("lib.rs"),
+            "fn foo() {}\r\nfn baz() {}\r\nfn bar() {}\r\n",
+        )?;
+        let expected_sections = vec![Section {
+            file_name: "lib.rs".to_string(),
+            line_start: 2,
+            line_end: 3,
+            added_lines: vec![AddedLine {
+                line: 2,
+                text: "fn baz() {}".to_string(),
+            }],
+        }];
+        let vcs = Git::with_target("master".to_string());
+        assert_eq!(expected_sections, vcs.sections(repo.path())?);
+        Ok(())
+    }
+
+    #[test]
+    fn submodules() -> Result<()> {
+        let repo = RepoFixture::new()?;
+        {
+            let mut submodule = repo
+                .repo
+                .submodule("../vendored", Path::new("vendored"), true)?;
+            let sub_repo = submodule.open()?;
+            sub_repo.config()?.set_str("user.name", "name")?;
+            sub_repo.config()?.set_str("user.email", "email")?;
+            fs::write(repo.path().join("vendored/lib.rs"), "fn foo() {}\n")?;
+            let mut index = sub_repo.index()?;
+            index.add_path(Path::new("lib.rs"))?;
+            index.write()?;
+            let tree = sub_repo.find_tree(index.write_tree()?)?;
+            let sig = sub_repo.signature()?;
+            sub_repo.commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[])?;
+            submodule.add_finalize()?;
+        }
+        let repo = repo.commit("master", &[".gitmodules"])?;
+        fs::write(
+            repo.path().join("vendored/lib.rs"),
+            "fn foo() {}\nfn bar() {}\n",
+        )?;
+        // Nested repositories that aren't submodules are left out
+        Repository::init(repo.path().join("nested"))?;
+        fs::write(repo.path().join("nested/lib.rs"), "fn foo() {}\n")?;
+
+        let expected_sections = vec![Section {
+            file_name: "vendored/lib.rs".to_string(),
+            line_start: 2,
+            line_end: 3,
+            added_lines: vec![AddedLine {
+                line: 2,
+                text: "fn bar() {}".to_string(),
+            }],
+        }];
+        let vcs = Git::default();
+        assert_eq!(expected_sections, vcs.sections(repo.path())?);
+        Ok(())
+    }
+
+    // The fixture files are checked for where the changes are
+    fn locations(sections: Vec<Section>) -> Vec<Section> {
+        sections
+            .into_iter()
+            .map(|s| Section {
+                added_lines: Vec::new(),
+                ..s
+            })
+            .collect()
+    }
+
     struct RepoFixture {
         dir: TempDir,
         repo: Repository,
@@ -255,6 +1426,14 @@ mod tests {
             Ok(self)
         }
 
+        pub fn remove(self, path: &str) -> Result<Self> {
+            fs::remove_file(self.dir.path().join(path))?;
+            let mut index = self.repo.index()?;
+            index.remove_path(path.as_ref())?;
+            index.write()?;
+            Ok(self)
+        }
+
         pub fn stage(self, paths: &[&str]) -> Result<Self> {
             let mut index = self.repo.index()?;
             for path in paths {
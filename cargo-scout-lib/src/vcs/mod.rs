@@ -1,16 +1,242 @@
 pub mod git;
 use crate::error::Error;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 pub trait VCS {
+    /// Returns the sections of the files the changes add or modify.
+    ///
+    /// # Errors
+    /// Fails if the VCS can't read the repository or compute the diff.
     fn sections<P>(&self, repo_path: P) -> Result<Vec<Section>, Error>
     where
         P: AsRef<Path>;
+
+    /// Returns the last commit that changed one of the lines between
+    /// `line_start` and `line_end`, or `None` if they aren't committed
+    /// or the VCS can't tell.
+    ///
+    /// # Errors
+    /// Fails if the VCS can't read the history of the file.
+    fn introduced_by<P>(
+        &self,
+        _repo_path: P,
+        _file_name: &str,
+        _line_start: u32,
+        _line_end: u32,
+    ) -> Result<Option<Origin>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(None)
+    }
+
+    /// Returns the oldest commit that introduced one of the lines
+    /// between `line_start` and `line_end`, telling how long the code has existed.
+    /// Lines changed since the last commit are left out,
+    /// it is `None` if none of them is committed or the VCS can't tell.
+    ///
+    /// # Errors
+    /// Fails if the VCS can't read the history of the file.
+    fn origin<P>(
+        &self,
+        _repo_path: P,
+        _file_name: &str,
+        _line_start: u32,
+        _line_end: u32,
+    ) -> Result<Option<Origin>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(None)
+    }
+
+    /// Returns the contents of a file before the changes,
+    /// or `None` if it didn't exist or the VCS can't tell.
+    ///
+    /// # Errors
+    /// Fails if the VCS can't read the file at the target.
+    fn original<P>(&self, _repo_path: P, _file_name: &str) -> Result<Option<String>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(None)
+    }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Section {
     pub file_name: String,
     pub line_start: u32,
     pub line_end: u32,
+    /// The lines the change added in the section, when the VCS keeps them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_lines: Vec<AddedLine>,
+}
+
+/// The commit a range of lines originates from
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Origin {
+    /// The commit id
+    pub commit: String,
+    /// The commit author name
+    pub author: String,
+    /// The commit time, in seconds since the epoch
+    pub time: i64,
+}
+
+/// A line added by a change, as it reads in the diff
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct AddedLine {
+    /// The line number in the new version of the file
+    pub line: u32,
+    /// The line, without its line ending
+    pub text: String,
+}
+
+impl Section {
+    /// Returns the section widened by `radius` lines on both sides.
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::vcs::Section;
+    /// let section = Section { file_name: "foo.rs".to_string(), line_start: 5, line_end: 7, added_lines: Vec::new() };
+    /// let expanded = section.expand(3);
+    /// assert_eq!((2, 10), (expanded.line_start, expanded.line_end));
+    /// ```
+    #[must_use]
+    pub fn expand(&self, radius: u32) -> Self {
+        Self {
+            file_name: self.file_name.clone(),
+            line_start: self.line_start.saturating_sub(radius),
+            line_end: self.line_end.saturating_add(radius),
+            added_lines: self.added_lines.clone(),
+        }
+    }
+
+    /// Returns true if the change can affect what the linter reports,
+    /// see [`is_rust_relevant`].
+    #[must_use]
+    pub fn is_rust_relevant(&self) -> bool {
+        is_rust_relevant(&self.file_name)
+    }
+}
+
+/// Returns true if changes to the file can affect what the linter reports:
+/// Rust sources (including `build.rs`), manifests, lockfiles and clippy configurations.
+///
+/// # Example
+/// ```
+/// # use cargo_scout_lib::vcs::is_rust_relevant;
+/// assert!(is_rust_relevant("foo/build.rs"));
+/// assert!(is_rust_relevant("foo/Cargo.toml"));
+/// assert!(is_rust_relevant("Cargo.lock"));
+/// assert!(!is_rust_relevant("README.md"));
+/// ```
+#[must_use]
+pub fn is_rust_relevant(file_name: &str) -> bool {
+    let file_name = file_name.rsplit(['/', '\\']).next().unwrap_or_default();
+    Path::new(file_name).extension().is_some_and(|e| e == "rs")
+        || ["Cargo.toml", "Cargo.lock", "clippy.toml", ".clippy.toml"].contains(&file_name)
+}
+
+/// Sorts sections by file and line, clamps invalid ranges,
+/// and merges sections that overlap or are adjacent within the same file.
+///
+/// This prevents duplicate findings when a file has many tiny adjacent hunks.
+///
+/// # Example
+/// ```
+/// # use cargo_scout_lib::vcs::{normalize_sections, Section};
+/// let sections = vec![
+///     Section { file_name: "foo.rs".to_string(), line_start: 5, line_end: 7, added_lines: Vec::new() },
+///     Section { file_name: "foo.rs".to_string(), line_start: 1, line_end: 4, added_lines: Vec::new() },
+/// ];
+/// let expected = vec![
+///     Section { file_name: "foo.rs".to_string(), line_start: 1, line_end: 7, added_lines: Vec::new() },
+/// ];
+/// assert_eq!(expected, normalize_sections(sections));
+/// ```
+#[must_use]
+pub fn normalize_sections(mut sections: Vec<Section>) -> Vec<Section> {
+    for s in &mut sections {
+        if s.line_end < s.line_start {
+            s.line_end = s.line_start;
+        }
+    }
+    sections.sort_by(|a, b| {
+        a.file_name
+            .cmp(&b.file_name)
+            .then(a.line_start.cmp(&b.line_start))
+    });
+    let mut normalized: Vec<Section> = Vec::with_capacity(sections.len());
+    for s in sections {
+        if let Some(last) = normalized.last_mut() {
+            if last.file_name == s.file_name && s.line_start <= last.line_end.saturating_add(1) {
+                last.line_end = last.line_end.max(s.line_end);
+                last.added_lines.extend(s.added_lines);
+                last.added_lines.sort_by_key(|l| l.line);
+                last.added_lines.dedup_by_key(|l| l.line);
+                continue;
+            }
+        }
+        normalized.push(s);
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(file_name: &str, line_start: u32, line_end: u32) -> Section {
+        Section {
+            file_name: file_name.to_string(),
+            line_start,
+            line_end,
+            added_lines: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_empty() {
+        assert!(normalize_sections(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_normalize_merges_overlapping_and_adjacent() {
+        let sections = vec![
+            section("foo.rs", 1, 3),
+            section("foo.rs", 2, 5),
+            section("foo.rs", 6, 8),
+            section("foo.rs", 10, 12),
+        ];
+        let expected = vec![section("foo.rs", 1, 8), section("foo.rs", 10, 12)];
+        assert_eq!(expected, normalize_sections(sections));
+    }
+
+    #[test]
+    fn test_normalize_keeps_files_apart() {
+        let sections = vec![
+            section("foo.rs", 1, 3),
+            section("bar.rs", 2, 5),
+            section("foo.rs", 4, 4),
+        ];
+        let expected = vec![section("bar.rs", 2, 5), section("foo.rs", 1, 4)];
+        assert_eq!(expected, normalize_sections(sections));
+    }
+
+    #[test]
+    fn test_normalize_clamps_invalid_ranges() {
+        let sections = vec![section("foo.rs", 10, 2), section("foo.rs", 20, 25)];
+        let expected = vec![section("foo.rs", 10, 10), section("foo.rs", 20, 25)];
+        assert_eq!(expected, normalize_sections(sections));
+    }
+
+    #[test]
+    fn test_normalize_contained_section() {
+        let sections = vec![section("foo.rs", 1, 20), section("foo.rs", 5, 6)];
+        let expected = vec![section("foo.rs", 1, 20)];
+        assert_eq!(expected, normalize_sections(sections));
+    }
 }
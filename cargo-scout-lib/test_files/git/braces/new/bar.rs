@@ -0,0 +1,12 @@
+pub struct Bar;
+
+pub fn sum(values: &[u32]) -> u32 {
+    let mut sum = 0;
+    for value in values {
+        if *value < 10 {
+            sum += value;
+        }
+    }
+
+    sum
+}
@@ -0,0 +1,11 @@
+pub struct Foo;
+
+pub fn check(values: &[u32]) -> bool {
+    for value in values {
+        if *value > 10 {
+            return true;
+        }
+    }
+
+    false
+}
@@ -0,0 +1,13 @@
+pub struct Bar {
+    baz: u32,
+}
+
+impl Foo {
+    pub fn new(bar: u32) -> Self {
+        Self { bar }
+    }
+}
+
+pub fn answer() -> u32 {
+    42
+}
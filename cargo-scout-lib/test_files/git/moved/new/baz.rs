@@ -0,0 +1,13 @@
+pub struct Foo {
+    bar: u32,
+}
+
+impl Foo {
+    pub fn new(bar: u32) -> Self {
+        Self { bar }
+    }
+
+    pub fn bar(&self) -> u32 {
+        self.bar
+    }
+}
@@ -0,0 +1,3 @@
+pub struct Foo {
+    bar: u32,
+}
@@ -0,0 +1,3 @@
+pub struct Bar {
+    baz: u32,
+}
@@ -0,0 +1,9 @@
+pub struct Foo {
+    bar: u32,
+}
+
+impl Foo {
+    pub fn new(bar: u32) -> Self {
+        Self { bar }
+    }
+}
@@ -0,0 +1,74 @@
+#![cfg(windows)]
+// Paths and line endings as they come on Windows checkouts,
+// run by the Windows job of the CI.
+
+use cargo_scout_lib::paths::{simplify, PathNormalizer};
+use cargo_scout_lib::vcs::{git::Git, AddedLine, Section, VCS};
+use git2::Repository;
+use std::fs;
+use tempfile::TempDir;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[test]
+fn canonical_paths_are_simplified() -> Result<()> {
+    let dir = TempDir::new()?;
+    let canonical = fs::canonicalize(dir.path())?;
+    assert!(canonical.to_string_lossy().starts_with(r"\\?\"));
+    assert!(!simplify(&canonical).to_string_lossy().starts_with(r"\\?\"));
+    Ok(())
+}
+
+#[test]
+fn absolute_paths_with_backslashes() -> Result<()> {
+    let dir = TempDir::new()?;
+    fs::create_dir_all(dir.path().join("rust").join("src"))?;
+    fs::write(dir.path().join("rust").join("src").join("lib.rs"), "")?;
+    let normalizer = PathNormalizer::new(dir.path(), dir.path().join("rust"));
+
+    let absolute = dir.path().join("rust").join("src").join("lib.rs");
+    assert_eq!(
+        "rust/src/lib.rs",
+        normalizer.normalize(&absolute.to_string_lossy())
+    );
+    let lowercase = absolute.to_string_lossy().to_ascii_lowercase();
+    assert_eq!("rust/src/lib.rs", normalizer.normalize(&lowercase));
+    assert_eq!("rust/src/lib.rs", normalizer.normalize(r"src\lib.rs"));
+    Ok(())
+}
+
+#[test]
+fn crlf_checkout() -> Result<()> {
+    let dir = TempDir::new()?;
+    let repo = Repository::init(dir.path())?;
+    let mut config = repo.config()?;
+    config.set_str("user.name", "name")?;
+    config.set_str("user.email", "email")?;
+    config.set_str("core.autocrlf", "true")?;
+
+    fs::write(dir.path().join("lib.rs"), "fn foo() {}\r\nfn bar() {}\r\n")?;
+    let mut index = repo.index()?;
+    index.add_path("lib.rs".as_ref())?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let sig = repo.signature()?;
+    let commit = repo.commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[])?;
+    repo.branch("target", &repo.find_commit(commit)?, false)?;
+
+    fs::write(
+        dir.path().join("lib.rs"),
+        "fn foo() {}\r\nfn baz() {}\r\nfn bar() {}\r\n",
+    )?;
+    let expected = vec![Section {
+        file_name: "lib.rs".to_string(),
+        line_start: 2,
+        line_end: 3,
+        added_lines: vec![AddedLine {
+            line: 2,
+            text: "fn baz() {}".to_string(),
+        }],
+    }];
+    let git = Git::with_target("target".to_string());
+    assert_eq!(expected, git.sections(dir.path())?);
+    Ok(())
+}
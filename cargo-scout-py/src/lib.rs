@@ -0,0 +1,110 @@
+// pyo3's generated wrappers convert the errors of functions that already return `PyErr`
+#![allow(clippy::useless_conversion)]
+
+use cargo_scout_lib::config::rust::CargoConfig;
+use cargo_scout_lib::config::scout::ScoutConfig;
+use cargo_scout_lib::linter::clippy::{workspace_root, Clippy};
+use cargo_scout_lib::linter::Lint;
+use cargo_scout_lib::output::{headline, level_name};
+use cargo_scout_lib::paths::PathNormalizer;
+use cargo_scout_lib::scout::Scout;
+use cargo_scout_lib::vcs::git::{repo_root, Git};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// A lint introduced by the changes
+#[pyclass(get_all, frozen, module = "cargo_scout")]
+#[derive(Debug, Clone)]
+struct Finding {
+    /// The lint code, such as `clippy::redundant_clone`
+    code: Option<String>,
+    /// error, warning, note, help or other
+    level: String,
+    /// The message, without its location
+    message: String,
+    /// The message as clippy renders it
+    rendered: String,
+    /// The file, relative to the repository root
+    file: Option<String>,
+    line_start: Option<u32>,
+    line_end: Option<u32>,
+    /// A stable identifier of the finding
+    fingerprint: String,
+    /// A shell command that reproduces the finding on its own
+    reproducer: Option<String>,
+}
+
+#[pymethods]
+impl Finding {
+    fn __repr__(&self) -> String {
+        format!(
+            "Finding(code={:?}, file={:?}, line_start={:?})",
+            self.code, self.file, self.line_start
+        )
+    }
+}
+
+impl From<&Lint> for Finding {
+    fn from(lint: &Lint) -> Self {
+        let span = lint.primary_span();
+        Self {
+            code: lint.code().map(str::to_string),
+            level: level_name(lint.level()).to_string(),
+            message: headline(lint),
+            rendered: lint
+                .message
+                .as_ref()
+                .map(|m| m.rendered.clone())
+                .unwrap_or_default(),
+            file: span.map(|s| s.file_name.clone()),
+            line_start: span.map(|s| s.line_start),
+            line_end: span.map(|s| s.line_end),
+            fingerprint: lint.fingerprint(),
+            reproducer: lint.reproducer.clone(),
+        }
+    }
+}
+
+/// Lints the project at `path`, and returns the findings introduced since `base`.
+///
+/// `config` is the path of a `scout.toml` file, relative to `path`.
+/// The current directory changes while the run lasts.
+#[pyfunction]
+#[pyo3(signature = (path, base = "master", config = "scout.toml"))]
+fn run(py: Python<'_>, path: PathBuf, base: &str, config: &str) -> PyResult<Vec<Finding>> {
+    py.allow_threads(move || run_in(&path, base, config))
+        .map(|lints| lints.iter().map(Finding::from).collect())
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
+
+fn run_in(path: &Path, base: &str, config: &str) -> Result<Vec<Lint>, cargo_scout_lib::Error> {
+    let previous_dir = std::env::current_dir()?;
+    std::env::set_current_dir(path)?;
+    let result = scout(base, config);
+    std::env::set_current_dir(previous_dir)?;
+    result
+}
+
+fn scout(base: &str, config: &str) -> Result<Vec<Lint>, cargo_scout_lib::Error> {
+    let current_dir = std::env::current_dir()?;
+    let scout_config = ScoutConfig::from_path(config)?;
+    let normalizer = PathNormalizer::new(repo_root(&current_dir)?, workspace_root(&current_dir)?);
+    let mut scout = Scout::new(
+        Git::with_target(base.to_string()),
+        CargoConfig::from_manifest_path("Cargo.toml")?,
+        Clippy::default(),
+    );
+    scout
+        .set_path_normalizer(normalizer)
+        .set_path_filter(scout_config.path_filter())
+        .set_filter(scout_config.lint_filter());
+    Ok(scout.run()?.into_iter().map(Lint::from).collect())
+}
+
+#[pymodule]
+fn cargo_scout(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Finding>()?;
+    m.add_function(wrap_pyfunction!(run, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Opens files at a line, with `--editor-cmd`, or `$VISUAL` or `$EDITOR`
+#[derive(Default, Debug, Clone)]
+pub struct Editor {
+    template: Option<String>,
+}
+
+impl Editor {
+    /// `template` is a command line where `{file}`, `{line}` and `{column}` are replaced,
+    /// e.g. `code -g {file}:{line}`.
+    #[must_use]
+    pub fn new(template: Option<String>) -> Self {
+        Self { template }
+    }
+
+    /// The editor, as shown to the user
+    pub fn name(&self) -> String {
+        self.template.clone().unwrap_or_else(default_editor)
+    }
+
+    /// Builds the command that opens `file` at `line`.
+    pub fn command(&self, file: &Path, line: u32, column: u32) -> Command {
+        match &self.template {
+            Some(template) => from_template(template, file, line, column),
+            None => from_editor(&default_editor(), file, line, column),
+        }
+    }
+}
+
+fn default_editor() -> String {
+    std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string())
+}
+
+// The file is appended when the template doesn't say where it goes
+fn from_template(template: &str, file: &Path, line: u32, column: u32) -> Command {
+    let file = file.display().to_string();
+    let mut words = template.split_whitespace().map(|word| {
+        word.replace("{file}", &file)
+            .replace("{line}", &line.to_string())
+            .replace("{column}", &column.max(1).to_string())
+    });
+    let mut command = Command::new(words.next().unwrap_or_default());
+    command.args(words);
+    if !template.contains("{file}") {
+        command.arg(file);
+    }
+    command
+}
+
+// The editor can have arguments, such as `code --wait`
+fn from_editor(editor: &str, file: &Path, line: u32, column: u32) -> Command {
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let mut command = Command::new(program);
+    command.args(words);
+    let name = Path::new(program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let position = format!("{}:{}:{}", file.display(), line, column.max(1));
+    match name.as_str() {
+        "code" | "codium" | "code-insiders" => command.arg("--goto").arg(position),
+        "hx" | "helix" | "subl" | "zed" => command.arg(position),
+        // vi, vim, nvim, emacs, nano, kak and most others
+        _ => command.arg(format!("+{line}")).arg(file),
+    };
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<String> {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_from_editor() {
+        let file = Path::new("/repo/src/lib.rs");
+        assert_eq!(
+            vec!["vim", "+3", "/repo/src/lib.rs"],
+            args(&from_editor("vim", file, 3, 5))
+        );
+        assert_eq!(
+            vec!["code", "--wait", "--goto", "/repo/src/lib.rs:3:5"],
+            args(&from_editor("code --wait", file, 3, 5))
+        );
+        assert_eq!(
+            vec!["/usr/bin/hx", "/repo/src/lib.rs:3:5"],
+            args(&from_editor("/usr/bin/hx", file, 3, 5))
+        );
+    }
+
+    #[test]
+    fn test_from_template() {
+        let file = Path::new("/repo/src/lib.rs");
+        let editor = Editor::new(Some("code -g {file}:{line}:{column}".to_string()));
+        assert_eq!(
+            vec!["code", "-g", "/repo/src/lib.rs:3:5"],
+            args(&editor.command(file, 3, 5))
+        );
+        let editor = Editor::new(Some("idea --line {line}".to_string()));
+        assert_eq!(
+            vec!["idea", "--line", "3", "/repo/src/lib.rs"],
+            args(&editor.command(file, 3, 0))
+        );
+    }
+}
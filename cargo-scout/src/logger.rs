@@ -0,0 +1,52 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Writes status messages to stderr, so stdout only carries the report.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            Level::Error => eprintln!("error: {}", record.args()),
+            Level::Warn => eprintln!("warning: {}", record.args()),
+            _ => eprintln!("{}", record.args()),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Returns the level matching the `--quiet` and `-v` flags.
+pub fn level_filter(quiet: bool, verbose: u8) -> LevelFilter {
+    match (quiet, verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    }
+}
+
+pub fn init(level: LevelFilter) {
+    if log::set_logger(&StderrLogger).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_filter() {
+        assert_eq!(LevelFilter::Error, level_filter(true, 2));
+        assert_eq!(LevelFilter::Info, level_filter(false, 0));
+        assert_eq!(LevelFilter::Debug, level_filter(false, 1));
+        assert_eq!(LevelFilter::Trace, level_filter(false, 2));
+    }
+}
@@ -1,21 +1,91 @@
+// The code structopt derives casts the occurrences of the flags,
+// and converts the `FromStr` errors of the options with `to_string`
+#![allow(clippy::implicit_clone, clippy::cast_possible_truncation)]
+
+use cargo_scout_lib::baseline::{Baseline, DEFAULT_BASELINE_PATH};
+use cargo_scout_lib::bench::{
+    compare, format_time, impacted_packages, Bench, BenchChange, BenchResults,
+};
+use cargo_scout_lib::cache::CachedLinter;
+use cargo_scout_lib::cancel::CancellationToken;
+use cargo_scout_lib::config::profile::{Backend, Profile};
 use cargo_scout_lib::config::rust::CargoConfig;
-use cargo_scout_lib::linter::clippy::Clippy;
-use cargo_scout_lib::linter::Lint;
+use cargo_scout_lib::config::scout::{ConfigPreview, ScoutConfig};
+use cargo_scout_lib::config::Config;
+use cargo_scout_lib::error::{EXIT_TOOL_FAILURE, EXIT_USAGE};
+use cargo_scout_lib::hooks::{self, HookKind, HookMode};
+use cargo_scout_lib::linter::clippy::{Clippy, TargetKind};
+use cargo_scout_lib::linter::explain::explain;
+use cargo_scout_lib::linter::rustfmt::RustFmt;
+use cargo_scout_lib::linter::wrapper;
+use cargo_scout_lib::linter::{Lint, Linter};
+use cargo_scout_lib::lock::{target_dir, TargetLock};
+use cargo_scout_lib::output::actions::ActionsFormatter;
+use cargo_scout_lib::output::azure::AzureFormatter;
+use cargo_scout_lib::output::checkstyle::CheckstyleFormatter;
+use cargo_scout_lib::output::human::HumanFormatter;
+use cargo_scout_lib::output::json::JsonFormatter;
+use cargo_scout_lib::output::junit::JunitFormatter;
+use cargo_scout_lib::output::markdown::MarkdownFormatter;
+use cargo_scout_lib::output::rdjson::RdjsonFormatter;
+use cargo_scout_lib::output::sarif::SarifFormatter;
+use cargo_scout_lib::output::teamcity::TeamcityFormatter;
+use cargo_scout_lib::output::vscode::{VscodeFormatter, VSCODE_DIAGNOSTICS_PATH};
+use cargo_scout_lib::output::{
+    docs_url, ColorChoice, Formatter, GroupBy, OutputFormat, OutputSpec, Reporter, RunMetadata,
+};
+use cargo_scout_lib::paths::{PathNormalizer, PathResolver};
+use cargo_scout_lib::policy::allow_failures::{
+    date, now, today, AllowFailures, DEFAULT_ALLOW_FAILURES_PATH,
+};
+use cargo_scout_lib::policy::ratchet::{Ratchet, DEFAULT_RATCHET_PATH};
+use cargo_scout_lib::policy::FailOn;
+use cargo_scout_lib::preflight::doctor::Doctor;
+use cargo_scout_lib::preflight::Preflight;
+use cargo_scout_lib::project::Project;
+use cargo_scout_lib::publish::checks::{head_sha, CheckRun};
+use cargo_scout_lib::publish::comment::{upsert, CommentAction, CurlThread, Provider, PullRequest};
+use cargo_scout_lib::publish::Publisher;
+use cargo_scout_lib::schema::SchemaKind;
+use cargo_scout_lib::scout::scope::Scope;
 use cargo_scout_lib::scout::Scout;
-use cargo_scout_lib::vcs::git::Git;
+use cargo_scout_lib::stats::badge::Badge;
+use cargo_scout_lib::stats::comparison::Comparison;
+use cargo_scout_lib::stats::trend::{code_trends, TrendRecord, TrendStore, DEFAULT_TREND_PATH};
+use cargo_scout_lib::stats::{Statistics, UNKNOWN_CODE};
+use cargo_scout_lib::vcs::git::{
+    head_commit, repo_root, target_branch, Git, RemoteTarget, Worktree,
+};
+use cargo_scout_lib::vcs::VCS;
 use cargo_scout_lib::Error;
+use editor::Editor;
+use progress::{TerminalProgress, Timings};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
 use structopt::StructOpt;
 
+mod editor;
+mod logger;
+mod progress;
+mod tui;
+mod watch;
+
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "cargo-scout",
     author,
     about = "Leave the codebase better than when you found it."
 )]
+#[allow(clippy::struct_excessive_bools)]
 struct Options {
-    #[structopt(short = "v", long = "verbose")]
-    /// Set the verbosity level
-    verbose: bool,
+    #[structopt(short = "v", long = "verbose", parse(from_occurrences))]
+    /// Set the verbosity level, -vv also shows the most detailed messages
+    verbose: u8,
+    #[structopt(short = "q", long = "quiet", conflicts_with = "verbose")]
+    /// Only print the report and errors
+    quiet: bool,
     #[structopt(long = "no-default-features")]
     /// Pass the no default features flag to clippy
     no_default_features: bool,
@@ -25,15 +95,80 @@ struct Options {
     #[structopt(long = "features")]
     /// Pass features to clippy
     features: Option<String>,
+    #[structopt(long = "package", value_name = "name", number_of_values = 1)]
+    /// Only lint this workspace member, changes to other members are ignored
+    packages: Vec<String>,
+    #[structopt(long = "workspace", conflicts_with = "packages")]
+    /// Lint every workspace member, including those the diff doesn't change
+    workspace: bool,
+    #[structopt(long = "with-dependents", alias = "lint-dependents")]
+    /// Also lint the members that depend on the changed ones, and blame their compile errors on the diff
+    with_dependents: bool,
+    #[structopt(long = "all-targets")]
+    /// Lint every target: libraries, binaries, tests, examples and benches
+    all_targets: bool,
+    #[structopt(long = "bins")]
+    /// Lint every binary
+    bins: bool,
+    #[structopt(long = "tests")]
+    /// Lint tests
+    tests: bool,
+    #[structopt(long = "examples")]
+    /// Lint examples
+    examples: bool,
+    #[structopt(long = "benches")]
+    /// Lint benches
+    benches: bool,
+    #[structopt(long = "target", value_name = "triple")]
+    /// Lint for the target triple (e.g. thumbv7em-none-eabihf) instead of the host
+    target: Option<String>,
     #[structopt(
         short = "b",
         long = "branch",
         value_name = "branch",
-        default_value = "master"
+        number_of_values = 1
     )]
-    /// Set the target branch
-    branch: String,
+    /// Set the target branch, the first of several that exists is used.
+    /// Defaults to the branch origin/HEAD points to, or main or master
+    branch: Vec<String>,
+    #[structopt(long = "install-missing")]
+    /// Install the toolchain pinned in rust-toolchain.toml and the components scout needs
+    install_missing: bool,
+    #[structopt(long = "auto-fetch")]
+    /// Fetch the target branch from its remote before diffing
+    auto_fetch: bool,
+    #[structopt(long = "fetch")]
+    /// Fetch the target branch from origin when it doesn't exist locally, as in shallow clones
+    fetch: bool,
+    #[structopt(long = "fetch-depth", value_name = "commits")]
+    /// Only fetch this many commits of the target branch, with --fetch or --remote
+    fetch_depth: Option<u32>,
+    #[structopt(long = "remote", value_name = "url", conflicts_with = "fetch")]
+    /// Diff with the target branch of this repository, which doesn't need to be a configured remote.
+    /// Defaults to its default branch
+    remote: Option<String>,
 
+    #[structopt(long = "include-macro-defs")]
+    /// Also report the lints in changed macro definitions, not only those where the changed code invokes macros
+    include_macro_defs: bool,
+    #[structopt(long = "blame")]
+    /// Report the last commit that changed the lines of each finding, its author, and the oldest commit they come from
+    blame: bool,
+    #[structopt(long = "open")]
+    /// Open each finding in $VISUAL or $EDITOR, one after the other
+    open: bool,
+    #[structopt(long = "editor-cmd", value_name = "template")]
+    /// The command opening a finding, with {file}, {line} and {column} replaced, e.g. "code -g {file}:{line}"
+    editor_cmd: Option<String>,
+    #[structopt(long = "show-hunks")]
+    /// Follow each finding with the lines of your diff it was reported for
+    show_hunks: bool,
+    #[structopt(long = "project-root", value_name = "directory", parse(from_os_str))]
+    /// Run as if scout was started in this directory, relative paths included
+    project_root: Option<PathBuf>,
+    #[structopt(long = "git-root", value_name = "directory", parse(from_os_str))]
+    /// Diff the git repository in this directory, instead of the one the project belongs to
+    git_root: Option<PathBuf>,
     #[structopt(short = "t", long = "cargo-toml", default_value = "./Cargo.toml")]
     /// Pass the path of the `Cargo.toml` file
     cargo_toml: String,
@@ -43,50 +178,1292 @@ struct Options {
     #[structopt(short = "p", long = "preview")]
     /// Enable nightly features (e.g. get lints even after the build has already been done.)
     preview: bool,
+    #[structopt(long = "rustc-wrapper", conflicts_with = "preview")]
+    /// Capture the diagnostics of each crate by running scout as the `RUSTC_WORKSPACE_WRAPPER`
+    rustc_wrapper: bool,
+    #[structopt(long = "target-dir", value_name = "directory", parse(from_os_str))]
+    /// Build in this directory, so scout doesn't invalidate the project's build cache
+    target_dir: Option<PathBuf>,
+    #[structopt(long = "no-cache")]
+    /// Always run clippy, instead of reusing the lints of a run on the same sources
+    no_cache: bool,
+    #[structopt(long = "no-wait")]
+    /// Fail instead of waiting when another scout run uses the same target directory
+    no_wait: bool,
+    #[structopt(short = "c", long = "config", default_value = "./scout.toml")]
+    /// Pass the path of the `scout.toml` configuration file
+    config: String,
+    #[structopt(long = "ignore-lints", value_name = "lints", use_delimiter = true)]
+    /// Comma separated list of lint codes to ignore (e.g. `clippy::module_name_repetitions`)
+    ignore_lints: Vec<String>,
+    #[structopt(long = "only-lints", value_name = "lints", use_delimiter = true)]
+    /// Comma separated list of lint codes to report, all others are ignored
+    only_lints: Vec<String>,
+    #[structopt(long = "include", value_name = "glob", number_of_values = 1)]
+    /// Only consider changes and lints in files matching this glob, relative to the repository root
+    include: Vec<String>,
+    #[structopt(long = "exclude", value_name = "glob", number_of_values = 1)]
+    /// Ignore changes and lints in files matching this glob (e.g. "generated/**")
+    exclude: Vec<String>,
+    #[structopt(long = "fail-on", value_name = "level", possible_values = &["error", "warning", "any"])]
+    /// Set the lint severity that makes the run fail [default: any]
+    fail_on: Option<FailOn>,
+    #[structopt(
+        long = "deny-new",
+        value_name = "code",
+        use_delimiter = true,
+        conflicts_with = "fail-on"
+    )]
+    /// Only fail the run when the diff introduces these lints (e.g. `clippy::unwrap_used,clippy::panic`), the other findings are advisory
+    deny_new: Vec<String>,
+    #[structopt(long = "profile", possible_values = &["security"])]
+    /// Also warn about a preset of lints, `security` for unwraps, panics, indexing and arithmetic overflows
+    profile: Option<Profile>,
+    #[structopt(long = "backend", use_delimiter = true, possible_values = &["audit", "geiger", "unsafe", "text-patterns", "typos", "license-headers", "coverage", "doc"])]
+    /// Also run other checks than clippy: audit, geiger, unsafe, text-patterns, typos, license-headers, coverage or doc, see the README
+    backends: Vec<Backend>,
+    #[structopt(long = "context", value_name = "lines", default_value = "0")]
+    /// Count lints within this many lines of a change as caused by the diff
+    context: u32,
+    #[structopt(
+        long = "scope",
+        value_name = "scope",
+        default_value = "line",
+        possible_values = &["line", "function", "item"]
+    )]
+    /// Attribute lints to the diff if they are on changed lines, in changed functions,
+    /// or in changed items
+    scope: Scope,
+    #[structopt(long = "skip-comment-changes")]
+    /// Don't run clippy when the diff only changes comments and doc comments,
+    /// the backends that don't build the code still check them
+    skip_comment_changes: bool,
+    #[structopt(long = "always-run")]
+    /// Run clippy even if no Rust source, manifest or build script changed
+    always_run: bool,
+    #[structopt(long = "max-changed-files", value_name = "files")]
+    /// Don't run clippy when the diff changes more files than this
+    max_changed_files: Option<usize>,
+    #[structopt(long = "max-changed-lines", value_name = "lines")]
+    /// Don't run clippy when the diff changes more lines than this
+    max_changed_lines: Option<usize>,
+    #[structopt(long = "clean-on-failure")]
+    /// Clear the incremental cache and run clippy again when it's corrupted
+    clean_on_failure: bool,
+    #[structopt(long = "timeout", value_name = "secs")]
+    /// Stop the run, and the cargo processes it started, after this many seconds
+    timeout: Option<u64>,
+    #[structopt(long = "ignore-moved")]
+    /// Treat moved or copied code as not new, so relocated code isn't linted
+    ignore_moved: bool,
+    #[structopt(
+        long = "color",
+        value_name = "when",
+        default_value = "auto",
+        possible_values = &["auto", "always", "never"]
+    )]
+    /// Color the output, `auto` honors the `NO_COLOR` environment variable
+    color: ColorChoice,
+    #[structopt(
+        long = "group-by",
+        value_name = "group",
+        default_value = "file",
+        possible_values = &["file", "code"]
+    )]
+    /// Group findings by file, or by lint code with a link to its documentation and the list of their locations
+    group_by: GroupBy,
+    #[structopt(
+        long = "output",
+        alias = "output-format",
+        value_name = "format[=path]",
+        number_of_values = 1
+    )]
+    /// Add an output, one of human (or text), json, sarif, junit, checkstyle, rdjson, vscode, markdown, azure, teamcity, actions, optionally written to a file [default: human]
+    output: Vec<OutputSpec>,
+    #[structopt(long = "emit", value_name = "target", possible_values = &["vscode"])]
+    /// Also write the findings where an editor integration expects them (.scout/vscode-diagnostics.json)
+    emit: Vec<OutputFormat>,
+    #[structopt(long = "output-file", value_name = "path")]
+    /// Write the first output without a path to a file instead of stdout
+    output_file: Option<String>,
+    #[structopt(long = "timings")]
+    /// Print how long git, clippy and the intersections each took
+    timings: bool,
+    #[structopt(long = "summary")]
+    /// Print a table of lint codes, counts and affected files to stderr, after the warnings
+    summary: bool,
+    #[structopt(long = "baseline", default_value = DEFAULT_BASELINE_PATH)]
+    /// Pass the path of the baseline file, whose findings will not be reported
+    baseline: String,
+    #[structopt(long = "allow-failures", default_value = DEFAULT_ALLOW_FAILURES_PATH)]
+    /// Pass the path of the file listing findings temporarily allowed to fail the run
+    allow_failures: String,
+    #[structopt(long = "ratchet-file", default_value = DEFAULT_RATCHET_PATH)]
+    /// Pass the path of the file holding the amount of findings of each lint the project may have
+    ratchet_file: String,
+    #[structopt(subcommand)]
+    command: Option<Command>,
 }
 
-fn main() -> Result<(), Error> {
-    let opts = Options::from_args();
-    let fail_if_errors = opts.without_error;
+impl Options {
+    // The kinds of targets clippy checks besides the default ones
+    fn target_kinds(&self) -> Vec<TargetKind> {
+        [
+            (self.all_targets, TargetKind::All),
+            (self.bins, TargetKind::Bins),
+            (self.tests, TargetKind::Tests),
+            (self.examples, TargetKind::Examples),
+            (self.benches, TargetKind::Benches),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, kind)| *kind)
+        .collect()
+    }
 
-    let vcs = Git::with_target(opts.branch);
-    let config = CargoConfig::from_manifest_path(opts.cargo_toml)?;
-    let mut linter = Clippy::default();
-    linter
-        .set_verbose(opts.verbose)
+    // Where each report goes, the human one to stdout by default
+    fn outputs(&self) -> Vec<OutputSpec> {
+        let mut outputs = self.output.clone();
+        if outputs.is_empty() {
+            outputs.push(OutputSpec {
+                format: OutputFormat::Human,
+                path: None,
+            });
+        }
+        if let Some(output_file) = &self.output_file {
+            if let Some(spec) = outputs.iter_mut().find(|spec| spec.path.is_none()) {
+                spec.path = Some(output_file.into());
+            }
+        }
+        for format in &self.emit {
+            outputs.push(OutputSpec {
+                format: *format,
+                path: Some(VSCODE_DIAGNOSTICS_PATH.into()),
+            });
+        }
+        outputs
+    }
+
+    // Checks the combinations of arguments clap can't tell apart
+    fn validate(&self) -> Result<(), String> {
+        let mut on_stdout = self
+            .output
+            .iter()
+            .filter(|spec| spec.path.is_none())
+            .count();
+        // --output-file writes the first of them to a file
+        if self.output_file.is_some() {
+            on_stdout = on_stdout.saturating_sub(1);
+        }
+        if on_stdout > 1 {
+            return Err(
+                "error: only one --output can be printed to stdout, write the others to a file with --output <format>=<path>"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    #[structopt(name = "lint")]
+    /// Run clippy and report the lints on the changed lines, what `cargo scout` does without a subcommand
+    Lint,
+    #[structopt(name = "fmt")]
+    /// Run rustfmt on the changed files and report the formatting changes it would make to the changed lines
+    Fmt {
+        #[structopt(long = "fmt-config", value_name = "rustfmt.toml")]
+        /// The rustfmt configuration to use instead of the one rustfmt finds
+        fmt_config: Option<PathBuf>,
+        #[structopt(long = "edition")]
+        /// The edition to parse the files with, instead of the one of their package
+        edition: Option<String>,
+    },
+    #[structopt(name = "baseline")]
+    /// Write every current finding to the baseline file, so subsequent runs ignore them
+    Baseline,
+    #[structopt(name = "ratchet")]
+    /// Record how many findings of each lint the whole project has, so runs fail when one of the amounts goes up
+    Ratchet {
+        #[structopt(subcommand)]
+        command: RatchetCommand,
+    },
+    #[structopt(name = "schema")]
+    /// Print the JSON Schema of an output
+    Schema {
+        #[structopt(possible_values = &["findings", "sections", "run-metadata"])]
+        kind: SchemaKind,
+    },
+    #[structopt(name = "explain")]
+    /// Print the documentation of a lint or error code, e.g. `clippy::needless_pass_by_value` or E0308
+    Explain { code: String },
+    #[structopt(name = "doctor")]
+    /// Print what scout finds out about the toolchains, tools and configuration files
+    Doctor,
+    #[structopt(name = "upgrade-check")]
+    /// Report how the findings of the whole project would differ with another toolchain
+    UpgradeCheck {
+        #[structopt(long = "toolchain", value_name = "toolchain")]
+        /// The candidate toolchain, as rustup names it (e.g. 1.80.0, beta, nightly-2024-06-01)
+        toolchain: String,
+    },
+    #[structopt(name = "stats")]
+    /// Record the findings of the whole project, and show how they changed over time
+    Stats {
+        #[structopt(long = "trend-file", default_value = DEFAULT_TREND_PATH)]
+        /// The file the records are appended to
+        trend_file: String,
+        #[structopt(subcommand)]
+        command: StatsCommand,
+    },
+    #[structopt(name = "preview-config")]
+    /// Report how the findings and exit status would differ with another configuration
+    PreviewConfig {
+        #[structopt(long = "against", value_name = "old-scout.toml")]
+        /// The configuration to compare the current one against
+        against: String,
+    },
+    #[structopt(name = "install-hook")]
+    /// Install a git hook that runs scout, against the upstream branch
+    InstallHook {
+        #[structopt(long = "hook", default_value = "pre-push", possible_values = &["pre-commit", "pre-push"])]
+        hook: HookKind,
+        #[structopt(long = "hook-mode", default_value = "full", possible_values = &["full", "fast"])]
+        /// `fast` only checks the formatting with rustfmt
+        mode: HookMode,
+        #[structopt(long = "force")]
+        /// Overwrite a hook that wasn't installed by scout
+        force: bool,
+    },
+    #[structopt(name = "uninstall-hook")]
+    /// Remove a git hook installed by scout
+    UninstallHook {
+        #[structopt(long = "hook", default_value = "pre-push", possible_values = &["pre-commit", "pre-push"])]
+        hook: HookKind,
+    },
+    #[structopt(name = "tui")]
+    /// Browse the findings, open them in $EDITOR, and ignore them by adding them to the baseline
+    Tui,
+    #[structopt(name = "watch")]
+    /// Lint the changes again every time a file is saved
+    Watch,
+    #[structopt(name = "comment")]
+    /// Run, then post the Markdown report on the pull request the CI job runs for, or update the one a previous run posted
+    Comment {
+        #[structopt(long = "provider", possible_values = &["github", "gitlab"])]
+        /// The code host, GitHub needs `GITHUB_TOKEN` and GitLab needs `GITLAB_TOKEN` in the environment
+        provider: Provider,
+    },
+    #[structopt(name = "bench")]
+    /// Run the criterion benchmarks of the changed crates on the target branch and on HEAD, and fail if one got slower
+    Bench {
+        #[structopt(long = "threshold", default_value = "5", value_name = "percent")]
+        /// How much slower a benchmark can get, in percent of its mean time
+        threshold: f64,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum StatsCommand {
+    #[structopt(name = "record")]
+    /// Lint the whole project, and append the amount of findings per lint code and crate
+    Record,
+    #[structopt(name = "show")]
+    /// Show how the amount of findings changed over the last records
+    Show {
+        #[structopt(long = "last", default_value = "10")]
+        last: usize,
+    },
+    #[structopt(name = "badge")]
+    /// Write a badge with the amount of findings of the latest record
+    Badge {
+        #[structopt(long = "svg", default_value = ".scout/badge.svg")]
+        /// Where to write the SVG image
+        svg: PathBuf,
+        #[structopt(long = "endpoint", value_name = "path")]
+        /// Also write the JSON a shields.io endpoint badge reads
+        endpoint: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum RatchetCommand {
+    #[structopt(name = "init")]
+    /// Lint the whole project, and write the amount of findings of each lint to the ratchet file
+    Init,
+    #[structopt(name = "update")]
+    /// Lint the whole project, and lower the amounts of the ratchet file to the current ones
+    Update,
+}
+
+fn main() {
+    // With --rustc-wrapper, cargo runs scout itself for every workspace crate
+    if wrapper::is_wrapper_invocation() {
+        match wrapper::wrap(std::env::args_os().skip(1)) {
+            Ok(code) => std::process::exit(code),
+            Err(e) => exit_with(&e),
+        }
+    }
+    // When run as `cargo scout`, cargo passes `scout` as the first argument
+    let args = std::env::args()
+        .enumerate()
+        .filter(|(i, arg)| !(*i == 1 && arg == "scout"))
+        .map(|(_, arg)| arg);
+    let opts = match Options::from_iter_safe(args) {
+        Ok(opts) => opts,
+        Err(e) if e.use_stderr() => {
+            eprintln!("{}", e.message);
+            std::process::exit(EXIT_USAGE);
+        }
+        // --help and --version
+        Err(e) => e.exit(),
+    };
+    if let Err(message) = opts.validate() {
+        eprintln!("{message}");
+        std::process::exit(EXIT_USAGE);
+    }
+    if let Err(e) = run(opts) {
+        exit_with(&e);
+    }
+}
+
+fn exit_with(error: &Error) -> ! {
+    eprintln!("Error: {error}");
+    std::process::exit(error.exit_code());
+}
+
+fn run(opts: Options) -> Result<(), Error> {
+    logger::init(logger::level_filter(opts.quiet, opts.verbose));
+    // The git root is relative to where scout was started
+    let git_root = opts
+        .git_root
+        .as_ref()
+        .map(std::fs::canonicalize)
+        .transpose()?;
+    if let Some(dir) = &opts.project_root {
+        std::env::set_current_dir(dir)?;
+    }
+    let repo_dir = match &git_root {
+        Some(root) => root.clone(),
+        None => std::env::current_dir()?,
+    };
+    if run_without_linting(&opts, &repo_dir)? {
+        return Ok(());
+    }
+    let branch = target_branch(&repo_dir, &opts.branch);
+    let remote_target = match &opts.remote {
+        Some(url) => {
+            let remote_branch = opts.branch.first().map_or("HEAD", String::as_str);
+            Some(RemoteTarget::fetch(
+                &repo_dir,
+                url,
+                remote_branch,
+                opts.fetch_depth,
+            )?)
+        }
+        None => None,
+    };
+    let branch = remote_target
+        .as_ref()
+        .map_or(branch, |target| target.reference().to_string());
+    log::info!("Diffing with {branch}");
+    let mut vcs = Git::with_target(branch.clone());
+    vcs.set_detect_moves(opts.ignore_moved)
+        .set_root(git_root.clone());
+    if opts.fetch {
+        vcs.fetch_missing_target(&repo_dir, opts.fetch_depth)?;
+    }
+    let mut preflight = Preflight::new(std::env::current_dir()?, branch.clone());
+    preflight
+        .set_git_root(git_root.clone())
+        .set_nightly(opts.preview)
+        .set_install_missing(opts.install_missing);
+    preflight.check()?;
+
+    let mut metadata = RunMetadata::new(env!("CARGO_PKG_VERSION"));
+    metadata.set_target_branch(Some(branch.clone()));
+    if opts.auto_fetch {
+        vcs.fetch_target(".")?;
+    } else if let Some(behind) = vcs.behind_upstream(".")?.filter(|behind| *behind > 0) {
+        log::warn!(
+            "{} is {} commit{} behind its remote branch, the diff may include changes that are already merged. Pull it, or run with --auto-fetch",
+            branch,
+            behind,
+            if behind == 1 { "" } else { "s" }
+        );
+    }
+    let scout_config = scout_config(&opts)?;
+    Session::new(opts, vcs, git_root, branch, metadata, scout_config)?.run()
+}
+
+// Runs the commands that don't lint the code, returns false for the other ones
+fn run_without_linting(opts: &Options, repo_dir: &Path) -> Result<bool, Error> {
+    match &opts.command {
+        Some(Command::Schema { kind }) => print!("{}", kind.schema()),
+        Some(Command::Explain { code }) => {
+            print!("{}", explain(code)?);
+            if let Some(url) = docs_url(code) {
+                println!("\nSee {url}");
+            }
+        }
+        Some(Command::Stats {
+            trend_file,
+            command: StatsCommand::Show { last },
+        }) => {
+            let records = TrendStore::new(trend_file).records()?;
+            display_trend(&records[records.len().saturating_sub(*last)..]);
+        }
+        Some(Command::Stats {
+            trend_file,
+            command: StatsCommand::Badge { svg, endpoint },
+        }) => {
+            let badge = Badge::from_record(TrendStore::new(trend_file).records()?.last());
+            write_file(svg, &badge.svg())?;
+            if let Some(endpoint) = endpoint {
+                write_file(endpoint, &badge.endpoint())?;
+            }
+            println!("{}: {}", badge.label, badge.message);
+        }
+        Some(Command::InstallHook { hook, mode, force }) => {
+            let path = hooks::install(hooks::hooks_dir(repo_dir)?, *hook, *mode, *force)?;
+            println!("Installed {}", path.display());
+        }
+        Some(Command::UninstallHook { hook }) => {
+            let hooks_dir = hooks::hooks_dir(repo_dir)?;
+            if hooks::uninstall(&hooks_dir, *hook)? {
+                println!("Removed {}", hooks_dir.join(hook.name()).display());
+            } else {
+                println!("There is no {} hook to remove", hook.name());
+            }
+        }
+        Some(Command::Doctor) => {
+            let branch = target_branch(repo_dir, &opts.branch);
+            let mut doctor = Doctor::new(std::env::current_dir()?, branch);
+            doctor
+                .set_manifest(opts.cargo_toml.clone().into())
+                .set_config(opts.config.clone().into())
+                .set_allow_failures(opts.allow_failures.clone().into())
+                .set_baseline(opts.baseline.clone().into());
+            println!("cargo-scout {}", env!("CARGO_PKG_VERSION"));
+            let report = doctor.report();
+            print!("{report}");
+            if !report.is_healthy() {
+                std::process::exit(EXIT_TOOL_FAILURE);
+            }
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+// The configuration file, with the options of the command line on top of it
+fn scout_config(opts: &Options) -> Result<ScoutConfig, Error> {
+    let mut scout_config = ScoutConfig::from_path(&opts.config)?;
+    scout_config.fail_on = opts.fail_on.or(scout_config.fail_on);
+    scout_config.deny_new.extend_from_slice(&opts.deny_new);
+    scout_config.profile = opts.profile.or(scout_config.profile);
+    for backend in &opts.backends {
+        if !scout_config.backends.contains(backend) {
+            scout_config.backends.push(*backend);
+        }
+    }
+    scout_config
+        .ignore_lints
+        .extend_from_slice(&opts.ignore_lints);
+    scout_config.only_lints.extend_from_slice(&opts.only_lints);
+    scout_config.include.extend_from_slice(&opts.include);
+    scout_config.exclude.extend_from_slice(&opts.exclude);
+    scout_config.max_changed_files = opts.max_changed_files.or(scout_config.max_changed_files);
+    scout_config.max_changed_lines = opts.max_changed_lines.or(scout_config.max_changed_lines);
+    scout_config.path_filter().validate()?;
+    Ok(scout_config)
+}
+
+fn cargo_config(opts: &Options) -> Result<CargoConfig, Error> {
+    let mut config = CargoConfig::from_manifest_path(&opts.cargo_toml)?;
+    if !opts.packages.is_empty() {
+        config.select_packages(&opts.packages)?;
+    }
+    Ok(config)
+}
+
+// Clippy as the options configure it, on the default toolchain
+fn clippy(
+    opts: &Options,
+    scout_config: &ScoutConfig,
+    cancellation: Option<CancellationToken>,
+) -> Result<Clippy, Error> {
+    // Clippy runs in each member's directory, so the target directory must be absolute
+    let custom_target_dir = match &opts.target_dir {
+        Some(dir) => Some(std::env::current_dir()?.join(dir)),
+        None => None,
+    };
+    let wrapper = if opts.rustc_wrapper {
+        Some(std::env::current_exe()?)
+    } else {
+        None
+    };
+    let mut clippy = Clippy::default();
+    clippy
+        .set_target_dir(custom_target_dir)
+        .set_verbose(opts.verbose > 0)
         .set_no_default_features(opts.no_default_features)
         .set_all_features(opts.all_features)
-        .set_features(opts.features)
-        .set_preview(opts.preview);
+        .set_features(opts.features.clone())
+        .set_target(opts.target.clone())
+        .set_target_kinds(opts.target_kinds())
+        .set_lints(scout_config.clippy_lints())
+        .set_preview(opts.preview)
+        .set_wrapper(wrapper)
+        .set_clean_on_failure(opts.clean_on_failure)
+        .set_cancellation(cancellation);
+    Ok(clippy)
+}
+
+// Clippy on `toolchain`, reusing the lints of previous runs unless --no-cache is given
+fn cached(
+    mut clippy: Clippy,
+    toolchain: Option<String>,
+    opts: &Options,
+    target_dir: &Path,
+) -> CachedLinter<Clippy> {
+    clippy.set_toolchain(toolchain);
+    let mut linter = CachedLinter::new(clippy);
+    linter
+        .set_enabled(!opts.no_cache)
+        .set_dir(target_dir.join("scout-cache"));
+    linter
+}
 
-    let scout = Scout::new(vcs, config, linter);
-    let relevant_lints = scout.run()?;
-    return_warnings(&relevant_lints, fail_if_errors)
+// What the commands that lint the code share
+struct Session {
+    opts: Options,
+    scout_config: ScoutConfig,
+    scout: Scout<Git, CargoConfig, Box<dyn Linter>>,
+    clippy: Clippy,
+    cancellation: Option<CancellationToken>,
+    git_root: Option<PathBuf>,
+    repo_root: PathBuf,
+    branch: String,
+    target_dir: PathBuf,
+    normalizer: PathNormalizer,
+    resolver: PathResolver,
+    formatter: HumanFormatter,
+    progress: Rc<TerminalProgress>,
+    metadata: RunMetadata,
+    _lock: TargetLock,
 }
 
-fn return_warnings(lints: &[Lint], without_error: bool) -> Result<(), Error> {
-    if lints.is_empty() {
-        println!("No warnings raised by clippy::pedantic in your diff, you're good to go!");
+impl Session {
+    fn new(
+        opts: Options,
+        vcs: Git,
+        git_root: Option<PathBuf>,
+        branch: String,
+        metadata: RunMetadata,
+        scout_config: ScoutConfig,
+    ) -> Result<Self, Error> {
+        let config = cargo_config(&opts)?;
+        let current_dir = std::env::current_dir()?;
+        let target_dir = match &opts.target_dir {
+            Some(dir) => current_dir.join(dir),
+            None => target_dir(&current_dir),
+        };
+        let cancellation = opts
+            .timeout
+            .map(|secs| CancellationToken::with_timeout(Duration::from_secs(secs)));
+        let clippy = clippy(&opts, &scout_config, cancellation.clone())?;
+        let mut formatter = HumanFormatter::new(opts.color.enabled());
+        formatter
+            .set_group_by(opts.group_by)
+            .set_reproducers(opts.verbose > 0)
+            .set_hunks(opts.show_hunks);
+        let progress = Rc::new(TerminalProgress::default());
+        let lock = TargetLock::acquire(&target_dir, !opts.no_wait)?;
+        let repo_root = match &git_root {
+            Some(root) => root.clone(),
+            None => repo_root(&current_dir)?,
+        };
+        let normalizer = PathNormalizer::new(repo_root.clone(), config.workspace_root()?);
+        let resolver = PathResolver::new(
+            config
+                .members()
+                .iter()
+                .map(|m| normalizer.repo_dir(&current_dir, m))
+                .collect(),
+        );
+        let linter = cached(clippy.clone(), None, &opts, &target_dir);
+        let scout_linter: Box<dyn Linter> = match &opts.command {
+            // The formatting doesn't depend on the features and targets clippy builds
+            Some(Command::Fmt {
+                fmt_config,
+                edition,
+            }) => {
+                let mut rustfmt = RustFmt::default();
+                rustfmt
+                    .set_config_path(fmt_config.as_ref().map(|c| current_dir.join(c)))
+                    .set_edition(edition.clone());
+                Box::new(rustfmt)
+            }
+            // The backends aren't cached, their findings change with the advisory database
+            _ if !scout_config.backends.is_empty() => {
+                let mut linters: Vec<Box<dyn Linter>> = vec![Box::new(linter)];
+                linters.extend(scout_config.backend_linters()?);
+                Box::new(linters)
+            }
+            _ => Box::new(linter),
+        };
+        let mut scout = Scout::new(vcs, config, scout_linter);
+        scout
+            .set_progress(Box::new(Rc::clone(&progress)))
+            .set_context(opts.context)
+            .set_scope(opts.scope)
+            .set_skip_comment_changes(opts.skip_comment_changes)
+            .set_blame(opts.blame)
+            .set_include_macro_defs(opts.include_macro_defs)
+            .set_always_run(opts.always_run)
+            .set_whole_workspace(opts.workspace)
+            .set_lint_dependents(opts.with_dependents)
+            .set_limits(scout_config.diff_limits())
+            .set_path_normalizer(normalizer.clone())
+            .set_path_filter(scout_config.path_filter());
+        if let Some(cancellation) = &cancellation {
+            scout.set_cancellation(cancellation.clone());
+        }
+        Ok(Self {
+            opts,
+            scout_config,
+            scout,
+            clippy,
+            cancellation,
+            git_root,
+            repo_root,
+            branch,
+            target_dir,
+            normalizer,
+            resolver,
+            formatter,
+            progress,
+            metadata,
+            _lock: lock,
+        })
+    }
+
+    fn run(mut self) -> Result<(), Error> {
+        match self.opts.command.take() {
+            Some(Command::Baseline) => {
+                let baseline = Baseline::from_lints(&self.scout.lints()?);
+                baseline.save(&self.opts.baseline)?;
+                println!(
+                    "Wrote {} findings to {}",
+                    baseline.entries().len(),
+                    self.opts.baseline
+                );
+                Ok(())
+            }
+            Some(Command::Stats {
+                trend_file,
+                command: StatsCommand::Record,
+            }) => {
+                let lints = self.scout_config.lint_filter().filter(self.scout.lints()?);
+                let record = TrendRecord::new(
+                    head_commit(&self.repo_root)?,
+                    now(),
+                    &Statistics::from_lints(&lints),
+                );
+                TrendStore::new(&trend_file).append(&record)?;
+                println!("Recorded {} findings to {}", record.total, trend_file);
+                Ok(())
+            }
+            Some(Command::Ratchet { command }) => self.ratchet(&command),
+            Some(Command::PreviewConfig { against }) => {
+                let old_config = ScoutConfig::from_path(against)?;
+                self.scout
+                    .set_baseline(Baseline::from_path(&self.opts.baseline)?);
+                let lints: Vec<Lint> = self.scout.run()?.into_iter().map(Lint::from).collect();
+                let preview = self.scout_config.preview(&old_config, &lints);
+                display_preview(&self.formatter, &preview, &self.metadata);
+                Ok(())
+            }
+            Some(Command::UpgradeCheck { toolchain }) => self.upgrade_check(&toolchain),
+            Some(Command::Bench { threshold }) => self.bench(threshold),
+            Some(
+                Command::Schema { .. }
+                | Command::Explain { .. }
+                | Command::Doctor
+                | Command::InstallHook { .. }
+                | Command::UninstallHook { .. }
+                | Command::Stats {
+                    command: StatsCommand::Show { .. } | StatsCommand::Badge { .. },
+                    ..
+                },
+            ) => {
+                unreachable!("handled before linting")
+            }
+            Some(Command::Tui) => {
+                let baseline = Baseline::from_path(&self.opts.baseline)?;
+                self.scout
+                    .set_filter(self.scout_config.lint_filter())
+                    .set_baseline(baseline.clone());
+                let lints: Vec<Lint> = self.scout.run()?.into_iter().map(Lint::from).collect();
+                let app = tui::App::new(
+                    lints,
+                    &self.resolver,
+                    baseline,
+                    self.opts.baseline.into(),
+                    self.repo_root,
+                );
+                tui::run(app, &Editor::new(self.opts.editor_cmd))
+            }
+            Some(Command::Watch) => {
+                self.scout
+                    .set_filter(self.scout_config.lint_filter())
+                    .set_baseline(Baseline::from_path(&self.opts.baseline)?);
+                let watcher =
+                    watch::ChangeWatcher::new(&self.repo_root, vec![self.target_dir.clone()])?;
+                let mut terminal = self.formatter.clone();
+                terminal.set_totals(true);
+                watch::watch(&mut self.scout, &terminal, &self.metadata, &watcher)
+            }
+            Some(Command::Fmt { .. }) => self.lint(None, true),
+            Some(Command::Comment { provider }) => self.lint(Some(provider), false),
+            Some(Command::Lint) | None => self.lint(None, false),
+        }
+    }
+
+    fn ratchet(&self, command: &RatchetCommand) -> Result<(), Error> {
+        let path = &self.opts.ratchet_file;
+        match command {
+            RatchetCommand::Init => {
+                if Path::new(path).exists() {
+                    return Err(Error::Usage(format!(
+                        "{path} already exists, run `cargo scout ratchet update` to lower its budgets"
+                    )));
+                }
+                let lints = self.scout_config.lint_filter().filter(self.scout.lints()?);
+                let ratchet = Ratchet::from_lints(&lints);
+                ratchet.save(path)?;
+                println!(
+                    "Wrote the budgets of {} lints to {}",
+                    ratchet.budgets.len(),
+                    path
+                );
+            }
+            RatchetCommand::Update => {
+                let Some(ratchet) = Ratchet::from_path(path)? else {
+                    return Err(Error::Usage(format!(
+                        "{path} doesn't exist, run `cargo scout ratchet init` first"
+                    )));
+                };
+                let lints = self.scout_config.lint_filter().filter(self.scout.lints()?);
+                let tightened = ratchet.tightened(&lints);
+                tightened.save(path)?;
+                println!(
+                    "Lowered the budgets of {} lints in {}",
+                    ratchet.check(&lints).decreased.len(),
+                    path
+                );
+            }
+        }
         Ok(())
-    } else {
-        display_warnings(&lints);
-        if without_error {
+    }
+
+    fn upgrade_check(&self, toolchain: &str) -> Result<(), Error> {
+        let current = self.scout.lints()?;
+        let mut git = Git::with_target(self.branch.clone());
+        git.set_root(self.git_root.clone());
+        let linter = cached(
+            self.clippy.clone(),
+            Some(toolchain.to_string()),
+            &self.opts,
+            &self.target_dir,
+        );
+        let mut candidate = Scout::new(git, cargo_config(&self.opts)?, linter);
+        candidate
+            .set_progress(Box::new(Rc::clone(&self.progress)))
+            .set_path_normalizer(self.normalizer.clone())
+            .set_path_filter(self.scout_config.path_filter());
+        if let Some(cancellation) = &self.cancellation {
+            candidate.set_cancellation(cancellation.clone());
+        }
+        let filter = self.scout_config.lint_filter();
+        let comparison =
+            Comparison::new(&filter.filter(current), &filter.filter(candidate.lints()?));
+        display_comparison(&self.formatter, &comparison, toolchain, &self.metadata);
+        Ok(())
+    }
+
+    fn bench(&self, threshold: f64) -> Result<(), Error> {
+        let mut git = Git::with_target(self.branch.clone());
+        git.set_root(self.git_root.clone());
+        let repo_root = std::fs::canonicalize(&self.repo_root)?;
+        let changed_files: Vec<PathBuf> = git
+            .sections(&repo_root)?
+            .iter()
+            .map(|s| repo_root.join(&s.file_name))
+            .collect();
+        let manifest = std::fs::canonicalize(&self.opts.cargo_toml)?;
+        let packages = impacted_packages(&Project::from_manifest_path(&manifest)?, &changed_files);
+        if packages.is_empty() {
+            println!("The changes don't impact any crate with benchmarks");
+            return Ok(());
+        }
+        // The worktree mirrors the repository, the manifest is at the same place in it
+        let worktree = Worktree::add(&repo_root, &self.branch)?;
+        let target_manifest = worktree
+            .path()
+            .join(manifest.strip_prefix(&repo_root).unwrap_or(&manifest));
+        let target_project = Project::from_manifest_path(&target_manifest)?;
+        let target_packages: Vec<String> = packages
+            .iter()
+            .filter(|p| target_project.packages().iter().any(|t| &t.name == *p))
+            .cloned()
+            .collect();
+        let dir = |manifest: &Path| manifest.parent().map(Path::to_path_buf).unwrap_or_default();
+        let mut bench = Bench::new(self.target_dir.join("scout-bench"));
+        // Crates that are new have nothing to compare to
+        let before = if target_packages.is_empty() {
+            BenchResults::default()
+        } else {
+            bench
+                .set_packages(target_packages)
+                .run(&dir(&target_manifest), "target")?
+        };
+        drop(worktree);
+        let after = bench.set_packages(packages).run(&dir(&manifest), "head")?;
+        let changes = compare(&before, &after);
+        display_bench(&changes, threshold, &self.branch);
+        let regressions: Vec<String> = changes
+            .iter()
+            .filter(|c| c.is_regression(threshold))
+            .map(|c| c.id.clone())
+            .collect();
+        if regressions.is_empty() {
             Ok(())
         } else {
-            Err(Error::NotClean)
+            Err(Error::BenchRegressions(regressions))
+        }
+    }
+
+    // Reports the findings of the diff, and fails the run depending on them
+    fn lint(mut self, comment_provider: Option<Provider>, is_fmt: bool) -> Result<(), Error> {
+        self.scout
+            .set_filter(self.scout_config.lint_filter())
+            .set_baseline(Baseline::from_path(&self.opts.baseline)?);
+        let mut tracked_lints = self.scout.run()?;
+        let deny_new = self.scout_config.deny_new();
+        for tracked in &mut tracked_lints {
+            deny_new.override_level(&mut tracked.lint);
+        }
+        let relevant_lints: Vec<Lint> = tracked_lints.iter().cloned().map(Lint::from).collect();
+        let mut terminal = self.formatter.clone();
+        terminal.set_totals(true);
+        let mut reporter = Reporter::default();
+        for spec in self.opts.outputs() {
+            let sink = if spec.format == OutputFormat::Human && spec.path.is_none() {
+                Box::new(terminal.clone())
+            } else {
+                report_formatter(spec.format, self.opts.group_by, self.resolver.clone())
+            };
+            reporter.add_sink(sink, spec.path);
+        }
+        // Every output goes to a file, keep telling the user what was found
+        if !reporter.writes_to_stdout() {
+            reporter.add_sink(Box::new(terminal), None);
+        }
+        reporter.report_tracked(&tracked_lints, &self.metadata)?;
+        if self.opts.open {
+            open_findings(
+                &Editor::new(self.opts.editor_cmd.clone()),
+                &self.resolver,
+                &self.repo_root,
+                &relevant_lints,
+            );
+        }
+        if let Some(provider) = comment_provider {
+            let report = MarkdownFormatter::new(self.resolver.clone())
+                .format_tracked(&tracked_lints, &self.metadata);
+            post_comment(provider, &report, &relevant_lints, &self.resolver)?;
+        }
+        if self.opts.summary {
+            display_summary(&Statistics::from_lints(&relevant_lints));
+        }
+        if self.opts.timings {
+            display_timings(&self.progress.timings());
+        }
+        let failing = failing_lints(
+            &AllowFailures::from_path(&self.opts.allow_failures)?,
+            &relevant_lints,
+        );
+        // The ratchet counts the clippy findings of the whole project,
+        // and decides whether the run fails instead of the findings of the diff
+        if !is_fmt && !self.opts.without_error {
+            if let Some(ratchet) = Ratchet::from_path(&self.opts.ratchet_file)? {
+                let lints = self.scout_config.lint_filter().filter(self.scout.lints()?);
+                return check_ratchet(&ratchet, &lints, &relevant_lints);
+            }
         }
+        return_warnings(
+            &failing,
+            self.opts.without_error,
+            self.scout_config.fail_on(),
+        )
     }
 }
 
-fn display_warnings(warnings: &[Lint]) {
-    for w in warnings {
-        if let Some(m) = &w.message {
-            for l in m.rendered.split('\n') {
-                println!("{}", l);
+// The findings that fail the run, without the ones that are temporarily allowed to
+fn failing_lints(allow_failures: &AllowFailures, lints: &[Lint]) -> Vec<Lint> {
+    let today = today();
+    for expired in allow_failures.expired(&today) {
+        log::warn!(
+            "Allowed failure owned by {} expired on {}",
+            expired.owner,
+            expired.expires
+        );
+    }
+    let failing = allow_failures.failing(lints, &today);
+    if failing.len() < lints.len() {
+        log::info!(
+            "{} findings are temporarily allowed to fail",
+            lints.len() - failing.len()
+        );
+    }
+    failing
+}
+
+// Fails when the whole project has more findings of a lint than its budget,
+// pointing at the findings of the diff that could be responsible
+fn check_ratchet(ratchet: &Ratchet, lints: &[Lint], diff_lints: &[Lint]) -> Result<(), Error> {
+    let check = ratchet.check(lints);
+    for change in &check.decreased {
+        log::info!(
+            "{} went down from {} to {} findings, run `cargo scout ratchet update` to lower its budget",
+            change.code,
+            change.budget,
+            change.count
+        );
+    }
+    if check.exceeded.is_empty() {
+        return Ok(());
+    }
+    for change in &check.exceeded {
+        let locations: Vec<String> = diff_lints
+            .iter()
+            .filter(|l| l.code().unwrap_or(UNKNOWN_CODE) == change.code)
+            .filter_map(Lint::primary_span)
+            .map(|s| format!("{}:{}", s.file_name, s.line_start))
+            .collect();
+        if locations.is_empty() {
+            log::error!(
+                "{} went up from {} to {} findings, outside of the diff",
+                change.code,
+                change.budget,
+                change.count
+            );
+        } else {
+            log::error!(
+                "{} went up from {} to {} findings, the diff introduces {}",
+                change.code,
+                change.budget,
+                change.count,
+                locations.join(", ")
+            );
+        }
+    }
+    Err(Error::RatchetExceeded(
+        check.exceeded.into_iter().map(|c| c.code).collect(),
+    ))
+}
+
+// The formatter used for machine readable reports, they are never colored
+fn report_formatter(
+    output: OutputFormat,
+    group_by: GroupBy,
+    resolver: PathResolver,
+) -> Box<dyn Formatter> {
+    match output {
+        OutputFormat::Human => {
+            let mut formatter = HumanFormatter::new(false);
+            formatter.set_group_by(group_by);
+            Box::new(formatter)
+        }
+        OutputFormat::Json => Box::new(JsonFormatter::new(resolver)),
+        OutputFormat::Sarif => Box::new(SarifFormatter::new(resolver)),
+        OutputFormat::Junit => Box::new(JunitFormatter::default()),
+        OutputFormat::Checkstyle => Box::new(CheckstyleFormatter::new(resolver)),
+        OutputFormat::Rdjson => Box::new(RdjsonFormatter::new(resolver)),
+        OutputFormat::Vscode => Box::new(VscodeFormatter::new(resolver)),
+        OutputFormat::Markdown => Box::new(MarkdownFormatter::new(resolver)),
+        OutputFormat::Azure => Box::new(AzureFormatter::new(resolver)),
+        OutputFormat::Teamcity => Box::new(TeamcityFormatter::new(resolver)),
+        OutputFormat::Actions => Box::new(ActionsFormatter::new(resolver)),
+    }
+}
+
+// Waits for the editor to exit before opening the next finding
+fn open_findings(editor: &Editor, resolver: &PathResolver, repo_root: &Path, lints: &[Lint]) {
+    for lint in lints {
+        let (Some(located), Some(span)) = (resolver.resolve_lint(lint), lint.primary_span()) else {
+            continue;
+        };
+        let file = repo_root.join(&located.repo_path);
+        match editor
+            .command(&file, span.line_start, span.column_start)
+            .status()
+        {
+            Ok(status) if status.success() => {}
+            Ok(status) => log::warn!("{} exited with {}", editor.name(), status),
+            Err(e) => {
+                log::warn!("Could not run {}: {}", editor.name(), e);
+                return;
             }
         }
     }
-    println!("Clippy::pedantic found {} warnings", warnings.len());
+}
+
+fn post_comment(
+    provider: Provider,
+    report: &str,
+    lints: &[Lint],
+    resolver: &PathResolver,
+) -> Result<(), Error> {
+    let Some(pull_request) = PullRequest::from_env(provider, |name| std::env::var(name).ok())?
+    else {
+        log::warn!("The job doesn't run for a pull request, the report isn't posted");
+        return Ok(());
+    };
+    let number = pull_request.number();
+    let publisher = Publisher::default();
+    match upsert(
+        &publisher,
+        &mut CurlThread::new(pull_request.clone()),
+        report,
+    )? {
+        CommentAction::Created => log::info!("Posted the report on #{number}"),
+        CommentAction::Updated(id) => {
+            log::info!("Updated the report in comment {id} on #{number}");
+        }
+        CommentAction::Unchanged(_) => log::info!("The report on #{number} is up to date"),
+    }
+    if provider == Provider::Github && !lints.is_empty() {
+        annotate(&publisher, pull_request, lints, resolver);
+    }
+    Ok(())
+}
+
+// Annotates the findings on the changed lines with a check run.
+// The report is already posted, so a token that can't create check runs only gets a warning
+fn annotate(
+    publisher: &Publisher,
+    pull_request: PullRequest,
+    lints: &[Lint],
+    resolver: &PathResolver,
+) {
+    let Some(head_sha) = head_sha(|name| std::env::var(name).ok()) else {
+        log::warn!("GITHUB_SHA is not set, the findings aren't annotated");
+        return;
+    };
+    let summary = format!("{} findings on the changed lines", lints.len());
+    let mut check_run = CheckRun::new(pull_request, head_sha, resolver.clone(), summary);
+    match publisher.publish(&mut check_run, lints) {
+        Ok(report) if report.not_annotated > 0 => log::warn!(
+            "Annotated {} findings, the {} others are only in the report",
+            report.annotated,
+            report.not_annotated
+        ),
+        Ok(report) => log::info!("Annotated {} findings", report.annotated),
+        Err(e) => log::warn!("The findings aren't annotated: {e}"),
+    }
+}
+
+fn display_preview(formatter: &HumanFormatter, preview: &ConfigPreview, metadata: &RunMetadata) {
+    println!("{} new findings would be reported:", preview.added.len());
+    print!("{}", formatter.format(&preview.added, metadata));
+    println!(
+        "{} findings would not be reported anymore:",
+        preview.removed.len()
+    );
+    print!("{}", formatter.format(&preview.removed, metadata));
+    let status = |fails| if fails { "fail" } else { "pass" };
+    println!(
+        "The run would {} with the new configuration (it would {} with the old one)",
+        status(preview.new_fails),
+        status(preview.old_fails)
+    );
+}
+
+fn display_comparison(
+    formatter: &HumanFormatter,
+    comparison: &Comparison,
+    toolchain: &str,
+    metadata: &RunMetadata,
+) {
+    println!(
+        "{} new findings would be raised with {}:",
+        comparison.added.len(),
+        toolchain
+    );
+    print!("{}", formatter.format(&comparison.added, metadata));
+    println!(
+        "{} findings would not be raised anymore with {}:",
+        comparison.removed.len(),
+        toolchain
+    );
+    print!("{}", formatter.format(&comparison.removed, metadata));
+    let codes = |codes: &BTreeSet<String>| {
+        if codes.is_empty() {
+            "none".to_string()
+        } else {
+            codes.iter().cloned().collect::<Vec<_>>().join(", ")
+        }
+    };
+    println!(
+        "Lint codes that start firing: {}",
+        codes(&comparison.new_codes)
+    );
+    println!(
+        "Lint codes that stop firing: {}",
+        codes(&comparison.gone_codes)
+    );
+}
+
+fn display_bench(changes: &[BenchChange], threshold: f64, branch: &str) {
+    if changes.is_empty() {
+        println!("No benchmark ran on both {branch} and HEAD");
+        return;
+    }
+    let width = changes
+        .iter()
+        .map(|c| c.id.len())
+        .chain(std::iter::once("benchmark".len()))
+        .max()
+        .unwrap_or_default();
+    println!(
+        "{:width$}  {:>12}  {:>12}  {:>8}",
+        "benchmark",
+        branch,
+        "HEAD",
+        "change",
+        width = width
+    );
+    for change in changes {
+        println!(
+            "{:width$}  {:>12}  {:>12}  {:>+7.1}%{}",
+            change.id,
+            format_time(change.before),
+            format_time(change.after),
+            change.percent(),
+            if change.is_regression(threshold) {
+                "  regressed"
+            } else {
+                ""
+            },
+            width = width
+        );
+    }
+}
+
+// Creates the parent directories, which don't exist on a fresh CI checkout
+fn write_file(path: &Path, content: &str) -> Result<(), Error> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+fn display_trend(records: &[TrendRecord]) {
+    let (Some(first), Some(last)) = (records.first(), records.last()) else {
+        println!("Nothing recorded yet, run `cargo scout stats record` first");
+        return;
+    };
+    println!("Date        Commit    Findings  Change");
+    let mut previous: Option<usize> = None;
+    for record in records {
+        let change = previous.map_or_else(String::new, |previous| {
+            if record.total >= previous {
+                format!("+{}", record.total - previous)
+            } else {
+                format!("-{}", previous - record.total)
+            }
+        });
+        println!(
+            "{}  {:<8}  {:>8}  {:>6}",
+            date(record.timestamp),
+            record.commit.get(..8).unwrap_or(&record.commit),
+            record.total,
+            change
+        );
+        previous = Some(record.total);
+    }
+    let trends = code_trends(first, last);
+    if !trends.is_empty() {
+        println!();
+        println!("Since {}:", date(first.timestamp));
+        let width = trends
+            .iter()
+            .map(|t| t.code.len())
+            .max()
+            .unwrap_or_default();
+        for trend in trends {
+            println!(
+                "{:<width$}  {:>5} -> {:<5} ({:+})",
+                trend.code,
+                trend.before,
+                trend.after,
+                trend.change(),
+                width = width
+            );
+        }
+    }
+}
+
+fn return_warnings(lints: &[Lint], without_error: bool, fail_on: FailOn) -> Result<(), Error> {
+    if without_error || !fail_on.should_fail(lints) {
+        Ok(())
+    } else {
+        Err(Error::NotClean { count: lints.len() })
+    }
+}
+
+fn display_timings(timings: &Timings) {
+    eprintln!("git:           {:.2}s", timings.git.as_secs_f64());
+    eprintln!("clippy:        {:.2}s", timings.clippy.as_secs_f64());
+    eprintln!("intersections: {:.2}s", timings.intersections.as_secs_f64());
+}
+
+// Printed to stderr, so it doesn't end up in a report written to stdout
+fn display_summary(stats: &Statistics) {
+    let code_width = stats
+        .by_code
+        .iter()
+        .map(|s| s.code.len())
+        .chain(std::iter::once("Lint".len()))
+        .max()
+        .unwrap_or_default();
+    eprintln!(
+        "{:<width$}  {:>5}  Files",
+        "Lint",
+        "Count",
+        width = code_width
+    );
+    for s in &stats.by_code {
+        eprintln!(
+            "{:<width$}  {:>5}  {}",
+            s.code,
+            s.count,
+            s.files.iter().cloned().collect::<Vec<_>>().join(", "),
+            width = code_width
+        );
+    }
+    if stats.by_crate.len() > 1 {
+        eprintln!();
+        for (name, count) in &stats.by_crate {
+            eprintln!("{name}: {count}");
+        }
+    }
+    eprintln!("Total: {}", stats.total);
 }
 
 #[cfg(test)]
@@ -97,18 +1474,74 @@ mod tests {
         let lints = vec![Lint {
             package_id: "cargo-scout".to_string(),
             src_path: None,
+            reproducer: None,
             message: None,
         }];
 
-        assert!(return_warnings(&lints, true).is_ok());
-        assert!(return_warnings(&lints, false).is_err());
+        assert!(return_warnings(&lints, true, FailOn::Any).is_ok());
+        assert!(return_warnings(&lints, false, FailOn::Any).is_err());
+    }
+
+    #[test]
+    fn test_return_status_with_fail_on() {
+        let lints = vec![Lint {
+            package_id: "cargo-scout".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: None,
+        }];
+
+        // Lints without a level are considered warnings
+        assert!(return_warnings(&lints, false, FailOn::Warning).is_err());
+        assert!(return_warnings(&lints, false, FailOn::Error).is_ok());
+    }
+
+    #[test]
+    fn test_package_selection_options() {
+        let opts =
+            Options::from_iter_safe(&["cargo-scout", "--package", "foo", "--package", "bar"])
+                .unwrap();
+        assert_eq!(vec!["foo", "bar"], opts.packages);
+        assert!(!opts.workspace);
+        assert!(
+            Options::from_iter_safe(&["cargo-scout", "--package", "foo", "--workspace"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_single_stdout_output() {
+        let validate = |args: &[&str]| {
+            Options::from_iter_safe(args.iter().copied())
+                .unwrap()
+                .validate()
+        };
+        assert!(validate(&["cargo-scout", "--output", "json"]).is_ok());
+        assert!(validate(&[
+            "cargo-scout",
+            "--output",
+            "json",
+            "--output",
+            "sarif=scout.sarif"
+        ])
+        .is_ok());
+        assert!(validate(&["cargo-scout", "--output", "json", "--output", "sarif"]).is_err());
+        assert!(validate(&[
+            "cargo-scout",
+            "--output",
+            "json",
+            "--output",
+            "sarif",
+            "--output-file",
+            "scout.json"
+        ])
+        .is_ok());
     }
 
     #[test]
     fn test_return_status_without_existing_lints() {
         let lints: Vec<Lint> = Vec::new();
 
-        assert!(return_warnings(&lints, true).is_ok());
-        assert!(return_warnings(&lints, false).is_ok());
+        assert!(return_warnings(&lints, true, FailOn::Any).is_ok());
+        assert!(return_warnings(&lints, false, FailOn::Any).is_ok());
     }
 }
@@ -0,0 +1,63 @@
+use cargo_scout_lib::progress::{Progress, Stage};
+use std::cell::RefCell;
+use std::time::Duration;
+
+/// How long each part of the run took
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Timings {
+    pub git: Duration,
+    pub clippy: Duration,
+    pub intersections: Duration,
+}
+
+/// Logs the current stage and records how long each stage took.
+#[derive(Default, Debug)]
+pub struct TerminalProgress {
+    timings: RefCell<Timings>,
+}
+
+impl TerminalProgress {
+    pub fn timings(&self) -> Timings {
+        *self.timings.borrow()
+    }
+}
+
+impl Progress for TerminalProgress {
+    fn started(&self, stage: &Stage) {
+        log::info!("{stage}...");
+    }
+
+    fn finished(&self, stage: &Stage, elapsed: Duration) {
+        log::debug!("{} done in {:.2}s", stage, elapsed.as_secs_f64());
+        let mut timings = self.timings.borrow_mut();
+        match stage {
+            Stage::Diffing => timings.git += elapsed,
+            Stage::Linting { .. } => timings.clippy += elapsed,
+            Stage::Intersecting => timings.intersections += elapsed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings() {
+        let progress = TerminalProgress::default();
+        let linting = |index| Stage::Linting {
+            member: "foo".to_string(),
+            index,
+            total: 2,
+        };
+        progress.finished(&Stage::Diffing, Duration::from_millis(10));
+        progress.finished(&linting(1), Duration::from_millis(100));
+        progress.finished(&linting(2), Duration::from_millis(200));
+        progress.finished(&Stage::Intersecting, Duration::from_millis(1));
+
+        let timings = progress.timings();
+        assert_eq!(Duration::from_millis(10), timings.git);
+        assert_eq!(Duration::from_millis(300), timings.clippy);
+        assert_eq!(Duration::from_millis(1), timings.intersections);
+    }
+}
@@ -0,0 +1,347 @@
+use crate::editor::Editor;
+use cargo_scout_lib::baseline::Baseline;
+use cargo_scout_lib::linter::{Level, Lint};
+use cargo_scout_lib::output::headline;
+use cargo_scout_lib::paths::PathResolver;
+use cargo_scout_lib::stats::UNKNOWN_CODE;
+use cargo_scout_lib::Error;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::path::{Path, PathBuf};
+
+const HELP: &str = "↑/↓ move  PgUp/PgDn scroll  enter open in $EDITOR  i ignore  q quit";
+
+/// A finding, where it is in the repository, and whether it was ignored
+#[derive(Debug, Clone)]
+struct Finding {
+    lint: Lint,
+    file: Option<String>,
+    line: u32,
+    column: u32,
+    ignored: bool,
+}
+
+/// The state of the findings browser
+pub struct App {
+    findings: Vec<Finding>,
+    selected: usize,
+    scroll: u16,
+    baseline: Baseline,
+    baseline_path: PathBuf,
+    repo_root: PathBuf,
+    status: String,
+}
+
+impl App {
+    /// Lists the findings by file, then by line.
+    ///
+    /// Ignored findings are added to `baseline`, then written to `baseline_path`.
+    #[must_use]
+    pub fn new(
+        lints: Vec<Lint>,
+        resolver: &PathResolver,
+        baseline: Baseline,
+        baseline_path: PathBuf,
+        repo_root: PathBuf,
+    ) -> Self {
+        let mut findings: Vec<Finding> = lints
+            .into_iter()
+            .map(|lint| {
+                let span = lint.primary_span();
+                Finding {
+                    file: resolver.resolve_lint(&lint).map(|r| r.repo_path),
+                    line: span.map_or(0, |s| s.line_start),
+                    column: span.map_or(0, |s| s.column_start),
+                    lint,
+                    ignored: false,
+                }
+            })
+            .collect();
+        findings.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+        Self {
+            findings,
+            selected: 0,
+            scroll: 0,
+            baseline,
+            baseline_path,
+            repo_root,
+            status: HELP.to_string(),
+        }
+    }
+
+    fn next(&mut self) {
+        if self.selected + 1 < self.findings.len() {
+            self.selected += 1;
+            self.scroll = 0;
+        }
+    }
+
+    fn previous(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+            self.scroll = 0;
+        }
+    }
+
+    /// Adds the selected finding to the baseline, so later runs don't report it.
+    fn ignore_selected(&mut self) -> Result<(), Error> {
+        let finding = match self.findings.get_mut(self.selected) {
+            Some(finding) if !finding.ignored => finding,
+            _ => return Ok(()),
+        };
+        if !self.baseline.add(&finding.lint) {
+            self.status = "This finding has no location, it can't be ignored".to_string();
+            return Ok(());
+        }
+        self.baseline.save(&self.baseline_path)?;
+        finding.ignored = true;
+        self.status = format!("Added to {}", self.baseline_path.display());
+        Ok(())
+    }
+
+    // The file, line and column of the selected finding
+    fn selected_location(&self) -> Option<(PathBuf, u32, u32)> {
+        let finding = self.findings.get(self.selected)?;
+        let file = finding.file.as_ref()?;
+        Some((self.repo_root.join(file), finding.line, finding.column))
+    }
+}
+
+/// Browses the findings until the user quits.
+pub fn run(mut app: App, editor: &Editor) -> Result<(), Error> {
+    if app.findings.is_empty() {
+        println!("There are no findings to browse");
+        return Ok(());
+    }
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, editor);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App, editor: &Editor) -> Result<(), Error> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Down | KeyCode::Char('j') => app.next(),
+            KeyCode::Up | KeyCode::Char('k') => app.previous(),
+            KeyCode::PageDown => app.scroll = app.scroll.saturating_add(10),
+            KeyCode::PageUp => app.scroll = app.scroll.saturating_sub(10),
+            KeyCode::Char('i') => app.ignore_selected()?,
+            KeyCode::Enter | KeyCode::Char('e') => {
+                if let Some((file, line, column)) = app.selected_location() {
+                    app.status = open_in_editor(terminal, editor, &file, line, column)?;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Hands the terminal over to the editor until it exits
+fn open_in_editor(
+    terminal: &mut DefaultTerminal,
+    editor: &Editor,
+    file: &Path,
+    line: u32,
+    column: u32,
+) -> Result<String, Error> {
+    disable_raw_mode()?;
+    execute!(std::io::stdout(), LeaveAlternateScreen)?;
+    let status = editor.command(file, line, column).status();
+    execute!(std::io::stdout(), EnterAlternateScreen)?;
+    enable_raw_mode()?;
+    terminal.clear()?;
+    Ok(match status {
+        Ok(status) if status.success() => HELP.to_string(),
+        Ok(status) => format!("{} exited with {}", editor.name(), status),
+        Err(e) => format!("Could not run {}: {}", editor.name(), e),
+    })
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(frame.area());
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(rows[0]);
+
+    let items: Vec<ListItem> = app
+        .findings
+        .iter()
+        .map(|f| {
+            let color = match f.lint.level() {
+                Some(Level::Error) => Color::Red,
+                Some(Level::Warning) | None => Color::Yellow,
+                _ => Color::Cyan,
+            };
+            let mut style = Style::default().fg(color);
+            if f.ignored {
+                style = style.add_modifier(Modifier::DIM | Modifier::CROSSED_OUT);
+            }
+            ListItem::new(Line::from(vec![
+                Span::raw(format!(
+                    "{}:{} ",
+                    f.file.as_deref().unwrap_or(UNKNOWN_CODE),
+                    f.line
+                )),
+                Span::styled(f.lint.code().unwrap_or(UNKNOWN_CODE).to_string(), style),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Findings ({})", app.findings.len())),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default();
+    state.select(Some(app.selected));
+    frame.render_stateful_widget(list, panes[0], &mut state);
+
+    let detail = app
+        .findings
+        .get(app.selected)
+        .map(details)
+        .unwrap_or_default();
+    let title = app
+        .findings
+        .get(app.selected)
+        .map(|f| headline(&f.lint))
+        .unwrap_or_default();
+    frame.render_widget(
+        Paragraph::new(detail)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .scroll((app.scroll, 0)),
+        panes[1],
+    );
+    frame.render_widget(
+        Paragraph::new(app.status.as_str()).style(Style::default().add_modifier(Modifier::DIM)),
+        rows[1],
+    );
+}
+
+// The rendered message, then the fixes clippy can apply
+fn details(finding: &Finding) -> Text<'static> {
+    let Some(message) = &finding.lint.message else {
+        return Text::default();
+    };
+    let mut lines: Vec<Line> = message
+        .rendered
+        .lines()
+        .map(|l| Line::raw(l.to_string()))
+        .collect();
+    let suggestions = message.machine_applicable_suggestions();
+    if !suggestions.is_empty() {
+        lines.push(Line::raw(String::new()));
+        lines.push(Line::styled(
+            "Suggested fix:",
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        for s in suggestions {
+            lines.push(Line::styled(
+                format!(
+                    "{}:{}: {}",
+                    s.file_name,
+                    s.line_start,
+                    s.suggested_replacement.as_deref().unwrap_or_default()
+                ),
+                Style::default().fg(Color::Green),
+            ));
+        }
+    }
+    if finding.ignored {
+        lines.push(Line::raw(String::new()));
+        lines.push(Line::raw("Ignored, added to the baseline"));
+    }
+    Text::from(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_scout_lib::linter::{Code, Message};
+
+    fn lint(file_name: &str, line: u32) -> Lint {
+        Lint {
+            package_id: "foo".to_string(),
+            src_path: None,
+            reproducer: None,
+            message: Some(Message {
+                message: String::new(),
+                rendered: "warning: redundant clone".to_string(),
+                code: Some(Code {
+                    code: "clippy::redundant_clone".to_string(),
+                    explanation: None,
+                }),
+                level: Some(Level::Warning),
+                children: Vec::new(),
+                spans: vec![cargo_scout_lib::linter::Span {
+                    file_name: file_name.to_string(),
+                    line_start: line,
+                    line_end: line,
+                    column_start: 5,
+                    column_end: 9,
+                    is_primary: true,
+                    ..cargo_scout_lib::linter::Span::default()
+                }],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_browse_and_ignore() -> Result<(), Error> {
+        let dir = tempfile::TempDir::new()?;
+        let baseline_path = dir.path().join("baseline.json");
+        let mut app = App::new(
+            vec![
+                lint("src/main.rs", 3),
+                lint("src/lib.rs", 7),
+                lint("src/lib.rs", 2),
+            ],
+            &PathResolver::default(),
+            Baseline::default(),
+            baseline_path.clone(),
+            PathBuf::from("/repo"),
+        );
+        assert_eq!(
+            Some((PathBuf::from("/repo/src/lib.rs"), 2, 5)),
+            app.selected_location()
+        );
+        app.previous();
+        assert_eq!(0, app.selected);
+        app.next();
+        app.next();
+        app.next();
+        assert_eq!(2, app.selected);
+        assert_eq!(
+            Some((PathBuf::from("/repo/src/main.rs"), 3, 5)),
+            app.selected_location()
+        );
+
+        app.ignore_selected()?;
+        app.ignore_selected()?;
+        assert!(app.findings[2].ignored);
+        let saved = Baseline::from_path(&baseline_path)?;
+        assert_eq!(1, saved.entries().len());
+        assert_eq!("src/main.rs", saved.entries()[0].file_name);
+        Ok(())
+    }
+}
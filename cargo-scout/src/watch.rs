@@ -0,0 +1,201 @@
+use cargo_scout_lib::linter::Linter;
+use cargo_scout_lib::output::human::HumanFormatter;
+use cargo_scout_lib::output::{Formatter, RunMetadata};
+use cargo_scout_lib::paths::simplify;
+use cargo_scout_lib::scout::TrackedLint;
+use cargo_scout_lib::vcs::VCS;
+use cargo_scout_lib::{config::Config, Error, Scout};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+// How long to wait for an editor or a formatter to be done writing files
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Waits for files of the working tree to change,
+/// ignoring the build outputs and git's own files.
+pub struct ChangeWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    root: PathBuf,
+    ignored: Vec<PathBuf>,
+}
+
+impl ChangeWatcher {
+    pub fn new(root: &Path, ignored: Vec<PathBuf>) -> Result<Self, Error> {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // The receiver only goes away when scout exits
+            let _ = sender.send(event);
+        })
+        .map_err(watch_error)?;
+        let root = simplify(&std::fs::canonicalize(root)?);
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(watch_error)?;
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            root,
+            ignored: ignored
+                .into_iter()
+                .map(|p| std::fs::canonicalize(&p).map_or(p, |p| simplify(&p)))
+                .collect(),
+        })
+    }
+
+    /// Blocks until a file changes, and returns the files that changed
+    /// until they stopped changing, relative to the root like the diff's file names.
+    pub fn wait(&self) -> Result<Vec<String>, Error> {
+        let mut changed = Vec::new();
+        while changed.is_empty() {
+            let event = self.events.recv().map_err(watch_error)?;
+            changed.extend(self.relevant_paths(event.map_err(watch_error)?));
+        }
+        while let Ok(event) = self.events.recv_timeout(DEBOUNCE) {
+            changed.extend(self.relevant_paths(event.map_err(watch_error)?));
+        }
+        changed.sort();
+        changed.dedup();
+        Ok(changed)
+    }
+
+    fn relevant_paths(&self, event: Event) -> Vec<String> {
+        match event.kind {
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {}
+            _ => return Vec::new(),
+        }
+        event
+            .paths
+            .into_iter()
+            .filter(|path| !is_ignored(path, &self.ignored))
+            .filter_map(|path| {
+                path.strip_prefix(&self.root)
+                    .ok()
+                    .map(|file| file.to_string_lossy().replace('\\', "/"))
+            })
+            .collect()
+    }
+}
+
+// Git's own files change on every git command, and the build outputs on every run
+fn is_ignored(path: &Path, ignored: &[PathBuf]) -> bool {
+    path.components().any(|c| c.as_os_str() == ".git")
+        || ignored.iter().any(|ignored| path.starts_with(ignored))
+}
+
+/// Lints the changes, then lints them again every time a file is saved, until scout is stopped.
+///
+/// After the first run, only the members the saved files belong to are linted again,
+/// the lints of the other members are kept. Errors are shown instead of ending the loop.
+pub fn watch<V, C, L>(
+    scout: &mut Scout<V, C, L>,
+    formatter: &HumanFormatter,
+    metadata: &RunMetadata,
+    watcher: &ChangeWatcher,
+) -> Result<(), Error>
+where
+    V: VCS,
+    C: Config,
+    L: Linter,
+{
+    let interactive = std::io::stdout().is_terminal();
+    let mut lints: Vec<TrackedLint> = Vec::new();
+    // The members to lint on the next run, every one of them when None.
+    // They're only cleared once a run succeeds
+    let mut pending: Option<BTreeSet<String>> = None;
+    loop {
+        scout.set_members(pending.as_ref().map(|m| m.iter().cloned().collect()));
+        match scout.run() {
+            Ok(found) => {
+                lints = match &pending {
+                    Some(members) => merge(scout, lints, found, members)?,
+                    None => found,
+                };
+                pending = Some(BTreeSet::new());
+                print!("{}", formatter.format_tracked(&lints, metadata));
+            }
+            Err(e) => eprintln!("Error: {e}"),
+        }
+        println!("Watching for changes, press Ctrl-C to stop");
+        let changed = watcher.wait()?;
+        if interactive {
+            // Clear the screen, so only the latest results are shown
+            print!("\x1b[2J\x1b[H");
+        }
+        let members = scout.members_of(&changed)?;
+        log::info!(
+            "{} changed, linting {}",
+            changed.join(", "),
+            if members.is_empty() {
+                "every member".to_string()
+            } else {
+                members.join(", ")
+            }
+        );
+        // A file outside the members, like a clippy configuration, can concern all of them
+        pending = match pending {
+            Some(mut pending) if !members.is_empty() => {
+                pending.extend(members);
+                Some(pending)
+            }
+            _ => None,
+        };
+    }
+}
+
+// The lints found in the members linted again, and the previous lints of the other members
+fn merge<V, C, L>(
+    scout: &Scout<V, C, L>,
+    previous: Vec<TrackedLint>,
+    found: Vec<TrackedLint>,
+    linted: &BTreeSet<String>,
+) -> Result<Vec<TrackedLint>, Error>
+where
+    V: VCS,
+    C: Config,
+    L: Linter,
+{
+    let mut lints = Vec::new();
+    for tracked in previous {
+        let file_names: Vec<String> = tracked
+            .lint
+            .message
+            .iter()
+            .flat_map(|m| m.primary_spans())
+            .map(|s| s.file_name.clone())
+            .collect();
+        let members = scout.members_of(&file_names)?;
+        if !members.iter().any(|m| linted.contains(m)) && !found.contains(&tracked) {
+            lints.push(tracked);
+        }
+    }
+    lints.extend(found);
+    Ok(lints)
+}
+
+fn watch_error(e: impl std::fmt::Display) -> Error {
+    Error::Io(std::io::Error::other(format!(
+        "could not watch the files: {e}"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_ignored() {
+        let ignored = vec![PathBuf::from("/repo/target")];
+        assert!(!is_ignored(Path::new("/repo/src/lib.rs"), &ignored));
+        assert!(is_ignored(Path::new("/repo/.git/index"), &ignored));
+        assert!(is_ignored(
+            Path::new("/repo/target/debug/scout-cache"),
+            &ignored
+        ));
+        assert!(!is_ignored(Path::new("/repo/targets.rs"), &ignored));
+    }
+}